@@ -8,7 +8,6 @@ use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
-use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use sc_finality_grandpa::SharedVoterState;
 use sc_keystore::LocalKeystore;
 use sc_telemetry::TelemetrySpan;
@@ -30,11 +29,10 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	sp_consensus::DefaultImportQueue<Block, FullClient>,
 	sc_transaction_pool::FullPool<Block, FullClient>,
 	(
-		sc_consensus_aura::AuraBlockImport<
+		sc_consensus_sassafras::SassafrasBlockImport<
 			Block,
 			FullClient,
 			sc_finality_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>,
-			AuraPair
 		>,
 		sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
 		Option<TelemetrySpan>,
@@ -63,13 +61,13 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
 
-	let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
+	let sassafras_block_import = sc_consensus_sassafras::SassafrasBlockImport::new(
 		grandpa_block_import.clone(), client.clone(),
 	);
 
-	let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
-		sc_consensus_aura::slot_duration(&*client)?,
-		aura_block_import.clone(),
+	let import_queue = sc_consensus_sassafras::import_queue(
+		sc_consensus_sassafras::slot_duration(&*client)?,
+		sassafras_block_import.clone(),
 		Some(Box::new(grandpa_block_import.clone())),
 		client.clone(),
 		inherent_data_providers.clone(),
@@ -87,7 +85,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		select_chain,
 		transaction_pool,
 		inherent_data_providers,
-		other: (aura_block_import, grandpa_link, telemetry_span),
+		other: (sassafras_block_import, grandpa_link, telemetry_span),
 	})
 }
 
@@ -138,7 +136,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
-		);
+		)?;
 	}
 
 	let role = config.role.clone();
@@ -192,8 +190,8 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _,_>(
-			sc_consensus_aura::slot_duration(&*client)?,
+		let sassafras = sc_consensus_sassafras::start_sassafras(
+			sc_consensus_sassafras::slot_duration(&*client)?,
 			client.clone(),
 			select_chain,
 			block_import,
@@ -206,9 +204,9 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 			can_author_with,
 		)?;
 
-		// the AURA authoring task is considered essential, i.e. if it
+		// the Sassafras authoring task is considered essential, i.e. if it
 		// fails we take down the service with it.
-		task_manager.spawn_essential_handle().spawn_blocking("aura", aura);
+		task_manager.spawn_essential_handle().spawn_blocking("sassafras", sassafras);
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -223,6 +221,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		// FIXME #1578 make this available through chainspec
 		gossip_duration: Duration::from_millis(333),
 		justification_period: 512,
+		archive_justifications: false,
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -281,14 +280,14 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 		select_chain.clone(),
 	)?;
 
-	let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
+	let sassafras_block_import = sc_consensus_sassafras::SassafrasBlockImport::new(
 		grandpa_block_import.clone(),
 		client.clone(),
 	);
 
-	let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _, _>(
-		sc_consensus_aura::slot_duration(&*client)?,
-		aura_block_import,
+	let import_queue = sc_consensus_sassafras::import_queue(
+		sc_consensus_sassafras::slot_duration(&*client)?,
+		sassafras_block_import,
 		Some(Box::new(grandpa_block_import)),
 		client.clone(),
 		InherentDataProviders::new(),
@@ -311,7 +310,7 @@ pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
-		);
+		)?;
 	}
 
 	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
@@ -0,0 +1,27 @@
+#![cfg(unix)]
+
+use assert_cmd::cargo::cargo_bin;
+use std::process::Command;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// The dev node authors blocks with Sassafras and the authored blocks pass re-verification
+/// through the Sassafras import queue.
+#[test]
+fn sassafras_authoring_works() {
+	let base_path = tempdir().expect("could not create a temp dir");
+
+	common::run_dev_node_for_a_while(base_path.path());
+
+	// With 6 second slots the node has authored at least two blocks in 30 seconds.
+	for block in &["1", "2"] {
+		let status = Command::new(cargo_bin("node-template"))
+			.args(&["check-block", "--dev", "--pruning", "archive", "-d"])
+			.arg(base_path.path())
+			.arg(block)
+			.status()
+			.unwrap();
+		assert!(status.success(), "block {} should pass the Sassafras import checks", block);
+	}
+}
@@ -56,6 +56,10 @@ pub struct Extensions {
 	pub fork_blocks: sc_client_api::ForkBlocks<Block>,
 	/// Known bad block hashes.
 	pub bad_blocks: sc_client_api::BadBlocks<Block>,
+	/// GRANDPA authority set changes forced at given blocks.
+	pub grandpa_hard_forks: grandpa::AuthoritySetHardForks<Block>,
+	/// BABE epoch data replacing the one announced at given blocks.
+	pub babe_epoch_overrides: sc_consensus_babe::EpochOverrides<Block>,
 }
 
 /// Specialized `ChainSpec`.
@@ -437,7 +441,7 @@ pub(crate) mod tests {
 			integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. }
-					= new_full_base(config,|_, _| ())?;
+					= new_full_base(config, Default::default(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(task_manager, client, network, transaction_pool))
 			},
 			|config| {
@@ -28,6 +28,33 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub authority_discovery: AuthorityDiscoveryParams,
+}
+
+/// Parameters of the authority discovery, used when the node runs as an authority.
+#[derive(Debug, StructOpt)]
+pub struct AuthorityDiscoveryParams {
+	/// Only publish the globally reachable addresses of this node on the DHT.
+	///
+	/// By default loopback and private network addresses are published as well, as they are
+	/// needed on local test networks.
+	#[structopt(long = "authority-discovery-global-ips-only")]
+	pub global_ips_only: bool,
+
+	/// Maximum interval in seconds in which the node publishes its own addresses on the DHT.
+	///
+	/// Defaults to one hour.
+	#[structopt(long = "authority-discovery-max-publish-interval", value_name = "SECONDS")]
+	pub max_publish_interval: Option<u64>,
+
+	/// Maximum interval in seconds in which the node queries the DHT for the addresses of the
+	/// other authorities.
+	///
+	/// Defaults to ten minutes.
+	#[structopt(long = "authority-discovery-max-query-interval", value_name = "SECONDS")]
+	pub max_query_interval: Option<u64>,
 }
 
 /// Possible subcommands of the main binary.
@@ -16,12 +16,13 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{chain_spec, service, Cli, Subcommand};
+use crate::{chain_spec, service, AuthorityDiscoveryParams, Cli, Subcommand};
 use node_executor::Executor;
 use node_runtime::{Block, RuntimeApi};
 use sc_cli::{Result, SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
 use crate::service::new_partial;
+use std::time::Duration;
 
 impl SubstrateCli for Cli {
 	fn impl_name() -> String {
@@ -75,10 +76,11 @@ pub fn run() -> Result<()> {
 	match &cli.subcommand {
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let authority_discovery_config = authority_discovery_config(&cli.authority_discovery);
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
 					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					_ => service::new_full(config, authority_discovery_config),
 				}.map_err(sc_cli::Error::Service)
 			})
 		}
@@ -151,3 +153,19 @@ pub fn run() -> Result<()> {
 		},
 	}
 }
+
+/// Build the configuration of the authority discovery worker from the command line parameters.
+fn authority_discovery_config(
+	params: &AuthorityDiscoveryParams,
+) -> sc_authority_discovery::WorkerConfig {
+	let default = sc_authority_discovery::WorkerConfig::default();
+
+	sc_authority_discovery::WorkerConfig {
+		max_publish_interval: params.max_publish_interval
+			.map_or(default.max_publish_interval, Duration::from_secs),
+		max_query_interval: params.max_query_interval
+			.map_or(default.max_query_interval, Duration::from_secs),
+		publish_non_global_ips: !params.global_ips_only,
+		..default
+	}
+}
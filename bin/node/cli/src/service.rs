@@ -43,6 +43,31 @@ type FullGrandpaBlockImport =
 	grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
 type LightClient = sc_service::TLightClient<Block, RuntimeApi, Executor>;
 
+/// GRANDPA authority set hard forks listed in the chain spec.
+fn grandpa_hard_forks(config: &Configuration) -> Vec<(
+	grandpa_primitives::SetId,
+	(<Block as BlockT>::Hash, node_primitives::BlockNumber),
+	grandpa_primitives::AuthorityList,
+)> {
+	sc_chain_spec::get_extension::<grandpa::AuthoritySetHardForks<Block>>(config.chain_spec.extensions())
+		.cloned()
+		.flatten()
+		.unwrap_or_default()
+		.into_iter()
+		.map(Into::into)
+		.collect()
+}
+
+/// BABE epoch overrides listed in the chain spec.
+fn babe_epoch_overrides(
+	config: &Configuration,
+) -> Vec<sc_consensus_babe::EpochOverride<<Block as BlockT>::Hash, node_primitives::BlockNumber>> {
+	sc_chain_spec::get_extension::<sc_consensus_babe::EpochOverrides<Block>>(config.chain_spec.extensions())
+		.cloned()
+		.flatten()
+		.unwrap_or_default()
+}
+
 pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponents<
 	FullClient, FullBackend, FullSelectChain,
 	sp_consensus::DefaultImportQueue<Block, FullClient>,
@@ -74,15 +99,19 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 	);
 
-	let (grandpa_block_import, grandpa_link) = grandpa::block_import(
-		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
+	let (grandpa_block_import, grandpa_link) = grandpa::block_import_with_authority_set_hard_forks(
+		client.clone(),
+		&(client.clone() as Arc<_>),
+		select_chain.clone(),
+		grandpa_hard_forks(&config),
 	)?;
 	let justification_import = grandpa_block_import.clone();
 
-	let (block_import, babe_link) = sc_consensus_babe::block_import(
+	let (block_import, babe_link) = sc_consensus_babe::block_import_with_epoch_overrides(
 		sc_consensus_babe::Config::get_or_compute(&*client)?,
 		grandpa_block_import,
 		client.clone(),
+		babe_epoch_overrides(&config),
 	)?;
 
 	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
@@ -173,8 +202,12 @@ pub struct NewFullBase {
 }
 
 /// Creates a full service from the configuration.
+///
+/// The `authority_discovery_config` is only used when running as an authority. Its
+/// `persisted_cache_file` is always placed in the network configuration directory.
 pub fn new_full_base(
 	mut config: Configuration,
+	authority_discovery_config: sc_authority_discovery::WorkerConfig,
 	with_startup_data: impl FnOnce(
 		&sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>,
 		&sc_consensus_babe::BabeLink<Block>,
@@ -215,7 +248,7 @@ pub fn new_full_base(
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
-		);
+		)?;
 	}
 
 	let role = config.role.clone();
@@ -226,6 +259,43 @@ pub fn new_full_base(
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let (authority_discovery_worker, authority_discovery_service) = if role.is_authority() {
+		let authority_discovery_role = sc_authority_discovery::Role::PublishAndDiscover(
+			keystore_container.keystore(),
+		);
+		let authority_discovery_config = sc_authority_discovery::WorkerConfig {
+			persisted_cache_file: config.network.net_config_path.as_ref()
+				.map(|path| path.join("authority_discovery_cache")),
+			..authority_discovery_config
+		};
+		let dht_event_stream = network.event_stream("authority-discovery")
+			.filter_map(|e| async move { match e {
+				Event::Dht(e) => Some(e),
+				_ => None,
+			}});
+		let (worker, service) = sc_authority_discovery::new_worker_and_service_with_config(
+			authority_discovery_config,
+			client.clone(),
+			network.clone(),
+			Box::pin(dht_event_stream),
+			authority_discovery_role,
+			prometheus_registry.clone(),
+		);
+		(Some(worker), Some(service))
+	} else {
+		(None, None)
+	};
+
+	let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
+		let mut io = rpc_extensions_builder(deny_unsafe, subscription_executor);
+		if let Some(service) = &authority_discovery_service {
+			io.extend_with(sc_authority_discovery_rpc::AuthorityDiscoveryApi::to_delegate(
+				sc_authority_discovery_rpc::AuthorityDiscoveryRpcHandler::new(service.clone(), deny_unsafe),
+			));
+		}
+		io
+	};
+
 	let (_rpc_handlers, telemetry_connection_notifier) = sc_service::spawn_tasks(
 		sc_service::SpawnTasksParams {
 			config,
@@ -278,23 +348,7 @@ pub fn new_full_base(
 	}
 
 	// Spawn authority discovery module.
-	if role.is_authority() {
-		let authority_discovery_role = sc_authority_discovery::Role::PublishAndDiscover(
-			keystore_container.keystore(),
-		);
-		let dht_event_stream = network.event_stream("authority-discovery")
-			.filter_map(|e| async move { match e {
-				Event::Dht(e) => Some(e),
-				_ => None,
-			}});
-		let (authority_discovery_worker, _service) = sc_authority_discovery::new_worker_and_service(
-			client.clone(),
-			network.clone(),
-			Box::pin(dht_event_stream),
-			authority_discovery_role,
-			prometheus_registry.clone(),
-		);
-
+	if let Some(authority_discovery_worker) = authority_discovery_worker {
 		task_manager.spawn_handle().spawn("authority-discovery-worker", authority_discovery_worker.run());
 	}
 
@@ -310,6 +364,7 @@ pub fn new_full_base(
 		// FIXME #1578 make this available through chainspec
 		gossip_duration: std::time::Duration::from_millis(333),
 		justification_period: 512,
+		archive_justifications: false,
 		name: Some(name),
 		observer_enabled: false,
 		keystore,
@@ -353,11 +408,12 @@ pub fn new_full_base(
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration)
--> Result<TaskManager, ServiceError> {
-	new_full_base(config, |_, _| ()).map(|NewFullBase { task_manager, .. }| {
-		task_manager
-	})
+pub fn new_full(
+	config: Configuration,
+	authority_discovery_config: sc_authority_discovery::WorkerConfig,
+) -> Result<TaskManager, ServiceError> {
+	new_full_base(config, authority_discovery_config, |_, _| ())
+		.map(|NewFullBase { task_manager, .. }| task_manager)
 }
 
 pub fn new_light_base(mut config: Configuration) -> Result<(
@@ -380,17 +436,19 @@ pub fn new_light_base(mut config: Configuration) -> Result<(
 		on_demand.clone(),
 	));
 
-	let (grandpa_block_import, _) = grandpa::block_import(
+	let (grandpa_block_import, _) = grandpa::block_import_with_authority_set_hard_forks(
 		client.clone(),
 		&(client.clone() as Arc<_>),
 		select_chain.clone(),
+		grandpa_hard_forks(&config),
 	)?;
 	let justification_import = grandpa_block_import.clone();
 
-	let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+	let (babe_block_import, babe_link) = sc_consensus_babe::block_import_with_epoch_overrides(
 		sc_consensus_babe::Config::get_or_compute(&*client)?,
 		grandpa_block_import,
 		client.clone(),
+		babe_epoch_overrides(&config),
 	)?;
 
 	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
@@ -422,7 +480,7 @@ pub fn new_light_base(mut config: Configuration) -> Result<(
 	if config.offchain_worker.enabled {
 		sc_service::build_offchain_workers(
 			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
-		);
+		)?;
 	}
 
 	let light_deps = node_rpc::LightDeps {
@@ -527,7 +585,7 @@ mod tests {
 				let mut setup_handles = None;
 				let NewFullBase {
 					task_manager, inherent_data_providers, client, network, transaction_pool, ..
-				} = new_full_base(config,
+				} = new_full_base(config, Default::default(),
 					|
 						block_import: &sc_consensus_babe::BabeBlockImport<Block, _, _>,
 						babe_link: &sc_consensus_babe::BabeLink<Block>,
@@ -632,7 +690,7 @@ mod tests {
 				params.body = Some(new_body);
 				params.intermediates.insert(
 					Cow::from(INTERMEDIATE_KEY),
-					Box::new(BabeIntermediate::<Block> { epoch_descriptor }) as Box<dyn Any>,
+					Box::new(BabeIntermediate::<Block> { epoch_descriptor }) as Box<dyn Any + Send>,
 				);
 				params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
 
@@ -696,7 +754,7 @@ mod tests {
 			crate::chain_spec::tests::integration_test_config_with_two_authorities(),
 			|config| {
 				let NewFullBase { task_manager, client, network, transaction_pool, .. }
-					= new_full_base(config,|_, _| ())?;
+					= new_full_base(config, Default::default(), |_, _| ())?;
 				Ok(sc_service_test::TestNetComponents::new(task_manager, client, network, transaction_pool))
 			},
 			|config| {
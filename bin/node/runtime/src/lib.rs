@@ -112,7 +112,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 261,
+	spec_version: 263,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 2,
@@ -1167,6 +1167,10 @@ impl_runtime_apis! {
 				.map(|p| p.encode())
 				.map(fg_primitives::OpaqueKeyOwnershipProof::new)
 		}
+
+		fn finality_delay() -> fg_primitives::FinalityDelay<NumberFor<Block>> {
+			Grandpa::finality_delay()
+		}
 	}
 
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -24,7 +24,7 @@ use sp_runtime::{
 	generic::BlockId, traits::{Block as BlockT, HashFor},
 };
 use sp_state_machine::{
-	OverlayedChanges, ExecutionManager, ExecutionStrategy, StorageProof,
+	OverlayedChanges, ExecutionManager, ExecutionStrategy, StorageProof, RecordedReads,
 };
 use sc_executor::{RuntimeVersion, NativeVersion};
 use sp_externalities::Extensions;
@@ -129,6 +129,20 @@ pub trait CallExecutor<B: BlockT> {
 		call_data: &[u8]
 	) -> Result<(Vec<u8>, StorageProof), sp_blockchain::Error>;
 
+	/// Execute a call to a contract on top of state in a block of given hash, recording
+	/// every storage value read from that state.
+	///
+	/// The changes made by the call are left in `overlay`.
+	fn call_recording_reads(
+		&self,
+		id: &BlockId<B>,
+		overlay: &mut OverlayedChanges,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+		extensions: Option<Extensions>,
+	) -> Result<(Vec<u8>, RecordedReads), sp_blockchain::Error>;
+
 	/// Get runtime version if supported.
 	fn native_runtime_version(&self) -> Option<&NativeVersion>;
 }
@@ -95,6 +95,10 @@ pub trait BlockBackend<Block: BlockT> {
 
 	/// Get block hash by number.
 	fn block_hash(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Option<Block::Hash>>;
+
+	/// Get the data indexed through the offchain indexing API under the given content hash.
+	/// Returns `None` if it isn't stored.
+	fn indexed_transaction(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>>;
 }
 
 /// Provide a list of potential uncle headers for a given block.
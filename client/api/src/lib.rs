@@ -29,6 +29,7 @@ pub mod light;
 pub mod leaves;
 pub mod notifications;
 pub mod proof_provider;
+pub mod storage_trace;
 
 pub use sp_blockchain as blockchain;
 pub use backend::*;
@@ -38,6 +39,7 @@ pub use client::*;
 pub use light::*;
 pub use notifications::*;
 pub use proof_provider::*;
+pub use storage_trace::*;
 
 pub use sp_state_machine::{StorageProof, ExecutionStrategy};
 
@@ -22,7 +22,7 @@ use sp_runtime::{
 	traits::{Block as BlockT},
 };
 use crate::{StorageProof, ChangesProof};
-use sp_storage::{ChildInfo, StorageKey, PrefixedStorageKey};
+use sp_storage::{ChildInfo, StorageKey, StorageData, PrefixedStorageKey};
 
 /// Interface for providing block proving utilities.
 pub trait ProofProvider<Block: BlockT> {
@@ -42,6 +42,27 @@ pub trait ProofProvider<Block: BlockT> {
 		keys: &mut dyn Iterator<Item=&[u8]>,
 	) -> sp_blockchain::Result<StorageProof>;
 
+	/// Reads up to `count` storage key/value pairs with the given prefix at a given
+	/// block, starting after `start_key` (or after `prefix` if not given), returning
+	/// the pairs and their read proof.
+	fn read_range_proof(
+		&self,
+		id: &BlockId<Block>,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> sp_blockchain::Result<(Vec<(StorageKey, StorageData)>, StorageProof)>;
+
+	/// Reads the same storage key/value pairs as [`Self::read_range_proof`], without
+	/// generating the proof.
+	fn read_range(
+		&self,
+		id: &BlockId<Block>,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> sp_blockchain::Result<Vec<(StorageKey, StorageData)>>;
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
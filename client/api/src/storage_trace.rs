@@ -0,0 +1,69 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage access tracing of block execution.
+
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_storage::{PrefixedStorageKey, StorageData, StorageKey};
+
+/// A storage value read from the parent state while executing a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageRead {
+	/// Child trie the key belongs to, `None` for the top-level trie.
+	pub child_storage_key: Option<PrefixedStorageKey>,
+	/// The key that was read.
+	pub key: StorageKey,
+	/// The value found in the parent state.
+	pub value: Option<StorageData>,
+}
+
+/// A storage value written while executing a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageWrite {
+	/// Child trie the key belongs to, `None` for the top-level trie.
+	pub child_storage_key: Option<PrefixedStorageKey>,
+	/// The key that was written.
+	pub key: StorageKey,
+	/// The value in the parent state.
+	pub old_value: Option<StorageData>,
+	/// The value after executing the block, `None` if it was deleted.
+	pub new_value: Option<StorageData>,
+	/// Indices of the extrinsics that modified the value.
+	///
+	/// Changes made outside of any extrinsic (e.g. during block initialization or
+	/// finalization) are reported with the `u32::max_value()` index.
+	pub extrinsics: Vec<u32>,
+}
+
+/// All storage accesses made while executing a block.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockStorageTrace {
+	/// Values read from the parent state, ordered by child trie and key.
+	pub reads: Vec<StorageRead>,
+	/// Values written by the block, ordered by child trie and key.
+	pub writes: Vec<StorageWrite>,
+}
+
+/// Interface for tracing the storage accesses of a block.
+pub trait StorageTraceProvider<Block: BlockT> {
+	/// Re-execute the block on top of its parent state and return every storage
+	/// value it read and wrote.
+	///
+	/// No changes are made.
+	fn trace_block_storage(&self, id: &BlockId<Block>) -> sp_blockchain::Result<BlockStorageTrace>;
+}
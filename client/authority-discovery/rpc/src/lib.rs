@@ -0,0 +1,154 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for the authority discovery.
+
+#![warn(missing_docs)]
+
+use futures::{future::BoxFuture, FutureExt as _, TryFutureExt as _};
+use jsonrpc_core::{
+	Error as RpcError,
+	futures::future as rpc_future,
+};
+use jsonrpc_derive::rpc;
+use sc_authority_discovery::Service;
+use sc_network::Multiaddr;
+use sc_rpc_api::DenyUnsafe;
+use sp_authority_discovery::AuthorityId;
+
+type FutureResult<T> = Box<dyn rpc_future::Future<Item = T, Error = RpcError> + Send>;
+
+/// Provides rpc methods for inspecting the authority discovery.
+#[rpc]
+pub trait AuthorityDiscoveryApi {
+	/// Returns the addresses of the given authority known by the local node, if any.
+	///
+	/// The addresses either have been discovered on the DHT or loaded from the persisted cache.
+	#[rpc(name = "authorityDiscovery_getAddresses")]
+	fn get_addresses(&self, authority_id: AuthorityId) -> FutureResult<Option<Vec<String>>>;
+}
+
+/// Source of the addresses of authorities known by the local node.
+pub trait ReportAddresses {
+	/// Returns the known addresses of the given authority, if any.
+	fn get(&self, authority_id: AuthorityId) -> BoxFuture<'static, Option<Vec<Multiaddr>>>;
+}
+
+impl ReportAddresses for Service {
+	fn get(&self, authority_id: AuthorityId) -> BoxFuture<'static, Option<Vec<Multiaddr>>> {
+		let mut service = self.clone();
+		async move { service.get_addresses_by_authority_id(authority_id).await }.boxed()
+	}
+}
+
+/// Implements the [`AuthorityDiscoveryApi`] RPC trait for inspecting the authority discovery.
+pub struct AuthorityDiscoveryRpcHandler<Addresses = Service> {
+	/// Source of the known authority addresses, usually the authority discovery [`Service`].
+	addresses: Addresses,
+	/// Whether to deny unsafe calls
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<Addresses> AuthorityDiscoveryRpcHandler<Addresses> {
+	/// Creates a new instance of the authority discovery RPC handler.
+	pub fn new(addresses: Addresses, deny_unsafe: DenyUnsafe) -> Self {
+		Self { addresses, deny_unsafe }
+	}
+}
+
+impl<Addresses> AuthorityDiscoveryApi for AuthorityDiscoveryRpcHandler<Addresses>
+where
+	Addresses: ReportAddresses + Send + Sync + 'static,
+{
+	fn get_addresses(&self, authority_id: AuthorityId) -> FutureResult<Option<Vec<String>>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(rpc_future::err(err.into()));
+		}
+
+		let future = self.addresses.get(authority_id).map(|addresses| {
+			let addresses = addresses.map(|addresses| addresses.iter().map(ToString::to_string).collect());
+			Ok::<_, RpcError>(addresses)
+		});
+
+		Box::new(future.boxed().compat())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::collections::HashMap;
+
+	use jsonrpc_core::futures::Future as _;
+	use sp_core::crypto::Public;
+
+	struct TestAddresses(HashMap<AuthorityId, Vec<Multiaddr>>);
+
+	impl ReportAddresses for TestAddresses {
+		fn get(&self, authority_id: AuthorityId) -> BoxFuture<'static, Option<Vec<Multiaddr>>> {
+			futures::future::ready(self.0.get(&authority_id).cloned()).boxed()
+		}
+	}
+
+	fn known_authority() -> (AuthorityId, Multiaddr) {
+		let authority = AuthorityId::from_slice(&[1; 32]);
+		let address = "/ip6/2001:db8::1/tcp/30333".parse().unwrap();
+
+		(authority, address)
+	}
+
+	fn rpc_handler(deny_unsafe: DenyUnsafe) -> AuthorityDiscoveryRpcHandler<TestAddresses> {
+		let (authority, address) = known_authority();
+		let addresses = TestAddresses(vec![(authority, vec![address])].into_iter().collect());
+
+		AuthorityDiscoveryRpcHandler::new(addresses, deny_unsafe)
+	}
+
+	#[test]
+	fn get_addresses_of_known_authority() {
+		let (authority, address) = known_authority();
+		let handler = rpc_handler(DenyUnsafe::No);
+
+		assert_eq!(
+			handler.get_addresses(authority).wait().unwrap(),
+			Some(vec![address.to_string()]),
+		);
+	}
+
+	#[test]
+	fn get_addresses_of_unknown_authority() {
+		let handler = rpc_handler(DenyUnsafe::No);
+
+		assert_eq!(
+			handler.get_addresses(AuthorityId::from_slice(&[2; 32])).wait().unwrap(),
+			None,
+		);
+	}
+
+	#[test]
+	fn get_addresses_is_considered_unsafe() {
+		let (authority, _) = known_authority();
+		let handler = rpc_handler(DenyUnsafe::Yes);
+
+		assert_eq!(
+			handler.get_addresses(authority).wait().unwrap_err(),
+			RpcError::method_not_found(),
+		);
+	}
+}
@@ -28,7 +28,7 @@
 
 pub use crate::{service::Service, worker::{NetworkProvider, Worker, Role}};
 
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use futures::channel::{mpsc, oneshot};
 use futures::Stream;
@@ -57,6 +57,17 @@ pub struct WorkerConfig {
 	///
 	/// By default this is set to 10 minutes.
 	pub max_query_interval: Duration,
+	/// Whether to publish addresses which are not globally reachable, e.g. loopback or private
+	/// network addresses, on the DHT.
+	///
+	/// Those are of no use to remote authorities, but are needed on local test networks. By
+	/// default this is set to `true`.
+	pub publish_non_global_ips: bool,
+	/// File in which the addresses of the authorities discovered so far are persisted.
+	///
+	/// The cache is loaded from this file on startup, so that a restarted node knows the addresses
+	/// of the other authorities before its DHT queries succeed. By default nothing is persisted.
+	pub persisted_cache_file: Option<PathBuf>,
 }
 
 impl Default for WorkerConfig {
@@ -75,6 +86,8 @@ impl Default for WorkerConfig {
 			// comparing `authority_discovery_authority_addresses_requested_total` and
 			// `authority_discovery_dht_event_received`.
 			max_query_interval: Duration::from_secs(10 * 60),
+			publish_non_global_ips: true,
+			persisted_cache_file: None,
 		}
 	}
 }
@@ -21,6 +21,7 @@ use crate::{error::{Error, Result}, interval::ExpIncInterval, ServicetoWorkerMsg
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -30,8 +31,9 @@ use futures::{FutureExt, Stream, StreamExt, stream::Fuse};
 use addr_cache::AddrCache;
 use async_trait::async_trait;
 use codec::Decode;
+use ip_network::IpNetwork;
 use libp2p::{core::multiaddr, multihash::{Multihash, Hasher}};
-use log::{debug, error, log_enabled};
+use log::{debug, error, log_enabled, warn};
 use prometheus_endpoint::{Counter, CounterVec, Gauge, Opts, U64, register};
 use prost::Message;
 use rand::{seq::SliceRandom, thread_rng};
@@ -118,6 +120,11 @@ pub struct Worker<Client, Network, Block, DhtEventStream> {
 	in_flight_lookups: HashMap<libp2p::kad::record::Key, AuthorityId>,
 
 	addr_cache: addr_cache::AddrCache,
+	/// File in which `addr_cache` is persisted, if any.
+	persisted_cache_file: Option<PathBuf>,
+
+	/// Whether to publish addresses which are not globally reachable.
+	publish_non_global_ips: bool,
 
 	metrics: Option<Metrics>,
 
@@ -160,7 +167,10 @@ where
 			config.max_query_interval,
 		);
 
-		let addr_cache = AddrCache::new();
+		let addr_cache = match &config.persisted_cache_file {
+			Some(path) => load_addr_cache(path),
+			None => AddrCache::new(),
+		};
 
 		let metrics = match prometheus_registry {
 			Some(registry) => {
@@ -175,6 +185,12 @@ where
 			None => None,
 		};
 
+		if let Some(metrics) = &metrics {
+			metrics.known_authorities_count.set(
+				addr_cache.num_ids().try_into().unwrap_or(std::u64::MAX)
+			);
+		}
+
 		Worker {
 			from_service: from_service.fuse(),
 			client,
@@ -185,6 +201,8 @@ where
 			pending_lookups: Vec::new(),
 			in_flight_lookups: HashMap::new(),
 			addr_cache,
+			persisted_cache_file: config.persisted_cache_file,
+			publish_non_global_ips: config.publish_non_global_ips,
 			role,
 			metrics,
 			phantom: PhantomData,
@@ -248,10 +266,12 @@ where
 		}
 	}
 
-	fn addresses_to_publish(&self) -> impl ExactSizeIterator<Item = Multiaddr> {
+	fn addresses_to_publish(&self) -> impl Iterator<Item = Multiaddr> {
 		let peer_id: Multihash = self.network.local_peer_id().into();
+		let publish_non_global_ips = self.publish_non_global_ips;
 		self.network.external_addresses()
 			.into_iter()
+			.filter(move |a| publish_non_global_ips || is_global(a))
 			.map(move |a| {
 				if a.iter().any(|p| matches!(p, multiaddr::Protocol::P2p(_))) {
 					a
@@ -268,7 +288,7 @@ where
 			Role::Discover => return Ok(()),
 		};
 
-		let addresses = self.addresses_to_publish();
+		let addresses = self.addresses_to_publish().collect::<Vec<_>>();
 
 		if let Some(metrics) = &self.metrics {
 			metrics.publish.inc();
@@ -278,7 +298,7 @@ where
 		}
 
 		let mut serialized_addresses = vec![];
-		schema::AuthorityAddresses { addresses: addresses.map(|a| a.to_vec()).collect() }
+		schema::AuthorityAddresses { addresses: addresses.into_iter().map(|a| a.to_vec()).collect() }
 			.encode(&mut serialized_addresses)
 			.map_err(Error::EncodingProto)?;
 
@@ -338,6 +358,8 @@ where
 			.collect();
 
 		self.addr_cache.retain_ids(&authorities);
+		// Persist what has been discovered since the previous refill.
+		self.persist_addr_cache();
 
 		authorities.shuffle(&mut thread_rng());
 		self.pending_lookups = authorities;
@@ -527,6 +549,25 @@ where
 		Ok(())
 	}
 
+	/// Write the address cache to the persisted cache file, if any.
+	fn persist_addr_cache(&self) {
+		let path = match &self.persisted_cache_file {
+			Some(path) => path,
+			None => return,
+		};
+
+		// Write to a temporary file first, to never leave a truncated cache behind.
+		let tmp_path = path.with_extension("tmp");
+		let result = std::fs::write(&tmp_path, self.addr_cache.encode())
+			.and_then(|()| std::fs::rename(&tmp_path, path));
+		if let Err(e) = result {
+			warn!(
+				target: LOG_TARGET,
+				"Failed to persist authority address cache to {}: {:?}", path.display(), e,
+			);
+		}
+	}
+
 	/// Retrieve our public keys within the current and next authority set.
 	//
 	// A node might have multiple authority discovery keys within its keystore, e.g. an old one and
@@ -586,6 +627,48 @@ where
 	}
 }
 
+/// Load the address cache persisted in the given file, or an empty cache if there is none.
+fn load_addr_cache(path: &Path) -> AddrCache {
+	let encoded = match std::fs::read(path) {
+		Ok(encoded) => encoded,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return AddrCache::new(),
+		Err(e) => {
+			warn!(
+				target: LOG_TARGET,
+				"Failed to read authority address cache from {}: {:?}", path.display(), e,
+			);
+			return AddrCache::new()
+		},
+	};
+
+	match AddrCache::decode(&encoded) {
+		Ok(cache) => {
+			debug!(
+				target: LOG_TARGET,
+				"Loaded addresses of {} authorities from {}.", cache.num_ids(), path.display(),
+			);
+			cache
+		},
+		Err(e) => {
+			warn!(
+				target: LOG_TARGET,
+				"Failed to decode authority address cache from {}: {:?}", path.display(), e,
+			);
+			AddrCache::new()
+		},
+	}
+}
+
+/// Returns `false` if the address contains an IP address which is not globally reachable.
+fn is_global(address: &Multiaddr) -> bool {
+	address.iter().all(|protocol| match protocol {
+		// `ip_network` is used as `is_global` of the standard library isn't stable yet.
+		multiaddr::Protocol::Ip4(ip) => IpNetwork::from(ip).is_global(),
+		multiaddr::Protocol::Ip6(ip) => IpNetwork::from(ip).is_global(),
+		_ => true,
+	})
+}
+
 fn hash_authority_id(id: &[u8]) -> libp2p::kad::record::Key {
 	libp2p::kad::record::Key::new(&libp2p::multihash::Sha2_256::digest(id))
 }
@@ -16,8 +16,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use codec::{Decode, Encode};
 use libp2p::core::multiaddr::{Multiaddr, Protocol};
-use std::collections::HashMap;
+use std::{collections::HashMap, convert::TryFrom};
 
 use sp_authority_discovery::AuthorityId;
 use sc_network::PeerId;
@@ -97,6 +98,33 @@ impl AddrCache {
 			}
 		}
 	}
+
+	/// Encodes the [`AuthorityId`] to [`Vec<Multiaddr>`] mappings of the cache, for them to be
+	/// persisted.
+	pub fn encode(&self) -> Vec<u8> {
+		self.authority_id_to_addresses.iter()
+			.map(|(authority_id, addresses)| {
+				(authority_id, addresses.iter().map(|a| a.to_vec()).collect::<Vec<_>>())
+			})
+			.collect::<Vec<_>>()
+			.encode()
+	}
+
+	/// Decodes a cache encoded with [`AddrCache::encode`].
+	///
+	/// Addresses which can't be parsed anymore are ignored.
+	pub fn decode(mut encoded: &[u8]) -> Result<Self, codec::Error> {
+		let entries = Vec::<(AuthorityId, Vec<Vec<u8>>)>::decode(&mut encoded)?;
+
+		let mut cache = AddrCache::new();
+		for (authority_id, addresses) in entries {
+			let addresses = addresses.into_iter()
+				.filter_map(|a| Multiaddr::try_from(a).ok())
+				.collect();
+			cache.insert(authority_id, addresses);
+		}
+		Ok(cache)
+	}
 }
 
 fn peer_id_from_multiaddr(addr: &Multiaddr) -> Option<PeerId> {
@@ -191,4 +219,39 @@ mod tests {
 			.max_tests(10)
 			.quickcheck(property as fn(_, _, _) -> TestResult)
 	}
+
+	#[test]
+	fn decodes_encoded_cache() {
+		fn property(
+			first: (TestAuthorityId, TestMultiaddr),
+			second: (TestAuthorityId, TestMultiaddr),
+		) -> TestResult {
+			let first: (AuthorityId, Multiaddr) = ((first.0).0, (first.1).0);
+			let second: (AuthorityId, Multiaddr) = ((second.0).0, (second.1).0);
+
+			let mut cache = AddrCache::new();
+			cache.insert(first.0.clone(), vec![first.1.clone()]);
+			cache.insert(second.0.clone(), vec![second.1.clone()]);
+
+			let decoded = AddrCache::decode(&cache.encode()).unwrap();
+
+			assert_eq!(cache.num_ids(), decoded.num_ids());
+			assert_eq!(
+				Some(&vec![second.1.clone()]),
+				decoded.get_addresses_by_authority_id(&second.0),
+				"Expect decoded cache to contain addresses of second authority."
+			);
+			assert_eq!(
+				Some(&first.0),
+				decoded.get_authority_id_by_peer_id(&peer_id_from_multiaddr(&first.1).unwrap()),
+				"Expect decoded cache to return `AuthorityId` of first authority."
+			);
+
+			TestResult::passed()
+		}
+
+		QuickCheck::new()
+			.max_tests(10)
+			.quickcheck(property as fn(_, _) -> TestResult)
+	}
 }
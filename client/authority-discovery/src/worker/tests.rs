@@ -632,6 +632,83 @@ fn addresses_to_publish_respects_existing_p2p_protocol() {
 	);
 }
 
+#[test]
+fn addresses_to_publish_filters_non_global_ips() {
+	let (_dht_event_tx, dht_event_rx) = channel(1000);
+	let global_address: Multiaddr = "/ip4/1.2.3.4/tcp/30333".parse().unwrap();
+	let network: Arc<TestNetwork> = Arc::new(TestNetwork {
+		external_addresses: vec![
+			"/ip4/127.0.0.1/tcp/30333".parse().unwrap(),
+			"/ip4/192.168.0.1/tcp/30333".parse().unwrap(),
+			global_address.clone(),
+		],
+		.. Default::default()
+	});
+
+	let (_to_worker, from_service) = mpsc::channel(0);
+	let worker = Worker::new(
+		from_service,
+		Arc::new(TestApi {
+			authorities: vec![],
+		}),
+		network.clone(),
+		Box::pin(dht_event_rx),
+		Role::PublishAndDiscover(Arc::new(KeyStore::new())),
+		None,
+		crate::WorkerConfig {
+			publish_non_global_ips: false,
+			.. Default::default()
+		},
+	);
+
+	assert_eq!(
+		vec![global_address.with(multiaddr::Protocol::P2p(network.peer_id.clone().into()))],
+		worker.addresses_to_publish().collect::<Vec<_>>(),
+		"Expect `addresses_to_publish` to only return globally reachable addresses.",
+	);
+}
+
+#[test]
+fn addr_cache_is_persisted_across_restarts() {
+	let dir = tempfile::tempdir().unwrap();
+	let config = || crate::WorkerConfig {
+		persisted_cache_file: Some(dir.path().join("authority_discovery_cache")),
+		.. Default::default()
+	};
+	let remote_public = block_on(KeyStore::new()
+		.sr25519_generate_new(key_types::AUTHORITY_DISCOVERY, None))
+		.unwrap();
+	let remote_multiaddr: Multiaddr = "/ip6/2001:db8:0:0:0:0:0:1/tcp/30333".parse::<Multiaddr>()
+		.unwrap()
+		.with(multiaddr::Protocol::P2p(PeerId::random().into()));
+	let new_worker = || {
+		let (_dht_event_tx, dht_event_rx) = channel(1000);
+		let (_to_worker, from_service) = mpsc::channel(0);
+		Worker::new(
+			from_service,
+			Arc::new(TestApi {
+				authorities: vec![remote_public.into()],
+			}),
+			Arc::new(TestNetwork::default()),
+			Box::pin(dht_event_rx),
+			Role::Discover,
+			None,
+			config(),
+		)
+	};
+
+	let mut worker = new_worker();
+	worker.inject_addresses(remote_public.into(), vec![remote_multiaddr.clone()]);
+	worker.persist_addr_cache();
+
+	let restarted_worker = new_worker();
+	assert_eq!(
+		Some(&vec![remote_multiaddr]),
+		restarted_worker.addr_cache.get_addresses_by_authority_id(&remote_public.into()),
+		"Expect restarted worker to know the addresses of the remote authority.",
+	);
+}
+
 #[test]
 fn lookup_throttling() {
 	let remote_multiaddr = {
@@ -189,6 +189,16 @@ arg_enum! {
 	}
 }
 
+arg_enum! {
+	/// Blocks the off-chain workers are run on.
+	#[allow(missing_docs)]
+	#[derive(Debug, Clone)]
+	pub enum OffchainWorkerTrigger {
+		Best,
+		Finalized,
+	}
+}
+
 /// Default value for the `--execution-syncing` parameter.
 pub const DEFAULT_EXECUTION_SYNCING: ExecutionStrategy = ExecutionStrategy::NativeElseWasm;
 /// Default value for the `--execution-import-block` parameter.
@@ -16,13 +16,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::params::node_key_params::NodeKeyParams;
+use crate::{error::{Error, Result}, params::node_key_params::NodeKeyParams};
 use sc_network::{
-	config::{NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig},
+	config::{
+		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SecureWebSocketConfig, SetConfig,
+		TransportConfig,
+	},
 	multiaddr::Protocol,
 };
 use sc_service::{ChainSpec, ChainType, config::{Multiaddr, MultiaddrWithPeerId}};
-use std::path::PathBuf;
+use std::{fs::File, io::{BufRead, BufReader}, path::PathBuf};
 use structopt::StructOpt;
 
 /// Parameters used to create the network configuration.
@@ -106,6 +109,34 @@ pub struct NetworkParams {
 	/// security improvements.
 	#[structopt(long)]
 	pub kademlia_disjoint_query_paths: bool,
+
+	/// Serve the transactions stored through offchain indexing to the peers connected over the
+	/// IPFS bitswap protocol. The node doesn't join the IPFS DHT, so it has to be connected to
+	/// explicitly.
+	#[structopt(long)]
+	pub ipfs_server: bool,
+
+	/// Accept secure WebSocket connections on the `/wss` addresses passed with `--listen-addr`,
+	/// using the certificate chain of the given PEM file.
+	///
+	/// This is what makes the node reachable by light clients running in a browser.
+	#[structopt(
+		long = "wss-certificate",
+		value_name = "PATH",
+		requires_all = &["wss-private-key", "wss-domain"]
+	)]
+	pub wss_certificate: Option<PathBuf>,
+
+	/// PEM file containing the private key of `--wss-certificate`.
+	#[structopt(long = "wss-private-key", value_name = "PATH", requires = "wss-certificate")]
+	pub wss_private_key: Option<PathBuf>,
+
+	/// Domain name `--wss-certificate` has been issued for.
+	///
+	/// Browsers refuse to open secure WebSockets to raw IP addresses, so the `/wss` listen
+	/// addresses are advertised under this domain name.
+	#[structopt(long = "wss-domain", value_name = "DOMAIN", requires = "wss-certificate")]
+	pub wss_domain: Option<String>,
 }
 
 impl NetworkParams {
@@ -119,7 +150,7 @@ impl NetworkParams {
 		node_name: &str,
 		node_key: NodeKeyConfig,
 		default_listen_port: u16,
-	) -> NetworkConfiguration {
+	) -> Result<NetworkConfiguration> {
 		let port = self.port.unwrap_or(default_listen_port);
 
 		let listen_addresses = if self.listen_addr.is_empty() {
@@ -147,7 +178,7 @@ impl NetworkParams {
 			|| is_dev
 			|| matches!(chain_type, ChainType::Local | ChainType::Development);
 
-		NetworkConfiguration {
+		Ok(NetworkConfiguration {
 			boot_nodes,
 			net_config_path,
 			default_peers_set: SetConfig {
@@ -171,11 +202,50 @@ impl NetworkParams {
 				enable_mdns: !is_dev && !self.no_mdns,
 				allow_private_ipv4: !self.no_private_ipv4,
 				wasm_external_transport: None,
+				secure_websocket: self.secure_websocket_config()?,
 			},
 			max_parallel_downloads: self.max_parallel_downloads,
 			allow_non_globals_in_dht,
 			kademlia_disjoint_query_paths: self.kademlia_disjoint_query_paths,
 			yamux_window_size: None,
-		}
+			ipfs_server: self.ipfs_server,
+		})
+	}
+
+	/// Read the certificate and private key of the secure WebSocket listeners, if any.
+	fn secure_websocket_config(&self) -> Result<Option<SecureWebSocketConfig>> {
+		let (certificate, private_key, domain) = match (
+			&self.wss_certificate,
+			&self.wss_private_key,
+			&self.wss_domain,
+		) {
+			(Some(certificate), Some(private_key), Some(domain)) =>
+				(certificate, private_key, domain),
+			_ => return Ok(None),
+		};
+
+		let certificate_chain = read_pem(certificate, rustls::internal::pemfile::certs)?;
+		// Both PKCS#8 and RSA private keys are supported.
+		let private_key = read_pem(private_key, rustls::internal::pemfile::pkcs8_private_keys)
+			.or_else(|_| read_pem(private_key, rustls::internal::pemfile::rsa_private_keys))?
+			.remove(0);
+
+		Ok(Some(SecureWebSocketConfig {
+			domain: domain.clone(),
+			private_key: private_key.0,
+			certificate_chain: certificate_chain.into_iter().map(|certificate| certificate.0).collect(),
+			trusted_certificates: Vec::new(),
+		}))
+	}
+}
+
+/// Read the PEM file at `path` with `parse`, returning an error if it contains no item.
+fn read_pem<T>(
+	path: &PathBuf,
+	parse: fn(&mut dyn BufRead) -> std::result::Result<Vec<T>, ()>,
+) -> Result<Vec<T>> {
+	match parse(&mut BufReader::new(File::open(path)?)) {
+		Ok(items) if !items.is_empty() => Ok(items),
+		_ => Err(Error::Input(format!("Invalid or empty PEM file {}", path.display()))),
 	}
 }
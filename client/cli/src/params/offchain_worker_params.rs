@@ -23,12 +23,13 @@
 //! targeted at handling input parameter parsing providing
 //! a reasonable abstraction.
 
+use std::{path::PathBuf, time::Duration};
 use structopt::StructOpt;
-use sc_service::config::OffchainWorkerConfig;
+use sc_service::config::{OffchainWorkerConfig, OffchainSchedulingPolicy, OffchainWorkerTrigger};
 use sc_network::config::Role;
 
 use crate::error;
-use crate::OffchainWorkerEnabled;
+use crate::{OffchainWorkerEnabled, OffchainWorkerTrigger as TriggerArg};
 
 
 /// Offchain worker related parameters.
@@ -55,6 +56,48 @@ pub struct OffchainWorkerParams {
 		value_name = "ENABLE_OFFCHAIN_INDEXING"
 	)]
 	pub indexing_enabled: bool,
+
+	/// HTTP proxy the HTTP requests of the offchain workers go through.
+	///
+	/// For example `http://127.0.0.1:3128`. HTTPS requests are tunneled through the proxy.
+	#[structopt(long = "offchain-http-proxy", value_name = "URI")]
+	pub http_proxy: Option<String>,
+
+	/// PEM file containing additional root certificates trusted by the HTTP client of the
+	/// offchain workers.
+	///
+	/// The certificates of the operating system are always trusted. Can be passed multiple times.
+	#[structopt(long = "offchain-http-ca-certificate", value_name = "PATH", parse(from_os_str))]
+	pub http_ca_certificates: Vec<PathBuf>,
+
+	/// Blocks the offchain workers are run on: every new best block, or every finalized block.
+	#[structopt(
+		long = "offchain-worker-trigger",
+		value_name = "TRIGGER",
+		possible_values = &TriggerArg::variants(),
+		case_insensitive = true,
+		default_value = "Best"
+	)]
+	pub trigger: TriggerArg,
+
+	/// Don't run the offchain workers while the node is catching up with the network.
+	///
+	/// By default they are run on every block, even while syncing.
+	#[structopt(long = "offchain-worker-skip-during-sync")]
+	pub skip_during_sync: bool,
+
+	/// Maximum number of offchain workers running at the same time.
+	///
+	/// Blocks imported while that many workers are running are skipped. Unlimited by default.
+	#[structopt(long = "offchain-worker-max-concurrent", value_name = "COUNT")]
+	pub max_concurrent: Option<usize>,
+
+	/// Maximum duration of the offchain workers of a block, in seconds.
+	///
+	/// Once it has elapsed, the HTTP requests of the workers are aborted and their calls to the
+	/// offchain API stop blocking.
+	#[structopt(long = "offchain-worker-timeout", value_name = "SECONDS")]
+	pub timeout: Option<u64>,
 }
 
 impl OffchainWorkerParams {
@@ -73,6 +116,20 @@ impl OffchainWorkerParams {
 
 		let indexing_enabled = enabled && self.indexing_enabled;
 
-		Ok(OffchainWorkerConfig { enabled, indexing_enabled })
+		Ok(OffchainWorkerConfig {
+			enabled,
+			indexing_enabled,
+			http_proxy: self.http_proxy.clone(),
+			http_ca_certificates: self.http_ca_certificates.clone(),
+			scheduling_policy: OffchainSchedulingPolicy {
+				trigger: match self.trigger {
+					TriggerArg::Best => OffchainWorkerTrigger::NewBest,
+					TriggerArg::Finalized => OffchainWorkerTrigger::Finalized,
+				},
+				skip_while_major_syncing: self.skip_during_sync,
+				max_concurrent_workers: self.max_concurrent,
+				timeout: self.timeout.map(Duration::from_secs),
+			},
+		})
 	}
 }
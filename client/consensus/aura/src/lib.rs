@@ -37,7 +37,7 @@ use std::{
 
 use futures::prelude::*;
 use parking_lot::Mutex;
-use log::{debug, info, trace};
+use log::{debug, info, trace, warn};
 use prometheus_endpoint::Registry;
 
 use codec::{Encode, Decode, Codec};
@@ -73,7 +73,7 @@ use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_INFO};
 
 use sc_consensus_slots::{
 	CheckedHeader, SlotInfo, SlotCompatible, StorageChanges, check_equivocation,
-	BackoffAuthoringBlocksStrategy,
+	BackoffAuthoringBlocksStrategy, PreVerifiedHeaders,
 };
 
 use sp_api::ApiExt;
@@ -437,17 +437,19 @@ fn find_pre_digest<B: BlockT, P: Pair>(header: &B::Header) -> Result<u64, Error<
 /// if it's successful, returns the pre-header and the digest item containing the seal.
 ///
 /// This digest item will always return `Some` when used with `as_aura_seal`.
+///
+/// The signature is not checked again if the header was already found to be signed by
+/// `pre_verified_author` and that is the expected author.
 //
-fn check_header<C, B: BlockT, P: Pair>(
-	client: &C,
+fn check_header<B: BlockT, P: Pair>(
 	slot_now: u64,
 	mut header: B::Header,
 	hash: B::Hash,
 	authorities: &[AuthorityId<P>],
+	pre_verified_author: Option<&AuthorityId<P>>,
 ) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, Error<B>> where
 	DigestItemFor<B>: CompatibleDigestItem<P>,
 	P::Signature: Decode,
-	C: sc_client_api::backend::AuxStore,
 	P::Public: Encode + Decode + PartialEq + Clone,
 {
 	let seal = match header.digest_mut().pop() {
@@ -474,22 +476,9 @@ fn check_header<C, B: BlockT, P: Pair>(
 
 		let pre_hash = header.hash();
 
-		if P::verify(&sig, pre_hash.as_ref(), expected_author) {
-			if let Some(equivocation_proof) = check_equivocation(
-				client,
-				slot_now,
-				slot_num,
-				&header,
-				expected_author,
-			).map_err(Error::Client)? {
-				info!(
-					"Slot author is equivocating at slot {} with headers {:?} and {:?}",
-					slot_num,
-					equivocation_proof.first_header.hash(),
-					equivocation_proof.second_header.hash(),
-				);
-			}
-
+		if pre_verified_author == Some(expected_author)
+			|| P::verify(&sig, pre_hash.as_ref(), expected_author)
+		{
 			Ok(CheckedHeader::Checked(header, (slot_num, seal)))
 		} else {
 			Err(Error::BadSignature(hash))
@@ -498,17 +487,123 @@ fn check_header<C, B: BlockT, P: Pair>(
 }
 
 /// A verifier for Aura blocks.
-pub struct AuraVerifier<C, P, CAW> {
+pub struct AuraVerifier<C, P: Pair, CAW> {
 	client: Arc<C>,
 	phantom: PhantomData<P>,
 	inherent_data_providers: sp_inherents::InherentDataProviders,
 	can_author_with: CAW,
+	/// Authors whose signature was checked ahead of import, by encoded header hash.
+	pre_verified_headers: PreVerifiedHeaders<Vec<u8>, AuthorityId<P>>,
+}
+
+impl<C, P: Pair, CAW: Clone> Clone for AuraVerifier<C, P, CAW> {
+	fn clone(&self) -> Self {
+		AuraVerifier {
+			client: self.client.clone(),
+			phantom: PhantomData,
+			inherent_data_providers: self.inherent_data_providers.clone(),
+			can_author_with: self.can_author_with.clone(),
+			pre_verified_headers: self.pre_verified_headers.clone(),
+		}
+	}
 }
 
-impl<C, P, CAW> AuraVerifier<C, P, CAW> where
+impl<C, P: Pair, CAW> AuraVerifier<C, P, CAW> where
 	P: Send + Sync + 'static,
 	CAW: Send + Sync + 'static,
 {
+	fn check_and_report_equivocation<B: BlockT>(
+		&self,
+		slot_now: u64,
+		slot: u64,
+		header: &B::Header,
+		author: &AuthorityId<P>,
+		origin: &BlockOrigin,
+	) -> Result<(), Error<B>> where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B> + AuxStore,
+		C::Api: AuraApi<B, AuthorityId<P>, Error = sp_blockchain::Error>,
+		P: Pair,
+		P::Public: Clone + Encode + Decode + Debug,
+	{
+		// don't report any equivocations during initial sync
+		// as they are most likely stale.
+		if *origin == BlockOrigin::NetworkInitialSync {
+			return Ok(());
+		}
+
+		// check if authorship of this header is an equivocation and return a proof if so.
+		let equivocation_proof =
+			match check_equivocation(&*self.client, slot_now, slot, header, author)
+				.map_err(Error::Client)?
+			{
+				Some(proof) => proof,
+				None => return Ok(()),
+			};
+
+		info!(
+			"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
+			author,
+			slot,
+			equivocation_proof.first_header.hash(),
+			equivocation_proof.second_header.hash(),
+		);
+
+		// get the best block on which we will build and send the equivocation report.
+		let best_id = BlockId::Hash(self.client.info().best_hash);
+
+		// runtimes without equivocation reporting don't expose the required methods.
+		let reporting_supported = self.client
+			.runtime_api()
+			.has_api_with::<dyn AuraApi<B, AuthorityId<P>, Error = ()>, _>(&best_id, |v| v >= 2)
+			.map_err(Error::Client)?;
+
+		if !reporting_supported {
+			debug!(target: "aura", "Runtime does not support equivocation reporting.");
+			return Ok(());
+		}
+
+		// generate a key ownership proof. we start by trying to generate the
+		// key owernship proof at the parent of the equivocating header, this
+		// will make sure that proof generation is successful since it happens
+		// during the on-going session (i.e. session keys are available in the
+		// state to be able to generate the proof). this might fail if the
+		// equivocation happens on the first block of the session, in which case
+		// its parent would be on the previous session. if generation on the
+		// parent header fails we try with best block as well.
+		let generate_key_owner_proof = |block_id: &BlockId<B>| {
+			self.client
+				.runtime_api()
+				.generate_key_ownership_proof(block_id, slot, equivocation_proof.offender.clone())
+				.map_err(Error::Client)
+		};
+
+		let parent_id = BlockId::Hash(*header.parent_hash());
+		let key_owner_proof = match generate_key_owner_proof(&parent_id)? {
+			Some(proof) => proof,
+			None => match generate_key_owner_proof(&best_id)? {
+				Some(proof) => proof,
+				None => {
+					debug!(target: "aura", "Equivocation offender is not part of the authority set.");
+					return Ok(());
+				}
+			},
+		};
+
+		// submit equivocation report at best block.
+		self.client
+			.runtime_api()
+			.submit_report_equivocation_unsigned_extrinsic(
+				&best_id,
+				equivocation_proof,
+				key_owner_proof,
+			)
+			.map_err(Error::Client)?;
+
+		info!(target: "aura", "Submitted equivocation report for author {:?}", author);
+
+		Ok(())
+	}
+
 	fn check_inherents<B: BlockT>(
 		&self,
 		block: B,
@@ -578,7 +673,8 @@ impl<B: BlockT, C, P, CAW> Verifier<B> for AuraVerifier<C, P, CAW> where
 		Sync +
 		sc_client_api::backend::AuxStore +
 		ProvideCache<B> +
-		BlockOf,
+		BlockOf +
+		HeaderBackend<B>,
 	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>> + ApiExt<B, Error = sp_blockchain::Error>,
 	DigestItemFor<B>: CompatibleDigestItem<P>,
 	P: Pair + Send + Sync + 'static,
@@ -606,15 +702,32 @@ impl<B: BlockT, C, P, CAW> Verifier<B> for AuraVerifier<C, P, CAW> where
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
 		// headers
-		let checked_header = check_header::<C, B, P>(
-			&self.client,
+		let pre_verified_author = self.pre_verified_headers.take(&hash.as_ref().to_vec());
+		let checked_header = check_header::<B, P>(
 			slot_now + 1,
 			header,
 			hash,
 			&authorities[..],
+			pre_verified_author.as_ref(),
 		).map_err(|e| e.to_string())?;
 		match checked_header {
 			CheckedHeader::Checked(pre_header, (slot_num, seal)) => {
+				// the header is valid but let's check if there was something else already
+				// proposed at the same slot by the given author. if there was, we will
+				// report the equivocation to the runtime.
+				let author = slot_author::<P>(slot_num, &authorities)
+					.expect("author was checked by `check_header`; qed");
+
+				if let Err(err) = self.check_and_report_equivocation::<B>(
+					slot_now + 1,
+					slot_num,
+					&pre_header,
+					author,
+					&origin,
+				) {
+					warn!(target: "aura", "Error checking/reporting Aura equivocation: {:?}", err);
+				}
+
 				// if the body is passed through, we need to use the runtime
 				// to check that the internally-set timestamp in the inherents
 				// actually matches the slot set in the seal.
@@ -678,6 +791,29 @@ impl<B: BlockT, C, P, CAW> Verifier<B> for AuraVerifier<C, P, CAW> where
 			}
 		}
 	}
+
+	fn pre_verify(&self, header: &B::Header, ancestor: &B::Hash) {
+		// the authorities rarely change, check the signature against the ones of the ancestor.
+		// `verify` only relies on it if the actual parent expects the same author.
+		let authorities = match authorities(self.client.as_ref(), &BlockId::Hash(*ancestor)) {
+			Ok(authorities) => authorities,
+			Err(_) => return,
+		};
+
+		let hash = header.hash();
+		// the slot is checked against the current one in `verify`.
+		if let Ok(CheckedHeader::Checked(_, (slot_num, _))) = check_header::<B, P>(
+			u64::max_value(),
+			header.clone(),
+			hash,
+			&authorities[..],
+			None,
+		) {
+			let author = slot_author::<P>(slot_num, &authorities)
+				.expect("author was checked by `check_header`; qed");
+			self.pre_verified_headers.insert(hash.as_ref().to_vec(), author.clone());
+		}
+	}
 }
 
 fn initialize_authorities_cache<A, B, C>(client: &C) -> Result<(), ConsensusError> where
@@ -851,7 +987,7 @@ pub fn import_queue<B, I, C, P, S, CAW>(
 	P::Public: Clone + Eq + Send + Sync + Hash + Debug + Encode + Decode,
 	P::Signature: Encode + Decode,
 	S: sp_core::traits::SpawnNamed,
-	CAW: CanAuthorWith<B> + Send + Sync + 'static,
+	CAW: CanAuthorWith<B> + Clone + Send + Sync + 'static,
 {
 	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
 	initialize_authorities_cache(&*client)?;
@@ -861,14 +997,16 @@ pub fn import_queue<B, I, C, P, S, CAW>(
 		inherent_data_providers,
 		phantom: PhantomData,
 		can_author_with,
+		pre_verified_headers: Default::default(),
 	};
 
-	Ok(BasicQueue::new(
+	Ok(BasicQueue::with_parallel_verification(
 		verifier,
 		Box::new(block_import),
 		justification_import,
 		spawner,
 		registry,
+		num_cpus::get(),
 	))
 }
 
@@ -968,6 +1106,7 @@ mod tests {
 						inherent_data_providers,
 						phantom: Default::default(),
 						can_author_with: AlwaysCanAuthor,
+						pre_verified_headers: Default::default(),
 					}
 				},
 				PeersClient::Light(_, _) => unreachable!("No (yet) tests for light client + Aura"),
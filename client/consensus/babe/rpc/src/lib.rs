@@ -38,7 +38,7 @@ use sp_core::{
 use sp_application_crypto::AppKey;
 use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
 use sc_rpc_api::DenyUnsafe;
-use sp_api::{ProvideRuntimeApi, BlockId};
+use sp_api::ProvideRuntimeApi;
 use sp_runtime::traits::{Block as BlockT, Header as _};
 use sp_consensus::{SelectChain, Error as ConsensusError};
 use sp_blockchain::{HeaderBackend, HeaderMetadata, Error as BlockChainError};
@@ -118,13 +118,8 @@ impl<B, C, SC> BabeApi for BabeRpcHandler<B, C, SC>
 			self.select_chain.clone(),
 		);
 		let future = async move {
-			let header = select_chain.best_chain().map_err(Error::Consensus)?;
-			let epoch_start = client.runtime_api()
-				.current_epoch_start(&BlockId::Hash(header.hash()))
-				.map_err(|err| {
-					Error::StringError(format!("{:?}", err))
-				})?;
-			let epoch = epoch_data(&shared_epoch, &client, &babe_config, epoch_start, &select_chain)?;
+			let slot_number = current_slot(&babe_config);
+			let epoch = epoch_data(&shared_epoch, &client, &babe_config, slot_number, &select_chain)?;
 			let (epoch_start, epoch_end) = (epoch.start_slot(), epoch.end_slot());
 
 			let mut claims: HashMap<AuthorityId, EpochAuthorship> = HashMap::new();
@@ -197,7 +192,16 @@ impl From<Error> for jsonrpc_core::Error {
 	}
 }
 
+/// Returns the slot number of the current time.
+fn current_slot(babe_config: &Config) -> u64 {
+	let slot_duration = babe_config.slot_duration().max(1);
+	sc_consensus_slots::duration_now().as_millis() as u64 / slot_duration
+}
+
 /// fetches the epoch data for a given slot_number.
+///
+/// Like block authoring, the epoch is moved forward to contain `slot_number` when no blocks
+/// were authored for one or more whole epochs.
 fn epoch_data<B, C, SC>(
 	epoch_changes: &SharedEpochChanges<B, Epoch>,
 	client: &Arc<C>,
@@ -211,14 +215,18 @@ fn epoch_data<B, C, SC>(
 		SC: SelectChain<B>,
 {
 	let parent = select_chain.best_chain()?;
-	epoch_changes.lock().epoch_data_for_child_of(
+	let epoch_changes = epoch_changes.lock();
+	let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
 		descendent_query(&**client),
 		&parent.hash(),
 		parent.number().clone(),
 		slot_number,
-		|slot| Epoch::genesis(&babe_config, slot),
 	)
 		.map_err(|e| Error::Consensus(ConsensusError::ChainLookup(format!("{:?}", e))))?
+		.ok_or(Error::Consensus(ConsensusError::InvalidAuthoritiesSet))?;
+
+	epoch_changes.viable_epoch(&epoch_descriptor, |slot| Epoch::genesis(&babe_config, slot))
+		.map(|epoch| epoch.as_ref().clone_for_slot(slot_number))
 		.ok_or(Error::Consensus(ConsensusError::InvalidAuthoritiesSet))
 }
 
@@ -289,9 +297,24 @@ mod tests {
 
 		io.extend_with(BabeApi::to_delegate(handler));
 		let request = r#"{"jsonrpc":"2.0","method":"babe_epochAuthorship","params": [],"id":1}"#;
-		let response = r#"{"jsonrpc":"2.0","result":{"5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY":{"primary":[0],"secondary":[1,2,4],"secondary_vrf":[]}},"id":1}"#;
 
-		assert_eq!(Some(response.into()), io.handle_request_sync(request));
+		let config = Config::get_or_compute(&TestClientBuilder::new().build()).unwrap();
+		let first_slot = current_slot(&config);
+		let response = io.handle_request_sync(request).unwrap();
+		let last_slot = current_slot(&config);
+
+		// no block was imported, so the genesis epoch starts at the slot of the request.
+		let mut response: serde_json::Value = serde_json::from_str(&response).unwrap();
+		let claims: HashMap<String, EpochAuthorship> =
+			serde_json::from_value(response["result"].take()).unwrap();
+		let alice = claims.get("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY")
+			.expect("Alice can claim slots in the current epoch");
+		let slots = alice.primary.iter().chain(&alice.secondary).chain(&alice.secondary_vrf);
+
+		assert!(alice.primary.len() + alice.secondary.len() + alice.secondary_vrf.len() > 0);
+		for slot in slots {
+			assert!(*slot >= first_slot && *slot < last_slot + config.epoch_length);
+		}
 	}
 
 	#[test]
@@ -114,7 +114,7 @@ use log::{debug, info, log, trace, warn};
 use prometheus_endpoint::Registry;
 use sc_consensus_slots::{
 	SlotInfo, SlotCompatible, StorageChanges, CheckedHeader, check_equivocation,
-	BackoffAuthoringBlocksStrategy,
+	BackoffAuthoringBlocksStrategy, PreVerifiedHeaders,
 };
 use sc_consensus_epochs::{
 	descendent_query, SharedEpochChanges, EpochChangesFor, Epoch as EpochT, ViableEpochDescriptor,
@@ -125,6 +125,7 @@ use sp_blockchain::{
 };
 use schnorrkel::SignatureError;
 use codec::{Encode, Decode};
+use serde::{Serialize, Deserialize};
 use sp_api::ApiExt;
 
 mod verification;
@@ -198,8 +199,61 @@ impl Epoch {
 			},
 		}
 	}
+
+	/// Clone the epoch, moving it forward so that it contains the given slot.
+	///
+	/// If no blocks were authored for one or more whole epochs, the latest announced
+	/// epoch data is reused for every later epoch. Only the epoch index and start slot
+	/// change, which must match the epoch index computed by the runtime.
+	pub fn clone_for_slot(&self, slot_number: SlotNumber) -> Epoch {
+		let mut epoch = self.clone();
+
+		let skipped_epochs = slot_number.saturating_sub(self.start_slot) / self.duration.max(1);
+		epoch.epoch_index = self.epoch_index
+			.checked_add(skipped_epochs)
+			.expect("epoch indices will never reach 2^64 before the death of the universe; qed");
+		epoch.start_slot = self.start_slot + skipped_epochs * self.duration;
+
+		epoch
+	}
 }
 
+/// Epoch data replacing the one announced at a given block.
+///
+/// Used to recover a chain whose announced authorities can no longer author blocks,
+/// usually provided through the chain spec.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EpochOverride<Hash, Number> {
+	/// Hash of the block announcing the epoch.
+	pub block_hash: Hash,
+	/// Number of the block announcing the epoch.
+	pub block_number: Number,
+	/// The authorities and their weights to use for the epoch.
+	pub authorities: Vec<(AuthorityId, BabeAuthorityWeight)>,
+	/// Randomness to use for the epoch, if it should not be the announced one.
+	pub randomness: Option<[u8; VRF_OUTPUT_LENGTH]>,
+}
+
+impl<Hash, Number> EpochOverride<Hash, Number> {
+	fn apply(&self, epoch: &mut Epoch) {
+		epoch.authorities = self.authorities.clone();
+		if let Some(randomness) = self.randomness {
+			epoch.randomness = randomness;
+		}
+	}
+
+	fn apply_to_descriptor(&self, descriptor: &mut NextEpochDescriptor) {
+		descriptor.authorities = self.authorities.clone();
+		if let Some(randomness) = self.randomness {
+			descriptor.randomness = randomness;
+		}
+	}
+}
+
+/// Epoch overrides, as they are provided by a chain spec extension.
+pub type EpochOverrides<Block> = Option<Vec<EpochOverride<<Block as BlockT>::Hash, NumberFor<Block>>>>;
+
 /// Errors encountered by the babe authorship task.
 #[derive(derive_more::Display, Debug)]
 pub enum Error<B: BlockT> {
@@ -578,10 +632,10 @@ where
 		debug!(target: "babe", "Attempting to claim slot {}", slot_number);
 		let s = authorship::claim_slot(
 			slot_number,
-			self.epoch_changes.lock().viable_epoch(
+			&self.epoch_changes.lock().viable_epoch(
 				&epoch_descriptor,
 				|slot| Epoch::genesis(&self.config, slot)
-			)?.as_ref(),
+			)?.as_ref().clone_for_slot(slot_number),
 			&self.keystore,
 		);
 
@@ -662,7 +716,7 @@ where
 			import_block.storage_changes = Some(storage_changes);
 			import_block.intermediates.insert(
 				Cow::from(INTERMEDIATE_KEY),
-				Box::new(BabeIntermediate::<B> { epoch_descriptor }) as Box<dyn Any>,
+				Box::new(BabeIntermediate::<B> { epoch_descriptor }) as Box<dyn Any + Send>,
 			);
 
 			Ok(import_block)
@@ -842,6 +896,28 @@ pub struct BabeVerifier<Block: BlockT, Client, SelectChain, CAW> {
 	epoch_changes: SharedEpochChanges<Block, Epoch>,
 	time_source: TimeSource,
 	can_author_with: CAW,
+	/// Headers checked ahead of import, along with the epoch they were checked against.
+	pre_verified_headers: PreVerifiedHeaders<
+		Block::Hash,
+		(Epoch, Block::Header, verification::VerifiedHeaderInfo<Block>),
+	>,
+}
+
+impl<Block: BlockT, Client, SelectChain: Clone, CAW: Clone> Clone
+	for BabeVerifier<Block, Client, SelectChain, CAW>
+{
+	fn clone(&self) -> Self {
+		BabeVerifier {
+			client: self.client.clone(),
+			select_chain: self.select_chain.clone(),
+			inherent_data_providers: self.inherent_data_providers.clone(),
+			config: self.config.clone(),
+			epoch_changes: self.epoch_changes.clone(),
+			time_source: self.time_source.clone(),
+			can_author_with: self.can_author_with.clone(),
+			pre_verified_headers: self.pre_verified_headers.clone(),
+		}
+	}
 }
 
 impl<Block, Client, SelectChain, CAW> BabeVerifier<Block, Client, SelectChain, CAW>
@@ -1018,21 +1094,31 @@ where
 		)
 			.map_err(|e| Error::<Block>::ForkTree(Box::new(e)))?
 			.ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash))?;
-		let viable_epoch = epoch_changes.viable_epoch(
+		let epoch = epoch_changes.viable_epoch(
 			&epoch_descriptor,
 			|slot| Epoch::genesis(&self.config, slot)
-		).ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash))?;
+		)
+			.ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash))?
+			.as_ref()
+			.clone_for_slot(pre_digest.slot_number());
 
 		// We add one to the current slot to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of headers
-		let v_params = verification::VerificationParams {
-			header: header.clone(),
-			pre_digest: Some(pre_digest),
-			slot_now: slot_now + 1,
-			epoch: viable_epoch.as_ref(),
+		let pre_verified = self.pre_verified_headers.take(&hash)
+			.filter(|(pre_verified_epoch, _, _)| {
+				*pre_verified_epoch == epoch && pre_digest.slot_number() <= slot_now + 1
+			});
+		let checked_header = match pre_verified {
+			Some((_, pre_header, verified_info)) => CheckedHeader::Checked(pre_header, verified_info),
+			None => verification::check_header::<Block>(verification::VerificationParams {
+				header: header.clone(),
+				pre_digest: Some(pre_digest),
+				slot_now: slot_now + 1,
+				epoch: &epoch,
+			})?,
 		};
 
-		match verification::check_header::<Block>(v_params)? {
+		match checked_header {
 			CheckedHeader::Checked(pre_header, verified_info) => {
 				let babe_pre_digest = verified_info.pre_digest.as_babe_pre_digest()
 					.expect("check_header always returns a pre-digest digest item; qed");
@@ -1080,7 +1166,7 @@ where
 				import_block.justification = justification;
 				import_block.intermediates.insert(
 					Cow::from(INTERMEDIATE_KEY),
-					Box::new(BabeIntermediate::<Block> { epoch_descriptor }) as Box<dyn Any>,
+					Box::new(BabeIntermediate::<Block> { epoch_descriptor }) as Box<dyn Any + Send>,
 				);
 				import_block.post_hash = Some(hash);
 
@@ -1095,6 +1181,51 @@ where
 			}
 		}
 	}
+
+	fn pre_verify(&self, header: &Block::Header, ancestor: &Block::Hash) {
+		let pre_digest = match find_pre_digest::<Block>(header) {
+			Ok(pre_digest) => pre_digest,
+			Err(_) => return,
+		};
+		let ancestor_number = match self.client.header_metadata(*ancestor) {
+			Ok(ancestor_header_metadata) => ancestor_header_metadata.number,
+			Err(_) => return,
+		};
+
+		// the epoch rarely changes within a batch, check the header against the epoch of a child
+		// of the ancestor. `verify` only relies on it if the actual parent leads to the same epoch.
+		let epoch = {
+			let epoch_changes = self.epoch_changes.lock();
+			let epoch_descriptor = match epoch_changes.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				ancestor,
+				ancestor_number,
+				pre_digest.slot_number(),
+			) {
+				Ok(Some(epoch_descriptor)) => epoch_descriptor,
+				_ => return,
+			};
+
+			match epoch_changes.viable_epoch(&epoch_descriptor, |slot| Epoch::genesis(&self.config, slot)) {
+				Some(epoch) => epoch.as_ref().clone_for_slot(pre_digest.slot_number()),
+				None => return,
+			}
+		};
+
+		// the slot is checked against the current one in `verify`.
+		let v_params = verification::VerificationParams {
+			header: header.clone(),
+			pre_digest: Some(pre_digest),
+			slot_now: SlotNumber::max_value(),
+			epoch: &epoch,
+		};
+
+		if let Ok(CheckedHeader::Checked(pre_header, verified_info)) =
+			verification::check_header::<Block>(v_params)
+		{
+			self.pre_verified_headers.insert(header.hash(), (epoch, pre_header, verified_info));
+		}
+	}
 }
 
 /// Register the babe inherent data provider, if not registered already.
@@ -1126,6 +1257,7 @@ pub struct BabeBlockImport<Block: BlockT, Client, I> {
 	client: Arc<Client>,
 	epoch_changes: SharedEpochChanges<Block, Epoch>,
 	config: Config,
+	epoch_overrides: Arc<HashMap<Block::Hash, EpochOverride<Block::Hash, NumberFor<Block>>>>,
 }
 
 impl<Block: BlockT, I: Clone, Client> Clone for BabeBlockImport<Block, Client, I> {
@@ -1135,6 +1267,7 @@ impl<Block: BlockT, I: Clone, Client> Clone for BabeBlockImport<Block, Client, I
 			client: self.client.clone(),
 			epoch_changes: self.epoch_changes.clone(),
 			config: self.config.clone(),
+			epoch_overrides: self.epoch_overrides.clone(),
 		}
 	}
 }
@@ -1145,12 +1278,38 @@ impl<Block: BlockT, Client, I> BabeBlockImport<Block, Client, I> {
 		epoch_changes: SharedEpochChanges<Block, Epoch>,
 		block_import: I,
 		config: Config,
+		epoch_overrides: Vec<EpochOverride<Block::Hash, NumberFor<Block>>>,
 	) -> Self {
+		// apply the overrides to any epoch that was already announced, the remaining
+		// ones are applied when importing the block announcing the epoch.
+		{
+			let mut epoch_changes = epoch_changes.lock();
+			for epoch_override in &epoch_overrides {
+				if let Some(epoch) = epoch_changes.signaled_epoch_mut(
+					&epoch_override.block_hash,
+					epoch_override.block_number,
+				) {
+					info!(target: "babe",
+						"🔧 Overriding epoch {} announced at block {}",
+						epoch.epoch_index,
+						epoch_override.block_hash,
+					);
+					epoch_override.apply(epoch);
+				}
+			}
+		}
+
+		let epoch_overrides = epoch_overrides
+			.into_iter()
+			.map(|epoch_override| (epoch_override.block_hash, epoch_override))
+			.collect();
+
 		BabeBlockImport {
 			client,
 			inner: block_import,
 			epoch_changes,
 			config,
+			epoch_overrides: Arc::new(epoch_overrides),
 		}
 	}
 }
@@ -1275,15 +1434,20 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 
 		let info = self.client.info();
 
-		if let Some(next_epoch_descriptor) = next_epoch_digest {
+		if let Some(mut next_epoch_descriptor) = next_epoch_digest {
 			old_epoch_changes = Some(epoch_changes.clone());
 
-			let viable_epoch = epoch_changes.viable_epoch(
+			if let Some(epoch_override) = self.epoch_overrides.get(&hash) {
+				info!(target: "babe", "🔧 Overriding epoch announced at block {}", hash);
+				epoch_override.apply_to_descriptor(&mut next_epoch_descriptor);
+			}
+
+			let mut viable_epoch = epoch_changes.viable_epoch(
 				&epoch_descriptor,
 				|slot| Epoch::genesis(&self.config, slot)
 			).ok_or_else(|| {
 				ConsensusError::ClientImport(Error::<Block>::FetchEpoch(parent_hash).into())
-			})?;
+			})?.into_cloned();
 
 			let epoch_config = next_config_digest.map(Into::into).unwrap_or_else(
 				|| viable_epoch.as_ref().config.clone()
@@ -1296,6 +1460,23 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 				log::Level::Info
 			};
 
+			if viable_epoch.as_ref().end_slot() <= slot_number {
+				// no blocks were authored for one or more whole epochs, so the block's
+				// slot lies beyond the epoch that was announced. we only move our local
+				// copy forward, so that the next epoch is imported with the right index
+				// and start slot. the epoch in the tree is left untouched since other
+				// forks may still be building on it, and the tree lookups only require
+				// `start_slot <= slot`.
+				let epoch = viable_epoch.as_ref().clone_for_slot(slot_number);
+				warn!(target: "babe",
+					"⏩ Skipping {} epochs, resuming from epoch {} at slot {}",
+					epoch.epoch_index - viable_epoch.as_ref().epoch_index,
+					epoch.epoch_index,
+					epoch.start_slot,
+				);
+				*viable_epoch.as_mut() = epoch;
+			}
+
 			log!(target: "babe",
 				log_level,
 				"👶 New epoch {} launching at block {} (block slot {} >= start slot {}).",
@@ -1450,6 +1631,20 @@ pub fn block_import<Client, Block: BlockT, I>(
 	client: Arc<Client>,
 ) -> ClientResult<(BabeBlockImport<Block, Client, I>, BabeLink<Block>)> where
 	Client: AuxStore + HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
+{
+	block_import_with_epoch_overrides(config, wrapped_block_import, client, Vec::new())
+}
+
+/// Like `block_import`, but the epoch data announced at the blocks given by the
+/// overrides is replaced with the given authorities and randomness. This applies both
+/// to epochs that were already imported and to blocks imported later on.
+pub fn block_import_with_epoch_overrides<Client, Block: BlockT, I>(
+	config: Config,
+	wrapped_block_import: I,
+	client: Arc<Client>,
+	epoch_overrides: Vec<EpochOverride<Block::Hash, NumberFor<Block>>>,
+) -> ClientResult<(BabeBlockImport<Block, Client, I>, BabeLink<Block>)> where
+	Client: AuxStore + HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
 {
 	let epoch_changes = aux_schema::load_epoch_changes::<Block, _>(&*client, &config)?;
 	let link = BabeLink {
@@ -1471,6 +1666,7 @@ pub fn block_import<Client, Block: BlockT, I>(
 		epoch_changes,
 		wrapped_block_import,
 		config,
+		epoch_overrides,
 	);
 
 	Ok((import, link))
@@ -1502,7 +1698,7 @@ pub fn import_queue<Block: BlockT, Client, SelectChain, Inner, CAW>(
 	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
 	Client::Api: BlockBuilderApi<Block> + BabeApi<Block> + ApiExt<Block, Error = sp_blockchain::Error>,
 	SelectChain: sp_consensus::SelectChain<Block> + 'static,
-	CAW: CanAuthorWith<Block> + Send + Sync + 'static,
+	CAW: CanAuthorWith<Block> + Clone + Send + Sync + 'static,
 {
 	register_babe_inherent_data_provider(&inherent_data_providers, babe_link.config.slot_duration)?;
 
@@ -1514,14 +1710,16 @@ pub fn import_queue<Block: BlockT, Client, SelectChain, Inner, CAW>(
 		epoch_changes: babe_link.epoch_changes,
 		time_source: babe_link.time_source,
 		can_author_with,
+		pre_verified_headers: Default::default(),
 	};
 
-	Ok(BasicQueue::new(
+	Ok(BasicQueue::with_parallel_verification(
 		verifier,
 		Box::new(block_import),
 		justification_import,
 		spawner,
 		registry,
+		num_cpus::get(),
 	))
 }
 
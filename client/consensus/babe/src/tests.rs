@@ -326,6 +326,7 @@ impl TestNetFactory for BabeTestNet {
 				epoch_changes: data.link.epoch_changes.clone(),
 				time_source: data.link.time_source.clone(),
 				can_author_with: AlwaysCanAuthor,
+				pre_verified_headers: Default::default(),
 			},
 			mutator: MUTATOR.with(|m| m.borrow().clone()),
 		}
@@ -569,13 +570,13 @@ fn can_author_block() {
 	}
 }
 
-// Propose and import a new BABE block on top of the given parent.
-fn propose_and_import_block<Transaction>(
+// Propose a new BABE block on top of the given parent, returning the import parameters
+// and the post hash of the block.
+fn propose_block<Transaction>(
 	parent: &TestHeader,
 	slot_number: Option<SlotNumber>,
 	proposer_factory: &mut DummyFactory,
-	block_import: &mut BoxBlockImport<TestBlock, Transaction>,
-) -> sp_core::H256 {
+) -> (BlockImportParams<TestBlock, Transaction>, sp_core::H256) {
 	let mut proposer = futures::executor::block_on(proposer_factory.init(parent)).unwrap();
 
 	let slot_number = slot_number.unwrap_or_else(|| {
@@ -626,9 +627,21 @@ fn propose_and_import_block<Transaction>(
 	import.body = Some(block.extrinsics);
 	import.intermediates.insert(
 		Cow::from(INTERMEDIATE_KEY),
-		Box::new(BabeIntermediate::<TestBlock> { epoch_descriptor }) as Box<dyn Any>,
+		Box::new(BabeIntermediate::<TestBlock> { epoch_descriptor }) as Box<dyn Any + Send>,
 	);
 	import.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+
+	(import, post_hash)
+}
+
+// Propose and import a new BABE block on top of the given parent.
+fn propose_and_import_block<Transaction>(
+	parent: &TestHeader,
+	slot_number: Option<SlotNumber>,
+	proposer_factory: &mut DummyFactory,
+	block_import: &mut BoxBlockImport<TestBlock, Transaction>,
+) -> sp_core::H256 {
+	let (import, post_hash) = propose_block(parent, slot_number, proposer_factory);
 	let import_result = block_import.import_block(import, Default::default()).unwrap();
 
 	match import_result {
@@ -786,6 +799,231 @@ fn importing_epoch_change_block_prunes_tree() {
 	);
 }
 
+#[test]
+fn importing_blocks_after_skipped_epochs() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_full().expect("Only full clients are used in tests").clone();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+	let epoch_changes = data.link.epoch_changes.clone();
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	let mut propose_and_import = |parent_hash, slot_number| {
+		let parent_header = client.header(&BlockId::Hash(parent_hash)).unwrap().unwrap();
+		propose_and_import_block(
+			&parent_header,
+			Some(slot_number),
+			&mut proposer_factory,
+			&mut block_import,
+		)
+	};
+
+	let epoch_for_child_of = |hash, number, slot_number| {
+		epoch_changes.lock().epoch_data_for_child_of(
+			descendent_query(&*client),
+			&hash,
+			number,
+			slot_number,
+			|slot| Epoch::genesis(&data.link.config, slot),
+		).unwrap().unwrap()
+	};
+
+	// the epoch duration is 6 slots in the test runtime. block #1 starts epoch #0 at
+	// slot 1 and block #2 starts epoch #1 at slot 7, announcing epoch #2 at slot 13.
+	let genesis_hash = client.chain_info().genesis_hash;
+	let b1 = propose_and_import(genesis_hash, 1);
+	let b2 = propose_and_import(b1, 7);
+
+	let epoch_2 = epoch_for_child_of(b2, 2, 13);
+	assert_eq!((epoch_2.epoch_index, epoch_2.start_slot), (2, 13));
+
+	// the chain halts for several epochs and resumes at slot 40, in epoch #6.
+	let b3 = propose_and_import(b2, 40);
+
+	// the data announced for epoch #2 is reused for the skipped epochs, so that the
+	// block is verified against epoch #6 which starts at slot 37.
+	let epoch_6 = epoch_for_child_of(b2, 2, 40).clone_for_slot(40);
+	assert_eq!((epoch_6.epoch_index, epoch_6.start_slot), (6, 37));
+	assert_eq!(epoch_6.authorities, epoch_2.authorities);
+	assert_eq!(epoch_6.randomness, epoch_2.randomness);
+
+	// the epoch announced by block #3 follows the epoch it was authored in.
+	let epoch_7 = epoch_for_child_of(b3, 3, 43);
+	assert_eq!((epoch_7.epoch_index, epoch_7.start_slot), (7, 43));
+
+	// later blocks in epoch #6 are not epoch changes, while the next epoch change
+	// happens as expected.
+	let b4 = propose_and_import(b3, 41);
+	let b5 = propose_and_import(b4, 43);
+
+	let epoch_8 = epoch_for_child_of(b5, 5, 49);
+	assert_eq!((epoch_8.epoch_index, epoch_8.start_slot), (8, 49));
+}
+
+#[test]
+fn epoch_overrides_replace_announced_epochs() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_full().expect("Only full clients are used in tests").clone();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+	let epoch_changes = data.link.epoch_changes.clone();
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	// block #2 starts epoch #1 and announces epoch #2.
+	let b1 = propose_and_import_block(
+		&client.header(&BlockId::Number(0)).unwrap().unwrap(),
+		Some(1),
+		&mut proposer_factory,
+		&mut block_import,
+	);
+	let b2 = propose_and_import_block(
+		&client.header(&BlockId::Hash(b1)).unwrap().unwrap(),
+		Some(7),
+		&mut proposer_factory,
+		&mut block_import,
+	);
+
+	let epoch_for_child_of_b2 = || {
+		epoch_changes.lock().epoch_data_for_child_of(
+			descendent_query(&*client),
+			&b2,
+			2,
+			13,
+			|slot| Epoch::genesis(&data.link.config, slot),
+		).unwrap().unwrap()
+	};
+
+	let announced = epoch_for_child_of_b2();
+	let authorities = vec![(AuthorityPair::from_seed(&[2; 32]).public(), 1)];
+
+	let epoch_override = EpochOverride {
+		block_hash: b2,
+		block_number: 2,
+		authorities: authorities.clone(),
+		randomness: None,
+	};
+
+	let _ = BabeBlockImport::<TestBlock, _, _>::new(
+		client.clone(),
+		epoch_changes.clone(),
+		client.clone(),
+		data.link.config.clone(),
+		vec![epoch_override],
+	);
+
+	// only the authorities of the already announced epoch are replaced.
+	let overridden = epoch_for_child_of_b2();
+	assert_eq!(overridden.authorities, authorities);
+	assert_eq!(overridden.randomness, announced.randomness);
+	assert_eq!(
+		(overridden.epoch_index, overridden.start_slot),
+		(announced.epoch_index, announced.start_slot),
+	);
+}
+
+#[test]
+fn epoch_overrides_apply_to_imported_announcements() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_full().expect("Only full clients are used in tests").clone();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+	let epoch_changes = data.link.epoch_changes.clone();
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	let b1 = propose_and_import_block(
+		&client.header(&BlockId::Number(0)).unwrap().unwrap(),
+		Some(1),
+		&mut proposer_factory,
+		&mut block_import,
+	);
+
+	// block #2 starts epoch #1 and announces epoch #2, the override for it is known
+	// before the block is imported.
+	let (import, b2) = propose_block(
+		&client.header(&BlockId::Hash(b1)).unwrap().unwrap(),
+		Some(7),
+		&mut proposer_factory,
+	);
+
+	let authorities = vec![(AuthorityPair::from_seed(&[2; 32]).public(), 1)];
+	let randomness = [3; VRF_OUTPUT_LENGTH];
+
+	let epoch_override = EpochOverride {
+		block_hash: b2,
+		block_number: 2,
+		authorities: authorities.clone(),
+		randomness: Some(randomness),
+	};
+
+	let override_block_import = BabeBlockImport::<TestBlock, _, _>::new(
+		client.clone(),
+		epoch_changes.clone(),
+		client.clone(),
+		data.link.config.clone(),
+		vec![epoch_override],
+	);
+	let mut override_block_import =
+		Box::new(PanickingBlockImport(override_block_import)) as BoxBlockImport<_, _>;
+
+	match override_block_import.import_block(import, Default::default()).unwrap() {
+		ImportResult::Imported(_) => {},
+		_ => panic!("expected block to be imported"),
+	}
+
+	// the descriptor stored for epoch #2 carries the overridden data.
+	let epoch_2 = epoch_changes.lock().epoch_data_for_child_of(
+		descendent_query(&*client),
+		&b2,
+		2,
+		13,
+		|slot| Epoch::genesis(&data.link.config, slot),
+	).unwrap().unwrap();
+
+	assert_eq!((epoch_2.epoch_index, epoch_2.start_slot), (2, 13));
+	assert_eq!(epoch_2.authorities, authorities);
+	assert_eq!(epoch_2.randomness, randomness);
+
+	// and it is persisted, so that it survives a restart.
+	let persisted = aux_schema::load_epoch_changes::<TestBlock, _>(&*client, &data.link.config)
+		.unwrap();
+	let persisted_epoch_2 = persisted.lock().epoch_data_for_child_of(
+		descendent_query(&*client),
+		&b2,
+		2,
+		13,
+		|slot| Epoch::genesis(&data.link.config, slot),
+	).unwrap().unwrap();
+
+	assert_eq!(persisted_epoch_2, epoch_2);
+}
+
 #[test]
 #[should_panic]
 fn verify_slots_are_strictly_increasing() {
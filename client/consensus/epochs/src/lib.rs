@@ -473,6 +473,21 @@ impl<Hash, Number, E: Epoch> EpochChanges<Hash, Number, E> where
 			})
 	}
 
+	/// Get a mutable reference to the epoch signaled at the given block.
+	///
+	/// For the first block of the chain this is the epoch following the genesis epoch.
+	/// Only data which isn't part of the epoch header may be changed, as the fork tree
+	/// keeps its own copy of it.
+	pub fn signaled_epoch_mut(&mut self, hash: &Hash, number: Number) -> Option<&mut E> {
+		self.epochs.get_mut(&(*hash, number))
+			.map(|v| {
+				match v {
+					PersistedEpoch::Genesis(_, ref mut epoch_1) => epoch_1,
+					PersistedEpoch::Regular(ref mut epoch_n) => epoch_n,
+				}
+			})
+	}
+
 	/// Get a mutable reference to a viable epoch with given descriptor.
 	pub fn viable_epoch_mut<G>(
 		&mut self,
@@ -122,7 +122,7 @@ impl<B, C> BabeConsensusDataProvider<B, C>
 				sp_consensus::Error::InvalidAuthoritiesSet
 			})?;
 
-		Ok(epoch.as_ref().clone())
+		Ok(epoch.as_ref().clone_for_slot(slot_number))
 	}
 }
 
@@ -234,7 +234,7 @@ impl<B, C> ConsensusDataProvider<B> for BabeConsensusDataProvider<B, C>
 
 		params.intermediates.insert(
 			Cow::from(INTERMEDIATE_KEY),
-			Box::new(BabeIntermediate::<B> { epoch_descriptor }) as Box<dyn Any>,
+			Box::new(BabeIntermediate::<B> { epoch_descriptor }) as Box<dyn Any + Send>,
 		);
 
 		Ok(())
@@ -33,6 +33,8 @@ mod codes {
 	pub const CONSENSUS_ERROR: i64 = 14_000;
 	pub const INHERENTS_ERROR: i64 = 15_000;
 	pub const BLOCKCHAIN_ERROR: i64 = 16_000;
+	pub const INVALID_REVERT_TARGET: i64 = 17_000;
+	pub const INVALID_TIME_ADVANCE: i64 = 18_000;
 	pub const UNKNOWN_ERROR: i64 = 20_000;
 }
 
@@ -59,6 +61,14 @@ pub enum Error {
 	#[display(fmt = "Supplied parent_hash: {} doesn't exist in chain", _0)]
 	#[from(ignore)]
 	BlockNotFound(String),
+	/// Supplied block can't be reverted to
+	#[display(fmt = "Cannot revert to block: {}", _0)]
+	#[from(ignore)]
+	InvalidRevertTarget(String),
+	/// Supplied time advance doesn't fit the timestamp offset
+	#[display(fmt = "Cannot advance time by {} milliseconds", _0)]
+	#[from(ignore)]
+	InvalidTimeAdvance(u64),
 	/// Some string error
 	#[display(fmt = "{}", _0)]
 	#[from(ignore)]
@@ -84,6 +94,8 @@ impl Error {
 			ConsensusError(_) => codes::CONSENSUS_ERROR,
 			InherentError(_) => codes::INHERENTS_ERROR,
 			BlockchainError(_) => codes::BLOCKCHAIN_ERROR,
+			InvalidRevertTarget(_) => codes::INVALID_REVERT_TARGET,
+			InvalidTimeAdvance(_) => codes::INVALID_TIME_ADVANCE,
 			SendError(_) | Canceled(_) => codes::SERVER_SHUTTING_DOWN,
 			_ => codes::UNKNOWN_ERROR
 		}
@@ -28,14 +28,16 @@ use sp_consensus::{
 use sp_blockchain::HeaderBackend;
 use sp_inherents::InherentDataProviders;
 use sp_runtime::{traits::Block as BlockT, Justification};
-use sc_client_api::backend::{Backend as ClientBackend, Finalizer};
+use sc_client_api::backend::{Backend as ClientBackend, Finalizer, StateBackendFor};
 use sc_transaction_pool::txpool;
 use std::{sync::Arc, marker::PhantomData};
 use prometheus_endpoint::Registry;
 
 mod error;
 mod finalize_block;
+mod revert_block;
 mod seal_block;
+mod time_travel;
 
 pub mod consensus;
 pub mod rpc;
@@ -44,10 +46,13 @@ pub use self::{
 	error::Error,
 	consensus::ConsensusDataProvider,
 	finalize_block::{finalize_block, FinalizeBlockParams},
-	seal_block::{SealBlockParams, seal_block, MAX_PROPOSAL_DURATION},
+	revert_block::{revert_to, RevertToParams},
+	seal_block::{SealBlockParams, StorageOverrides, seal_block, MAX_PROPOSAL_DURATION},
+	time_travel::TimeTravel,
 	rpc::{EngineCommand, CreatedBlock},
 };
-use sp_api::{ProvideRuntimeApi, TransactionFor};
+use sp_api::{ApiExt, ProvideRuntimeApi, TransactionFor};
+use sp_core::storage::{StorageKey, StorageData};
 
 /// The verifier for the manual seal engine; instantly finalizes.
 struct ManualSealVerifier;
@@ -68,6 +73,10 @@ impl<B: BlockT> Verifier<B> for ManualSealVerifier {
 
 		Ok((import_params, None))
 	}
+
+	fn requires_imported_parent(&self) -> bool {
+		false
+	}
 }
 
 /// Instantiate the import queue for the manual seal consensus engine.
@@ -90,7 +99,7 @@ pub fn import_queue<Block, Transaction>(
 }
 
 /// Params required to start the instant sealing authorship task.
-pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool::ChainApi, SC, CS> {
+pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool::ChainApi, SC, CS, CB> {
 	/// Block import instance for well. importing blocks.
 	pub block_import: BI,
 
@@ -100,6 +109,9 @@ pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool
 	/// Client instance
 	pub client: Arc<C>,
 
+	/// Client backend, used to apply storage overrides and revert blocks.
+	pub backend: Arc<CB>,
+
 	/// Shared reference to the transaction pool.
 	pub pool: Arc<txpool::Pool<A>>,
 
@@ -118,7 +130,7 @@ pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool
 }
 
 /// Params required to start the manual sealing authorship task.
-pub struct InstantSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool::ChainApi, SC> {
+pub struct InstantSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpool::ChainApi, SC, CB> {
 	/// Block import instance for well. importing blocks.
 	pub block_import: BI,
 
@@ -128,6 +140,9 @@ pub struct InstantSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, A: txpoo
 	/// Client instance
 	pub client: Arc<C>,
 
+	/// Client backend.
+	pub backend: Arc<CB>,
+
 	/// Shared reference to the transaction pool.
 	pub pool: Arc<txpool::Pool<A>>,
 
@@ -147,13 +162,14 @@ pub async fn run_manual_seal<B, BI, CB, E, C, A, SC, CS>(
 		mut block_import,
 		mut env,
 		client,
+		backend,
 		pool,
 		mut commands_stream,
 		select_chain,
 		inherent_data_providers,
 		consensus_data_provider,
 		..
-	}: ManualSealParams<B, BI, E, C, A, SC, CS>
+	}: ManualSealParams<B, BI, E, C, A, SC, CS, CB>
 )
 	where
 		A: txpool::ChainApi<Block=B> + 'static,
@@ -161,6 +177,7 @@ pub async fn run_manual_seal<B, BI, CB, E, C, A, SC, CS>(
 		BI: BlockImport<B, Error = sp_consensus::Error, Transaction = sp_api::TransactionFor<C, B>>
 			+ Send + Sync + 'static,
 		C: HeaderBackend<B> + Finalizer<B, CB> + ProvideRuntimeApi<B> + 'static,
+		C::Api: ApiExt<B, StateBackend = StateBackendFor<CB, B>>,
 		CB: ClientBackend<B> + 'static,
 		E: Environment<B> + 'static,
 		E::Proposer: Proposer<B, Transaction = TransactionFor<C, B>>,
@@ -168,6 +185,9 @@ pub async fn run_manual_seal<B, BI, CB, E, C, A, SC, CS>(
 		SC: SelectChain<B> + 'static,
 		TransactionFor<C, B>: 'static,
 {
+	let mut time_travel = TimeTravel::default();
+	let mut storage_overrides = StorageOverrides::new();
+
 	while let Some(command) = commands_stream.next().await {
 		match command {
 			EngineCommand::SealNewBlock {
@@ -187,8 +207,11 @@ pub async fn run_manual_seal<B, BI, CB, E, C, A, SC, CS>(
 						block_import: &mut block_import,
 						inherent_data_provider: &inherent_data_providers,
 						consensus_data_provider: consensus_data_provider.as_ref().map(|p| &**p),
+						time_travel: &mut time_travel,
+						storage_overrides: &mut storage_overrides,
 						pool: pool.clone(),
 						client: client.clone(),
+						backend: backend.clone(),
 					}
 				).await;
 			}
@@ -203,6 +226,31 @@ pub async fn run_manual_seal<B, BI, CB, E, C, A, SC, CS>(
 					}
 				).await
 			}
+			EngineCommand::SetTimestamp { timestamp, mut sender } => {
+				time_travel.set_timestamp(timestamp);
+				rpc::send_result(&mut sender, Ok(()))
+			}
+			EngineCommand::AdvanceTime { millis, mut sender } => {
+				rpc::send_result(&mut sender, time_travel.advance(millis))
+			}
+			EngineCommand::SetStorage { key, value, mut sender } => {
+				let (key, value) = (StorageKey(key), value.map(StorageData));
+				match storage_overrides.iter_mut().find(|(k, _)| *k == key) {
+					Some(entry) => entry.1 = value,
+					None => storage_overrides.push((key, value)),
+				}
+				rpc::send_result(&mut sender, Ok(()))
+			}
+			EngineCommand::RevertTo { hash, sender } => {
+				revert_to(
+					RevertToParams {
+						hash,
+						sender,
+						client: client.clone(),
+						backend: backend.clone(),
+					}
+				).await
+			}
 		}
 	}
 }
@@ -215,12 +263,13 @@ pub async fn run_instant_seal<B, BI, CB, E, C, A, SC>(
 		block_import,
 		env,
 		client,
+		backend,
 		pool,
 		select_chain,
 		consensus_data_provider,
 		inherent_data_providers,
 		..
-	}: InstantSealParams<B, BI, E, C, A, SC>
+	}: InstantSealParams<B, BI, E, C, A, SC, CB>
 )
 	where
 		A: txpool::ChainApi<Block=B> + 'static,
@@ -228,6 +277,7 @@ pub async fn run_instant_seal<B, BI, CB, E, C, A, SC>(
 		BI: BlockImport<B, Error = sp_consensus::Error, Transaction = sp_api::TransactionFor<C, B>>
 			+ Send + Sync + 'static,
 		C: HeaderBackend<B> + Finalizer<B, CB> + ProvideRuntimeApi<B> + 'static,
+		C::Api: ApiExt<B, StateBackend = StateBackendFor<CB, B>>,
 		CB: ClientBackend<B> + 'static,
 		E: Environment<B> + 'static,
 		E::Proposer: Proposer<B, Transaction = TransactionFor<C, B>>,
@@ -252,6 +302,7 @@ pub async fn run_instant_seal<B, BI, CB, E, C, A, SC>(
 			block_import,
 			env,
 			client,
+			backend,
 			pool,
 			commands_stream,
 			select_chain,
@@ -277,7 +328,7 @@ mod tests {
 	use sp_consensus::ImportedAux;
 	use sp_inherents::InherentDataProviders;
 	use sc_basic_authorship::ProposerFactory;
-	use sc_client_api::BlockBackend;
+	use sc_client_api::{BlockBackend, StorageProvider};
 
 	fn api() -> Arc<TestApi> {
 		Arc::new(TestApi::empty())
@@ -288,6 +339,7 @@ mod tests {
 	#[tokio::test]
 	async fn instant_seal() {
 		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
 		let (client, select_chain) = builder.build_with_longest_chain();
 		let client = Arc::new(client);
 		let inherent_data_providers = InherentDataProviders::new();
@@ -321,6 +373,7 @@ mod tests {
 				block_import: client.clone(),
 				env,
 				client: client.clone(),
+				backend,
 				pool: pool.pool().clone(),
 				commands_stream,
 				select_chain,
@@ -359,6 +412,7 @@ mod tests {
 	#[tokio::test]
 	async fn manual_seal_and_finalization() {
 		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
 		let (client, select_chain) = builder.build_with_longest_chain();
 		let client = Arc::new(client);
 		let inherent_data_providers = InherentDataProviders::new();
@@ -379,6 +433,7 @@ mod tests {
 				block_import: client.clone(),
 				env,
 				client: client.clone(),
+				backend,
 				pool: pool.pool().clone(),
 				commands_stream,
 				select_chain,
@@ -433,6 +488,7 @@ mod tests {
 	#[tokio::test]
 	async fn manual_seal_fork_blocks() {
 		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
 		let (client, select_chain) = builder.build_with_longest_chain();
 		let client = Arc::new(client);
 		let inherent_data_providers = InherentDataProviders::new();
@@ -454,6 +510,7 @@ mod tests {
 				block_import: client.clone(),
 				env,
 				client: client.clone(),
+				backend,
 				pool: pool.pool().clone(),
 				commands_stream,
 				select_chain,
@@ -532,4 +589,124 @@ mod tests {
 		// assert that fork block is in the db
 		assert!(client.header(&BlockId::Hash(imported.hash)).unwrap().is_some())
 	}
+
+	#[tokio::test]
+	async fn manual_seal_storage_overrides_and_revert() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let inherent_data_providers = InherentDataProviders::new();
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(), api(), None, RevalidationType::Full, spawner.clone(),
+		));
+		let env = ProposerFactory::new(
+			spawner.clone(),
+			client.clone(),
+			pool.clone(),
+			None,
+		);
+		let (mut sink, commands_stream) = futures::channel::mpsc::channel(1024);
+		let future = run_manual_seal(
+			ManualSealParams {
+				block_import: client.clone(),
+				env,
+				client: client.clone(),
+				backend,
+				pool: pool.pool().clone(),
+				commands_stream,
+				select_chain,
+				consensus_data_provider: None,
+				inherent_data_providers,
+			}
+		);
+		std::thread::spawn(|| {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			// spawn the background authorship task
+			rt.block_on(future);
+		});
+
+		let key = StorageKey(b"manual_seal_override".to_vec());
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SetStorage {
+			key: key.0.clone(),
+			value: Some(vec![42]),
+			sender: Some(tx),
+		}).await.unwrap();
+		assert_eq!(rx.await.unwrap().unwrap(), ());
+
+		let mut hashes = Vec::new();
+		for _ in 0..3 {
+			let (tx, rx) = futures::channel::oneshot::channel();
+			sink.send(EngineCommand::SealNewBlock {
+				parent_hash: None,
+				sender: Some(tx),
+				create_empty: true,
+				finalize: false,
+			}).await.unwrap();
+			hashes.push(rx.await.unwrap().unwrap().hash);
+		}
+
+		// the override is part of the first block's state, and kept afterwards.
+		assert_eq!(client.storage(&BlockId::Number(0), &key).unwrap(), None);
+		assert_eq!(client.storage(&BlockId::Hash(hashes[0]), &key).unwrap(), Some(StorageData(vec![42])));
+		assert_eq!(client.storage(&BlockId::Hash(hashes[2]), &key).unwrap(), Some(StorageData(vec![42])));
+
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::RevertTo { hash: hashes[0], sender: Some(tx) }).await.unwrap();
+		assert_eq!(rx.await.unwrap().unwrap(), 2);
+		assert_eq!(client.info().best_hash, hashes[0]);
+
+		// blocks on top of the reverted chain get the same number again.
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::SealNewBlock {
+			parent_hash: None,
+			sender: Some(tx),
+			create_empty: true,
+			finalize: true,
+		}).await.unwrap();
+		let created_block = rx.await.unwrap().unwrap();
+		assert_eq!(client.info().best_number, 2);
+		assert_eq!(client.info().best_hash, created_block.hash);
+
+		// finalized blocks can't be reverted.
+		let (tx, rx) = futures::channel::oneshot::channel();
+		sink.send(EngineCommand::RevertTo { hash: hashes[0], sender: Some(tx) }).await.unwrap();
+		assert_matches::assert_matches!(rx.await.unwrap(), Err(Error::InvalidRevertTarget(_)));
+	}
+
+	#[test]
+	fn time_travel_shifts_timestamp_inherent() {
+		use sp_timestamp::INHERENT_IDENTIFIER;
+
+		let mut time_travel = TimeTravel::default();
+		assert!(time_travel.is_idle());
+
+		let mut inherent_data = sp_inherents::InherentData::new();
+		inherent_data.put_data(INHERENT_IDENTIFIER, &1_000u64).unwrap();
+
+		time_travel.set_timestamp(5_000);
+		assert_eq!(time_travel.apply(&mut inherent_data).unwrap(), 5_000);
+		assert_eq!(inherent_data.get_data::<u64>(&INHERENT_IDENTIFIER).unwrap(), Some(5_000));
+
+		// the offset is kept for the following blocks.
+		let mut inherent_data = sp_inherents::InherentData::new();
+		inherent_data.put_data(INHERENT_IDENTIFIER, &2_000u64).unwrap();
+		assert_eq!(time_travel.apply(&mut inherent_data).unwrap(), 6_000);
+
+		assert_eq!(time_travel.advance(1_000).unwrap(), 5_000);
+		let mut inherent_data = sp_inherents::InherentData::new();
+		inherent_data.put_data(INHERENT_IDENTIFIER, &3_000u64).unwrap();
+		assert_eq!(time_travel.apply(&mut inherent_data).unwrap(), 8_000);
+
+		// advances that don't fit in the offset are rejected without moving the clock.
+		assert_matches::assert_matches!(
+			time_travel.advance(u64::max_value()),
+			Err(Error::InvalidTimeAdvance(_))
+		);
+		let mut inherent_data = sp_inherents::InherentData::new();
+		inherent_data.put_data(INHERENT_IDENTIFIER, &3_000u64).unwrap();
+		assert_eq!(time_travel.apply(&mut inherent_data).unwrap(), 8_000);
+	}
 }
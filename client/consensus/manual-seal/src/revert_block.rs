@@ -0,0 +1,92 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Block reverting utilities
+
+use crate::{Error, rpc};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, UniqueSaturatedInto},
+	generic::BlockId,
+};
+use sp_blockchain::HeaderBackend;
+use std::sync::Arc;
+use sc_client_api::backend::Backend as ClientBackend;
+
+/// params for reverting the best chain.
+pub struct RevertToParams<B: BlockT, C, CB> {
+	/// hash of the block to revert to
+	pub hash: <B as BlockT>::Hash,
+	/// sender to report errors/success to the rpc.
+	pub sender: rpc::Sender<u64>,
+	/// header backend
+	pub client: Arc<C>,
+	/// client backend
+	pub backend: Arc<CB>,
+}
+
+/// reverts the best chain back to the given block. Finalized blocks are never reverted.
+pub async fn revert_to<B, C, CB>(params: RevertToParams<B, C, CB>)
+	where
+		B: BlockT,
+		C: HeaderBackend<B>,
+		CB: ClientBackend<B>,
+{
+	let RevertToParams {
+		hash,
+		mut sender,
+		client,
+		backend,
+	} = params;
+
+	let result = (|| -> Result<u64, Error> {
+		let number = match client.header(BlockId::Hash(hash))? {
+			Some(header) => *header.number(),
+			None => return Err(Error::BlockNotFound(format!("{}", hash))),
+		};
+
+		if client.hash(number)? != Some(hash) {
+			return Err(Error::InvalidRevertTarget(format!("{} is not on the best chain", hash)))
+		}
+
+		let info = client.info();
+		if number < info.finalized_number {
+			return Err(Error::InvalidRevertTarget(format!("{} is below the last finalized block", hash)))
+		}
+
+		let to_revert = info.best_number - number;
+		let (reverted, _) = backend.revert(to_revert, false)?;
+		if reverted != to_revert {
+			return Err(Error::InvalidRevertTarget(
+				format!("only {} of {} blocks could be reverted", reverted, to_revert),
+			))
+		}
+
+		Ok(reverted.unique_saturated_into())
+	})();
+
+	match result {
+		Err(e) => {
+			log::warn!("Failed to revert to block {:?}", e);
+			rpc::send_result(&mut sender, Err(e))
+		}
+		Ok(reverted) => {
+			log::info!("⏪ Reverted {} blocks, new best block: {}", reverted, hash);
+			rpc::send_result(&mut sender, Ok(reverted))
+		}
+	}
+}
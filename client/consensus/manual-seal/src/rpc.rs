@@ -29,6 +29,7 @@ use futures::{
 };
 use serde::{Deserialize, Serialize};
 use sp_runtime::Justification;
+use sp_core::Bytes;
 pub use self::gen_client::Client as ManualSealClient;
 
 /// Future's type for jsonrpc
@@ -63,7 +64,39 @@ pub enum EngineCommand<Hash> {
 		sender: Sender<()>,
 		/// finalization justification
 		justification: Option<Justification>,
-	}
+	},
+	/// Tells the engine to use the given timestamp, in milliseconds, for the next block.
+	SetTimestamp {
+		/// timestamp of the next block
+		timestamp: u64,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Tells the engine to move the timestamp of the following blocks forward.
+	AdvanceTime {
+		/// milliseconds to move forward by
+		millis: u64,
+		/// sender to report the total time offset in milliseconds to the rpc.
+		sender: Sender<i64>,
+	},
+	/// Tells the engine to write the given storage value in the next block.
+	///
+	/// A value of `None` deletes the key.
+	SetStorage {
+		/// storage key
+		key: Vec<u8>,
+		/// new storage value
+		value: Option<Vec<u8>>,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Tells the engine to revert the best chain back to the block with the supplied hash
+	RevertTo {
+		/// hash of the block
+		hash: Hash,
+		/// sender to report the number of reverted blocks to the rpc.
+		sender: Sender<u64>,
+	},
 }
 
 /// RPC trait that provides methods for interacting with the manual-seal authorship task over rpc.
@@ -85,6 +118,26 @@ pub trait ManualSealApi<Hash> {
 		hash: Hash,
 		justification: Option<Justification>
 	) -> FutureResult<bool>;
+
+	/// Sets the timestamp, in milliseconds, of the next block created by the authorship task.
+	/// Following blocks keep the resulting offset to the system clock.
+	#[rpc(name = "engine_setTimestamp")]
+	fn set_timestamp(&self, timestamp: u64) -> FutureResult<bool>;
+
+	/// Moves the timestamp of the following blocks forward, returning the total time offset
+	/// in milliseconds.
+	#[rpc(name = "engine_advanceTime")]
+	fn advance_time(&self, millis: u64) -> FutureResult<i64>;
+
+	/// Overrides a storage value in the next block created by the authorship task.
+	/// Only meant for testing, the override is not the result of any extrinsic.
+	#[rpc(name = "engine_setStorage")]
+	fn set_storage(&self, key: Bytes, value: Option<Bytes>) -> FutureResult<bool>;
+
+	/// Reverts the best chain back to the block with the supplied hash, returning the number
+	/// of reverted blocks. Finalized blocks can't be reverted.
+	#[rpc(name = "engine_revertTo")]
+	fn revert_to(&self, hash: Hash) -> FutureResult<u64>;
 }
 
 /// A struct that implements the [`ManualSealApi`].
@@ -144,6 +197,58 @@ impl<Hash: Send + 'static> ManualSealApi<Hash> for ManualSeal<Hash> {
 
 		Box::new(future.boxed().map_err(Error::from).compat())
 	}
+
+	fn set_timestamp(&self, timestamp: u64) -> FutureResult<bool> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::SetTimestamp { timestamp, sender: Some(sender) }).await?;
+
+			receiver.await?.map(|_| true)
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
+
+	fn advance_time(&self, millis: u64) -> FutureResult<i64> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::AdvanceTime { millis, sender: Some(sender) }).await?;
+
+			receiver.await?
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
+
+	fn set_storage(&self, key: Bytes, value: Option<Bytes>) -> FutureResult<bool> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::SetStorage {
+				key: key.to_vec(),
+				value: value.map(|value| value.to_vec()),
+				sender: Some(sender),
+			}).await?;
+
+			receiver.await?.map(|_| true)
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
+
+	fn revert_to(&self, hash: Hash) -> FutureResult<u64> {
+		let mut sink = self.import_block_channel.clone();
+		let future = async move {
+			let (sender, receiver) = oneshot::channel();
+			sink.send(EngineCommand::RevertTo { hash, sender: Some(sender) }).await?;
+
+			receiver.await?
+		};
+
+		Box::new(future.boxed().map_err(Error::from).compat())
+	}
 }
 
 /// report any errors or successes encountered by the authorship task back
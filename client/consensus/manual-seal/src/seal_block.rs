@@ -18,10 +18,10 @@
 
 //! Block sealing utilities
 
-use crate::{Error, rpc, CreatedBlock, ConsensusDataProvider};
+use crate::{Error, rpc, CreatedBlock, ConsensusDataProvider, TimeTravel};
 use std::sync::Arc;
 use sp_runtime::{
-	traits::{Block as BlockT, Header as HeaderT},
+	traits::{Block as BlockT, Header as HeaderT, HashFor, NumberFor},
 	generic::BlockId,
 };
 use futures::prelude::*;
@@ -31,16 +31,24 @@ use sp_consensus::{
 	BlockImportParams, BlockOrigin, ImportResult, SelectChain,
 };
 use sp_blockchain::HeaderBackend;
+use sp_core::storage::{ChildInfo, StorageKey, StorageData};
+use sp_state_machine::{Backend as StateBackend, StorageChanges};
+use sc_client_api::backend::{
+	Backend as ClientBackend, StateBackendFor, TransactionFor as BackendTransactionFor,
+};
 use std::collections::HashMap;
 use std::time::Duration;
 use sp_inherents::InherentDataProviders;
-use sp_api::{ProvideRuntimeApi, TransactionFor};
+use sp_api::{ApiExt, ProvideRuntimeApi, TransactionFor};
 
 /// max duration for creating a proposal in secs
 pub const MAX_PROPOSAL_DURATION: u64 = 10;
 
+/// Storage values written on top of the next sealed block, `None` deletes the key.
+pub type StorageOverrides = Vec<(StorageKey, Option<StorageData>)>;
+
 /// params for sealing a new block
-pub struct SealBlockParams<'a, B: BlockT, BI, SC, C: ProvideRuntimeApi<B>, E, P: txpool::ChainApi> {
+pub struct SealBlockParams<'a, B: BlockT, BI, SC, C: ProvideRuntimeApi<B>, E, P: txpool::ChainApi, CB> {
 	/// if true, empty blocks(without extrinsics) will be created.
 	/// otherwise, will return Error::EmptyTransactionPool.
 	pub create_empty: bool,
@@ -54,6 +62,8 @@ pub struct SealBlockParams<'a, B: BlockT, BI, SC, C: ProvideRuntimeApi<B>, E, P:
 	pub pool: Arc<txpool::Pool<P>>,
 	/// header backend
 	pub client: Arc<C>,
+	/// client backend, used to apply the storage overrides
+	pub backend: Arc<CB>,
 	/// Environment trait object for creating a proposer
 	pub env: &'a mut E,
 	/// SelectChain object
@@ -64,30 +74,39 @@ pub struct SealBlockParams<'a, B: BlockT, BI, SC, C: ProvideRuntimeApi<B>, E, P:
 	pub block_import: &'a mut BI,
 	/// inherent data provider
 	pub inherent_data_provider: &'a InherentDataProviders,
+	/// adjustments to the timestamp inherent
+	pub time_travel: &'a mut TimeTravel,
+	/// storage values to override in this block, drained once the block is proposed.
+	pub storage_overrides: &'a mut StorageOverrides,
 }
 
 /// seals a new block with the given params
-pub async fn seal_block<B, BI, SC, C, E, P>(
+pub async fn seal_block<B, BI, SC, C, E, P, CB>(
 	SealBlockParams {
 		create_empty,
 		finalize,
 		pool,
 		parent_hash,
 		client,
+		backend,
 		select_chain,
 		block_import,
 		env,
 		inherent_data_provider,
 		consensus_data_provider: digest_provider,
+		time_travel,
+		storage_overrides,
 		mut sender,
 		..
-	}: SealBlockParams<'_, B, BI, SC, C, E, P>
+	}: SealBlockParams<'_, B, BI, SC, C, E, P, CB>
 )
 	where
 		B: BlockT,
 		BI: BlockImport<B, Error = sp_consensus::Error, Transaction = sp_api::TransactionFor<C, B>>
 			+ Send + Sync + 'static,
 		C: HeaderBackend<B> + ProvideRuntimeApi<B>,
+		C::Api: ApiExt<B, StateBackend = StateBackendFor<CB, B>>,
+		CB: ClientBackend<B>,
 		E: Environment<B>,
 		E::Proposer: Proposer<B, Transaction = TransactionFor<C, B>>,
 		P: txpool::ChainApi<Block=B>,
@@ -114,7 +133,10 @@ pub async fn seal_block<B, BI, SC, C, E, P>(
 
 		let proposer = env.init(&parent)
 			.map_err(|err| Error::StringError(format!("{:?}", err))).await?;
-		let id = inherent_data_provider.create_inherent_data()?;
+		let mut id = inherent_data_provider.create_inherent_data()?;
+		if !time_travel.is_idle() {
+			time_travel.apply(&mut id)?;
+		}
 		let inherents_len = id.len();
 
 		let digest = if let Some(digest_provider) = digest_provider {
@@ -130,12 +152,24 @@ pub async fn seal_block<B, BI, SC, C, E, P>(
 			return Err(Error::EmptyTransactionPool)
 		}
 
-		let (header, body) = proposal.block.deconstruct();
+		let (mut header, body) = proposal.block.deconstruct();
+		let mut storage_changes = proposal.storage_changes;
+		if !storage_overrides.is_empty() {
+			let overrides = std::mem::take(storage_overrides);
+			let state_root = apply_storage_overrides::<B, CB>(
+				&*backend,
+				&parent,
+				overrides,
+				&mut storage_changes,
+			)?;
+			header.set_state_root(state_root);
+		}
+
 		let mut params = BlockImportParams::new(BlockOrigin::Own, header.clone());
 		params.body = Some(body);
 		params.finalized = finalize;
 		params.fork_choice = Some(ForkChoiceStrategy::LongestChain);
-		params.storage_changes = Some(proposal.storage_changes);
+		params.storage_changes = Some(storage_changes);
 
 		if let Some(digest_provider) = digest_provider {
 			digest_provider.append_block_import(&parent, &mut params, &id)?;
@@ -151,3 +185,48 @@ pub async fn seal_block<B, BI, SC, C, E, P>(
 
 	rpc::send_result(&mut sender, future.await)
 }
+
+/// Writes the storage overrides on top of the changes of a proposed block and returns the
+/// new state root.
+///
+/// Changes tries are not updated with the overridden values.
+fn apply_storage_overrides<B, CB>(
+	backend: &CB,
+	parent: &B::Header,
+	overrides: StorageOverrides,
+	storage_changes: &mut StorageChanges<BackendTransactionFor<CB, B>, HashFor<B>, NumberFor<B>>,
+) -> Result<B::Hash, Error>
+	where
+		B: BlockT,
+		CB: ClientBackend<B>,
+{
+	let main_changes = &mut storage_changes.main_storage_changes;
+	for (key, value) in overrides {
+		let value = value.map(|value| value.0);
+		match main_changes.iter_mut().find(|(k, _)| *k == key.0) {
+			Some(change) => change.1 = value,
+			None => main_changes.push((key.0, value)),
+		}
+	}
+
+	let state = backend.state_at(BlockId::Hash(parent.hash()))?;
+	let child_infos = storage_changes.child_storage_changes.iter()
+		.map(|(storage_key, _)| ChildInfo::new_default(storage_key))
+		.collect::<Vec<_>>();
+
+	let (state_root, transaction) = state.full_storage_root(
+		storage_changes.main_storage_changes.iter()
+			.map(|(k, v)| (&k[..], v.as_ref().map(|v| &v[..]))),
+		child_infos.iter()
+			.zip(storage_changes.child_storage_changes.iter())
+			.map(|(child_info, (_, changes))| (
+				child_info,
+				changes.iter().map(|(k, v)| (&k[..], v.as_ref().map(|v| &v[..]))),
+			)),
+	);
+
+	storage_changes.transaction = transaction;
+	storage_changes.transaction_storage_root = state_root;
+
+	Ok(state_root)
+}
@@ -0,0 +1,82 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Control over the timestamp inherent of sealed blocks.
+
+use crate::Error;
+use sp_inherents::InherentData;
+use sp_timestamp::{InherentType, INHERENT_IDENTIFIER};
+use std::{convert::TryFrom, time::SystemTime};
+
+/// Shifts the timestamp inherent provided by the registered inherent data providers.
+///
+/// The shift is kept for all following blocks, so once the clock has been moved forward
+/// the chain keeps producing blocks relative to the new time.
+#[derive(Debug, Default)]
+pub struct TimeTravel {
+	/// offset in milliseconds applied to the provided timestamp.
+	offset: i64,
+	/// exact timestamp requested for the next block.
+	next_timestamp: Option<InherentType>,
+}
+
+impl TimeTravel {
+	/// Use `timestamp` for the next block, and shift the following ones accordingly.
+	pub fn set_timestamp(&mut self, timestamp: InherentType) {
+		self.next_timestamp = Some(timestamp);
+	}
+
+	/// Move the clock forward by `millis` and return the total offset in milliseconds.
+	///
+	/// Fails if `millis` doesn't fit in the offset, the clock is left untouched then.
+	pub fn advance(&mut self, millis: u64) -> Result<i64, Error> {
+		let shift = i64::try_from(millis).map_err(|_| Error::InvalidTimeAdvance(millis))?;
+		self.offset = self.offset.saturating_add(shift);
+		if let Some(next) = self.next_timestamp.as_mut() {
+			*next = next.saturating_add(millis);
+		}
+		Ok(self.offset)
+	}
+
+	/// Rewrite the timestamp in the given inherent data, returning the timestamp used.
+	///
+	/// When no timestamp provider is registered the system time is used as a base.
+	pub fn apply(&mut self, inherent_data: &mut InherentData) -> Result<InherentType, Error> {
+		let provided = match inherent_data.get_data::<InherentType>(&INHERENT_IDENTIFIER)? {
+			Some(timestamp) => timestamp,
+			None => SystemTime::now()
+				.duration_since(SystemTime::UNIX_EPOCH)
+				.map_err(|err| Error::StringError(format!("{}", err)))?
+				.as_millis() as InherentType,
+		};
+
+		if let Some(next) = self.next_timestamp.take() {
+			self.offset = next as i64 - provided as i64;
+		}
+
+		let timestamp = (provided as i64).saturating_add(self.offset).max(0) as InherentType;
+		inherent_data.replace_data(INHERENT_IDENTIFIER, &timestamp);
+
+		Ok(timestamp)
+	}
+
+	/// Returns true if the timestamp inherent is left untouched.
+	pub fn is_idle(&self) -> bool {
+		self.offset == 0 && self.next_timestamp.is_none()
+	}
+}
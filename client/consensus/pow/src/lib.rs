@@ -21,7 +21,9 @@
 //! To use this engine, you can need to have a struct that implements
 //! `PowAlgorithm`. After that, pass an instance of the struct, along
 //! with other necessary client references to `import_queue` to setup
-//! the queue. Use the `start_mine` function for basic CPU mining.
+//! the queue. Use the `start_mining_worker` function to build blocks to be
+//! mined, and either `start_cpu_mining` for multi-threaded CPU mining or the
+//! RPC interface in the `rpc` module for external miners.
 //!
 //! The auxiliary storage for PoW engine only stores the total difficulty.
 //! For other storage requirements for particular PoW algorithm (such as
@@ -30,10 +32,22 @@
 //! for the auxiliary storage. It is also possible to just use the runtime
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
+//!
+//! Chains that keep their difficulty adjustment algorithm in the runtime (e.g.
+//! using `pallet-difficulty`) can implement `PowAlgorithm::difficulty` with
+//! [`runtime_difficulty`].
+//!
+//! By default `PowBlockImport` follows the chain with the highest total
+//! difficulty. A different rule can be plugged in through
+//! `PowBlockImport::with_fork_choice` by implementing `PowForkChoice`.
 
 mod worker;
+pub mod rpc;
 
-pub use crate::worker::{MiningWorker, MiningMetadata, MiningBuild};
+pub use crate::worker::{
+	MiningWorker, MiningMetadata, MiningBuild, CpuMiningAlgorithm, CpuMiningHandle, SealedBlock,
+	start_cpu_mining,
+};
 
 use std::{
 	sync::Arc, any::Any, borrow::Cow, collections::HashMap, marker::PhantomData,
@@ -42,13 +56,16 @@ use std::{
 use futures::{prelude::*, future::Either};
 use parking_lot::Mutex;
 use sc_client_api::{BlockOf, backend::AuxStore, BlockchainEvents};
-use sp_blockchain::{HeaderBackend, ProvideCache, well_known_cache_keys::Id as CacheKeyId};
+use sp_blockchain::{
+	HeaderBackend, HeaderMetadata, ProvideCache, well_known_cache_keys::Id as CacheKeyId,
+	lowest_common_ancestor,
+};
 use sp_block_builder::BlockBuilder as BlockBuilderApi;
 use sp_runtime::{Justification, RuntimeString};
 use sp_runtime::generic::{BlockId, Digest, DigestItem};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_api::ProvideRuntimeApi;
-use sp_consensus_pow::{Seal, TotalDifficulty, POW_ENGINE_ID};
+use sp_consensus_pow::{Seal, TotalDifficulty, DifficultyApi, POW_ENGINE_ID};
 use sp_inherents::{InherentDataProviders, InherentData};
 use sp_consensus::{
 	BlockImportParams, BlockOrigin, ForkChoiceStrategy, SyncOracle, Environment, Proposer,
@@ -159,7 +176,7 @@ impl<Difficulty> PowAux<Difficulty> where
 /// Algorithm used for proof of work.
 pub trait PowAlgorithm<B: BlockT> {
 	/// Difficulty for the algorithm.
-	type Difficulty: TotalDifficulty + Default + Encode + Decode + Ord + Clone + Copy;
+	type Difficulty: TotalDifficulty + Default + Encode + Decode + Ord + Clone + Copy + Send;
 
 	/// Get the next block's difficulty.
 	///
@@ -200,8 +217,127 @@ pub trait PowAlgorithm<B: BlockT> {
 	) -> Result<bool, Error<B>>;
 }
 
+/// Fetch the difficulty of the block built on top of `parent` from the runtime,
+/// using the `DifficultyApi`. Meant to be used by `PowAlgorithm::difficulty`
+/// implementations of chains whose difficulty adjustment lives in the runtime.
+pub fn runtime_difficulty<B, C, Difficulty>(
+	client: &C,
+	parent: B::Hash,
+) -> Result<Difficulty, Error<B>> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: DifficultyApi<B, Difficulty, Error = sp_blockchain::Error>,
+	Difficulty: Decode,
+{
+	client.runtime_api()
+		.difficulty(&BlockId::Hash(parent))
+		.map_err(Error::Client)
+}
+
+/// The block being imported and the current best block, as seen by a fork choice rule.
+pub struct ForkChoiceParams<'a, B: BlockT, Difficulty> {
+	/// Header of the imported block, without its seal.
+	pub header: &'a B::Header,
+	/// Seal of the imported block.
+	pub seal: &'a Seal,
+	/// Difficulty data of the imported block.
+	pub aux: &'a PowAux<Difficulty>,
+	/// Header of the current best block.
+	pub best_header: &'a B::Header,
+	/// Difficulty data of the current best block.
+	pub best_aux: &'a PowAux<Difficulty>,
+}
+
+/// Rule deciding whether a newly imported block becomes the new best block.
+pub trait PowForkChoice<B: BlockT, Algorithm: PowAlgorithm<B>> {
+	/// Return the fork choice strategy used to import the given block.
+	fn fork_choice(
+		&self,
+		algorithm: &Algorithm,
+		params: ForkChoiceParams<B, Algorithm::Difficulty>,
+	) -> Result<ForkChoiceStrategy, Error<B>>;
+}
+
+/// Follow the chain with the highest total difficulty, breaking ties with
+/// `PowAlgorithm::break_tie`. This is the default fork choice rule.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeaviestChain;
+
+impl<B: BlockT, Algorithm: PowAlgorithm<B>> PowForkChoice<B, Algorithm> for HeaviestChain {
+	fn fork_choice(
+		&self,
+		algorithm: &Algorithm,
+		params: ForkChoiceParams<B, Algorithm::Difficulty>,
+	) -> Result<ForkChoiceStrategy, Error<B>> {
+		let is_new_best = match params.aux.total_difficulty.cmp(&params.best_aux.total_difficulty) {
+			Ordering::Less => false,
+			Ordering::Greater => true,
+			Ordering::Equal => {
+				let best_inner_seal = fetch_seal::<B>(
+					params.best_header.digest().logs.last(),
+					params.best_header.hash(),
+				)?;
+
+				algorithm.break_tie(&best_inner_seal, params.seal)
+			},
+		};
+
+		Ok(ForkChoiceStrategy::Custom(is_new_best))
+	}
+}
+
+/// Follow the heaviest chain, but never switch to a chain which does not
+/// include the last finalized block, however heavy it is.
+pub struct FinalityAwareHeaviestChain<C> {
+	client: Arc<C>,
+}
+
+impl<C> FinalityAwareHeaviestChain<C> {
+	/// Create a new fork choice rule reading the finalized block from the given client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> Clone for FinalityAwareHeaviestChain<C> {
+	fn clone(&self) -> Self {
+		Self { client: self.client.clone() }
+	}
+}
+
+impl<B, C, Algorithm> PowForkChoice<B, Algorithm> for FinalityAwareHeaviestChain<C> where
+	B: BlockT,
+	C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error>,
+	Algorithm: PowAlgorithm<B>,
+{
+	fn fork_choice(
+		&self,
+		algorithm: &Algorithm,
+		params: ForkChoiceParams<B, Algorithm::Difficulty>,
+	) -> Result<ForkChoiceStrategy, Error<B>> {
+		let finalized_hash = self.client.info().finalized_hash;
+		let parent_hash = *params.header.parent_hash();
+
+		let ancestor = lowest_common_ancestor(&*self.client, finalized_hash, parent_hash)
+			.map_err(Error::Client)?;
+
+		if ancestor.hash != finalized_hash {
+			debug!(
+				target: "pow",
+				"Block {:?} is not a descendant of finalized block {:?}, not making it best.",
+				params.header.hash(),
+				finalized_hash,
+			);
+
+			return Ok(ForkChoiceStrategy::Custom(false))
+		}
+
+		HeaviestChain.fork_choice(algorithm, params)
+	}
+}
+
 /// A block importer for PoW.
-pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CAW> {
+pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CAW, F = HeaviestChain> {
 	algorithm: Algorithm,
 	inner: I,
 	select_chain: S,
@@ -209,10 +345,11 @@ pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CAW> {
 	inherent_data_providers: sp_inherents::InherentDataProviders,
 	check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
 	can_author_with: CAW,
+	fork_choice: F,
 }
 
-impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CAW: Clone> Clone
-	for PowBlockImport<B, I, C, S, Algorithm, CAW>
+impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CAW: Clone, F: Clone> Clone
+	for PowBlockImport<B, I, C, S, Algorithm, CAW, F>
 {
 	fn clone(&self) -> Self {
 		Self {
@@ -223,6 +360,7 @@ impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CAW: Clone> Clone
 			inherent_data_providers: self.inherent_data_providers.clone(),
 			check_inherents_after: self.check_inherents_after.clone(),
 			can_author_with: self.can_author_with.clone(),
+			fork_choice: self.fork_choice.clone(),
 		}
 	}
 }
@@ -254,6 +392,34 @@ impl<B, I, C, S, Algorithm, CAW> PowBlockImport<B, I, C, S, Algorithm, CAW> wher
 			select_chain,
 			inherent_data_providers,
 			can_author_with,
+			fork_choice: HeaviestChain,
+		}
+	}
+}
+
+impl<B, I, C, S, Algorithm, CAW, F> PowBlockImport<B, I, C, S, Algorithm, CAW, F> where
+	B: BlockT,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync,
+	I::Error: Into<ConsensusError>,
+	C: ProvideRuntimeApi<B> + Send + Sync + HeaderBackend<B> + AuxStore + ProvideCache<B> + BlockOf,
+	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>,
+	Algorithm: PowAlgorithm<B>,
+	CAW: CanAuthorWith<B>,
+{
+	/// Use the given fork choice rule instead of the current one. Blocks
+	/// which already carry a fork choice strategy are not affected.
+	pub fn with_fork_choice<F2>(self, fork_choice: F2) -> PowBlockImport<B, I, C, S, Algorithm, CAW, F2>
+		where F2: PowForkChoice<B, Algorithm>,
+	{
+		PowBlockImport {
+			algorithm: self.algorithm,
+			inner: self.inner,
+			select_chain: self.select_chain,
+			client: self.client,
+			inherent_data_providers: self.inherent_data_providers,
+			check_inherents_after: self.check_inherents_after,
+			can_author_with: self.can_author_with,
+			fork_choice,
 		}
 	}
 
@@ -308,7 +474,7 @@ impl<B, I, C, S, Algorithm, CAW> PowBlockImport<B, I, C, S, Algorithm, CAW> wher
 	}
 }
 
-impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, Algorithm, CAW> where
+impl<B, I, C, S, Algorithm, CAW, F> BlockImport<B> for PowBlockImport<B, I, C, S, Algorithm, CAW, F> where
 	B: BlockT,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync,
 	I::Error: Into<ConsensusError>,
@@ -318,6 +484,7 @@ impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, A
 	Algorithm: PowAlgorithm<B>,
 	Algorithm::Difficulty: 'static,
 	CAW: CanAuthorWith<B>,
+	F: PowForkChoice<B, Algorithm>,
 {
 	type Error = ConsensusError;
 	type Transaction = sp_api::TransactionFor<C, B>;
@@ -388,20 +555,16 @@ impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, A
 		let key = aux_key(&block.post_hash());
 		block.auxiliary.push((key, Some(aux.encode())));
 		if block.fork_choice.is_none() {
-			block.fork_choice = Some(ForkChoiceStrategy::Custom(
-				match aux.total_difficulty.cmp(&best_aux.total_difficulty) {
-					Ordering::Less => false,
-					Ordering::Greater => true,
-					Ordering::Equal => {
-						let best_inner_seal = fetch_seal::<B>(
-							best_header.digest().logs.last(),
-							best_hash,
-						)?;
-
-						self.algorithm.break_tie(&best_inner_seal, &inner_seal)
-					},
-				}
-			));
+			block.fork_choice = Some(self.fork_choice.fork_choice(
+				&self.algorithm,
+				ForkChoiceParams {
+					header: &block.header,
+					seal: &inner_seal,
+					aux: &aux,
+					best_header: &best_header,
+					best_aux: &best_aux,
+				},
+			)?);
 		}
 
 		self.inner.import_block(block, new_cache).map_err(Into::into)
@@ -414,6 +577,12 @@ pub struct PowVerifier<B: BlockT, Algorithm> {
 	_marker: PhantomData<B>,
 }
 
+impl<B: BlockT, Algorithm: Clone> Clone for PowVerifier<B, Algorithm> {
+	fn clone(&self) -> Self {
+		Self::new(self.algorithm.clone())
+	}
+}
+
 impl<B: BlockT, Algorithm> PowVerifier<B, Algorithm> {
 	pub fn new(
 		algorithm: Algorithm,
@@ -474,12 +643,17 @@ impl<B: BlockT, Algorithm> Verifier<B> for PowVerifier<B, Algorithm> where
 		import_block.justification = justification;
 		import_block.intermediates.insert(
 			Cow::from(INTERMEDIATE_KEY),
-			Box::new(intermediate) as Box<dyn Any>
+			Box::new(intermediate) as Box<dyn Any + Send>
 		);
 		import_block.post_hash = Some(hash);
 
 		Ok((import_block, None))
 	}
+
+	fn requires_imported_parent(&self) -> bool {
+		// Only the seal is checked here, the difficulty is checked against the parent on import.
+		false
+	}
 }
 
 /// Register the PoW inherent data provider, if not registered already.
@@ -519,12 +693,13 @@ pub fn import_queue<B, Transaction, Algorithm>(
 
 	let verifier = PowVerifier::new(algorithm);
 
-	Ok(BasicQueue::new(
+	Ok(BasicQueue::with_parallel_verification(
 		verifier,
 		block_import,
 		justification_import,
 		spawner,
 		registry,
+		num_cpus::get(),
 	))
 }
 
@@ -569,7 +744,7 @@ pub fn start_mining_worker<Block, C, S, Algorithm, E, SO, CAW>(
 	let worker = Arc::new(Mutex::new(MiningWorker::<Block, Algorithm, C> {
 		build: None,
 		algorithm: algorithm.clone(),
-		block_import,
+		block_import: Arc::new(Mutex::new(block_import)),
 	}));
 	let worker_ret = worker.clone();
 
@@ -730,3 +905,6 @@ fn fetch_seal<B: BlockT>(
 		_ => return Err(Error::<B>::HeaderUnsealed(hash).into()),
 	}
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,114 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//! RPC interface for external PoW miners.
+//!
+//! External miners poll `pow_getWork` for the current mining build and submit
+//! the seals they find with `pow_submitSeal`.
+
+use std::sync::Arc;
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{MiningWorker, PowAlgorithm};
+
+pub use self::gen_client::Client as PowClient;
+
+/// Error code returned when a seal is submitted for outdated work.
+const STALE_WORK_ERROR: i64 = 1;
+
+/// Work to be done by an external miner.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Work<Hash, Difficulty> {
+	/// Best block the work is built on.
+	pub best_hash: Hash,
+	/// Pre-hash to be sealed.
+	pub pre_hash: Hash,
+	/// Pre-runtime digest of the block, if any.
+	pub pre_runtime: Option<Bytes>,
+	/// Target difficulty of the seal.
+	pub difficulty: Difficulty,
+}
+
+/// RPC trait exposing the mining worker to external miners.
+#[rpc]
+pub trait PowApi<Hash, Difficulty> {
+	/// Returns the current work, or `None` if the node has no block to mine yet.
+	#[rpc(name = "pow_getWork")]
+	fn get_work(&self) -> Result<Option<Work<Hash, Difficulty>>>;
+
+	/// Submits a seal for the work with the given pre-hash. Returns whether the
+	/// seal was valid and the block imported.
+	#[rpc(name = "pow_submitSeal")]
+	fn submit_seal(&self, pre_hash: Hash, seal: Bytes) -> Result<bool>;
+}
+
+/// A struct that implements the [`PowApi`].
+pub struct Pow<Block: BlockT, Algorithm: PowAlgorithm<Block>, C: sp_api::ProvideRuntimeApi<Block>> {
+	worker: Arc<Mutex<MiningWorker<Block, Algorithm, C>>>,
+}
+
+impl<Block, Algorithm, C> Pow<Block, Algorithm, C> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block>,
+	C: sp_api::ProvideRuntimeApi<Block>,
+{
+	/// Create new `Pow` serving the work of the given mining worker.
+	pub fn new(worker: Arc<Mutex<MiningWorker<Block, Algorithm, C>>>) -> Self {
+		Self { worker }
+	}
+}
+
+impl<Block, Algorithm, C> PowApi<Block::Hash, Algorithm::Difficulty> for Pow<Block, Algorithm, C> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + 'static,
+	Algorithm::Difficulty: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static,
+	C: sp_api::ProvideRuntimeApi<Block> + 'static,
+	MiningWorker<Block, Algorithm, C>: Send,
+{
+	fn get_work(&self) -> Result<Option<Work<Block::Hash, Algorithm::Difficulty>>> {
+		Ok(self.worker.lock().metadata().map(|metadata| Work {
+			best_hash: metadata.best_hash,
+			pre_hash: metadata.pre_hash,
+			pre_runtime: metadata.pre_runtime.map(Into::into),
+			difficulty: metadata.difficulty,
+		}))
+	}
+
+	fn submit_seal(&self, pre_hash: Block::Hash, seal: Bytes) -> Result<bool> {
+		let block = {
+			let mut worker = self.worker.lock();
+
+			if worker.metadata().map(|metadata| metadata.pre_hash) != Some(pre_hash) {
+				return Err(Error {
+					code: ErrorCode::ServerError(STALE_WORK_ERROR),
+					message: "Work is stale or unknown".into(),
+					data: None,
+				})
+			}
+
+			worker.seal(seal.0)
+		};
+
+		Ok(block.map_or(false, |block| block.import()))
+	}
+}
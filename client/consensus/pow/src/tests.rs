@@ -0,0 +1,285 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! PoW tests.
+
+use super::*;
+use sc_block_builder::BlockBuilderProvider;
+use sp_consensus::Proposal;
+use sp_core::H256;
+use sp_runtime::traits::Header as _;
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block as TestBlock, Header as TestHeader, Transfer},
+};
+
+type Difficulty = u128;
+
+/// A PoW algorithm with a fixed difficulty of one, where a seal is the encoded pre-hash and
+/// nonce, and is valid for every nonce that is a multiple of 7.
+#[derive(Clone, Default)]
+struct TestAlgorithm {
+	/// Whether a tie between two chains is broken in favor of the new block.
+	break_ties_for_new: bool,
+}
+
+impl TestAlgorithm {
+	fn seal(pre_hash: H256, nonce: u64) -> Seal {
+		(pre_hash, nonce).encode()
+	}
+}
+
+impl PowAlgorithm<TestBlock> for TestAlgorithm {
+	type Difficulty = Difficulty;
+
+	fn difficulty(&self, _parent: H256) -> Result<Difficulty, Error<TestBlock>> {
+		Ok(1)
+	}
+
+	fn break_tie(&self, _own_seal: &Seal, _new_seal: &Seal) -> bool {
+		self.break_ties_for_new
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<TestBlock>,
+		pre_hash: &H256,
+		_pre_digest: Option<&[u8]>,
+		seal: &Seal,
+		_difficulty: Difficulty,
+	) -> Result<bool, Error<TestBlock>> {
+		Ok(match <(H256, u64)>::decode(&mut &seal[..]) {
+			Ok((seal_pre_hash, nonce)) => seal_pre_hash == *pre_hash && nonce % 7 == 0,
+			Err(_) => false,
+		})
+	}
+}
+
+fn sealed_header(parent_hash: H256, number: u64) -> TestHeader {
+	TestHeader::new(
+		number,
+		Default::default(),
+		Default::default(),
+		parent_hash,
+		Digest { logs: vec![DigestItem::Seal(POW_ENGINE_ID, TestAlgorithm::seal(parent_hash, 0))] },
+	)
+}
+
+fn aux(total_difficulty: Difficulty) -> PowAux<Difficulty> {
+	PowAux { difficulty: 1, total_difficulty }
+}
+
+fn fork_choice<F: PowForkChoice<TestBlock, TestAlgorithm>>(
+	fork_choice: &F,
+	algorithm: &TestAlgorithm,
+	header: &TestHeader,
+	total_difficulty: Difficulty,
+	best_total_difficulty: Difficulty,
+) -> ForkChoiceStrategy {
+	let best_header = sealed_header(H256::random(), 1);
+	let seal = TestAlgorithm::seal(header.hash(), 0);
+
+	fork_choice.fork_choice(
+		algorithm,
+		ForkChoiceParams {
+			header,
+			seal: &seal,
+			aux: &aux(total_difficulty),
+			best_header: &best_header,
+			best_aux: &aux(best_total_difficulty),
+		},
+	).unwrap()
+}
+
+#[test]
+fn heaviest_chain_follows_the_total_difficulty() {
+	let algorithm = TestAlgorithm::default();
+	let header = sealed_header(H256::random(), 2);
+
+	assert_eq!(fork_choice(&HeaviestChain, &algorithm, &header, 3, 2), ForkChoiceStrategy::Custom(true));
+	assert_eq!(fork_choice(&HeaviestChain, &algorithm, &header, 2, 3), ForkChoiceStrategy::Custom(false));
+}
+
+#[test]
+fn heaviest_chain_breaks_ties_with_the_algorithm() {
+	let header = sealed_header(H256::random(), 2);
+
+	let keep_best = TestAlgorithm { break_ties_for_new: false };
+	assert_eq!(fork_choice(&HeaviestChain, &keep_best, &header, 2, 2), ForkChoiceStrategy::Custom(false));
+
+	let prefer_new = TestAlgorithm { break_ties_for_new: true };
+	assert_eq!(fork_choice(&HeaviestChain, &prefer_new, &header, 2, 2), ForkChoiceStrategy::Custom(true));
+}
+
+#[test]
+fn finality_aware_heaviest_chain_ignores_heavier_forks_below_finality() {
+	let mut client = substrate_test_runtime_client::new();
+	let genesis_hash = client.chain_info().genesis_hash;
+
+	// #0 - a1 (finalized)
+	//    \ b1
+	let a1 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+	let mut builder = client.new_block_at(&BlockId::Hash(genesis_hash), Default::default(), false)
+		.unwrap();
+	builder.push_transfer(Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Ferdie.into(),
+		amount: 41,
+		nonce: 0,
+	}).unwrap();
+	let b1 = builder.build().unwrap().block;
+	client.import(BlockOrigin::Own, b1.clone()).unwrap();
+
+	client.finalize_block(BlockId::Hash(a1.hash()), None).unwrap();
+
+	let algorithm = TestAlgorithm { break_ties_for_new: true };
+	let finality_aware = FinalityAwareHeaviestChain::new(Arc::new(client));
+
+	// a heavier block on the fork not including the finalized block is never best.
+	let below_finality = sealed_header(b1.hash(), 2);
+	assert_eq!(
+		fork_choice(&finality_aware, &algorithm, &below_finality, 10, 2),
+		ForkChoiceStrategy::Custom(false),
+	);
+
+	// a heavier block on top of the finalized block is.
+	let above_finality = sealed_header(a1.hash(), 2);
+	assert_eq!(
+		fork_choice(&finality_aware, &algorithm, &above_finality, 10, 2),
+		ForkChoiceStrategy::Custom(true),
+	);
+
+	// and it falls back to the heaviest chain rule otherwise.
+	assert_eq!(
+		fork_choice(&finality_aware, &algorithm, &above_finality, 1, 2),
+		ForkChoiceStrategy::Custom(false),
+	);
+	assert_eq!(
+		fork_choice(&finality_aware, &algorithm, &above_finality, 2, 2),
+		ForkChoiceStrategy::Custom(true),
+	);
+}
+
+impl CpuMiningAlgorithm<TestBlock> for TestAlgorithm {
+	fn mine(
+		&self,
+		metadata: &MiningMetadata<H256, Difficulty>,
+		nonce: u64,
+	) -> Result<Option<Seal>, Error<TestBlock>> {
+		Ok(Some(Self::seal(metadata.pre_hash, nonce)).filter(|_| nonce % 7 == 0))
+	}
+}
+
+type TestMiningWorker = MiningWorker<TestBlock, TestAlgorithm, TestClient>;
+
+/// Create a test client and a mining worker importing into it, with a build on top of genesis.
+///
+/// Returns the client, the worker and the pre-hash of its build.
+fn mining_worker() -> (Arc<TestClient>, Arc<Mutex<TestMiningWorker>>, H256) {
+	let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+	let client = Arc::new(client);
+
+	let inherent_data_providers = InherentDataProviders::new();
+	register_pow_inherent_data_provider(&inherent_data_providers).unwrap();
+
+	let block_import = PowBlockImport::new(
+		client.clone(),
+		client.clone(),
+		TestAlgorithm::default(),
+		// the test runtime has no timestamp inherent to check.
+		u64::max_value(),
+		select_chain,
+		inherent_data_providers,
+		sp_consensus::AlwaysCanAuthor,
+	);
+
+	let built = client.new_block(Default::default()).unwrap().build().unwrap();
+	let pre_hash = built.block.header().hash();
+
+	let mut worker = MiningWorker {
+		build: None,
+		algorithm: TestAlgorithm::default(),
+		block_import: Arc::new(Mutex::new(Box::new(block_import) as BoxBlockImport<_, _>)),
+	};
+	worker.on_build(MiningBuild {
+		metadata: MiningMetadata {
+			best_hash: client.chain_info().genesis_hash,
+			pre_hash,
+			pre_runtime: None,
+			difficulty: 1,
+		},
+		proposal: Proposal {
+			block: built.block,
+			proof: built.proof,
+			storage_changes: built.storage_changes,
+		},
+	});
+
+	(client, Arc::new(Mutex::new(worker)), pre_hash)
+}
+
+#[test]
+fn cpu_mining_threads_mine_a_block() {
+	let (client, worker, pre_hash) = mining_worker();
+
+	let mining = start_cpu_mining(worker.clone(), TestAlgorithm::default(), 4).unwrap();
+
+	let started = std::time::Instant::now();
+	while client.chain_info().best_number == 0 {
+		assert!(started.elapsed() < Duration::from_secs(10), "No block was mined");
+		std::thread::sleep(Duration::from_millis(10));
+	}
+	mining.stop();
+
+	// the mined block was imported with a valid seal and the build was consumed.
+	let best_header = client.header(&BlockId::Number(1)).unwrap().unwrap();
+	let seal = fetch_seal::<TestBlock>(best_header.digest().logs.last(), best_header.hash()).unwrap();
+	assert!(TestAlgorithm::default().verify(&BlockId::Number(0), &pre_hash, None, &seal, 1).unwrap());
+	assert!(worker.lock().metadata().is_none());
+}
+
+#[test]
+fn external_miners_submit_seals_over_rpc() {
+	use crate::rpc::{Pow, PowApi};
+
+	let (client, worker, pre_hash) = mining_worker();
+	let pow = Pow::new(worker.clone());
+
+	let work = pow.get_work().unwrap().expect("the worker has a build");
+	assert_eq!(work.pre_hash, pre_hash);
+	assert_eq!(work.difficulty, 1);
+
+	// a seal for work that is not the current one is rejected as stale.
+	let stale = pow.submit_seal(H256::random(), TestAlgorithm::seal(pre_hash, 0).into()).unwrap_err();
+	assert_eq!(stale.code, jsonrpc_core::ErrorCode::ServerError(1));
+
+	// an invalid seal is refused, and the work can still be mined.
+	assert_eq!(pow.submit_seal(pre_hash, TestAlgorithm::seal(pre_hash, 1).into()).unwrap(), false);
+	assert_eq!(client.chain_info().best_number, 0);
+	assert!(pow.get_work().unwrap().is_some());
+
+	// a valid seal imports the block.
+	assert_eq!(pow.submit_seal(pre_hash, TestAlgorithm::seal(pre_hash, 7).into()).unwrap(), true);
+	assert_eq!(client.chain_info().best_number, 1);
+
+	// the work was consumed, submitting it again is stale.
+	assert_eq!(pow.get_work().unwrap(), None);
+	assert!(pow.submit_seal(pre_hash, TestAlgorithm::seal(pre_hash, 7).into()).is_err());
+}
@@ -16,7 +16,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::{pin::Pin, time::Duration, collections::HashMap, any::Any, borrow::Cow};
+use std::{
+	pin::Pin, time::Duration, collections::HashMap, any::Any, borrow::Cow, thread,
+	sync::{Arc, atomic::{AtomicBool, Ordering}},
+};
+use parking_lot::Mutex;
 use sc_client_api::ImportNotifications;
 use sp_runtime::{DigestItem, traits::Block as BlockT, generic::BlockId};
 use sp_consensus::{Proposal, BlockOrigin, BlockImportParams, import_queue::BoxBlockImport};
@@ -24,7 +28,13 @@ use futures::{prelude::*, task::{Context, Poll}};
 use futures_timer::Delay;
 use log::*;
 
-use crate::{INTERMEDIATE_KEY, POW_ENGINE_ID, Seal, PowAlgorithm, PowIntermediate};
+use crate::{INTERMEDIATE_KEY, POW_ENGINE_ID, Seal, PowAlgorithm, PowIntermediate, Error};
+
+/// Number of nonces a mining thread tries before checking for new work.
+const NONCES_PER_ROUND: usize = 10_000;
+
+/// How long an idle mining thread waits before checking for new work.
+const IDLE_DURATION: Duration = Duration::from_millis(100);
 
 /// Mining metadata. This is the information needed to start an actual mining loop.
 #[derive(Clone, Eq, PartialEq)]
@@ -51,7 +61,7 @@ pub struct MiningBuild<Block: BlockT, Algorithm: PowAlgorithm<Block>, C: sp_api:
 pub struct MiningWorker<Block: BlockT, Algorithm: PowAlgorithm<Block>, C: sp_api::ProvideRuntimeApi<Block>> {
 	pub(crate) build: Option<MiningBuild<Block, Algorithm, C>>,
 	pub(crate) algorithm: Algorithm,
-	pub(crate) block_import: BoxBlockImport<Block, sp_api::TransactionFor<C, Block>>,
+	pub(crate) block_import: Arc<Mutex<BoxBlockImport<Block, sp_api::TransactionFor<C, Block>>>>,
 }
 
 impl<Block, Algorithm, C> MiningWorker<Block, Algorithm, C> where
@@ -84,74 +94,251 @@ impl<Block, Algorithm, C> MiningWorker<Block, Algorithm, C> where
 
 	/// Submit a mined seal. The seal will be validated again. Returns true if the submission is
 	/// successful.
+	///
+	/// The block is imported while the worker is borrowed. Callers sharing the worker behind a
+	/// lock should rather [`seal`](Self::seal) the block under the lock and import it after
+	/// releasing the lock.
 	pub fn submit(&mut self, seal: Seal) -> bool {
-		if let Some(build) = self.build.take() {
-			match self.algorithm.verify(
-				&BlockId::Hash(build.metadata.best_hash),
-				&build.metadata.pre_hash,
-				build.metadata.pre_runtime.as_ref().map(|v| &v[..]),
-				&seal,
-				build.metadata.difficulty,
-			) {
-				Ok(true) => (),
-				Ok(false) => {
-					warn!(
-						target: "pow",
-						"Unable to import mined block: seal is invalid",
-					);
-					return false
-				},
-				Err(err) => {
-					warn!(
-						target: "pow",
-						"Unable to import mined block: {:?}",
-						err,
-					);
-					return false
-				},
-			}
+		self.seal(seal).map_or(false, |block| block.import())
+	}
+
+	/// Validate a mined seal and take the current build to be imported with it.
+	///
+	/// Returns `None` if there is no build or the seal is invalid, in which case the build is kept
+	/// so that mining can go on.
+	pub fn seal(
+		&mut self,
+		seal: Seal,
+	) -> Option<SealedBlock<Block, sp_api::TransactionFor<C, Block>>> {
+		let build = match self.build.take() {
+			Some(build) => build,
+			None => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: build does not exist",
+				);
+				return None
+			},
+		};
+
+		match self.algorithm.verify(
+			&BlockId::Hash(build.metadata.best_hash),
+			&build.metadata.pre_hash,
+			build.metadata.pre_runtime.as_ref().map(|v| &v[..]),
+			&seal,
+			build.metadata.difficulty,
+		) {
+			Ok(true) => (),
+			Ok(false) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: seal is invalid",
+				);
+				self.build = Some(build);
+				return None
+			},
+			Err(err) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: {:?}",
+					err,
+				);
+				self.build = Some(build);
+				return None
+			},
+		}
+
+		let seal = DigestItem::Seal(POW_ENGINE_ID, seal);
+		let (header, body) = build.proposal.block.deconstruct();
+
+		let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+		import_block.post_digests.push(seal);
+		import_block.body = Some(body);
+		import_block.storage_changes = Some(build.proposal.storage_changes);
+
+		let intermediate = PowIntermediate::<Algorithm::Difficulty> {
+			difficulty: Some(build.metadata.difficulty),
+		};
+
+		import_block.intermediates.insert(
+			Cow::from(INTERMEDIATE_KEY),
+			Box::new(intermediate) as Box<dyn Any + Send>
+		);
+
+		Some(SealedBlock {
+			best_hash: build.metadata.best_hash,
+			import_block,
+			block_import: self.block_import.clone(),
+		})
+	}
+}
+
+/// A block sealed by [`MiningWorker::seal`], ready to be imported.
+pub struct SealedBlock<Block: BlockT, Transaction> {
+	best_hash: Block::Hash,
+	import_block: BlockImportParams<Block, Transaction>,
+	block_import: Arc<Mutex<BoxBlockImport<Block, Transaction>>>,
+}
+
+impl<Block: BlockT, Transaction> SealedBlock<Block, Transaction> {
+	/// Import the sealed block. Returns true if the import is successful.
+	pub fn import(self) -> bool {
+		match self.block_import.lock().import_block(self.import_block, HashMap::default()) {
+			Ok(_) => {
+				info!(
+					target: "pow",
+					"✅ Successfully mined block on top of: {}",
+					self.best_hash
+				);
+				true
+			},
+			Err(err) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: {:?}",
+					err,
+				);
+				false
+			},
+		}
+	}
+}
+
+/// A PoW algorithm which can search for seals on the CPU.
+pub trait CpuMiningAlgorithm<B: BlockT>: PowAlgorithm<B> {
+	/// Try to build a valid seal for the given mining metadata with the given nonce.
+	///
+	/// Returns `None` if the nonce does not lead to a seal meeting the difficulty.
+	fn mine(
+		&self,
+		metadata: &MiningMetadata<B::Hash, Self::Difficulty>,
+		nonce: u64,
+	) -> Result<Option<Seal>, Error<B>>;
+}
+
+/// Handle to the threads started by `start_cpu_mining`. Mining stops when it is dropped.
+pub struct CpuMiningHandle {
+	stop: Arc<AtomicBool>,
+	threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl CpuMiningHandle {
+	/// Stop mining and wait for all mining threads to exit.
+	pub fn stop(mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+
+		for thread in self.threads.drain(..) {
+			let _ = thread.join();
+		}
+	}
+}
+
+impl Drop for CpuMiningHandle {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Relaxed);
+	}
+}
+
+/// Start mining on `threads` CPU threads, using the work provided by the given
+/// mining worker and submitting the found seals back to it.
+///
+/// Each thread tries a disjoint set of nonces, and restarts from the beginning
+/// whenever the worker moves on to a new build.
+pub fn start_cpu_mining<Block, Algorithm, C>(
+	worker: Arc<Mutex<MiningWorker<Block, Algorithm, C>>>,
+	algorithm: Algorithm,
+	threads: usize,
+) -> std::io::Result<CpuMiningHandle> where
+	Block: BlockT,
+	C: sp_api::ProvideRuntimeApi<Block> + 'static,
+	Algorithm: CpuMiningAlgorithm<Block> + Clone + Send + 'static,
+	Algorithm::Difficulty: 'static,
+	MiningWorker<Block, Algorithm, C>: Send,
+{
+	let threads = threads.max(1);
+	let stop = Arc::new(AtomicBool::new(false));
+
+	let handles = (0..threads).map(|index| {
+		let worker = worker.clone();
+		let algorithm = algorithm.clone();
+		let stop = stop.clone();
+
+		thread::Builder::new()
+			.name(format!("pow-miner-{}", index))
+			.spawn(move || mine_loop(worker, algorithm, stop, index as u64, threads as u64))
+	}).collect::<Result<Vec<_>, _>>();
+
+	let handles = match handles {
+		Ok(handles) => handles,
+		Err(err) => {
+			stop.store(true, Ordering::Relaxed);
+			return Err(err)
+		},
+	};
 
-			let seal = DigestItem::Seal(POW_ENGINE_ID, seal);
-			let (header, body) = build.proposal.block.deconstruct();
-
-			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
-			import_block.post_digests.push(seal);
-			import_block.body = Some(body);
-			import_block.storage_changes = Some(build.proposal.storage_changes);
-
-			let intermediate = PowIntermediate::<Algorithm::Difficulty> {
-				difficulty: Some(build.metadata.difficulty),
-			};
-
-			import_block.intermediates.insert(
-				Cow::from(INTERMEDIATE_KEY),
-				Box::new(intermediate) as Box<dyn Any>
-			);
-
-			match self.block_import.import_block(import_block, HashMap::default()) {
-				Ok(_) => {
-					info!(
-						target: "pow",
-						"✅ Successfully mined block on top of: {}",
-						build.metadata.best_hash
-					);
-					true
+	Ok(CpuMiningHandle { stop, threads: handles })
+}
+
+fn mine_loop<Block, Algorithm, C>(
+	worker: Arc<Mutex<MiningWorker<Block, Algorithm, C>>>,
+	algorithm: Algorithm,
+	stop: Arc<AtomicBool>,
+	first_nonce: u64,
+	nonce_step: u64,
+) where
+	Block: BlockT,
+	C: sp_api::ProvideRuntimeApi<Block>,
+	Algorithm: CpuMiningAlgorithm<Block>,
+	Algorithm::Difficulty: 'static,
+{
+	let mut pre_hash = None;
+	let mut nonce = first_nonce;
+
+	while !stop.load(Ordering::Relaxed) {
+		let metadata = match worker.lock().metadata() {
+			Some(metadata) => metadata,
+			None => {
+				thread::sleep(IDLE_DURATION);
+				continue
+			},
+		};
+
+		if pre_hash != Some(metadata.pre_hash) {
+			pre_hash = Some(metadata.pre_hash);
+			nonce = first_nonce;
+		}
+
+		for _ in 0..NONCES_PER_ROUND {
+			match algorithm.mine(&metadata, nonce) {
+				Ok(Some(seal)) => {
+					let block = {
+						let mut worker = worker.lock();
+
+						// another thread may have already submitted a seal for this build.
+						if worker.metadata().map(|m| m.pre_hash) == pre_hash {
+							worker.seal(seal)
+						} else {
+							None
+						}
+					};
+
+					// the block is imported without holding the worker lock, so that the other
+					// threads are not blocked for the duration of the import.
+					if let Some(block) = block {
+						block.import();
+					}
+
+					break
 				},
+				Ok(None) => {},
 				Err(err) => {
-					warn!(
-						target: "pow",
-						"Unable to import mined block: {:?}",
-						err,
-					);
-					false
+					warn!(target: "pow", "Mining failed: {:?}", err);
+					thread::sleep(IDLE_DURATION);
+					break
 				},
 			}
-		} else {
-			warn!(
-				target: "pow",
-				"Unable to import mined block: build does not exist",
-			);
-			false
+
+			nonce = nonce.wrapping_add(nonce_step);
 		}
 	}
 }
@@ -0,0 +1,871 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sassafras (Semi Anonymous Sortition of Staked Assignees For Fixed-time Rhythmic Assignment
+//! of Slots) consensus in substrate.
+//!
+//! Time is divided in slots of t seconds each and slots are grouped in epochs. During the
+//! first half of an epoch every authority submits VRF tickets for the next epoch through
+//! unsigned extrinsics. The runtime sorts the tickets by their VRF output and assigns them, in
+//! order, to the slots of the next epoch. Every slot thus has exactly one author known in
+//! advance, which is queried from the runtime through [`SassafrasApi::slot_author`]. Slots
+//! without a ticket fall back to round-robin assignment.
+//!
+//! The author is allowed to issue one block but not more during that slot,
+//! and it will be built upon the longest valid chain that has been seen.
+//!
+//! Blocks from future slots will be either deferred or rejected depending on how
+//! far in the future they are.
+#![forbid(missing_docs, unsafe_code)]
+use std::{
+	sync::Arc, time::Duration, thread, marker::PhantomData, pin::Pin,
+	collections::HashMap, convert::TryInto,
+};
+
+use futures::prelude::*;
+use parking_lot::Mutex;
+use log::{debug, info, trace, warn};
+use prometheus_endpoint::Registry;
+
+use sp_consensus::{
+	self, BlockImport, Environment, Proposer, CanAuthorWith, ForkChoiceStrategy, BlockImportParams,
+	BlockOrigin, Error as ConsensusError, SelectChain, SlotData, BlockCheckParams, ImportResult
+};
+use sp_consensus::import_queue::{
+	Verifier, BasicQueue, DefaultImportQueue, BoxJustificationImport,
+};
+use sc_client_api::{backend::AuxStore, BlockOf};
+use sp_blockchain::{self, Result as CResult, well_known_cache_keys::Id as CacheKeyId, HeaderBackend};
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_core::crypto::{Pair, Public};
+use sp_application_crypto::AppKey;
+use sp_runtime::{
+	generic::BlockId,
+	traits::NumberFor, Justification,
+};
+use sp_runtime::traits::{Block as BlockT, Header, DigestItemFor, Zero};
+use sp_api::ProvideRuntimeApi;
+use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
+use sp_inherents::{InherentDataProviders, InherentData};
+use sp_timestamp::{
+	TimestampInherentData, InherentType as TimestampInherent, InherentError as TIError
+};
+use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG, CONSENSUS_INFO};
+
+use sc_consensus_slots::{
+	CheckedHeader, SlotInfo, SlotCompatible, StorageChanges, check_equivocation,
+	BackoffAuthoringBlocksStrategy,
+};
+
+use sp_api::ApiExt;
+
+pub use sp_consensus_sassafras::{
+	SassafrasApi, SassafrasConfiguration, SASSAFRAS_ENGINE_ID, AuthorityId, AuthorityIndex,
+	AuthorityPair, AuthoritySignature, Ticket, VRFOutput, VRFProof, make_ticket_transcript_data,
+	digests::{CompatibleDigestItem, PreDigest},
+	inherents::{
+		InherentType as SassafrasInherent,
+		SassafrasInherentData, INHERENT_IDENTIFIER, InherentDataProvider,
+	},
+};
+pub use sp_consensus::SyncOracle;
+
+/// Slot duration type for Sassafras.
+pub type SlotDuration = sc_consensus_slots::SlotDuration<SassafrasConfiguration>;
+
+/// Get type of `SlotDuration` for Sassafras.
+pub fn slot_duration<B, C>(client: &C) -> CResult<SlotDuration> where
+	B: BlockT,
+	C: AuxStore + ProvideRuntimeApi<B>,
+	C::Api: SassafrasApi<B, Error = sp_blockchain::Error>,
+{
+	SlotDuration::get_or_compute(client, |a, b| a.configuration(b))
+}
+
+/// Get the author of the given slot, as assigned by the runtime at the given block.
+fn slot_author<B, C>(
+	client: &C,
+	at: &BlockId<B>,
+	slot_number: u64,
+	authorities: &[AuthorityId],
+) -> Option<(AuthorityIndex, AuthorityId)> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: SassafrasApi<B>,
+{
+	let authority_index = client.runtime_api().slot_author(at, slot_number).ok()??;
+
+	authorities
+		.get(authority_index as usize)
+		.map(|authority| (authority_index, authority.clone()))
+}
+
+/// Generate the tickets for the epoch after the one described by `config`, for every authority
+/// of `config` with a key in the given keystore.
+pub fn generate_tickets(
+	keystore: &SyncCryptoStorePtr,
+	config: &SassafrasConfiguration,
+) -> Vec<Ticket> {
+	let next_epoch = config.epoch_index + 1;
+
+	config.authorities
+		.iter()
+		.enumerate()
+		.filter(|(_, authority)| SyncCryptoStore::has_keys(
+			&**keystore,
+			&[(authority.to_raw_vec(), AuthorityId::ID)],
+		))
+		.flat_map(|(authority_index, authority)| (0..config.max_attempts).filter_map(move |attempt| {
+			let transcript_data = make_ticket_transcript_data(
+				&config.randomness,
+				attempt,
+				next_epoch,
+			);
+
+			SyncCryptoStore::sr25519_vrf_sign(
+				&**keystore,
+				AuthorityId::ID,
+				authority.as_ref(),
+				transcript_data,
+			).ok().map(|signature| Ticket {
+				authority_index: authority_index as AuthorityIndex,
+				attempt,
+				vrf_output: VRFOutput(signature.output),
+				vrf_proof: VRFProof(signature.proof),
+			})
+		}))
+		.collect()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct SassafrasSlotCompatible;
+
+impl SlotCompatible for SassafrasSlotCompatible {
+	fn extract_timestamp_and_slot(
+		&self,
+		data: &InherentData,
+	) -> Result<(TimestampInherent, SassafrasInherent, std::time::Duration), sp_consensus::Error> {
+		data.timestamp_inherent_data()
+			.and_then(|t| data.sassafras_inherent_data().map(|a| (t, a)))
+			.map_err(Into::into)
+			.map_err(sp_consensus::Error::InherentData)
+			.map(|(x, y)| (x, y, Default::default()))
+	}
+}
+
+/// Start the sassafras worker. The returned future should be run in a futures executor.
+pub fn start_sassafras<B, C, SC, E, I, SO, CAW, BS, Error>(
+	slot_duration: SlotDuration,
+	client: Arc<C>,
+	select_chain: SC,
+	block_import: I,
+	env: E,
+	sync_oracle: SO,
+	inherent_data_providers: InherentDataProviders,
+	force_authoring: bool,
+	backoff_authoring_blocks: Option<BS>,
+	keystore: SyncCryptoStorePtr,
+	can_author_with: CAW,
+) -> Result<impl Future<Output = ()>, sp_consensus::Error> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + BlockOf + AuxStore + HeaderBackend<B> + Send + Sync,
+	C::Api: SassafrasApi<B>,
+	SC: SelectChain<B>,
+	E: Environment<B, Error = Error> + Send + Sync + 'static,
+	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
+	SO: SyncOracle + Send + Sync + Clone,
+	CAW: CanAuthorWith<B> + Send,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + 'static,
+{
+	let worker = SassafrasWorker {
+		client,
+		block_import: Arc::new(Mutex::new(block_import)),
+		env,
+		keystore,
+		sync_oracle: sync_oracle.clone(),
+		force_authoring,
+		backoff_authoring_blocks,
+		tickets_submitted_for: Mutex::new(None),
+	};
+	register_sassafras_inherent_data_provider(
+		&inherent_data_providers,
+		slot_duration.slot_duration()
+	)?;
+	Ok(sc_consensus_slots::start_slot_worker::<_, _, _, _, _, SassafrasSlotCompatible, _>(
+		slot_duration,
+		select_chain,
+		worker,
+		sync_oracle,
+		inherent_data_providers,
+		SassafrasSlotCompatible,
+		can_author_with,
+	))
+}
+
+struct SassafrasWorker<C, E, I, SO, BS> {
+	client: Arc<C>,
+	block_import: Arc<Mutex<I>>,
+	env: E,
+	keystore: SyncCryptoStorePtr,
+	sync_oracle: SO,
+	force_authoring: bool,
+	backoff_authoring_blocks: Option<BS>,
+	/// The epoch we last submitted tickets for.
+	tickets_submitted_for: Mutex<Option<u64>>,
+}
+
+impl<B, C, E, I, Error, SO, BS> sc_consensus_slots::SimpleSlotWorker<B>
+	for SassafrasWorker<C, E, I, SO, BS>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + BlockOf + HeaderBackend<B> + Sync,
+	C::Api: SassafrasApi<B>,
+	E: Environment<B, Error = Error>,
+	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Clone,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + 'static,
+	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
+{
+	type BlockImport = I;
+	type SyncOracle = SO;
+	type CreateProposer = Pin<Box<
+		dyn Future<Output = Result<E::Proposer, sp_consensus::Error>> + Send + 'static
+	>>;
+	type Proposer = E::Proposer;
+	type Claim = (AuthorityIndex, AuthorityId);
+	type EpochData = SassafrasConfiguration;
+
+	fn logging_target(&self) -> &'static str {
+		"sassafras"
+	}
+
+	fn block_import(&self) -> Arc<Mutex<Self::BlockImport>> {
+		self.block_import.clone()
+	}
+
+	fn epoch_data(
+		&self,
+		header: &B::Header,
+		_slot_number: u64,
+	) -> Result<Self::EpochData, sp_consensus::Error> {
+		self.client
+			.runtime_api()
+			.configuration(&BlockId::Hash(header.hash()))
+			.map_err(|e| sp_consensus::Error::ChainLookup(format!("{:?}", e)))
+	}
+
+	fn authorities_len(&self, epoch_data: &Self::EpochData) -> Option<usize> {
+		Some(epoch_data.authorities.len())
+	}
+
+	fn claim_slot(
+		&self,
+		header: &B::Header,
+		slot_number: u64,
+		epoch_data: &Self::EpochData,
+	) -> Option<Self::Claim> {
+		let expected_author = slot_author(
+			&*self.client,
+			&BlockId::Hash(header.hash()),
+			slot_number,
+			&epoch_data.authorities,
+		);
+
+		expected_author.filter(|(_, author)| SyncCryptoStore::has_keys(
+			&*self.keystore,
+			&[(author.to_raw_vec(), AuthorityId::ID)],
+		))
+	}
+
+	fn notify_slot(
+		&self,
+		header: &B::Header,
+		_slot_number: u64,
+		epoch_data: &Self::EpochData,
+	) {
+		let next_epoch = epoch_data.epoch_index + 1;
+		// the epoch is only recorded once the tickets are submitted, so that a failed
+		// submission is retried on the next slot.
+		if *self.tickets_submitted_for.lock() == Some(next_epoch) {
+			return;
+		}
+
+		let tickets = generate_tickets(&self.keystore, epoch_data);
+		if tickets.is_empty() {
+			return;
+		}
+
+		let tickets_len = tickets.len();
+		match self.client.runtime_api().submit_tickets_unsigned_extrinsic(
+			&BlockId::Hash(header.hash()),
+			tickets,
+		) {
+			Ok(true) => {
+				*self.tickets_submitted_for.lock() = Some(next_epoch);
+				debug!(
+					target: "sassafras",
+					"Submitted {} tickets for epoch {}",
+					tickets_len,
+					next_epoch,
+				);
+			},
+			Ok(false) => warn!(
+				target: "sassafras",
+				"Unable to submit tickets for epoch {}, retrying on the next slot",
+				next_epoch,
+			),
+			Err(e) => warn!(
+				target: "sassafras",
+				"Error submitting tickets for epoch {}, retrying on the next slot: {:?}",
+				next_epoch,
+				e,
+			),
+		}
+	}
+
+	fn pre_digest_data(
+		&self,
+		slot_number: u64,
+		claim: &Self::Claim,
+	) -> Vec<sp_runtime::DigestItem<B::Hash>> {
+		vec![
+			<DigestItemFor<B> as CompatibleDigestItem>::sassafras_pre_digest(PreDigest {
+				authority_index: claim.0,
+				slot_number,
+			}),
+		]
+	}
+
+	fn block_import_params(&self) -> Box<dyn Fn(
+		B::Header,
+		&B::Hash,
+		Vec<B::Extrinsic>,
+		StorageChanges<sp_api::TransactionFor<C, B>, B>,
+		Self::Claim,
+		Self::EpochData,
+	) -> Result<
+		sp_consensus::BlockImportParams<B, sp_api::TransactionFor<C, B>>,
+		sp_consensus::Error> + Send + 'static>
+	{
+		let keystore = self.keystore.clone();
+		Box::new(move |header, header_hash, body, storage_changes, (_, public), _epoch| {
+			// sign the pre-sealed hash of the block and then
+			// add it to a digest item.
+			let public_type_pair = public.to_public_crypto_pair();
+			let public = public.to_raw_vec();
+			let signature = SyncCryptoStore::sign_with(
+				&*keystore,
+				<AuthorityId as AppKey>::ID,
+				&public_type_pair,
+				header_hash.as_ref()
+			).map_err(|e| sp_consensus::Error::CannotSign(
+				public.clone(), e.to_string(),
+			))?;
+			let signature: AuthoritySignature = signature.clone().try_into()
+				.map_err(|_| sp_consensus::Error::InvalidSignature(
+					signature, public
+				))?;
+
+			let signature_digest_item =
+				<DigestItemFor<B> as CompatibleDigestItem>::sassafras_seal(signature);
+
+			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+			import_block.post_digests.push(signature_digest_item);
+			import_block.body = Some(body);
+			import_block.storage_changes = Some(storage_changes);
+			import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+
+			Ok(import_block)
+		})
+	}
+
+	fn force_authoring(&self) -> bool {
+		self.force_authoring
+	}
+
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		if let Some(ref strategy) = self.backoff_authoring_blocks {
+			if let Ok(chain_head_slot) = find_pre_digest::<B>(chain_head) {
+				return strategy.should_backoff(
+					*chain_head.number(),
+					chain_head_slot.slot_number,
+					self.client.info().finalized_number,
+					slot_number,
+					self.logging_target(),
+				);
+			}
+		}
+		false
+	}
+
+	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
+		&mut self.sync_oracle
+	}
+
+	fn proposer(&mut self, block: &B::Header) -> Self::CreateProposer {
+		Box::pin(self.env.init(block).map_err(|e| {
+			sp_consensus::Error::ClientImport(format!("{:?}", e)).into()
+		}))
+	}
+
+	fn proposing_remaining_duration(
+		&self,
+		head: &B::Header,
+		slot_info: &SlotInfo,
+	) -> Option<std::time::Duration> {
+		let slot_remaining = self.slot_remaining_duration(slot_info);
+
+		let parent_slot = match find_pre_digest::<B>(head) {
+			Err(_) => return Some(slot_remaining),
+			Ok(d) => d.slot_number,
+		};
+
+		if let Some(slot_lenience) =
+			sc_consensus_slots::slot_lenience_exponential(parent_slot, slot_info)
+		{
+			debug!(target: "sassafras",
+				"No block for {} slots. Applying linear lenience of {}s",
+				slot_info.number.saturating_sub(parent_slot + 1),
+				slot_lenience.as_secs(),
+			);
+
+			Some(slot_remaining + slot_lenience)
+		} else {
+			Some(slot_remaining)
+		}
+	}
+}
+
+fn sassafras_err<B: BlockT>(error: Error<B>) -> Error<B> {
+	debug!(target: "sassafras", "{}", error);
+	error
+}
+
+#[derive(derive_more::Display, Debug)]
+enum Error<B: BlockT> {
+	#[display(fmt = "Multiple Sassafras pre-runtime headers")]
+	MultipleHeaders,
+	#[display(fmt = "No Sassafras pre-runtime digest found")]
+	NoDigestFound,
+	#[display(fmt = "Header {:?} is unsealed", _0)]
+	HeaderUnsealed(B::Hash),
+	#[display(fmt = "Header {:?} has a bad seal", _0)]
+	HeaderBadSeal(B::Hash),
+	#[display(fmt = "Slot Author not found")]
+	SlotAuthorNotFound,
+	#[display(fmt = "Slot {} is assigned to authority {}, not {}", _0, _1, _2)]
+	UnexpectedAuthor(u64, AuthorityIndex, AuthorityIndex),
+	#[display(fmt = "Bad signature on {:?}", _0)]
+	BadSignature(B::Hash),
+	#[display(fmt = "Rejecting block too far in future")]
+	TooFarInFuture,
+	Client(sp_blockchain::Error),
+	DataProvider(String),
+	Runtime(String),
+	#[display(fmt = "Slot number must increase: parent slot: {}, this slot: {}", _0, _1)]
+	SlotNumberMustIncrease(u64, u64),
+	#[display(fmt = "Parent ({}) of {} unavailable. Cannot import", _0, _1)]
+	ParentUnavailable(B::Hash, B::Hash),
+}
+
+impl<B: BlockT> std::convert::From<Error<B>> for String {
+	fn from(error: Error<B>) -> String {
+		error.to_string()
+	}
+}
+
+fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<PreDigest, Error<B>> {
+	if header.number().is_zero() {
+		return Ok(PreDigest { authority_index: 0, slot_number: 0 });
+	}
+
+	let mut pre_digest: Option<PreDigest> = None;
+	for log in header.digest().logs() {
+		trace!(target: "sassafras", "Checking log {:?}", log);
+		match (log.as_sassafras_pre_digest(), pre_digest.is_some()) {
+			(Some(_), true) => Err(sassafras_err(Error::MultipleHeaders))?,
+			(None, _) => trace!(target: "sassafras", "Ignoring digest not meant for us"),
+			(s, false) => pre_digest = s,
+		}
+	}
+	pre_digest.ok_or_else(|| sassafras_err(Error::NoDigestFound))
+}
+
+/// check a header has been signed by the author the runtime assigned to its slot. If the slot
+/// is too far in the future, an error will be returned. if it's successful, returns the
+/// pre-header and the digest item containing the seal.
+///
+/// This digest item will always return `Some` when used with `as_sassafras_seal`.
+fn check_header<C, B: BlockT>(
+	client: &C,
+	slot_now: u64,
+	mut header: B::Header,
+	hash: B::Hash,
+	authorities: &[AuthorityId],
+) -> Result<CheckedHeader<B::Header, (u64, DigestItemFor<B>)>, Error<B>> where
+	C: ProvideRuntimeApi<B> + AuxStore,
+	C::Api: SassafrasApi<B>,
+{
+	let seal = match header.digest_mut().pop() {
+		Some(x) => x,
+		None => return Err(Error::HeaderUnsealed(hash)),
+	};
+
+	let sig = seal.as_sassafras_seal().ok_or_else(|| {
+		sassafras_err(Error::HeaderBadSeal(hash))
+	})?;
+
+	let pre_digest = find_pre_digest::<B>(&header)?;
+	let slot_num = pre_digest.slot_number;
+
+	if slot_num > slot_now {
+		header.digest_mut().push(seal);
+		Ok(CheckedHeader::Deferred(header, slot_num))
+	} else {
+		// check the block is authored by the authority assigned to the slot,
+		// as of the parent state.
+		let (expected_index, expected_author) = match slot_author(
+			client,
+			&BlockId::Hash(*header.parent_hash()),
+			slot_num,
+			authorities,
+		) {
+			None => return Err(Error::SlotAuthorNotFound),
+			Some(author) => author,
+		};
+
+		if pre_digest.authority_index != expected_index {
+			return Err(sassafras_err(Error::UnexpectedAuthor(
+				slot_num,
+				expected_index,
+				pre_digest.authority_index,
+			)));
+		}
+
+		let pre_hash = header.hash();
+
+		if AuthorityPair::verify(&sig, pre_hash, &expected_author) {
+			if let Some(equivocation_proof) = check_equivocation(
+				client,
+				slot_now,
+				slot_num,
+				&header,
+				&expected_author,
+			).map_err(Error::Client)? {
+				info!(
+					"Slot author is equivocating at slot {} with headers {:?} and {:?}",
+					slot_num,
+					equivocation_proof.first_header.hash(),
+					equivocation_proof.second_header.hash(),
+				);
+			}
+
+			Ok(CheckedHeader::Checked(header, (slot_num, seal)))
+		} else {
+			Err(Error::BadSignature(hash))
+		}
+	}
+}
+
+/// A verifier for Sassafras blocks.
+pub struct SassafrasVerifier<C, CAW> {
+	client: Arc<C>,
+	inherent_data_providers: sp_inherents::InherentDataProviders,
+	can_author_with: CAW,
+}
+
+impl<C, CAW> SassafrasVerifier<C, CAW> where
+	CAW: Send + Sync + 'static,
+{
+	fn check_inherents<B: BlockT>(
+		&self,
+		block: B,
+		block_id: BlockId<B>,
+		inherent_data: InherentData,
+		timestamp_now: u64,
+	) -> Result<(), Error<B>> where
+		C: ProvideRuntimeApi<B>, C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>,
+		CAW: CanAuthorWith<B>,
+	{
+		const MAX_TIMESTAMP_DRIFT_SECS: u64 = 60;
+
+		if let Err(e) = self.can_author_with.can_author_with(&block_id) {
+			debug!(
+				target: "sassafras",
+				"Skipping `check_inherents` as authoring version is not compatible: {}",
+				e,
+			);
+
+			return Ok(())
+		}
+
+		let inherent_res = self.client.runtime_api().check_inherents(
+			&block_id,
+			block,
+			inherent_data,
+		).map_err(Error::Client)?;
+
+		if !inherent_res.ok() {
+			inherent_res
+				.into_errors()
+				.try_for_each(|(i, e)| match TIError::try_from(&i, &e) {
+					Some(TIError::ValidAtTimestamp(timestamp)) => {
+						// halt import until timestamp is valid.
+						// reject when too far ahead.
+						if timestamp > timestamp_now + MAX_TIMESTAMP_DRIFT_SECS {
+							return Err(Error::TooFarInFuture);
+						}
+
+						let diff = timestamp.saturating_sub(timestamp_now);
+						info!(
+							target: "sassafras",
+							"halting for block {} seconds in the future",
+							diff
+						);
+						telemetry!(CONSENSUS_INFO; "sassafras.halting_for_future_block";
+							"diff" => ?diff
+						);
+						thread::sleep(Duration::from_secs(diff));
+						Ok(())
+					},
+					Some(TIError::Other(e)) => Err(Error::Runtime(e.into())),
+					None => Err(Error::DataProvider(
+						self.inherent_data_providers.error_to_string(&i, &e)
+					)),
+				})
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl<B: BlockT, C, CAW> Verifier<B> for SassafrasVerifier<C, CAW> where
+	C: ProvideRuntimeApi<B> + Send + Sync + AuxStore + BlockOf,
+	C::Api: BlockBuilderApi<B> + SassafrasApi<B> + ApiExt<B, Error = sp_blockchain::Error>,
+	CAW: CanAuthorWith<B> + Send + Sync + 'static,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: B::Header,
+		justification: Option<Justification>,
+		mut body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let mut inherent_data = self.inherent_data_providers
+			.create_inherent_data()
+			.map_err(|e| e.into_string())?;
+		let (timestamp_now, slot_now, _) = SassafrasSlotCompatible
+			.extract_timestamp_and_slot(&inherent_data)
+			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
+		let hash = header.hash();
+		let parent_hash = *header.parent_hash();
+		let configuration = self.client
+			.runtime_api()
+			.configuration(&BlockId::Hash(parent_hash))
+			.map_err(|e| format!("Could not fetch configuration at {:?}: {:?}", parent_hash, e))?;
+
+		// we add one to allow for some small drift.
+		let checked_header = check_header::<C, B>(
+			&self.client,
+			slot_now + 1,
+			header,
+			hash,
+			&configuration.authorities[..],
+		).map_err(|e| e.to_string())?;
+		match checked_header {
+			CheckedHeader::Checked(pre_header, (slot_num, seal)) => {
+				// if the body is passed through, we need to use the runtime
+				// to check that the internally-set timestamp in the inherents
+				// actually matches the slot set in the seal.
+				if let Some(inner_body) = body.take() {
+					inherent_data.sassafras_replace_inherent_data(slot_num);
+					let block = B::new(pre_header.clone(), inner_body);
+
+					self.check_inherents(
+						block.clone(),
+						BlockId::Hash(parent_hash),
+						inherent_data,
+						timestamp_now,
+					).map_err(|e| e.to_string())?;
+
+					let (_, inner_body) = block.deconstruct();
+					body = Some(inner_body);
+				}
+
+				trace!(target: "sassafras", "Checked {:?}; importing.", pre_header);
+				telemetry!(CONSENSUS_TRACE; "sassafras.checked_and_importing";
+					"pre_header" => ?pre_header
+				);
+
+				let mut import_block = BlockImportParams::new(origin, pre_header);
+				import_block.post_digests.push(seal);
+				import_block.body = body;
+				import_block.justification = justification;
+				import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+				import_block.post_hash = Some(hash);
+
+				Ok((import_block, None))
+			}
+			CheckedHeader::Deferred(a, b) => {
+				debug!(target: "sassafras", "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
+				telemetry!(CONSENSUS_DEBUG; "sassafras.header_too_far_in_future";
+					"hash" => ?hash, "a" => ?a, "b" => ?b
+				);
+				Err(format!("Header {:?} rejected: too far in the future", hash))
+			}
+		}
+	}
+}
+
+/// Register the sassafras inherent data provider, if not registered already.
+fn register_sassafras_inherent_data_provider(
+	inherent_data_providers: &InherentDataProviders,
+	slot_duration: u64,
+) -> Result<(), sp_consensus::Error> {
+	if !inherent_data_providers.has_provider(&INHERENT_IDENTIFIER) {
+		inherent_data_providers
+			.register_provider(InherentDataProvider::new(slot_duration))
+			.map_err(Into::into)
+			.map_err(sp_consensus::Error::InherentData)
+	} else {
+		Ok(())
+	}
+}
+
+/// A block-import handler for Sassafras.
+pub struct SassafrasBlockImport<Block: BlockT, C, I: BlockImport<Block>> {
+	inner: I,
+	client: Arc<C>,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block: BlockT, C, I: Clone + BlockImport<Block>> Clone for SassafrasBlockImport<Block, C, I> {
+	fn clone(&self) -> Self {
+		SassafrasBlockImport {
+			inner: self.inner.clone(),
+			client: self.client.clone(),
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<Block: BlockT, C, I: BlockImport<Block>> SassafrasBlockImport<Block, C, I> {
+	/// New sassafras block import.
+	pub fn new(
+		inner: I,
+		client: Arc<C>,
+	) -> Self {
+		Self {
+			inner,
+			client,
+			_phantom: PhantomData,
+		}
+	}
+}
+
+impl<Block: BlockT, C, I> BlockImport<Block> for SassafrasBlockImport<Block, C, I> where
+	I: BlockImport<Block, Transaction = sp_api::TransactionFor<C, Block>> + Send + Sync,
+	I::Error: Into<ConsensusError>,
+	C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+{
+	type Error = ConsensusError;
+	type Transaction = sp_api::TransactionFor<C, Block>;
+
+	fn check_block(
+		&mut self,
+		block: BlockCheckParams<Block>,
+	) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block).map_err(Into::into)
+	}
+
+	fn import_block(
+		&mut self,
+		block: BlockImportParams<Block, Self::Transaction>,
+		new_cache: HashMap<CacheKeyId, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		let hash = block.post_hash();
+		let slot_number = find_pre_digest::<Block>(&block.header)
+			.expect("valid Sassafras headers must contain a predigest; \
+					 header has been already verified; qed")
+			.slot_number;
+
+		let parent_hash = *block.header.parent_hash();
+		let parent_header = self.client.header(BlockId::Hash(parent_hash))
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.ok_or_else(|| ConsensusError::ChainLookup(sassafras_err(
+				Error::<Block>::ParentUnavailable(parent_hash, hash)
+			).into()))?;
+
+		let parent_slot = find_pre_digest::<Block>(&parent_header)
+			.expect("valid Sassafras headers contain a pre-digest; \
+					parent header has already been verified; qed")
+			.slot_number;
+
+		// make sure that slot number is strictly increasing
+		if slot_number <= parent_slot {
+			return Err(
+				ConsensusError::ClientImport(sassafras_err(
+					Error::<Block>::SlotNumberMustIncrease(parent_slot, slot_number)
+				).into())
+			);
+		}
+
+		self.inner.import_block(block, new_cache).map_err(Into::into)
+	}
+}
+
+/// Start an import queue for the Sassafras consensus algorithm.
+pub fn import_queue<B, I, C, S, CAW>(
+	slot_duration: SlotDuration,
+	block_import: I,
+	justification_import: Option<BoxJustificationImport<B>>,
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+	spawner: &S,
+	registry: Option<&Registry>,
+	can_author_with: CAW,
+) -> Result<DefaultImportQueue<B, C>, sp_consensus::Error> where
+	B: BlockT,
+	C::Api: BlockBuilderApi<B> + SassafrasApi<B> + ApiExt<B, Error = sp_blockchain::Error>,
+	C: 'static + ProvideRuntimeApi<B> + BlockOf + Send + Sync + AuxStore + HeaderBackend<B>,
+	I: BlockImport<B, Error=ConsensusError, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	S: sp_core::traits::SpawnNamed,
+	CAW: CanAuthorWith<B> + Send + Sync + 'static,
+{
+	register_sassafras_inherent_data_provider(&inherent_data_providers, slot_duration.slot_duration())?;
+
+	let verifier = SassafrasVerifier {
+		client,
+		inherent_data_providers,
+		can_author_with,
+	};
+
+	Ok(BasicQueue::new(
+		verifier,
+		Box::new(block_import),
+		justification_import,
+		spawner,
+		registry,
+	))
+}
+
+#[cfg(test)]
+mod tests;
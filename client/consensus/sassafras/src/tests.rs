@@ -0,0 +1,383 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sassafras testsuite
+
+use super::*;
+use sp_consensus::{NoNetwork as DummyOracle, Proposal, RecordProof, AlwaysCanAuthor};
+use sc_network_test::{Block as TestBlock, *};
+use sp_runtime::traits::{Block as BlockT, DigestFor};
+use sc_network::config::ProtocolConfig;
+use sp_keyring::sr25519::Keyring;
+use sc_client_api::{BlockchainEvents, ExecutorProvider};
+use sc_consensus_slots::{SimpleSlotWorker, BackoffAuthoringOnFinalizedHeadLagging};
+use std::task::Poll;
+use sc_block_builder::BlockBuilderProvider;
+use sp_runtime::traits::Header as _;
+use sp_application_crypto::key_types::SASSAFRAS;
+use substrate_test_runtime_client::{TestClient, runtime::{Extrinsic, Header, H256}};
+use sc_keystore::LocalKeystore;
+
+type Error = sp_blockchain::Error;
+
+struct DummyFactory(Arc<TestClient>);
+struct DummyProposer(u64, Arc<TestClient>);
+
+impl Environment<TestBlock> for DummyFactory {
+	type Proposer = DummyProposer;
+	type CreateProposer = futures::future::Ready<Result<DummyProposer, Error>>;
+	type Error = Error;
+
+	fn init(&mut self, parent_header: &<TestBlock as BlockT>::Header)
+		-> Self::CreateProposer
+	{
+		futures::future::ready(Ok(DummyProposer(parent_header.number + 1, self.0.clone())))
+	}
+}
+
+impl Proposer<TestBlock> for DummyProposer {
+	type Error = Error;
+	type Transaction = sc_client_api::TransactionFor<
+		substrate_test_runtime_client::Backend,
+		TestBlock
+	>;
+	type Proposal = future::Ready<Result<Proposal<TestBlock, Self::Transaction>, Error>>;
+
+	fn propose(
+		self,
+		_: InherentData,
+		digests: DigestFor<TestBlock>,
+		_: Duration,
+		_: RecordProof,
+	) -> Self::Proposal {
+		let r = self.1.new_block(digests).unwrap().build().map_err(|e| e.into());
+
+		future::ready(r.map(|b| Proposal {
+			block: b.block,
+			proof: b.proof,
+			storage_changes: b.storage_changes,
+		}))
+	}
+}
+
+const SLOT_DURATION: u64 = 1000;
+
+pub struct SassafrasTestNet {
+	peers: Vec<Peer<()>>,
+}
+
+impl TestNetFactory for SassafrasTestNet {
+	type Verifier = SassafrasVerifier<PeersFullClient, AlwaysCanAuthor>;
+	type PeerData = ();
+
+	/// Create new test network with peers and given config.
+	fn from_config(_config: &ProtocolConfig) -> Self {
+		SassafrasTestNet {
+			peers: Vec::new(),
+		}
+	}
+
+	fn make_verifier(&self, client: PeersClient, _cfg: &ProtocolConfig, _peer_data: &())
+		-> Self::Verifier
+	{
+		match client {
+			PeersClient::Full(client, _) => {
+				let slot_duration = slot_duration(&*client).expect("slot duration available");
+				let inherent_data_providers = InherentDataProviders::new();
+				register_sassafras_inherent_data_provider(
+					&inherent_data_providers,
+					slot_duration.slot_duration(),
+				).expect("Registers sassafras inherent data provider");
+
+				assert_eq!(slot_duration.slot_duration(), SLOT_DURATION);
+				SassafrasVerifier {
+					client,
+					inherent_data_providers,
+					can_author_with: AlwaysCanAuthor,
+				}
+			},
+			PeersClient::Light(_, _) => unreachable!("No (yet) tests for light client + Sassafras"),
+		}
+	}
+
+	fn peer(&mut self, i: usize) -> &mut Peer<Self::PeerData> {
+		&mut self.peers[i]
+	}
+
+	fn peers(&self) -> &Vec<Peer<Self::PeerData>> {
+		&self.peers
+	}
+
+	fn mut_peers<F: FnOnce(&mut Vec<Peer<Self::PeerData>>)>(&mut self, closure: F) {
+		closure(&mut self.peers);
+	}
+}
+
+fn create_keystore(key: Option<Keyring>) -> (SyncCryptoStorePtr, tempfile::TempDir) {
+	let keystore_path = tempfile::tempdir().expect("Creates keystore path");
+	let keystore = Arc::new(LocalKeystore::open(keystore_path.path(), None)
+		.expect("Creates keystore."));
+
+	SyncCryptoStore::sr25519_generate_new(&*keystore, SASSAFRAS, key.map(|k| k.to_seed()).as_deref())
+		.expect("Creates authority key");
+
+	(keystore, keystore_path)
+}
+
+#[test]
+fn authoring_blocks() {
+	sp_tracing::try_init_simple();
+	let net = SassafrasTestNet::new(3);
+
+	let peers = &[
+		(0, Keyring::Alice),
+		(1, Keyring::Bob),
+		(2, Keyring::Charlie),
+	];
+
+	let net = Arc::new(Mutex::new(net));
+	let mut import_notifications = Vec::new();
+	let mut sassafras_futures = Vec::new();
+
+	let mut keystore_paths = Vec::new();
+	for (peer_id, key) in peers {
+		let mut net = net.lock();
+		let peer = net.peer(*peer_id);
+		let client = peer.client().as_full().expect("full clients are created").clone();
+		let select_chain = peer.select_chain().expect("full client has a select chain");
+		let (keystore, keystore_path) = create_keystore(Some(*key));
+		keystore_paths.push(keystore_path);
+
+		let environ = DummyFactory(client.clone());
+		import_notifications.push(
+			client.import_notification_stream()
+				.take_while(|n| future::ready(!(n.origin != BlockOrigin::Own && n.header.number() < &5)))
+				.for_each(move |_| future::ready(()))
+		);
+
+		let slot_duration = slot_duration(&*client).expect("slot duration available");
+
+		let inherent_data_providers = InherentDataProviders::new();
+		register_sassafras_inherent_data_provider(
+			&inherent_data_providers, slot_duration.slot_duration()
+		).expect("Registers sassafras inherent data provider");
+
+		sassafras_futures.push(start_sassafras(
+			slot_duration,
+			client.clone(),
+			select_chain,
+			client,
+			environ,
+			DummyOracle,
+			inherent_data_providers,
+			false,
+			Some(BackoffAuthoringOnFinalizedHeadLagging::default()),
+			keystore,
+			sp_consensus::AlwaysCanAuthor,
+		).expect("Starts sassafras"));
+	}
+
+	futures::executor::block_on(future::select(
+		future::poll_fn(move |cx| {
+			net.lock().poll(cx);
+			Poll::<()>::Pending
+		}),
+		future::select(
+			future::join_all(sassafras_futures),
+			future::join_all(import_notifications)
+		)
+	));
+}
+
+#[test]
+fn only_the_assigned_authority_claims_the_slot() {
+	let mut net = SassafrasTestNet::new(1);
+	let peer = net.peer(0);
+	let client = peer.client().as_full().expect("full clients are created").clone();
+	let (keystore, _keystore_path) = create_keystore(Some(Keyring::Bob));
+
+	let worker = SassafrasWorker {
+		client: client.clone(),
+		block_import: Arc::new(Mutex::new(client.clone())),
+		env: DummyFactory(client.clone()),
+		keystore,
+		sync_oracle: DummyOracle.clone(),
+		force_authoring: false,
+		backoff_authoring_blocks: Some(BackoffAuthoringOnFinalizedHeadLagging::default()),
+		tickets_submitted_for: Mutex::new(None),
+	};
+
+	let head = client.header(BlockId::Number(0)).unwrap().unwrap();
+	let configuration = worker.epoch_data(&head, 0).unwrap();
+
+	// the test runtime assigns slots round-robin over Alice, Bob and Charlie.
+	for slot in 0..9 {
+		let claim = worker.claim_slot(&head, slot, &configuration);
+		if slot % 3 == 1 {
+			assert_eq!(claim, Some((1, Keyring::Bob.public().into())));
+		} else {
+			assert!(claim.is_none());
+		}
+	}
+}
+
+/// A transaction pool recording the extrinsics submitted by the runtime.
+#[derive(Default)]
+struct RecordingPool(Mutex<Vec<Extrinsic>>);
+
+impl sp_transaction_pool::OffchainSubmitTransaction<TestBlock> for RecordingPool {
+	fn submit_at(&self, _at: &BlockId<TestBlock>, extrinsic: Extrinsic) -> Result<(), ()> {
+		self.0.lock().push(extrinsic);
+		Ok(())
+	}
+}
+
+#[test]
+fn failed_ticket_submissions_are_retried() {
+	let mut net = SassafrasTestNet::new(1);
+	let peer = net.peer(0);
+	let client = peer.client().as_full().expect("full clients are created").clone();
+	let (keystore, _keystore_path) = create_keystore(Some(Keyring::Bob));
+
+	let worker = SassafrasWorker {
+		client: client.clone(),
+		block_import: Arc::new(Mutex::new(client.clone())),
+		env: DummyFactory(client.clone()),
+		keystore,
+		sync_oracle: DummyOracle.clone(),
+		force_authoring: false,
+		backoff_authoring_blocks: Some(BackoffAuthoringOnFinalizedHeadLagging::default()),
+		tickets_submitted_for: Mutex::new(None),
+	};
+
+	let head = client.header(BlockId::Number(0)).unwrap().unwrap();
+	let configuration = worker.epoch_data(&head, 0).unwrap();
+
+	// without a transaction pool the submission fails.
+	worker.notify_slot(&head, 0, &configuration);
+	assert_eq!(*worker.tickets_submitted_for.lock(), None);
+
+	let pool = Arc::new(RecordingPool::default());
+	client.execution_extensions().register_transaction_pool(&pool);
+
+	worker.notify_slot(&head, 1, &configuration);
+	assert_eq!(*worker.tickets_submitted_for.lock(), Some(configuration.epoch_index + 1));
+
+	let submitted = pool.0.lock().clone();
+	assert_eq!(submitted.len(), 1);
+	match &submitted[0] {
+		Extrinsic::SassafrasTickets(tickets) => {
+			assert_eq!(tickets.len(), configuration.max_attempts as usize);
+			assert!(tickets.iter().all(|ticket| ticket.authority_index == 1));
+		},
+		extrinsic => panic!("unexpected extrinsic: {:?}", extrinsic),
+	}
+
+	// the tickets of an epoch are only submitted once.
+	worker.notify_slot(&head, 2, &configuration);
+	assert_eq!(pool.0.lock().len(), 1);
+}
+
+#[test]
+fn tickets_are_generated_for_local_authorities_only() {
+	let client = substrate_test_runtime_client::new();
+	let mut configuration = client.runtime_api()
+		.configuration(&BlockId::Number(0))
+		.unwrap();
+	configuration.max_attempts = 3;
+
+	let (keystore, _keystore_path) = create_keystore(Some(Keyring::Charlie));
+	let tickets = generate_tickets(&keystore, &configuration);
+
+	assert_eq!(tickets.len(), 3);
+	for (attempt, ticket) in tickets.iter().enumerate() {
+		assert_eq!(ticket.authority_index, 2);
+		assert_eq!(ticket.attempt, attempt as u32);
+		assert!(ticket.verify(
+			&configuration.authorities[2],
+			&configuration.randomness,
+			configuration.epoch_index + 1,
+		));
+		assert!(!ticket.verify(
+			&configuration.authorities[2],
+			&configuration.randomness,
+			configuration.epoch_index,
+		));
+	}
+
+	// a keystore without any authority key generates no tickets.
+	let (keystore, _keystore_path) = create_keystore(None);
+	assert!(generate_tickets(&keystore, &configuration).is_empty());
+}
+
+#[test]
+fn blocks_from_the_wrong_author_are_rejected() {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let genesis = client.header(BlockId::Number(0)).unwrap().unwrap();
+	let authorities = client.runtime_api()
+		.configuration(&BlockId::Number(0))
+		.unwrap()
+		.authorities;
+
+	let seal_header = |authority_index: AuthorityIndex, slot_number: u64, key: Keyring| {
+		let mut header = Header::new(
+			1,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(0),
+			genesis.hash(),
+			Default::default(),
+		);
+		header.digest_mut().push(<DigestItemFor<TestBlock> as CompatibleDigestItem>::sassafras_pre_digest(
+			PreDigest { authority_index, slot_number },
+		));
+		let signature = AuthorityPair::from(key.pair()).sign(header.hash().as_ref());
+		header.digest_mut().push(<DigestItemFor<TestBlock> as CompatibleDigestItem>::sassafras_seal(
+			signature,
+		));
+		let hash = header.hash();
+		(header, hash)
+	};
+
+	// slot 4 belongs to Bob.
+	let (header, hash) = seal_header(1, 4, Keyring::Bob);
+	assert!(matches!(
+		check_header::<_, TestBlock>(&*client, 5, header, hash, &authorities),
+		Ok(CheckedHeader::Checked(_, (4, _))),
+	));
+
+	// Alice claiming Bob's slot.
+	let (header, hash) = seal_header(0, 4, Keyring::Alice);
+	assert!(matches!(
+		check_header::<_, TestBlock>(&*client, 5, header, hash, &authorities),
+		Err(super::Error::UnexpectedAuthor(4, 1, 0)),
+	));
+
+	// Alice signing for Bob.
+	let (header, hash) = seal_header(1, 4, Keyring::Alice);
+	assert!(matches!(
+		check_header::<_, TestBlock>(&*client, 5, header, hash, &authorities),
+		Err(super::Error::BadSignature(_)),
+	));
+
+	// blocks from future slots are deferred.
+	let (header, hash) = seal_header(1, 7, Keyring::Bob);
+	assert!(matches!(
+		check_header::<_, TestBlock>(&*client, 5, header, hash, &authorities),
+		Ok(CheckedHeader::Deferred(_, 7)),
+	));
+}
@@ -19,6 +19,7 @@
 //! Schema for slots in the aux-db.
 
 use codec::{Encode, Decode};
+use parking_lot::{Mutex, const_mutex};
 use sc_client_api::backend::AuxStore;
 use sp_blockchain::{Result as ClientResult, Error as ClientError};
 use sp_consensus_slots::EquivocationProof;
@@ -32,6 +33,10 @@ pub const MAX_SLOT_CAPACITY: u64 = 1000;
 /// We prune slots when they reach this number.
 pub const PRUNING_BOUND: u64 = 2 * MAX_SLOT_CAPACITY;
 
+/// Serializes the read-modify-write of the saved headers in [`check_equivocation`], headers of the
+/// same slot can be verified concurrently by the import queue.
+static SLOT_HEADER_LOCK: Mutex<()> = const_mutex(());
+
 fn load_decode<C, T>(backend: &C, key: &[u8]) -> ClientResult<Option<T>>
 	where
 		C: AuxStore,
@@ -50,6 +55,9 @@ fn load_decode<C, T>(backend: &C, key: &[u8]) -> ClientResult<Option<T>>
 /// Checks if the header is an equivocation and returns the proof in that case.
 ///
 /// Note: it detects equivocations only when slot_now - slot <= MAX_SLOT_CAPACITY.
+///
+/// Concurrent calls are serialized, so that two headers of the same author and slot verified at
+/// the same time are detected as an equivocation.
 pub fn check_equivocation<C, H, P>(
 	backend: &C,
 	slot_now: u64,
@@ -67,6 +75,8 @@ pub fn check_equivocation<C, H, P>(
 		return Ok(None);
 	}
 
+	let _lock = SLOT_HEADER_LOCK.lock();
+
 	// Key for this slot.
 	let mut curr_slot_key = SLOT_HEADER_MAP_KEY.to_vec();
 	slot.using_encoded(|s| curr_slot_key.extend(s));
@@ -140,6 +150,7 @@ mod test {
 	use sp_runtime::testing::{Header as HeaderTest, Digest as DigestTest};
 	use substrate_test_runtime_client;
 
+	use std::sync::{Arc, Barrier};
 	use super::{MAX_SLOT_CAPACITY, PRUNING_BOUND, check_equivocation};
 
 	fn create_header(number: u64) -> HeaderTest {
@@ -246,4 +257,35 @@ mod test {
 			).unwrap().is_none(),
 		);
 	}
+
+	#[test]
+	fn same_slot_siblings_checked_concurrently_are_equivocations() {
+		const SIBLINGS: usize = 8;
+
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let (pair, _seed) = sr25519::Pair::generate();
+		let public = pair.public();
+
+		// siblings of the same parent, verified concurrently as part of the same batch.
+		let parent_hash = H256::random();
+		let barrier = Arc::new(Barrier::new(SIBLINGS));
+		let checks = (0..SIBLINGS).map(|_| {
+			let mut header = create_header(1);
+			header.parent_hash = parent_hash;
+			header.state_root = H256::random();
+			let (client, barrier) = (client.clone(), barrier.clone());
+
+			std::thread::spawn(move || {
+				barrier.wait();
+				check_equivocation(&*client, 2, 2, &header, &public).unwrap()
+			})
+		}).collect::<Vec<_>>();
+
+		let equivocations = checks.into_iter()
+			.filter_map(|check| check.join().unwrap())
+			.count();
+
+		// every header but the first one seen is an equivocation.
+		assert_eq!(equivocations, SIBLINGS - 1);
+	}
 }
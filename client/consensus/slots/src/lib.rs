@@ -32,7 +32,7 @@ pub use slots::SlotInfo;
 use slots::Slots;
 pub use aux_schema::{check_equivocation, MAX_SLOT_CAPACITY, PRUNING_BOUND};
 
-use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}};
+use std::{collections::HashMap, fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}};
 use codec::{Decode, Encode};
 use futures::{prelude::*, future::{self, Either}};
 use futures_timer::Delay;
@@ -473,6 +473,46 @@ pub enum CheckedHeader<H, S> {
 	Checked(H, S),
 }
 
+/// Maximum number of entries kept by [`PreVerifiedHeaders`].
+const MAX_PRE_VERIFIED_HEADERS: usize = 4096;
+
+/// Results of the seal checks done ahead of import by
+/// [`Verifier::pre_verify`](sp_consensus::import_queue::Verifier::pre_verify), shared between the
+/// clones of a verifier.
+///
+/// Maps the hash of a header to the outcome of the check, along with what it was checked against,
+/// e.g. the expected author. Entries are taken out when the header is verified.
+pub struct PreVerifiedHeaders<Hash, T>(Arc<Mutex<HashMap<Hash, T>>>);
+
+impl<Hash: std::hash::Hash + Eq, T> PreVerifiedHeaders<Hash, T> {
+	/// Remember the outcome of the checks of the header with the given hash.
+	pub fn insert(&self, hash: Hash, checked: T) {
+		let mut headers = self.0.lock();
+		// entries are left behind if the import of a batch fails, don't let them pile up.
+		if headers.len() >= MAX_PRE_VERIFIED_HEADERS {
+			headers.clear();
+		}
+		headers.insert(hash, checked);
+	}
+
+	/// Take the outcome of the checks of the header with the given hash, if any.
+	pub fn take(&self, hash: &Hash) -> Option<T> {
+		self.0.lock().remove(hash)
+	}
+}
+
+impl<Hash, T> Clone for PreVerifiedHeaders<Hash, T> {
+	fn clone(&self) -> Self {
+		PreVerifiedHeaders(self.0.clone())
+	}
+}
+
+impl<Hash: std::hash::Hash + Eq, T> Default for PreVerifiedHeaders<Hash, T> {
+	fn default() -> Self {
+		PreVerifiedHeaders(Default::default())
+	}
+}
+
 
 
 #[derive(Debug, thiserror::Error)]
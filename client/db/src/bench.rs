@@ -360,6 +360,16 @@ impl<B: BlockT> StateBackend<HashFor<B>> for BenchmarkingState<B> {
 		}
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		if let Some(ref state) = *self.state.borrow() {
+			state.apply_to_keys_with_prefix_while(prefix, f)
+		}
+	}
+
 	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
 		&self,
 		child_info: &ChildInfo,
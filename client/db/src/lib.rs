@@ -67,7 +67,7 @@ use hash_db::Prefix;
 use sp_trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
 use sp_database::Transaction;
 use sp_core::{Hasher, ChangesTrieConfiguration};
-use sp_core::offchain::OffchainOverlayedChange;
+use sp_core::offchain::{OffchainOverlayedChange, STORAGE_PREFIX, TRANSACTION_INDEX_KEY_PREFIX};
 use sp_core::storage::{well_known_keys, ChildInfo};
 use sp_arithmetic::traits::Saturating;
 use sp_runtime::{generic::{DigestItem, BlockId}, Justification, Storage};
@@ -203,6 +203,14 @@ impl<B: BlockT> StateBackend<HashFor<B>> for RefTrackingState<B> {
 		self.state.apply_to_child_keys_while(child_info, f)
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.state.apply_to_keys_with_prefix_while(prefix, f)
+	}
+
 	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
 		&self,
 		child_info: &ChildInfo,
@@ -357,7 +365,7 @@ pub(crate) mod columns {
 	/// Offchain workers local storage
 	pub const OFFCHAIN: u32 = 9;
 	pub const CACHE: u32 = 10;
-	/// Transactions
+	/// Transactions, and data indexed through the offchain indexing API, keyed by hash.
 	pub const TRANSACTION: u32 = 11;
 }
 
@@ -576,6 +584,10 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 	fn children(&self, parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
 		children::read_children(&*self.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)
 	}
+
+	fn indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<Option<Vec<u8>>> {
+		Ok(self.db.get(columns::TRANSACTION, hash.as_ref()))
+	}
 }
 
 impl<Block: BlockT> sc_client_api::blockchain::ProvideCache<Block> for BlockchainDb<Block> {
@@ -679,16 +691,48 @@ pub struct BlockImportOperation<Block: BlockT> {
 }
 
 impl<Block: BlockT> BlockImportOperation<Block> {
-	fn apply_offchain(&mut self, transaction: &mut Transaction<DbHash>) {
+	/// Writes the offchain storage changes, and returns the ones made through offchain indexing,
+	/// which also have to be tracked per block, along with the content hashes of the stored and
+	/// removed indexed transactions. The data of indexed transactions is written right away, but
+	/// they are only referenced once it is known whether the block storing them is finalized, and
+	/// only removed once the removing block is finalized.
+	fn apply_offchain(
+		&mut self,
+		transaction: &mut Transaction<DbHash>,
+	) -> (offchain::IndexedChanges, Vec<Vec<u8>>, Vec<Vec<u8>>) {
+		let mut indexed = Vec::new();
+		let mut stored_transactions = Vec::new();
+		let mut removed_transactions = Vec::new();
 		for ((prefix, key), value_operation) in self.offchain_storage_updates.drain(..) {
-			let key = crate::offchain::concatenate_prefix_and_key(&prefix, &key);
+			// Indexed transactions are stored by content hash in their own column.
+			if prefix == STORAGE_PREFIX && key.starts_with(TRANSACTION_INDEX_KEY_PREFIX) {
+				let hash = key[TRANSACTION_INDEX_KEY_PREFIX.len()..].to_vec();
+				match value_operation {
+					OffchainOverlayedChange::SetValue(val) => {
+						offchain::store_indexed_transaction(transaction, &hash, val);
+						stored_transactions.push(hash);
+					},
+					OffchainOverlayedChange::Remove => removed_transactions.push(hash),
+				}
+				continue
+			}
+			let db_key = crate::offchain::concatenate_prefix_and_key(&prefix, &key);
 			match value_operation {
-				OffchainOverlayedChange::SetValue(val) =>
-					transaction.set_from_vec(columns::OFFCHAIN, &key, val),
-				OffchainOverlayedChange::Remove =>
-					transaction.remove(columns::OFFCHAIN, &key),
+				OffchainOverlayedChange::SetValue(val) => {
+					if prefix == STORAGE_PREFIX {
+						indexed.push((key, Some(val.clone())));
+					}
+					transaction.set_from_vec(columns::OFFCHAIN, &db_key, val)
+				},
+				OffchainOverlayedChange::Remove => {
+					if prefix == STORAGE_PREFIX {
+						indexed.push((key, None));
+					}
+					transaction.remove(columns::OFFCHAIN, &db_key)
+				},
 			}
 		}
+		(indexed, stored_transactions, removed_transactions)
 	}
 
 	fn apply_aux(&mut self, transaction: &mut Transaction<DbHash>) {
@@ -982,6 +1026,17 @@ impl<Block: BlockT> Backend<Block> {
 			prefix_keys: !config.source.supports_ref_counting(),
 		};
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
+		let info = blockchain.info();
+		if info.finalized_hash != Default::default() {
+			let mut transaction = Transaction::new();
+			offchain::migrate_indexed_view(
+				&*db,
+				&mut transaction,
+				info.finalized_hash.as_ref(),
+				info.finalized_number.saturated_into::<u64>(),
+			);
+			db.commit(transaction)?;
+		}
 		let changes_tries_storage = DbChangesTrieStorage::new(
 			db,
 			blockchain.header_metadata_cache.clone(),
@@ -1176,7 +1231,6 @@ impl<Block: BlockT> Backend<Block> {
 		let mut finalization_displaced_leaves = None;
 
 		operation.apply_aux(&mut transaction);
-		operation.apply_offchain(&mut transaction);
 
 		let mut meta_updates = Vec::with_capacity(operation.finalized_blocks.len());
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
@@ -1198,6 +1252,11 @@ impl<Block: BlockT> Backend<Block> {
 			last_finalized_hash = block_hash;
 		}
 
+		// applied after the finalizations above, so that the removals of indexed transactions they
+		// cause don't override them being stored again by the imported block.
+		let (indexed_changes, stored_transactions, removed_transactions) =
+			operation.apply_offchain(&mut transaction);
+
 		let imported = if let Some(pending_block) = operation.pending_block {
 			let hash = pending_block.header.hash();
 			let parent_hash = *pending_block.header.parent_hash();
@@ -1336,6 +1395,24 @@ impl<Block: BlockT> Backend<Block> {
 			// release state reference so that it can be finalized
 			let cache = operation.old_state.into_cache_changes();
 
+			// the journal of a block finalized on import is merged right away by `note_finalized`.
+			offchain::reference_indexed_transactions(
+				&*self.storage.db,
+				&mut transaction,
+				&stored_transactions,
+				false,
+			);
+			offchain::write_indexed_journal(
+				&mut transaction,
+				hash.as_ref(),
+				&offchain::IndexedJournal {
+					parent: parent_hash.as_ref().to_vec(),
+					changes: indexed_changes,
+					stored_transactions,
+					removed_transactions,
+				},
+			);
+
 			if finalized {
 				// TODO: ensure best chain contains this block.
 				self.ensure_sequential_finalization(header, Some(last_finalized_hash))?;
@@ -1381,6 +1458,24 @@ impl<Block: BlockT> Backend<Block> {
 
 			Some((pending_block.header, number, hash, enacted, retracted, displaced_leaf, is_best, cache))
 		} else {
+			// without a block to track them against, the changes are considered final.
+			offchain::apply_indexed_changes(
+				&*self.storage.db,
+				&mut transaction,
+				self.blockchain.meta.read().finalized_number.saturated_into::<u64>(),
+				indexed_changes,
+			);
+			offchain::reference_indexed_transactions(
+				&*self.storage.db,
+				&mut transaction,
+				&stored_transactions,
+				true,
+			);
+			offchain::remove_indexed_transactions(
+				&*self.storage.db,
+				&mut transaction,
+				&removed_transactions,
+			);
 			None
 		};
 
@@ -1486,8 +1581,18 @@ impl<Block: BlockT> Backend<Block> {
 			}
 		}
 
+		offchain::finalize_indexed_journal(
+			&*self.storage.db,
+			transaction,
+			f_hash.as_ref(),
+			f_num.saturated_into::<u64>(),
+		);
+
 		self.prune_blocks(transaction, f_num)?;
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
+		for leaf in new_displaced.leaves() {
+			offchain::discard_indexed_fork(&*self.storage.db, transaction, leaf.as_ref());
+		}
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
 			&mut Some(ref mut displaced) => displaced.merge(new_displaced),
@@ -1508,6 +1613,9 @@ impl<Block: BlockT> Backend<Block> {
 				return Ok(())
 			}
 			let number = finalized.saturating_sub(keep.into());
+			if let Some(hash) = self.blockchain.hash(number)? {
+				offchain::prune_indexed_view(transaction, hash.as_ref(), number.saturated_into::<u64>());
+			}
 			match read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY, BlockId::<Block>::number(number))? {
 				Some(body) => {
 					debug!(target: "db", "Removing block #{}", number);
@@ -1776,6 +1884,11 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 						}
 						transaction.set_from_vec(columns::META, meta_keys::BEST_BLOCK, key);
 						transaction.remove(columns::KEY_LOOKUP, removed.hash().as_ref());
+						offchain::revert_indexed_block(
+							&*self.storage.db,
+							&mut transaction,
+							removed_hash.as_ref(),
+						);
 						children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, best_hash);
 						self.storage.db.commit(transaction)?;
 						self.changes_tries_storage.post_commit(Some(changes_trie_cache_ops));
@@ -2609,4 +2722,127 @@ pub(crate) mod tests {
 			assert_eq!(Some(vec![4.into()]), bc.body(BlockId::hash(blocks[4])).unwrap());
 		}
 	}
+
+	#[test]
+	fn indexed_transactions_are_stored_by_hash() {
+		let backend = Backend::<Block>::new_test(1, 0);
+		let data = b"some indexed data".to_vec();
+		let hash = BlakeTwo256::hash(&data);
+		let index_key = sp_core::offchain::transaction_index_key(hash.as_ref());
+		let genesis = insert_header(&backend, 0, Default::default(), None, Default::default());
+
+		let insert = |number, parent_hash, extrinsics_root, change| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root,
+			};
+			let header_hash = header.hash();
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+			op.set_block_data(header, Some(Vec::new()), None, NewBlockState::Best).unwrap();
+			if let Some(change) = change {
+				op.update_offchain_storage(vec![((STORAGE_PREFIX.to_vec(), index_key.clone()), change)])
+					.unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+			header_hash
+		};
+		let store = || Some(OffchainOverlayedChange::SetValue(data.clone()));
+		let remove = || Some(OffchainOverlayedChange::Remove);
+		let indexed_transaction = || backend.blockchain().indexed_transaction(&hash).unwrap();
+
+		let block1 = insert(1, genesis, Default::default(), store());
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+		assert!(sp_core::offchain::OffchainStorage::get(
+			&backend.offchain_storage().unwrap(),
+			STORAGE_PREFIX,
+			&index_key,
+		).is_none());
+
+		// the removal is only applied once the removing block is finalized, so the data is kept
+		// when that block ends up on a discarded fork.
+		insert(2, block1, Default::default(), remove());
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+		let b2 = insert(2, block1, H256::from_low_u64_be(42), None);
+		backend.finalize_block(BlockId::Hash(block1), None).unwrap();
+		backend.finalize_block(BlockId::Hash(b2), None).unwrap();
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+
+		// data stored again by a descendant of the removing block is kept.
+		let b3 = insert(3, b2, Default::default(), remove());
+		let b4 = insert(4, b3, Default::default(), store());
+		backend.finalize_block(BlockId::Hash(b3), None).unwrap();
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+
+		let b5 = insert(5, b4, Default::default(), remove());
+		backend.finalize_block(BlockId::Hash(b4), None).unwrap();
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+		backend.finalize_block(BlockId::Hash(b5), None).unwrap();
+		assert_eq!(indexed_transaction(), None);
+
+		// data only stored by the blocks of a discarded fork is removed with them.
+		let c6 = insert(6, b5, Default::default(), store());
+		insert(7, c6, Default::default(), None);
+		let b6 = insert(6, b5, H256::from_low_u64_be(42), None);
+		assert_eq!(indexed_transaction(), Some(data.clone()));
+		backend.finalize_block(BlockId::Hash(b6), None).unwrap();
+		assert_eq!(indexed_transaction(), None);
+	}
+
+	#[test]
+	fn offchain_indexed_values_are_fork_aware() {
+		use sp_core::offchain::{OffchainStorage, StorageError};
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let genesis = insert_header(&backend, 0, Default::default(), None, Default::default());
+
+		let insert = |number, parent_hash, extrinsics_root, value: Option<&[u8]>| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root,
+			};
+			let header_hash = header.hash();
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+			op.set_block_data(header, Some(Vec::new()), None, NewBlockState::Best).unwrap();
+			if let Some(value) = value {
+				let change = OffchainOverlayedChange::SetValue(value.to_vec());
+				op.update_offchain_storage(vec![((STORAGE_PREFIX.to_vec(), b"key".to_vec()), change)])
+					.unwrap();
+			}
+			backend.commit_operation(op).unwrap();
+			header_hash
+		};
+		let storage = backend.offchain_storage().unwrap();
+		let indexed_get = |at: H256| storage.indexed_get(at.as_ref(), b"key");
+
+		let a1 = insert(1, genesis, Default::default(), Some(b"a1"));
+		let b1 = insert(1, genesis, H256::from_low_u64_be(42), Some(b"b1"));
+		let a2 = insert(2, a1, Default::default(), None);
+		assert_eq!(indexed_get(genesis), Ok(None));
+		assert_eq!(indexed_get(a2), Ok(Some(b"a1".to_vec())));
+		assert_eq!(indexed_get(b1), Ok(Some(b"b1".to_vec())));
+		assert_eq!(indexed_get(H256::from_low_u64_be(42)), Err(StorageError::UnknownBlock));
+
+		// finalizing `a2` discards the fork of `b1`, while the older finalized blocks are kept.
+		backend.finalize_block(BlockId::Hash(a1), None).unwrap();
+		backend.finalize_block(BlockId::Hash(a2), None).unwrap();
+		assert_eq!(indexed_get(a2), Ok(Some(b"a1".to_vec())));
+		assert_eq!(indexed_get(a1), Ok(Some(b"a1".to_vec())));
+		assert_eq!(indexed_get(genesis), Ok(None));
+		assert_eq!(indexed_get(b1), Err(StorageError::UnknownBlock));
+
+		// reverted blocks are forgotten.
+		let a3 = insert(3, a2, Default::default(), Some(b"a3"));
+		assert_eq!(indexed_get(a3), Ok(Some(b"a3".to_vec())));
+		backend.revert(1, false).unwrap();
+		assert_eq!(indexed_get(a3), Err(StorageError::UnknownBlock));
+		assert_eq!(indexed_get(a2), Ok(Some(b"a1".to_vec())));
+	}
 }
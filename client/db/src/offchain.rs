@@ -17,13 +17,91 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! RocksDB-based offchain workers local storage.
+//!
+//! Besides the local storage itself, this keeps track of the values written by the runtime
+//! through offchain indexing in a fork-aware way. The changes made by each block which is not
+//! finalized yet are recorded in a journal, along with the hash of its parent, and are only
+//! merged into the history of the values of the finalized chain once the block gets finalized.
+//! The journals of the blocks which are reverted or which end up on a discarded fork are simply
+//! dropped. The view of a finalized block is kept until the block is pruned, and its changes are
+//! undone if it is reverted.
+//!
+//! Indexed transactions are stored by content hash in their own column as soon as a block stores
+//! them, but their removal is journaled the same way and only happens once the removing block is
+//! finalized, so that blocks of other forks can still serve them. They are reference counted by
+//! the blocks storing them, so that the ones only stored by reverted blocks or blocks of discarded
+//! forks are removed as well.
 
 use std::{collections::HashMap, sync::Arc};
 
 use crate::{columns, Database, DbHash, Transaction};
+use codec::{Decode, Encode};
+use sp_core::offchain::StorageError;
+use sp_database::Change;
 use parking_lot::Mutex;
 use log::error;
 
+/// Prefix of the journals of the offchain indexing changes of the non-finalized blocks.
+const INDEXED_JOURNAL_PREFIX: &[u8] = b"indexed_journal";
+/// Prefix of the finalized blocks whose view is kept.
+const INDEXED_CANONICAL_PREFIX: &[u8] = b"indexed_canonical";
+/// Prefix of the history of the values written through offchain indexing by finalized blocks.
+const INDEXED_HISTORY_PREFIX: &[u8] = b"indexed_history";
+/// Key of the number of the oldest finalized block whose view is kept.
+const INDEXED_KEPT_FROM: &[u8] = b"indexed_kept_from";
+/// Prefix of the values written through offchain indexing as of the last finalized block, by the
+/// previous versions of the database. They are read until the key is written again.
+const LEGACY_INDEXED_FINALIZED_PREFIX: &[u8] = b"indexed_finalized";
+/// Key of the hash of the last finalized block, as of which the legacy values were kept.
+const LEGACY_INDEXED_FINALIZED_HEAD: &[u8] = b"indexed_head";
+/// Key of the number of the block as of which the legacy values were kept, once migrated.
+const LEGACY_INDEXED_NUMBER: &[u8] = b"indexed_legacy_number";
+
+/// Maximum number of journals walked through to read the view of a block which is not
+/// finalized, i.e. maximum distance from the block to its last finalized ancestor.
+const MAX_INDEXED_JOURNAL_DEPTH: usize = 4096;
+/// Prefix of the references to each indexed transaction.
+const TRANSACTION_REFS_PREFIX: &[u8] = b"indexed_transaction_refs";
+
+/// Keys written by a block through offchain indexing, along with their new value, or `None` if
+/// they have been removed.
+pub(crate) type IndexedChanges = Vec<(Vec<u8>, Option<Vec<u8>>)>;
+
+/// Offchain indexing changes made by a block which is not finalized yet.
+#[derive(Debug, Default, Encode, Decode)]
+pub(crate) struct IndexedJournal {
+	/// Hash of the parent of the block.
+	pub parent: Vec<u8>,
+	/// Changes made by the block, in the order they have been made.
+	pub changes: IndexedChanges,
+	/// Content hashes of the indexed transactions stored by the block.
+	pub stored_transactions: Vec<Vec<u8>>,
+	/// Content hashes of the indexed transactions removed by the block.
+	pub removed_transactions: Vec<Vec<u8>>,
+}
+
+/// A finalized block whose view is kept.
+#[derive(Debug, Encode, Decode)]
+struct IndexedCanonical {
+	/// Number of the block.
+	number: u64,
+	/// Keys written by the block, whose changes have to be undone if it is reverted.
+	keys: Vec<Vec<u8>>,
+}
+
+/// Values written under a key by finalized blocks, along with the number of the writing block,
+/// oldest first. `None` if the key has been removed.
+type IndexedHistory = Vec<(u64, Option<Vec<u8>>)>;
+
+/// References to an indexed transaction, which is kept as long as it has any.
+#[derive(Debug, Default, Encode, Decode)]
+struct TransactionRefs {
+	/// Number of the blocks which stored the transaction and are not finalized yet.
+	pending: u32,
+	/// Whether the transaction has been stored by a finalized block and not removed since.
+	finalized: bool,
+}
+
 /// Offchain local storage
 #[derive(Clone)]
 pub struct LocalStorage {
@@ -79,6 +157,67 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 		self.db.get(columns::OFFCHAIN, &concatenate_prefix_and_key(prefix, key))
 	}
 
+	fn iter_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+		let mut entries = Vec::new();
+		self.db.iter_prefix(
+			columns::OFFCHAIN,
+			&concatenate_prefix_and_key(prefix, key_prefix),
+			&mut |key, value| entries.push((key[prefix.len()..].to_vec(), value.to_vec())),
+		).map_err(|err| {
+			error!("Error iterating over local storage: {}", err);
+			StorageError::Database
+		})?;
+		Ok(entries)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Result<u64, StorageError> {
+		let mut tx = Transaction::new();
+		let mut count = 0;
+		self.db.iter_prefix(
+			columns::OFFCHAIN,
+			&concatenate_prefix_and_key(prefix, key_prefix),
+			&mut |key, _| {
+				tx.remove(columns::OFFCHAIN, key);
+				count += 1;
+			},
+		).map_err(|err| {
+			error!("Error iterating over local storage: {}", err);
+			StorageError::Database
+		})?;
+
+		self.db.commit(tx).map_err(|err| {
+			error!("Error clearing local storage: {}", err);
+			StorageError::Database
+		})?;
+		Ok(count)
+	}
+
+	/// Only the finalized blocks which are not pruned yet and the blocks which are not finalized
+	/// yet are known. The view of the latter is read by walking back to their last finalized
+	/// ancestor, which fails if it is more than [`MAX_INDEXED_JOURNAL_DEPTH`] blocks away.
+	fn indexed_get(&self, at: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+		// reads aren't part of any transaction.
+		let no_changes = Transaction::new();
+		let mut block = at.to_vec();
+		for _ in 0..=MAX_INDEXED_JOURNAL_DEPTH {
+			if let Some(canonical) = read_indexed_canonical(&*self.db, &no_changes, &block) {
+				let history = read_indexed_history(&*self.db, &no_changes, key);
+				return Ok(indexed_value_at(&history, canonical.number))
+			}
+			let journal = read_pending_indexed_journal(&*self.db, &no_changes, &block)
+				.ok_or(StorageError::UnknownBlock)?;
+			if let Some((_, value)) = journal.changes.into_iter().rev().find(|(k, _)| k == key) {
+				return Ok(value)
+			}
+			block = journal.parent;
+		}
+		Err(StorageError::TooFarFromFinalized)
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -125,6 +264,341 @@ pub(crate) fn concatenate_prefix_and_key(prefix: &[u8], key: &[u8]) -> Vec<u8> {
 		.collect()
 }
 
+fn indexed_journal_key(block: &[u8]) -> Vec<u8> {
+	concatenate_prefix_and_key(INDEXED_JOURNAL_PREFIX, block)
+}
+
+fn indexed_canonical_key(block: &[u8]) -> Vec<u8> {
+	concatenate_prefix_and_key(INDEXED_CANONICAL_PREFIX, block)
+}
+
+/// Read a key of the offchain column, taking the changes already made by `transaction` into
+/// account.
+fn get_pending(
+	db: &dyn Database<DbHash>,
+	transaction: &Transaction<DbHash>,
+	key: &[u8],
+) -> Option<Vec<u8>> {
+	for change in transaction.0.iter().rev() {
+		match change {
+			Change::Set(col, k, value) if *col == columns::OFFCHAIN && k == key =>
+				return Some(value.clone()),
+			Change::Remove(col, k) if *col == columns::OFFCHAIN && k == key => return None,
+			_ => {},
+		}
+	}
+	db.get(columns::OFFCHAIN, key)
+}
+
+fn read_pending_indexed_journal(
+	db: &dyn Database<DbHash>,
+	transaction: &Transaction<DbHash>,
+	block: &[u8],
+) -> Option<IndexedJournal> {
+	decode_indexed_journal(get_pending(db, transaction, &indexed_journal_key(block))?)
+}
+
+fn decode_indexed_journal(encoded: Vec<u8>) -> Option<IndexedJournal> {
+	match IndexedJournal::decode(&mut &encoded[..]) {
+		Ok(journal) => Some(journal),
+		Err(err) => {
+			error!("Error decoding offchain indexing journal: {}", err);
+			None
+		},
+	}
+}
+
+fn read_indexed_canonical(
+	db: &dyn Database<DbHash>,
+	transaction: &Transaction<DbHash>,
+	block: &[u8],
+) -> Option<IndexedCanonical> {
+	let encoded = get_pending(db, transaction, &indexed_canonical_key(block))?;
+	match IndexedCanonical::decode(&mut &encoded[..]) {
+		Ok(canonical) => Some(canonical),
+		Err(err) => {
+			error!("Error decoding offchain indexing canonical block: {}", err);
+			None
+		},
+	}
+}
+
+fn read_number(
+	db: &dyn Database<DbHash>,
+	transaction: &Transaction<DbHash>,
+	key: &[u8],
+) -> Option<u64> {
+	get_pending(db, transaction, key).and_then(|number| u64::decode(&mut &number[..]).ok())
+}
+
+/// Read the history of the values of a key, falling back to the value written by the previous
+/// versions of the database, if any.
+fn read_indexed_history(
+	db: &dyn Database<DbHash>,
+	transaction: &Transaction<DbHash>,
+	key: &[u8],
+) -> IndexedHistory {
+	let history_key = concatenate_prefix_and_key(INDEXED_HISTORY_PREFIX, key);
+	if let Some(encoded) = get_pending(db, transaction, &history_key) {
+		return IndexedHistory::decode(&mut &encoded[..]).unwrap_or_else(|err| {
+			error!("Error decoding offchain indexing history: {}", err);
+			Vec::new()
+		})
+	}
+	let legacy_key = concatenate_prefix_and_key(LEGACY_INDEXED_FINALIZED_PREFIX, key);
+	let legacy_number = read_number(db, transaction, LEGACY_INDEXED_NUMBER);
+	match (get_pending(db, transaction, &legacy_key), legacy_number) {
+		(Some(value), Some(number)) => vec![(number, Some(value))],
+		_ => Vec::new(),
+	}
+}
+
+fn write_indexed_history(
+	transaction: &mut Transaction<DbHash>,
+	key: &[u8],
+	history: IndexedHistory,
+) {
+	let history_key = concatenate_prefix_and_key(INDEXED_HISTORY_PREFIX, key);
+	if history.is_empty() {
+		transaction.remove(columns::OFFCHAIN, &history_key);
+	} else {
+		transaction.set_from_vec(columns::OFFCHAIN, &history_key, history.encode());
+	}
+	let legacy_key = concatenate_prefix_and_key(LEGACY_INDEXED_FINALIZED_PREFIX, key);
+	transaction.remove(columns::OFFCHAIN, &legacy_key);
+}
+
+/// The value of a key as seen by the finalized block with the given number.
+fn indexed_value_at(history: &IndexedHistory, number: u64) -> Option<Vec<u8>> {
+	history.iter().rev().find(|(n, _)| *n <= number).and_then(|(_, value)| value.clone())
+}
+
+/// Drop the values which aren't seen by any kept block anymore.
+fn compact_indexed_history(history: &mut IndexedHistory, kept_from: u64) {
+	if let Some(oldest_seen) = history.iter().rposition(|(n, _)| *n <= kept_from) {
+		history.drain(..oldest_seen);
+		if history[0].1.is_none() {
+			history.remove(0);
+		}
+	}
+}
+
+/// Record the offchain indexing changes of a block which is not finalized.
+pub(crate) fn write_indexed_journal(
+	transaction: &mut Transaction<DbHash>,
+	block: &[u8],
+	journal: &IndexedJournal,
+) {
+	transaction.set_from_vec(columns::OFFCHAIN, &indexed_journal_key(block), journal.encode());
+}
+
+/// Drop the offchain indexing changes of a block, e.g. because it is reverted.
+fn remove_indexed_journal(transaction: &mut Transaction<DbHash>, block: &[u8]) {
+	transaction.remove(columns::OFFCHAIN, &indexed_journal_key(block));
+}
+
+/// Apply offchain indexing changes made by the finalized block with the given number to the
+/// history of the values, and return the written keys.
+pub(crate) fn apply_indexed_changes(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	number: u64,
+	changes: IndexedChanges,
+) -> Vec<Vec<u8>> {
+	let kept_from = read_number(db, transaction, INDEXED_KEPT_FROM).unwrap_or(0);
+	let mut keys = Vec::new();
+	for (key, value) in changes {
+		let mut history = read_indexed_history(db, transaction, &key);
+		history.push((number, value));
+		compact_indexed_history(&mut history, kept_from);
+		write_indexed_history(transaction, &key, history);
+		keys.push(key);
+	}
+	keys.sort();
+	keys.dedup();
+	keys
+}
+
+/// Store the data of an indexed transaction. It is only kept once referenced with
+/// [`reference_indexed_transactions`].
+pub(crate) fn store_indexed_transaction(
+	transaction: &mut Transaction<DbHash>,
+	hash: &[u8],
+	data: Vec<u8>,
+) {
+	transaction.set_from_vec(columns::TRANSACTION, hash, data);
+}
+
+fn transaction_refs_key(hash: &[u8]) -> Vec<u8> {
+	concatenate_prefix_and_key(TRANSACTION_REFS_PREFIX, hash)
+}
+
+/// Update the references to an indexed transaction, removing it if it has none left.
+fn update_transaction_refs(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	hash: &[u8],
+	update: impl FnOnce(&mut TransactionRefs),
+) {
+	let key = transaction_refs_key(hash);
+	let mut refs = get_pending(db, transaction, &key)
+		.and_then(|refs| TransactionRefs::decode(&mut &refs[..]).ok())
+		.unwrap_or_default();
+	update(&mut refs);
+	if refs.pending == 0 && !refs.finalized {
+		transaction.remove(columns::TRANSACTION, hash);
+		transaction.remove(columns::OFFCHAIN, &key);
+	} else {
+		transaction.set_from_vec(columns::OFFCHAIN, &key, refs.encode());
+	}
+}
+
+/// Reference the indexed transactions stored by a block, depending on whether it is finalized.
+pub(crate) fn reference_indexed_transactions(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	hashes: &[Vec<u8>],
+	finalized: bool,
+) {
+	for hash in hashes {
+		update_transaction_refs(db, transaction, hash, |refs| if finalized {
+			refs.finalized = true;
+		} else {
+			refs.pending += 1;
+		});
+	}
+}
+
+/// Remove indexed transactions, removed by a finalized block.
+///
+/// A transaction stored again by a block which is not finalized yet is kept, as that block may be
+/// a descendant of the removing one.
+pub(crate) fn remove_indexed_transactions(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	hashes: &[Vec<u8>],
+) {
+	for hash in hashes {
+		update_transaction_refs(db, transaction, hash, |refs| refs.finalized = false);
+	}
+}
+
+/// Drop the references of a block which is not finalized to the indexed transactions it stored.
+fn release_indexed_transactions(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	hashes: &[Vec<u8>],
+) {
+	for hash in hashes {
+		update_transaction_refs(
+			db,
+			transaction,
+			hash,
+			|refs| refs.pending = refs.pending.saturating_sub(1),
+		);
+	}
+}
+
+/// Merge the journal of a newly finalized block into the history of the values, keep its view
+/// and update the indexed transactions it stored and removed.
+pub(crate) fn finalize_indexed_journal(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	block: &[u8],
+	number: u64,
+) {
+	let journal = read_pending_indexed_journal(db, transaction, block).unwrap_or_default();
+	let keys = apply_indexed_changes(db, transaction, number, journal.changes);
+	transaction.set_from_vec(
+		columns::OFFCHAIN,
+		&indexed_canonical_key(block),
+		IndexedCanonical { number, keys }.encode(),
+	);
+	for hash in &journal.stored_transactions {
+		update_transaction_refs(db, transaction, hash, |refs| {
+			refs.pending = refs.pending.saturating_sub(1);
+			refs.finalized = true;
+		});
+	}
+	remove_indexed_transactions(db, transaction, &journal.removed_transactions);
+	remove_indexed_journal(transaction, block);
+}
+
+/// Drop the view of a reverted block. The journal of a block which is not finalized is dropped,
+/// along with its references to the indexed transactions it stored, while the changes of a
+/// finalized block are removed from the history of the values.
+pub(crate) fn revert_indexed_block(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	block: &[u8],
+) {
+	if let Some(journal) = read_pending_indexed_journal(db, transaction, block) {
+		release_indexed_transactions(db, transaction, &journal.stored_transactions);
+		remove_indexed_journal(transaction, block);
+	}
+	if let Some(canonical) = read_indexed_canonical(db, transaction, block) {
+		for key in canonical.keys {
+			let mut history = read_indexed_history(db, transaction, &key);
+			while history.last().map_or(false, |(n, _)| *n == canonical.number) {
+				history.pop();
+			}
+			write_indexed_history(transaction, &key, history);
+		}
+		transaction.remove(columns::OFFCHAIN, &indexed_canonical_key(block));
+	}
+}
+
+/// Drop the view of a finalized block which is pruned. The blocks are pruned in order, so the
+/// views of the blocks before it are gone as well.
+pub(crate) fn prune_indexed_view(transaction: &mut Transaction<DbHash>, block: &[u8], number: u64) {
+	transaction.remove(columns::OFFCHAIN, &indexed_canonical_key(block));
+	transaction.set_from_vec(columns::OFFCHAIN, INDEXED_KEPT_FROM, (number + 1).encode());
+}
+
+/// Migrate the values written through offchain indexing by the previous versions of the
+/// database, which only kept the view of the last finalized block, and make sure that the view
+/// of the given last finalized block is kept.
+///
+/// The legacy values are read as written by the last finalized block until their key is written
+/// again, so that they don't have to be iterated over.
+pub(crate) fn migrate_indexed_view(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	finalized: &[u8],
+	number: u64,
+) {
+	if db.get(columns::OFFCHAIN, LEGACY_INDEXED_FINALIZED_HEAD).is_some() {
+		transaction.set_from_vec(columns::OFFCHAIN, LEGACY_INDEXED_NUMBER, number.encode());
+		transaction.remove(columns::OFFCHAIN, LEGACY_INDEXED_FINALIZED_HEAD);
+	}
+	if read_indexed_canonical(db, transaction, finalized).is_none() {
+		transaction.set_from_vec(
+			columns::OFFCHAIN,
+			&indexed_canonical_key(finalized),
+			IndexedCanonical { number, keys: Vec::new() }.encode(),
+		);
+	}
+}
+
+/// Drop the journals of all the blocks of a discarded fork, walking back from its leaf to the
+/// first block without journal, along with their references to the indexed transactions they
+/// stored.
+///
+/// The journals dropped earlier in the same transaction are taken into account, so that the
+/// blocks shared by several discarded forks are only dropped once.
+pub(crate) fn discard_indexed_fork(
+	db: &dyn Database<DbHash>,
+	transaction: &mut Transaction<DbHash>,
+	leaf: &[u8],
+) {
+	let mut block = leaf.to_vec();
+	while let Some(journal) = read_pending_indexed_journal(db, transaction, &block) {
+		release_indexed_transactions(db, transaction, &journal.stored_transactions);
+		remove_indexed_journal(transaction, &block);
+		block = journal.parent;
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -156,4 +630,147 @@ mod tests {
 		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
 	}
 
+	#[test]
+	fn should_iterate_over_prefix() {
+		let mut storage = LocalStorage::new_test();
+		storage.set(b"prefix", b"key1", b"value1");
+		storage.set(b"prefix", b"key2", b"value2");
+		storage.set(b"prefix", b"other", b"value3");
+		storage.set(b"other", b"key3", b"value4");
+
+		let mut entries = storage.iter_prefix(b"prefix", b"key").unwrap();
+		entries.sort();
+		assert_eq!(entries, vec![
+			(b"key1".to_vec(), b"value1".to_vec()),
+			(b"key2".to_vec(), b"value2".to_vec()),
+		]);
+		assert_eq!(storage.iter_prefix(b"prefix", b"").unwrap().len(), 3);
+
+		assert_eq!(storage.clear_prefix(b"prefix", b"key"), Ok(2));
+		assert_eq!(storage.iter_prefix(b"prefix", b"").unwrap().len(), 1);
+		assert_eq!(storage.get(b"other", b"key3"), Some(b"value4".to_vec()));
+	}
+
+	#[test]
+	fn should_only_see_indexed_changes_of_ancestors() {
+		let storage = LocalStorage::new_test();
+		let mut tx = Transaction::new();
+		write_indexed_journal(&mut tx, b"genesis", &IndexedJournal {
+			changes: vec![
+				(b"key".to_vec(), Some(b"genesis".to_vec())),
+				(b"other".to_vec(), Some(b"value".to_vec())),
+			],
+			..Default::default()
+		});
+		finalize_indexed_journal(&*storage.db, &mut tx, b"genesis", 0);
+		write_indexed_journal(&mut tx, b"a", &IndexedJournal {
+			parent: b"genesis".to_vec(),
+			changes: vec![(b"key".to_vec(), Some(b"a".to_vec()))],
+			..Default::default()
+		});
+		write_indexed_journal(&mut tx, b"b", &IndexedJournal {
+			parent: b"a".to_vec(),
+			changes: vec![(b"other".to_vec(), None)],
+			..Default::default()
+		});
+		write_indexed_journal(&mut tx, b"c", &IndexedJournal {
+			parent: b"genesis".to_vec(),
+			changes: Vec::new(),
+			..Default::default()
+		});
+		storage.db.commit(tx).unwrap();
+
+		assert_eq!(storage.indexed_get(b"b", b"key"), Ok(Some(b"a".to_vec())));
+		assert_eq!(storage.indexed_get(b"b", b"other"), Ok(None));
+		assert_eq!(storage.indexed_get(b"c", b"key"), Ok(Some(b"genesis".to_vec())));
+		assert_eq!(storage.indexed_get(b"c", b"other"), Ok(Some(b"value".to_vec())));
+		assert_eq!(storage.indexed_get(b"unknown", b"key"), Err(StorageError::UnknownBlock));
+
+		// finalizing `a` discards the fork of `c`.
+		let mut tx = Transaction::new();
+		finalize_indexed_journal(&*storage.db, &mut tx, b"a", 1);
+		discard_indexed_fork(&*storage.db, &mut tx, b"c");
+		storage.db.commit(tx).unwrap();
+
+		assert_eq!(storage.indexed_get(b"a", b"key"), Ok(Some(b"a".to_vec())));
+		assert_eq!(storage.indexed_get(b"b", b"other"), Ok(None));
+		assert_eq!(storage.indexed_get(b"b", b"key"), Ok(Some(b"a".to_vec())));
+		assert_eq!(storage.indexed_get(b"c", b"key"), Err(StorageError::UnknownBlock));
+
+		// the view of the finalized blocks is kept until they are pruned.
+		assert_eq!(storage.indexed_get(b"genesis", b"key"), Ok(Some(b"genesis".to_vec())));
+		let mut tx = Transaction::new();
+		prune_indexed_view(&mut tx, b"genesis", 0);
+		storage.db.commit(tx).unwrap();
+		assert_eq!(storage.indexed_get(b"genesis", b"key"), Err(StorageError::UnknownBlock));
+		assert_eq!(storage.indexed_get(b"a", b"other"), Ok(Some(b"value".to_vec())));
+
+		// reverting a finalized block undoes its changes.
+		let mut tx = Transaction::new();
+		revert_indexed_block(&*storage.db, &mut tx, b"b");
+		revert_indexed_block(&*storage.db, &mut tx, b"a");
+		storage.db.commit(tx).unwrap();
+		assert_eq!(storage.indexed_get(b"a", b"key"), Err(StorageError::UnknownBlock));
+		assert_eq!(storage.indexed_get(b"b", b"key"), Err(StorageError::UnknownBlock));
+		assert_eq!(
+			read_indexed_history(&*storage.db, &Transaction::new(), b"key"),
+			vec![(0, Some(b"genesis".to_vec()))],
+		);
+	}
+
+	#[test]
+	fn should_bound_the_walk_to_the_finalized_ancestor() {
+		let storage = LocalStorage::new_test();
+		let mut tx = Transaction::new();
+		finalize_indexed_journal(&*storage.db, &mut tx, &0u64.encode(), 0);
+		for number in 1..=MAX_INDEXED_JOURNAL_DEPTH as u64 + 1 {
+			write_indexed_journal(&mut tx, &number.encode(), &IndexedJournal {
+				parent: (number - 1).encode(),
+				..Default::default()
+			});
+		}
+		storage.db.commit(tx).unwrap();
+
+		let deepest = MAX_INDEXED_JOURNAL_DEPTH as u64;
+		assert_eq!(storage.indexed_get(&deepest.encode(), b"key"), Ok(None));
+		assert_eq!(
+			storage.indexed_get(&(deepest + 1).encode(), b"key"),
+			Err(StorageError::TooFarFromFinalized),
+		);
+	}
+
+	#[test]
+	fn should_migrate_the_legacy_indexed_values() {
+		let storage = LocalStorage::new_test();
+		let mut tx = Transaction::new();
+		tx.set(columns::OFFCHAIN, LEGACY_INDEXED_FINALIZED_HEAD, b"head");
+		tx.set(
+			columns::OFFCHAIN,
+			&concatenate_prefix_and_key(LEGACY_INDEXED_FINALIZED_PREFIX, b"key"),
+			b"legacy",
+		);
+		storage.db.commit(tx).unwrap();
+
+		let mut tx = Transaction::new();
+		migrate_indexed_view(&*storage.db, &mut tx, b"head", 5);
+		write_indexed_journal(&mut tx, b"next", &IndexedJournal {
+			parent: b"head".to_vec(),
+			..Default::default()
+		});
+		storage.db.commit(tx).unwrap();
+
+		assert_eq!(storage.indexed_get(b"head", b"key"), Ok(Some(b"legacy".to_vec())));
+		assert_eq!(storage.indexed_get(b"next", b"key"), Ok(Some(b"legacy".to_vec())));
+		assert!(storage.db.get(columns::OFFCHAIN, LEGACY_INDEXED_FINALIZED_HEAD).is_none());
+
+		// the legacy value is replaced once the key is written again.
+		let mut tx = Transaction::new();
+		apply_indexed_changes(&*storage.db, &mut tx, 6, vec![(b"key".to_vec(), None)]);
+		storage.db.commit(tx).unwrap();
+		assert_eq!(storage.indexed_get(b"head", b"key"), Ok(Some(b"legacy".to_vec())));
+		assert!(storage.db.get(
+			columns::OFFCHAIN,
+			&concatenate_prefix_and_key(LEGACY_INDEXED_FINALIZED_PREFIX, b"key"),
+		).is_none());
+	}
 }
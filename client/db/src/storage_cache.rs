@@ -611,6 +611,14 @@ impl<S: StateBackend<HashFor<B>>, B: BlockT> StateBackend<HashFor<B>> for Cachin
 		self.state.apply_to_child_keys_while(child_info, f)
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.state.apply_to_keys_with_prefix_while(prefix, f)
+	}
+
 	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
 		self.state.next_storage_key(key)
 	}
@@ -793,6 +801,14 @@ impl<S: StateBackend<HashFor<B>>, B: BlockT> StateBackend<HashFor<B>> for Syncin
 		self.caching_state().apply_to_child_keys_while(child_info, f)
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.caching_state().apply_to_keys_with_prefix_while(prefix, f)
+	}
+
 	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
 		self.caching_state().next_storage_key(key)
 	}
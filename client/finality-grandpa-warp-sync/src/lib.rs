@@ -64,6 +64,7 @@ pub fn generate_request_response_config(protocol_id: ProtocolId) -> RequestRespo
 		max_response_size: 16 * 1024 * 1024,
 		request_timeout: Duration::from_secs(10),
 		inbound_queue: None,
+		bandwidth_limit: Default::default(),
 	}
 }
 
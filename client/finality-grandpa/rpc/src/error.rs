@@ -31,6 +31,10 @@ pub enum Error {
 	/// GRANDPA prove finality failed.
 	#[display(fmt = "GRANDPA prove finality rpc failed: {}", _0)]
 	ProveFinalityFailed(sc_finality_grandpa::FinalityProofError),
+	/// GRANDPA failed to fetch a justification.
+	#[display(fmt = "GRANDPA justification rpc failed: {}", _0)]
+	#[from(ignore)]
+	JustificationFailed(sc_finality_grandpa::FinalityProofError),
 }
 
 /// The error codes returned by jsonrpc.
@@ -43,6 +47,8 @@ pub enum ErrorCode {
 	VoterStateTooLarge,
 	/// Failed to prove finality.
 	ProveFinality,
+	/// Failed to fetch a justification.
+	Justification,
 }
 
 impl From<Error> for ErrorCode {
@@ -52,6 +58,7 @@ impl From<Error> for ErrorCode {
 			Error::AuthoritySetIdReportedAsUnreasonablyLarge => ErrorCode::AuthoritySetTooLarge,
 			Error::VoterStateReportsUnreasonablyLargeNumbers => ErrorCode::VoterStateTooLarge,
 			Error::ProveFinalityFailed(_) => ErrorCode::ProveFinality,
+			Error::JustificationFailed(_) => ErrorCode::Justification,
 		}
 	}
 }
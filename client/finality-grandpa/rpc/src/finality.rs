@@ -24,6 +24,9 @@ use sp_runtime::traits::{Block as BlockT, NumberFor};
 #[derive(Serialize, Deserialize)]
 pub struct EncodedFinalityProof(pub sp_core::Bytes);
 
+#[derive(Serialize, Deserialize)]
+pub struct EncodedJustification(pub sp_core::Bytes);
+
 /// Local trait mainly to allow mocking in tests.
 pub trait RpcFinalityProofProvider<Block: BlockT> {
 	/// Prove finality for the given block number by returning a Justification for the last block of
@@ -32,6 +35,12 @@ pub trait RpcFinalityProofProvider<Block: BlockT> {
 		&self,
 		block: NumberFor<Block>,
 	) -> Result<Option<EncodedFinalityProof>, sc_finality_grandpa::FinalityProofError>;
+
+	/// Return the justification stored for the given block, if any.
+	fn rpc_justification(
+		&self,
+		block: Block::Hash,
+	) -> Result<Option<EncodedJustification>, sc_finality_grandpa::FinalityProofError>;
 }
 
 impl<B, Block> RpcFinalityProofProvider<Block> for FinalityProofProvider<B, Block>
@@ -47,4 +56,12 @@ where
 		self.prove_finality(block)
 			.map(|x| x.map(|y| EncodedFinalityProof(y.into())))
 	}
+
+	fn rpc_justification(
+		&self,
+		block: Block::Hash,
+	) -> Result<Option<EncodedJustification>, sc_finality_grandpa::FinalityProofError> {
+		self.justification(block)
+			.map(|x| x.map(|y| EncodedJustification(y.into())))
+	}
 }
@@ -39,7 +39,7 @@ mod report;
 use sc_finality_grandpa::GrandpaJustificationStream;
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 
-use finality::{EncodedFinalityProof, RpcFinalityProofProvider};
+use finality::{EncodedFinalityProof, EncodedJustification, RpcFinalityProofProvider};
 use report::{ReportAuthoritySet, ReportVoterState, ReportedRoundStates};
 use notification::JustificationNotification;
 
@@ -89,6 +89,15 @@ pub trait GrandpaApi<Notification, Hash, Number> {
 		&self,
 		block: Number,
 	) -> FutureResult<Option<EncodedFinalityProof>>;
+
+	/// Returns the justification stored for the given block, if any. Besides
+	/// authority set changes, justifications are only stored periodically, see
+	/// the `justification_period` GRANDPA configuration.
+	#[rpc(name = "grandpa_justification")]
+	fn justification(
+		&self,
+		block: Hash,
+	) -> FutureResult<Option<EncodedJustification>>;
 }
 
 /// Implements the GrandpaApi RPC trait for interacting with GRANDPA.
@@ -184,12 +193,28 @@ where
 				.compat()
 		)
 	}
+	fn justification(
+		&self,
+		block: Block::Hash,
+	) -> FutureResult<Option<EncodedJustification>> {
+		let result = self.finality_proof_provider.rpc_justification(block);
+		let future = async move { result }.boxed();
+		Box::new(
+			future
+				.map_err(|e| {
+					warn!("Error fetching justification: {}", e);
+					error::Error::JustificationFailed(e)
+				})
+				.map_err(jsonrpc_core::Error::from)
+				.compat()
+		)
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use std::{collections::HashSet, convert::TryInto, sync::Arc};
+	use std::{collections::{HashMap, HashSet}, convert::TryInto, sync::Arc};
 	use jsonrpc_core::{Notification, Output, types::Params};
 
 	use parity_scale_codec::{Encode, Decode};
@@ -216,6 +241,7 @@ mod tests {
 
 	struct TestFinalityProofProvider {
 		finality_proof: Option<FinalityProof<Header>>,
+		justifications: HashMap<Vec<u8>, Vec<u8>>,
 	}
 
 	fn voters() -> HashSet<AuthorityId> {
@@ -264,6 +290,15 @@ mod tests {
 					.into()
 			)))
 		}
+
+		fn rpc_justification(
+			&self,
+			block: Block::Hash,
+		) -> Result<Option<EncodedJustification>, sc_finality_grandpa::FinalityProofError> {
+			Ok(self.justifications
+				.get(block.as_ref())
+				.map(|justification| EncodedJustification(justification.clone().into())))
+		}
 	}
 
 	impl ReportVoterState for TestVoterState {
@@ -317,7 +352,12 @@ mod tests {
 		VoterState: ReportVoterState + Send + Sync + 'static,
 	{
 		let (justification_sender, justification_stream) = GrandpaJustificationStream::channel();
-		let finality_proof_provider = Arc::new(TestFinalityProofProvider { finality_proof });
+		let finality_proof_provider = Arc::new(TestFinalityProofProvider {
+			finality_proof,
+			justifications: vec![(header(42).hash().as_bytes().to_vec(), create_justification().encode())]
+				.into_iter()
+				.collect(),
+		});
 
 		let handler = GrandpaRpcHandler::new(
 			TestAuthoritySet,
@@ -536,4 +576,28 @@ mod tests {
 		let finality_proof_rpc: FinalityProof<Header> = Decode::decode(&mut &result[..]).unwrap();
 		assert_eq!(finality_proof_rpc, finality_proof);
 	}
+
+	#[test]
+	fn justification_with_test_finality_proof_provider() {
+		let (io,  _) = setup_io_handler(TestVoterState);
+		let meta = sc_rpc::Metadata::default();
+
+		let request = format!(
+			"{{\"jsonrpc\":\"2.0\",\"method\":\"grandpa_justification\",\"params\":[\"{:?}\"],\"id\":1}}",
+			header(42).hash(),
+		);
+		let resp = io.handle_request_sync(&request, meta.clone());
+		let mut resp: serde_json::Value = serde_json::from_str(&resp.unwrap()).unwrap();
+		let result: sp_core::Bytes = serde_json::from_value(resp["result"].take()).unwrap();
+		let justification: GrandpaJustification<Block> = Decode::decode(&mut &result[..]).unwrap();
+		assert_eq!(justification, create_justification());
+
+		// no justification is stored for other blocks
+		let request = format!(
+			"{{\"jsonrpc\":\"2.0\",\"method\":\"grandpa_justification\",\"params\":[\"{:?}\"],\"id\":1}}",
+			header(41).hash(),
+		);
+		let resp = io.handle_request_sync(&request, meta);
+		assert_eq!(resp, Some(r#"{"jsonrpc":"2.0","result":null,"id":1}"#.into()));
+	}
 }
@@ -189,7 +189,9 @@ where
 	}
 
 	/// Create a new authority set.
-	pub(crate) fn new(
+	///
+	/// Returns `None` if the authority list is empty or contains a zero weight.
+	pub fn new(
 		authorities: AuthorityList,
 		set_id: u64,
 		pending_standard_changes: ForkTree<H, N, PendingChange<H, N>>,
@@ -1631,6 +1631,7 @@ mod tests {
 		crate::Config {
 			gossip_duration: Duration::from_millis(10),
 			justification_period: 256,
+			archive_justifications: false,
 			keystore: None,
 			name: None,
 			is_authority: true,
@@ -135,6 +135,7 @@ fn config() -> crate::Config {
 	crate::Config {
 		gossip_duration: std::time::Duration::from_millis(10),
 		justification_period: 256,
+		archive_justifications: false,
 		keystore: None,
 		name: None,
 		is_authority: true,
@@ -37,7 +37,7 @@ use finality_grandpa::{
 use sp_blockchain::HeaderMetadata;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
-	Block as BlockT, Header as HeaderT, NumberFor, Zero,
+	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
 };
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_INFO};
 
@@ -1114,6 +1114,7 @@ where
 			self.client.clone(),
 			&self.authority_set,
 			Some(self.config.justification_period.into()),
+			self.config.archive_justifications,
 			hash,
 			number,
 			(round, commit).into(),
@@ -1178,6 +1179,7 @@ pub(crate) fn finalize_block<BE, Block, Client>(
 	client: Arc<Client>,
 	authority_set: &SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
 	justification_period: Option<NumberFor<Block>>,
+	archive_justifications: bool,
 	hash: Block::Hash,
 	number: NumberFor<Block>,
 	justification_or_commit: JustificationOrCommit<Block>,
@@ -1209,6 +1211,20 @@ where
 		return Ok(());
 	}
 
+	if let (true, Some(justification_period), JustificationOrCommit::Commit((round_number, commit))) =
+		(archive_justifications, justification_period, &justification_or_commit)
+	{
+		archive_checkpoint_justifications(
+			&client,
+			justification_period,
+			status.finalized_number,
+			hash,
+			number,
+			*round_number,
+			commit,
+		)?;
+	}
+
 	// FIXME #1483: clone only when changed
 	let old_authority_set = authority_set.clone();
 
@@ -1356,3 +1372,76 @@ where
 		}
 	}
 }
+
+/// Finalize every block at a multiple of `justification_period` between the last
+/// finalized block and the given commit target, storing a justification with each
+/// of them. The commit precommits target descendants of all these blocks, so the
+/// same votes justify them.
+fn archive_checkpoint_justifications<BE, Block, Client>(
+	client: &Arc<Client>,
+	justification_period: NumberFor<Block>,
+	last_finalized_number: NumberFor<Block>,
+	hash: Block::Hash,
+	number: NumberFor<Block>,
+	round_number: RoundNumber,
+	commit: &Commit<Block>,
+) -> Result<(), Error>
+where
+	Block: BlockT,
+	BE: Backend<Block>,
+	Client: crate::ClientForGrandpa<Block, BE>,
+{
+	if justification_period.is_zero() {
+		return Ok(());
+	}
+
+	let first_checkpoint =
+		(last_finalized_number / justification_period + One::one()) * justification_period;
+	if first_checkpoint >= number {
+		return Ok(());
+	}
+
+	// collect the checkpoints on the chain of the commit target.
+	let mut checkpoints = Vec::new();
+	let mut current_hash = hash;
+	loop {
+		let header = client.header(BlockId::Hash(current_hash))?
+			.ok_or_else(|| Error::Blockchain(format!("Missing header for block {:?}", current_hash)))?;
+		let current_number = *header.number();
+
+		if current_number < first_checkpoint {
+			break;
+		}
+
+		if current_number < number && (current_number % justification_period).is_zero() {
+			checkpoints.push((current_hash, current_number));
+		}
+
+		current_hash = *header.parent_hash();
+	}
+
+	for (target_hash, target_number) in checkpoints.into_iter().rev() {
+		let justification = GrandpaJustification::from_commit(
+			client,
+			round_number,
+			Commit::<Block> {
+				target_hash,
+				target_number,
+				precommits: commit.precommits.clone(),
+			},
+		)?;
+
+		debug!(target: "afg", "Archiving justification for checkpoint ({:?}, {})", target_number, target_hash);
+
+		client.lock_import_and_run(|import_op| {
+			client.apply_finality(
+				import_op,
+				BlockId::Hash(target_hash),
+				Some(justification.encode()),
+				true,
+			)
+		})?;
+	}
+
+	Ok(())
+}
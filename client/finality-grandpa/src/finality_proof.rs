@@ -47,7 +47,9 @@ use sp_runtime::{
 	traits::{NumberFor, Block as BlockT, Header as HeaderT, Zero, One},
 };
 use sc_client_api::backend::Backend;
-use sp_finality_grandpa::{AuthorityId, AuthorityList};
+use sp_finality_grandpa::{AuthorityId, AuthorityList, AuthoritySetProof, AuthoritySetProofFragment};
+
+pub use sp_finality_grandpa::FinalityProof;
 
 use crate::authorities::AuthoritySetChanges;
 use crate::justification::GrandpaJustification;
@@ -122,19 +124,18 @@ where
 			block,
 		)
 	}
-}
 
-/// Finality for block B is proved by providing:
-/// 1) the justification for the descendant block F;
-/// 2) headers sub-chain (B; F] if B != F;
-#[derive(Debug, PartialEq, Encode, Decode, Clone)]
-pub struct FinalityProof<Header: HeaderT> {
-	/// The hash of block F for which justification is provided.
-	pub block: Header::Hash,
-	/// Justification of the block F.
-	pub justification: Vec<u8>,
-	/// The set of headers in the range (B; F] that we believe are unknown to the caller. Ordered.
-	pub unknown_headers: Vec<Header>,
+	/// Returns the justification stored for the given finalized block, if any.
+	/// Justifications are stored for authority set changes and periodically,
+	/// depending on the GRANDPA configuration.
+	pub fn justification(
+		&self,
+		block: Block::Hash,
+	) -> Result<Option<Justification>, FinalityProofError> {
+		self.backend.blockchain()
+			.justification(BlockId::Hash(block))
+			.map_err(FinalityProofError::Client)
+	}
 }
 
 /// Errors occurring when trying to prove finality
@@ -151,23 +152,6 @@ pub enum FinalityProofError {
 	Client(sp_blockchain::Error),
 }
 
-/// Single fragment of authority set proof.
-///
-/// Finality for block B is proved by providing:
-/// 1) headers of this block;
-/// 2) the justification for the block containing a authority set change digest;
-#[derive(Debug, PartialEq, Clone, Encode, Decode)]
-pub(crate) struct AuthoritySetProofFragment<Header: HeaderT> {
-	/// The header of the given block.
-	pub header: Header,
-	/// Justification of the block F.
-	pub justification: Vec<u8>,
-}
-
-/// Proof of authority set is the ordered set of authority set fragments, where:
-/// - last fragment match target block.
-type AuthoritySetProof<Header> = Vec<AuthoritySetProofFragment<Header>>;
-
 fn prove_finality<Block, B, J>(
 	blockchain: &B,
 	authority_set_changes: AuthoritySetChanges<NumberFor<Block>>,
@@ -373,102 +357,21 @@ fn get_warp_sync_proof_fragment<Block: BlockT, B: BlockchainBackend<Block>>(
 ///
 /// Returns the header of the target block.
 #[allow(unused)]
-pub(crate) fn check_warp_sync_proof<Block: BlockT, J>(
+pub(crate) fn check_warp_sync_proof<Block: BlockT>(
 	current_set_id: u64,
 	current_authorities: AuthorityList,
 	remote_proof: Vec<u8>,
 ) -> ClientResult<(Block::Header, u64, AuthorityList)>
 where
-		NumberFor<Block>: BlockNumberOps,
-		J: Decode + ProvableJustification<Block::Header> + BlockJustification<Block::Header>,
+	NumberFor<Block>: BlockNumberOps,
 {
-	// decode finality proof
-	let proof = AuthoritySetProof::<Block::Header>::decode(&mut &remote_proof[..])
-		.map_err(|_| ClientError::BadJustification("failed to decode authority proof".into()))?;
-
-		let last = proof.len() - 1;
-
-		let mut result = (current_set_id, current_authorities, NumberFor::<Block>::zero());
-
-		for (ix, fragment) in proof.into_iter().enumerate() {
-			let is_last = ix == last;
-			result = check_warp_sync_proof_fragment::<Block, J>(
-				result.0,
-				&result.1,
-				&result.2,
-				is_last,
-				&fragment,
-			)?;
-
-			if is_last {
-				return Ok((fragment.header, result.0, result.1))
-			}
-		}
-
-		// empty proof can't prove anything
-		return Err(ClientError::BadJustification("empty proof of authority".into()));
-}
-
-/// Check finality authority set sequence.
-fn check_warp_sync_proof_fragment<Block: BlockT, J>(
-	current_set_id: u64,
-	current_authorities: &AuthorityList,
-	previous_checked_block: &NumberFor<Block>,
-	is_last: bool,
-	authorities_proof: &AuthoritySetProofFragment<Block::Header>,
-) -> ClientResult<(u64, AuthorityList, NumberFor<Block>)>
-where
-		NumberFor<Block>: BlockNumberOps,
-		J: Decode + ProvableJustification<Block::Header> + BlockJustification<Block::Header>,
-{
-	let justification: J = Decode::decode(&mut authorities_proof.justification.as_slice())
-		.map_err(|_| ClientError::JustificationDecode)?;
-		justification.verify(current_set_id, &current_authorities)?;
-
-		// assert justification is for this header
-		if &justification.number() != authorities_proof.header.number()
-			|| justification.hash().as_ref() != authorities_proof.header.hash().as_ref() {
-				return Err(ClientError::Backend("Invalid authority warp proof, justification do not match header".to_string()));
-		}
-
-		if authorities_proof.header.number() <= previous_checked_block {
-			return Err(ClientError::Backend("Invalid authority warp proof".to_string()));
-		}
-		let current_block = authorities_proof.header.number();
-		let mut at_block = None;
-		if let Some(sp_finality_grandpa::ScheduledChange {
-			next_authorities,
-			delay,
-		}) = crate::import::find_scheduled_change::<Block>(&authorities_proof.header) {
-			let dest = *current_block + delay;
-			at_block = Some((dest, next_authorities));
-		}
-		if let Some((block_number, sp_finality_grandpa::ScheduledChange {
-			next_authorities,
-			delay,
-		})) = crate::import::find_forced_change::<Block>(&authorities_proof.header) {
-			let dest = block_number + delay;
-			at_block = Some((dest, next_authorities));
-		}
-
-		// Fragment without change only allowed for proof last block.
-		if at_block.is_none() && !is_last {
-			return Err(ClientError::Backend("Invalid authority warp proof".to_string()));
-		}
-		if let Some((at_block, next_authorities)) = at_block {
-			Ok((current_set_id + 1, next_authorities, at_block))
-		} else {
-			Ok((current_set_id, current_authorities.clone(), current_block.clone()))
-		}
-}
-
-/// Block info extracted from the justification.
-pub(crate) trait BlockJustification<Header: HeaderT> {
-	/// Block number justified.
-	fn number(&self) -> Header::Number;
-
-	/// Block hash justified.
-	fn hash(&self) -> Header::Hash;
+	sp_finality_grandpa::verification::verify_authority_set_proof::<Block::Header>(
+		&remote_proof,
+		current_set_id,
+		current_authorities,
+	).map_err(|error| {
+		ClientError::BadJustification(format!("Invalid authority warp proof: {:?}", error))
+	})
 }
 
 /// Check GRANDPA proof-of-finality for the given block.
@@ -530,15 +433,6 @@ where
 	}
 }
 
-impl<Block: BlockT> BlockJustification<Block::Header> for GrandpaJustification<Block> {
-	fn number(&self) -> NumberFor<Block> {
-		self.commit.target_number.clone()
-	}
-	fn hash(&self) -> Block::Hash {
-		self.commit.target_hash.clone()
-	}
-}
-
 /// Simple cache for warp sync queries.
 pub struct WarpSyncFragmentCache<Header: HeaderT> {
 	cache: linked_hash_map::LinkedHashMap<
@@ -600,6 +494,7 @@ pub(crate) mod tests {
 	use sp_finality_grandpa::AuthorityList;
 	use sc_client_api::NewBlockState;
 	use sc_client_api::in_mem::Blockchain as InMemoryBlockchain;
+	use sp_keyring::Ed25519Keyring;
 	use substrate_test_runtime_client::runtime::{Block, Header, H256};
 
 	pub(crate) type FinalityProof = super::FinalityProof<Header>;
@@ -617,24 +512,6 @@ pub(crate) mod tests {
 		}
 	}
 
-	#[derive(Debug, PartialEq, Encode, Decode)]
-	pub struct TestBlockJustification(TestJustification, u64, H256);
-
-	impl BlockJustification<Header> for TestBlockJustification {
-		fn number(&self) -> <Header as HeaderT>::Number {
-			self.1
-		}
-		fn hash(&self) -> <Header as HeaderT>::Hash {
-			self.2.clone()
-		}
-	}
-
-	impl ProvableJustification<Header> for TestBlockJustification {
-		fn verify(&self, set_id: u64, authorities: &[(AuthorityId, u64)]) -> ClientResult<()> {
-			self.0.verify(set_id, authorities)
-		}
-	}
-
 	fn header(number: u64) -> Header {
 		let parent_hash = match number {
 			0 => Default::default(),
@@ -649,6 +526,34 @@ pub(crate) mod tests {
 		)
 	}
 
+	fn authorities(keys: &[Ed25519Keyring]) -> AuthorityList {
+		keys.iter().map(|key| (key.public().into(), 1)).collect()
+	}
+
+	fn signed_justification(header: &Header, set_id: u64, keys: &[Ed25519Keyring]) -> Vec<u8> {
+		let precommit = finality_grandpa::Precommit {
+			target_hash: header.hash(),
+			target_number: *header.number(),
+		};
+		let msg = finality_grandpa::Message::Precommit(precommit.clone());
+		let encoded = sp_finality_grandpa::localized_payload(1, set_id, &msg);
+		let commit = finality_grandpa::Commit {
+			target_hash: header.hash(),
+			target_number: *header.number(),
+			precommits: keys.iter().map(|key| finality_grandpa::SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: key.sign(&encoded[..]).into(),
+				id: key.public().into(),
+			}).collect(),
+		};
+
+		GrandpaJustification::<Block>::from_commit(
+			&Arc::new(InMemoryBlockchain::<Block>::new()),
+			1,
+			commit,
+		).unwrap().encode()
+	}
+
 	fn test_blockchain() -> InMemoryBlockchain<Block> {
 		let blockchain = InMemoryBlockchain::<Block>::new();
 		blockchain
@@ -825,10 +730,12 @@ pub(crate) mod tests {
 
 	#[test]
 	fn warp_sync_proof_encoding_decoding() {
+		use Ed25519Keyring::{Alice, Bob, Charlie, Dave, Eve};
+
 		fn test_blockchain(
 			nb_blocks: u64,
-			mut set_change: &[(u64, Vec<u8>)],
-			mut justifications: &[(u64, Vec<u8>)],
+			mut set_change: &[(u64, Vec<Ed25519Keyring>)],
+			mut justifications: &[(u64, Vec<Ed25519Keyring>)],
 		) -> (InMemoryBlockchain<Block>, Vec<H256>) {
 			let blockchain = InMemoryBlockchain::<Block>::new();
 			let mut hashes = Vec::<H256>::new();
@@ -839,7 +746,7 @@ pub(crate) mod tests {
 				set_change.first()
 					.map(|j| if i == j.0 {
 						set_change = &set_change[1..];
-						let next_authorities: Vec<_> = j.1.iter().map(|i| (AuthorityId::from_slice(&[*i; 32]), 1u64)).collect();
+						let next_authorities = authorities(&j.1);
 						set_id_next += 1;
 						header.digest_mut().logs.push(
 							sp_runtime::generic::DigestItem::Consensus(
@@ -858,16 +765,7 @@ pub(crate) mod tests {
 				let justification = justifications.first()
 					.and_then(|j| if i == j.0 {
 						justifications = &justifications[1..];
-
-						let authority = j.1.iter().map(|j|
-							(AuthorityId::from_slice(&[*j; 32]), 1u64)
-						).collect();
-						let justification = TestBlockJustification(
-							TestJustification((set_id, authority), vec![i as u8]),
-							i,
-							header_hash,
-						);
-						Some(justification.encode())
+						Some(signed_justification(&header, set_id, &j.1))
 					} else {
 						None
 					});
@@ -876,19 +774,19 @@ pub(crate) mod tests {
 
 				blockchain.insert(header_hash, header, justification, None, NewBlockState::Final)
 					.unwrap();
-					}
+			}
 			(blockchain, hashes)
 		}
 
 		let (blockchain, hashes) = test_blockchain(
 			7,
-			vec![(3, vec![9])].as_slice(),
+			vec![(3, vec![Dave])].as_slice(),
 			vec![
-			(1, vec![1, 2, 3]),
-			(2, vec![1, 2, 3]),
-			(3, vec![1, 2, 3]),
-			(4, vec![9]),
-			(6, vec![9]),
+			(1, vec![Alice, Bob, Charlie]),
+			(2, vec![Alice, Bob, Charlie]),
+			(3, vec![Alice, Bob, Charlie]),
+			(4, vec![Dave]),
+			(6, vec![Dave]),
 			].as_slice(),
 		);
 
@@ -898,25 +796,20 @@ pub(crate) mod tests {
 		let proof = prove_warp_sync(&blockchain, hashes[6], None, Some(&mut cache)).unwrap();
 		assert_eq!(proof_no_cache, proof);
 
-		let initial_authorities: Vec<_> = [1u8, 2, 3].iter().map(|i|
-			(AuthorityId::from_slice(&[*i; 32]), 1u64)
-		).collect();
-
-		let authorities_next: Vec<_> = [9u8].iter().map(|i|
-			(AuthorityId::from_slice(&[*i; 32]), 1u64)
-		).collect();
+		let initial_authorities = authorities(&[Alice, Bob, Charlie]);
+		let authorities_next = authorities(&[Dave]);
 
-		assert!(check_warp_sync_proof::<Block, TestBlockJustification>(
+		assert!(check_warp_sync_proof::<Block>(
 				0,
 				initial_authorities.clone(),
 				proof.clone(),
 		).is_err());
-		assert!(check_warp_sync_proof::<Block, TestBlockJustification>(
+		assert!(check_warp_sync_proof::<Block>(
 				0,
 				authorities_next.clone(),
 				proof.clone(),
 		).is_err());
-		assert!(check_warp_sync_proof::<Block, TestBlockJustification>(
+		assert!(check_warp_sync_proof::<Block>(
 				1,
 				initial_authorities.clone(),
 				proof.clone(),
@@ -925,7 +818,7 @@ pub(crate) mod tests {
 			_header,
 			current_set_id,
 			current_set,
-		) = check_warp_sync_proof::<Block, TestBlockJustification>(
+		) = check_warp_sync_proof::<Block>(
 		1,
 		authorities_next.clone(),
 		proof.clone(),
@@ -940,7 +833,7 @@ pub(crate) mod tests {
 			_header,
 			current_set_id,
 			current_set,
-		) = check_warp_sync_proof::<Block, TestBlockJustification>(
+		) = check_warp_sync_proof::<Block>(
 		0,
 		initial_authorities.clone(),
 		proof.clone(),
@@ -952,15 +845,15 @@ pub(crate) mod tests {
 		// two changes
 		let (blockchain, hashes) = test_blockchain(
 			13,
-			vec![(3, vec![7]), (8, vec![9])].as_slice(),
+			vec![(3, vec![Eve]), (8, vec![Dave])].as_slice(),
 			vec![
-			(1, vec![1, 2, 3]),
-			(2, vec![1, 2, 3]),
-			(3, vec![1, 2, 3]),
-			(4, vec![7]),
-			(6, vec![7]),
-			(8, vec![7]), // warning, requires a justification on change set
-			(10, vec![9]),
+			(1, vec![Alice, Bob, Charlie]),
+			(2, vec![Alice, Bob, Charlie]),
+			(3, vec![Alice, Bob, Charlie]),
+			(4, vec![Eve]),
+			(6, vec![Eve]),
+			(8, vec![Eve]), // warning, requires a justification on change set
+			(10, vec![Dave]),
 			].as_slice(),
 		);
 
@@ -970,7 +863,7 @@ pub(crate) mod tests {
 			_header,
 			current_set_id,
 			current_set,
-		) = check_warp_sync_proof::<Block, TestBlockJustification>(
+		) = check_warp_sync_proof::<Block>(
 		0,
 		initial_authorities.clone(),
 		proof.clone(),
@@ -979,4 +872,82 @@ pub(crate) mod tests {
 		assert_eq!(current_set_id, 2);
 		assert_eq!(current_set, authorities_next);
 	}
+
+	#[test]
+	fn proofs_are_verified_by_the_no_std_verifier() {
+		use sp_finality_grandpa::verification::{self, Error as VerificationError};
+
+		let alice = authorities(&[Ed25519Keyring::Alice]);
+		let bob = authorities(&[Ed25519Keyring::Bob]);
+
+		// block #4 hands over from alice (set 0) to bob (set 1), and block #7 is justified
+		// by bob.
+		let blockchain = InMemoryBlockchain::<Block>::new();
+		let mut headers = Vec::<Header>::new();
+		for number in 0..8 {
+			let mut header = header(number);
+			if let Some(parent) = headers.last() {
+				header.set_parent_hash(parent.hash());
+			}
+			if number == 4 {
+				header.digest_mut().logs.push(sp_runtime::generic::DigestItem::Consensus(
+					sp_finality_grandpa::GRANDPA_ENGINE_ID,
+					sp_finality_grandpa::ConsensusLog::ScheduledChange(
+						sp_finality_grandpa::ScheduledChange { delay: 0u64, next_authorities: bob.clone() }
+					).encode(),
+				));
+			}
+
+			let header_justification = match number {
+				4 => Some(signed_justification(&header, 0, &[Ed25519Keyring::Alice])),
+				7 => Some(signed_justification(&header, 1, &[Ed25519Keyring::Bob])),
+				_ => None,
+			};
+
+			blockchain
+				.insert(header.hash(), header.clone(), header_justification, None, NewBlockState::Final)
+				.unwrap();
+			headers.push(header);
+		}
+
+		let mut authority_set_changes = AuthoritySetChanges::empty();
+		authority_set_changes.append(0, 4);
+		authority_set_changes.append(1, 7);
+
+		let finality_proof = prove_finality::<_, _, GrandpaJustification<Block>>(
+			&blockchain,
+			authority_set_changes,
+			5,
+		).unwrap().unwrap();
+		let authority_set_proof = prove_warp_sync(&blockchain, headers[0].hash(), None, None).unwrap();
+
+		// the proofs verify against the right authority sets only.
+		let proof = verification::verify_finality_proof::<Header>(&finality_proof, 1, &bob).unwrap();
+		assert_eq!(proof.block, headers[7].hash());
+		assert_eq!(proof.unknown_headers, vec![headers[6].clone()]);
+		assert_eq!(
+			verification::verify_finality_proof::<Header>(&finality_proof, 0, &bob),
+			Err(VerificationError::InvalidSignature),
+		);
+		assert_eq!(
+			verification::verify_finality_proof::<Header>(&finality_proof, 1, &alice),
+			Err(VerificationError::InvalidCommit),
+		);
+
+		let (header, set_id, authorities) = verification::verify_authority_set_proof::<Header>(
+			&authority_set_proof,
+			0,
+			alice.clone(),
+		).unwrap();
+		assert_eq!((header, set_id, authorities), (headers[7].clone(), 1, bob.clone()));
+
+		// starting from alice's set, the authority set proof leads to bob's set.
+		let proof = verification::verify_finality_proof_with_authority_set_proof::<Header>(
+			&authority_set_proof,
+			&finality_proof,
+			0,
+			alice.clone(),
+		).unwrap();
+		assert_eq!(proof.block, headers[7].hash());
+	}
 }
@@ -635,6 +635,7 @@ where
 			self.inner.clone(),
 			&self.authority_set,
 			None,
+			false,
 			hash,
 			number,
 			justification.into(),
@@ -16,16 +16,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use sp_blockchain::{Error as ClientError, HeaderBackend};
 use parity_scale_codec::{Encode, Decode};
 use finality_grandpa::voter_set::VoterSet;
-use finality_grandpa::{Error as GrandpaError};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{NumberFor, Block as BlockT, Header as HeaderT};
 use sp_finality_grandpa::AuthorityId;
+use sp_finality_grandpa::verification::{self, Error as VerificationError};
 
 use crate::{Commit, Error};
 
@@ -37,12 +37,13 @@ use crate::{Commit, Error};
 ///
 /// This is meant to be stored in the db and passed around the network to other
 /// nodes, and are used by syncing nodes to prove authority set handoffs.
+///
+/// The encoding and the verification are the ones of the no_std
+/// `sp_finality_grandpa::verification::GrandpaJustification`.
 #[derive(Clone, Encode, Decode, PartialEq, Eq, Debug)]
-pub struct GrandpaJustification<Block: BlockT> {
-	round: u64,
-	pub(crate) commit: Commit<Block>,
-	votes_ancestries: Vec<Block::Header>,
-}
+pub struct GrandpaJustification<Block: BlockT>(
+	pub(crate) verification::GrandpaJustification<Block::Header>,
+);
 
 impl<Block: BlockT> GrandpaJustification<Block> {
 	/// Create a GRANDPA justification from the given commit. This method
@@ -84,7 +85,11 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 			}
 		}
 
-		Ok(GrandpaJustification { round, commit, votes_ancestries })
+		Ok(GrandpaJustification(verification::GrandpaJustification {
+			round,
+			commit,
+			votes_ancestries,
+		}))
 	}
 
 	/// Decode a GRANDPA justification and validate the commit and the votes'
@@ -101,7 +106,7 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 		let justification = GrandpaJustification::<Block>::decode(&mut &*encoded)
 			.map_err(|_| ClientError::JustificationDecode)?;
 
-		if (justification.commit.target_hash, justification.commit.target_number) != finalized_target {
+		if justification.0.target() != finalized_target {
 			let msg = "invalid commit target in grandpa justification".to_string();
 			Err(ClientError::BadJustification(msg))
 		} else {
@@ -114,111 +119,15 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 	where
 		NumberFor<Block>: finality_grandpa::BlockNumberOps,
 	{
-		use finality_grandpa::Chain;
-
-		let ancestry_chain = AncestryChain::<Block>::new(&self.votes_ancestries);
-
-		match finality_grandpa::validate_commit(
-			&self.commit,
-			voters,
-			&ancestry_chain,
-		) {
-			Ok(ref result) if result.ghost().is_some() => {},
-			_ => {
-				let msg = "invalid commit in grandpa justification".to_string();
-				return Err(ClientError::BadJustification(msg));
-			}
-		}
-
-		let mut buf = Vec::new();
-		let mut visited_hashes = HashSet::new();
-		for signed in self.commit.precommits.iter() {
-			if !sp_finality_grandpa::check_message_signature_with_buffer(
-				&finality_grandpa::Message::Precommit(signed.precommit.clone()),
-				&signed.id,
-				&signed.signature,
-				self.round,
-				set_id,
-				&mut buf,
-			) {
-				return Err(ClientError::BadJustification(
-					"invalid signature for precommit in grandpa justification".to_string()));
-			}
-
-			if self.commit.target_hash == signed.precommit.target_hash {
-				continue;
-			}
-
-			match ancestry_chain.ancestry(self.commit.target_hash, signed.precommit.target_hash) {
-				Ok(route) => {
-					// ancestry starts from parent hash but the precommit target hash has been visited
-					visited_hashes.insert(signed.precommit.target_hash);
-					for hash in route {
-						visited_hashes.insert(hash);
-					}
-				},
-				_ => {
-					return Err(ClientError::BadJustification(
-						"invalid precommit ancestry proof in grandpa justification".to_string()));
-				},
-			}
-		}
-
-		let ancestry_hashes = self.votes_ancestries
-			.iter()
-			.map(|h: &Block::Header| h.hash())
-			.collect();
-
-		if visited_hashes != ancestry_hashes {
-			return Err(ClientError::BadJustification(
-				"invalid precommit ancestries in grandpa justification with unused headers".to_string()));
-		}
-
-		Ok(())
-	}
-}
-
-/// A utility trait implementing `finality_grandpa::Chain` using a given set of headers.
-/// This is useful when validating commits, using the given set of headers to
-/// verify a valid ancestry route to the target commit block.
-struct AncestryChain<Block: BlockT> {
-	ancestry: HashMap<Block::Hash, Block::Header>,
-}
-
-impl<Block: BlockT> AncestryChain<Block> {
-	fn new(ancestry: &[Block::Header]) -> AncestryChain<Block> {
-		let ancestry: HashMap<_, _> = ancestry
-			.iter()
-			.cloned()
-			.map(|h: Block::Header| (h.hash(), h))
-			.collect();
-
-		AncestryChain { ancestry }
-	}
-}
-
-impl<Block: BlockT> finality_grandpa::Chain<Block::Hash, NumberFor<Block>> for AncestryChain<Block> where
-	NumberFor<Block>: finality_grandpa::BlockNumberOps
-{
-	fn ancestry(&self, base: Block::Hash, block: Block::Hash) -> Result<Vec<Block::Hash>, GrandpaError> {
-		let mut route = Vec::new();
-		let mut current_hash = block;
-		loop {
-			if current_hash == base { break; }
-			match self.ancestry.get(&current_hash) {
-				Some(current_header) => {
-					current_hash = *current_header.parent_hash();
-					route.push(current_hash);
-				},
-				_ => return Err(GrandpaError::NotDescendent),
-			}
-		}
-		route.pop(); // remove the base
-
-		Ok(route)
-	}
-
-	fn best_chain_containing(&self, _block: Block::Hash) -> Option<(Block::Hash, NumberFor<Block>)> {
-		None
+		self.0.verify_with_voter_set(set_id, voters).map_err(|error| {
+			let msg = match error {
+				VerificationError::InvalidSignature =>
+					"invalid signature for precommit in grandpa justification",
+				VerificationError::InvalidAncestry =>
+					"invalid precommit ancestry proof in grandpa justification",
+				_ => "invalid commit in grandpa justification",
+			};
+			ClientError::BadJustification(msg.to_string())
+		})
 	}
 }
@@ -67,6 +67,7 @@ use sc_client_api::{
 	ExecutionStrategy, Finalizer, TransactionFor, ExecutorProvider,
 };
 use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 use prometheus_endpoint::{PrometheusError, Registry};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::{HeaderBackend, Error as ClientError, HeaderMetadata};
@@ -121,13 +122,14 @@ mod observer;
 mod until_imported;
 mod voting_rule;
 
-pub use authorities::{SharedAuthoritySet, AuthoritySet};
+pub use authorities::{SharedAuthoritySet, AuthoritySet, AuthoritySetChanges, PendingChange};
 pub use finality_proof::{FinalityProof, FinalityProofProvider, FinalityProofError};
 pub use notification::{GrandpaJustificationSender, GrandpaJustificationStream};
 pub use import::GrandpaBlockImport;
 pub use justification::GrandpaJustification;
 pub use voting_rule::{
-	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
+	BeforeBestBlockBy, RuntimeFinalityDelay, ThreeQuartersOfTheUnfinalizedChain, VotingRule,
+	VotingRulesBuilder,
 };
 pub use finality_grandpa::voter::report;
 pub use finality_proof::{prove_warp_sync, WarpSyncFragmentCache};
@@ -259,6 +261,10 @@ pub struct Config {
 	/// at least every justification_period blocks. There are some other events which might cause
 	/// justification generation.
 	pub justification_period: u32,
+	/// Whether to store a justification for every block at a multiple of
+	/// `justification_period` rather than only for the first block finalized past
+	/// it, so that third parties can fetch justifications at predictable heights.
+	pub archive_justifications: bool,
 	/// Whether the GRANDPA observer protocol is live on the network and thereby
 	/// a full-node not running as a validator is running the GRANDPA observer
 	/// protocol (we will only issue catch-up requests to authorities when the
@@ -519,6 +525,33 @@ where
 	)
 }
 
+/// An authority set change forced at a given block, regardless of what the runtime
+/// signaled there.
+///
+/// Used to recover a stalled chain, usually provided through the chain spec.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritySetHardFork<Hash, Number> {
+	/// The id of the authority set being replaced.
+	pub set_id: SetId,
+	/// Hash of the block at which the change is signaled.
+	pub block_hash: Hash,
+	/// Number of the block at which the change is signaled.
+	pub block_number: Number,
+	/// The authorities of the new set.
+	pub authorities: AuthorityList,
+}
+
+impl<Hash, Number> From<AuthoritySetHardFork<Hash, Number>> for (SetId, (Hash, Number), AuthorityList) {
+	fn from(hard_fork: AuthoritySetHardFork<Hash, Number>) -> Self {
+		(hard_fork.set_id, (hard_fork.block_hash, hard_fork.block_number), hard_fork.authorities)
+	}
+}
+
+/// Authority set hard forks, as they are provided by a chain spec extension.
+pub type AuthoritySetHardForks<Block> =
+	Option<Vec<AuthoritySetHardFork<<Block as BlockT>::Hash, NumberFor<Block>>>>;
+
 /// Make block importer and link half necessary to tie the background voter to
 /// it. A vector of authority set hard forks can be passed, any authority set
 /// change signaled at the given block (either already signalled or in a further
@@ -675,6 +708,7 @@ pub fn grandpa_peers_set_config() -> sc_network::config::NonDefaultSetConfig {
 		notifications_protocol: communication::GRANDPA_PROTOCOL_NAME.into(),
 		// Notifications reach ~256kiB in size at the time of writing on Kusama and Polkadot.
 		max_notification_size: 1024 * 1024,
+		bandwidth_limit: Default::default(),
 		set_config: sc_network::config::SetConfig {
 			in_peers: 25,
 			out_peers: 25,
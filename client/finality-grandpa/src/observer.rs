@@ -121,6 +121,7 @@ where
 				client.clone(),
 				&authority_set,
 				None,
+				false,
 				finalized_hash,
 				finalized_number,
 				(round, commit).into(),
@@ -43,7 +43,10 @@ use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_runtime::generic::{BlockId, DigestItem};
 use sp_core::H256;
 use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
-use sp_finality_grandpa::{GRANDPA_ENGINE_ID, AuthorityList, EquivocationProof, GrandpaApi, OpaqueKeyOwnershipProof};
+use sp_finality_grandpa::{
+	GRANDPA_ENGINE_ID, AuthorityList, EquivocationProof, FinalityDelay, GrandpaApi,
+	OpaqueKeyOwnershipProof,
+};
 
 use authorities::AuthoritySet;
 use sc_block_builder::BlockBuilderProvider;
@@ -150,14 +153,21 @@ impl TestNetFactory for GrandpaTestNet {
 #[derive(Default, Clone)]
 pub(crate) struct TestApi {
 	genesis_authorities: AuthorityList,
+	finality_delay: FinalityDelay<BlockNumber>,
 }
 
 impl TestApi {
 	pub fn new(genesis_authorities: AuthorityList) -> Self {
 		TestApi {
 			genesis_authorities,
+			finality_delay: Default::default(),
 		}
 	}
+
+	pub fn with_finality_delay(mut self, finality_delay: FinalityDelay<BlockNumber>) -> Self {
+		self.finality_delay = finality_delay;
+		self
+	}
 }
 
 pub(crate) struct RuntimeApi {
@@ -193,6 +203,10 @@ sp_api::mock_impl_runtime_apis! {
 		) -> Option<OpaqueKeyOwnershipProof> {
 			None
 		}
+
+		fn finality_delay(&self) -> FinalityDelay<BlockNumber> {
+			self.inner.finality_delay
+		}
 	}
 }
 
@@ -250,6 +264,7 @@ fn initialize_grandpa(
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
@@ -393,6 +408,7 @@ fn finalize_3_voters_1_full_observer() {
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore: None,
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
@@ -486,6 +502,7 @@ fn transition_3_voters_twice_1_full_observer() {
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
@@ -615,6 +632,78 @@ fn justification_is_generated_periodically() {
 	}
 }
 
+#[test]
+fn justifications_are_archived_at_checkpoints() {
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters.clone()), 1);
+	let peer = net.peer(0);
+	let link = peer.data.lock().take().unwrap();
+
+	// add 21 blocks
+	peer.push_blocks(21, false);
+
+	let client = peer.client().clone();
+	let header = |number| client.header(&BlockId::Number(number)).unwrap().unwrap();
+	let target = header(21);
+
+	// a commit for block 21, signed by the only voter
+	let commit = {
+		let (round, set_id) = (1, 0);
+
+		let precommit = finality_grandpa::Precommit {
+			target_hash: target.hash(),
+			target_number: *target.number(),
+		};
+
+		let msg = finality_grandpa::Message::Precommit(precommit.clone());
+		let encoded = sp_finality_grandpa::localized_payload(round, set_id, &msg);
+
+		finality_grandpa::Commit {
+			target_hash: target.hash(),
+			target_number: *target.number(),
+			precommits: vec![finality_grandpa::SignedPrecommit {
+				precommit,
+				signature: peers[0].sign(&encoded[..]).into(),
+				id: peers[0].public().into(),
+			}],
+		}
+	};
+
+	environment::finalize_block::<substrate_test_runtime_client::Backend, Block, _>(
+		link.client.clone(),
+		&link.persistent_data.authority_set,
+		Some(8),
+		true,
+		target.hash(),
+		*target.number(),
+		(1, commit).into(),
+		false,
+		None,
+	).unwrap();
+
+	assert_eq!(client.info().finalized_number, 21);
+
+	// every checkpoint gets a justification proving its own finality
+	let voter_set = finality_grandpa::voter_set::VoterSet::new(voters).unwrap();
+	for checkpoint in &[8, 16] {
+		let checkpoint = header(*checkpoint);
+		let justification = client.justification(&BlockId::Hash(checkpoint.hash())).unwrap().unwrap();
+
+		assert!(GrandpaJustification::<Block>::decode_and_verify_finalizes(
+			&justification,
+			(checkpoint.hash(), *checkpoint.number()),
+			0,
+			&voter_set,
+		).is_ok());
+	}
+
+	// the commit target itself isn't a checkpoint
+	assert!(client.justification(&BlockId::Number(21)).unwrap().is_none());
+	assert!(client.justification(&BlockId::Number(12)).unwrap().is_none());
+}
+
 #[test]
 fn sync_justifications_on_change_blocks() {
 	let mut runtime = Runtime::new().unwrap();
@@ -919,6 +1008,7 @@ fn voter_persists_its_votes() {
 		let config = Config {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_period: 32,
+			archive_justifications: false,
 			keystore: Some(bob_keystore.clone()),
 			name: Some(format!("peer#{}", 1)),
 			is_authority: true,
@@ -962,6 +1052,7 @@ fn voter_persists_its_votes() {
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", 0)),
 				is_authority: true,
@@ -1004,6 +1095,7 @@ fn voter_persists_its_votes() {
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", 0)),
 				is_authority: true,
@@ -1163,6 +1255,7 @@ fn finalize_3_voters_1_light_observer() {
 		Config {
 			gossip_duration: TEST_GOSSIP_DURATION,
 			justification_period: 32,
+			archive_justifications: false,
 			keystore: None,
 			name: Some("observer".to_string()),
 			is_authority: false,
@@ -1204,6 +1297,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 			config: Config {
 				gossip_duration: TEST_GOSSIP_DURATION,
 				justification_period: 32,
+				archive_justifications: false,
 				keystore,
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
@@ -1326,6 +1420,7 @@ where
 	let config = Config {
 		gossip_duration: TEST_GOSSIP_DURATION,
 		justification_period: 32,
+		archive_justifications: false,
 		keystore,
 		name: None,
 		is_authority: true,
@@ -1448,6 +1543,61 @@ fn grandpa_environment_respects_voting_rules() {
 	);
 }
 
+#[test]
+fn grandpa_environment_respects_runtime_finality_delay() {
+	use finality_grandpa::Chain;
+
+	let peers = &[Ed25519Keyring::Alice];
+	let voters = make_ids(peers);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters.clone()), 1);
+	let peer = net.peer(0);
+	let network_service = peer.network_service().clone();
+	let link = peer.data.lock().take().unwrap();
+
+	// add 21 blocks
+	peer.push_blocks(21, false);
+
+	let env_with_delay = |finality_delay| test_environment(
+		&link,
+		None,
+		network_service.clone(),
+		RuntimeFinalityDelay::new(Arc::new(
+			TestApi::new(voters.clone()).with_finality_delay(finality_delay),
+		)),
+	);
+
+	let best_chain_containing = |env: &TestEnvironment<_, _>| env.best_chain_containing(
+		peer.client().info().finalized_hash
+	).unwrap().1;
+
+	// no restrictions set by the runtime, vote on the best block
+	assert_eq!(
+		best_chain_containing(&env_with_delay(FinalityDelay { min_lag: 0, paused: false })),
+		21,
+	);
+
+	// keep the vote 5 blocks behind the best block
+	assert_eq!(
+		best_chain_containing(&env_with_delay(FinalityDelay { min_lag: 5, paused: false })),
+		16,
+	);
+
+	// a lag longer than the chain can't go below the base
+	assert_eq!(
+		best_chain_containing(&env_with_delay(FinalityDelay { min_lag: 50, paused: false })),
+		0,
+	);
+
+	peer.client().finalize_block(BlockId::Number(10), None, false).unwrap();
+
+	// while paused we never vote past the last finalized block
+	assert_eq!(
+		best_chain_containing(&env_with_delay(FinalityDelay { min_lag: 0, paused: true })),
+		10,
+	);
+}
+
 #[test]
 fn grandpa_environment_never_overwrites_round_voter_state() {
 	use finality_grandpa::voter::Environment;
@@ -1593,6 +1743,57 @@ fn imports_justification_for_regular_blocks_on_import() {
 	);
 }
 
+#[test]
+fn authority_set_hard_forks_are_applied_on_import() {
+	let api = TestApi::new(make_ids(&[Ed25519Keyring::Alice]));
+	let forced_authorities = make_ids(&[Ed25519Keyring::Bob, Ed25519Keyring::Charlie]);
+
+	let mut net = GrandpaTestNet::new(api.clone(), 1);
+	let (client, backend) = match net.peer(0).client().clone() {
+		PeersClient::Full(client, backend) => (client, backend),
+		PeersClient::Light(..) => unreachable!("only full clients are used in tests"),
+	};
+
+	let block = client.new_block_at(&BlockId::Number(0), Default::default(), false)
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	let block_hash = block.hash();
+
+	// the hard fork is described the same way as in a chain spec extension.
+	let hard_fork: AuthoritySetHardFork<Hash, BlockNumber> = serde_json::from_value(
+		serde_json::json!({
+			"setId": 0,
+			"blockHash": block_hash,
+			"blockNumber": 1,
+			"authorities": forced_authorities,
+		}),
+	).unwrap();
+
+	let (mut block_import, link) = block_import_with_authority_set_hard_forks(
+		client.clone(),
+		&api,
+		LongestChain::new(backend),
+		vec![hard_fork.into()],
+	).unwrap();
+
+	// the block doesn't signal any change, but the hard fork forces one.
+	let mut import = BlockImportParams::new(BlockOrigin::File, block.header);
+	import.body = Some(block.extrinsics);
+	import.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+	assert_matches!(
+		block_import.import_block(import, HashMap::new()),
+		Ok(ImportResult::Imported(_))
+	);
+
+	let authority_set = link.persistent_data.authority_set.inner().read();
+	let pending_changes = authority_set.pending_changes().collect::<Vec<_>>();
+	assert_eq!(pending_changes.len(), 1);
+	assert_eq!(pending_changes[0].canon_hash, block_hash);
+	assert_eq!(pending_changes[0].next_authorities, forced_authorities);
+}
+
 #[test]
 fn grandpa_environment_doesnt_send_equivocation_reports_for_itself() {
 	use finality_grandpa::voter::Environment;
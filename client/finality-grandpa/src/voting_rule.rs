@@ -24,7 +24,10 @@
 
 use std::sync::Arc;
 
+use log::debug;
 use sc_client_api::blockchain::HeaderBackend;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_finality_grandpa::GrandpaApi;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor, One, Zero};
 
@@ -150,6 +153,89 @@ impl<Block, B> VotingRule<Block, B> for ThreeQuartersOfTheUnfinalizedChain where
 	}
 }
 
+/// A voting rule that enforces the finality restrictions set by on-chain
+/// governance, as returned by `GrandpaApi::finality_delay` at the best block.
+///
+/// Votes are kept at least `min_lag` blocks behind the best block and, while
+/// voting is paused, are never cast on anything past the given `base`. Runtimes
+/// that don't expose the API leave the vote unrestricted.
+pub struct RuntimeFinalityDelay<C> {
+	client: Arc<C>,
+}
+
+impl<C> RuntimeFinalityDelay<C> {
+	/// Create a new voting rule reading the finality restrictions through the
+	/// given client.
+	pub fn new(client: Arc<C>) -> Self {
+		RuntimeFinalityDelay { client }
+	}
+}
+
+impl<C> Clone for RuntimeFinalityDelay<C> {
+	fn clone(&self) -> Self {
+		RuntimeFinalityDelay {
+			client: self.client.clone(),
+		}
+	}
+}
+
+impl<Block, B, C> VotingRule<Block, B> for RuntimeFinalityDelay<C> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	C: ProvideRuntimeApi<Block> + Send + Sync,
+	C::Api: GrandpaApi<Block, Error = sp_blockchain::Error>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		use sp_arithmetic::traits::Saturating;
+
+		let at = BlockId::Hash(best_target.hash());
+		let api = self.client.runtime_api();
+
+		match api.has_api_with::<dyn GrandpaApi<Block, Error = sp_blockchain::Error>, _>(&at, |v| v >= 3) {
+			Ok(true) => {},
+			Ok(false) => return None,
+			Err(e) => {
+				debug!(target: "afg", "Failed to check runtime finality delay support: {:?}", e);
+				return None;
+			},
+		}
+
+		let delay = match api.finality_delay(&at) {
+			Ok(delay) => delay,
+			Err(e) => {
+				debug!(target: "afg", "Failed to fetch runtime finality delay: {:?}", e);
+				return None;
+			},
+		};
+
+		// the base is always an ancestor of our current target, don't vote
+		// past it while voting is paused. votes can't be restricted below the
+		// base, so a lag longer than the unfinalized chain stops at it too.
+		let target_number = if delay.paused {
+			*base.number()
+		} else {
+			best_target.number().saturating_sub(delay.min_lag).max(*base.number())
+		};
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		find_target(
+			backend,
+			target_number,
+			current_target,
+		)
+	}
+}
+
 // walk backwards until we find the target block
 fn find_target<Block, B>(
 	backend: &B,
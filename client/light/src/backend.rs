@@ -453,6 +453,18 @@ impl<H: Hasher> StateBackend<H> for GenesisOrUnavailableState<H>
 		}
 	}
 
+	fn apply_to_keys_with_prefix_while<A: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		action: A,
+	) {
+		match *self {
+			GenesisOrUnavailableState::Genesis(ref state) =>
+				state.apply_to_keys_with_prefix_while(prefix, action),
+			GenesisOrUnavailableState::Unavailable => (),
+		}
+	}
+
 	fn for_child_keys_with_prefix<A: FnMut(&[u8])>(
 		&self,
 		child_info: &ChildInfo,
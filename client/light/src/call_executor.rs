@@ -32,7 +32,7 @@ use sp_runtime::{
 use sp_externalities::Extensions;
 use sp_state_machine::{
 	self, Backend as StateBackend, OverlayedChanges, ExecutionStrategy, create_proof_check_backend,
-	execution_proof_check_on_trie_backend, ExecutionManager, StorageProof,
+	execution_proof_check_on_trie_backend, ExecutionManager, StorageProof, RecordedReads,
 };
 use hash_db::Hasher;
 
@@ -166,6 +166,21 @@ impl<Block, B, Local> CallExecutor<Block> for
 		Err(ClientError::NotAvailableOnLightClient)
 	}
 
+	fn call_recording_reads(
+		&self,
+		id: &BlockId<Block>,
+		overlay: &mut OverlayedChanges,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+		extensions: Option<Extensions>,
+	) -> ClientResult<(Vec<u8>, RecordedReads)> {
+		match self.backend.is_local_state_available(id) {
+			true => self.local.call_recording_reads(id, overlay, method, call_data, strategy, extensions),
+			false => Err(ClientError::NotAvailableOnLightClient),
+		}
+	}
+
 	fn native_runtime_version(&self) -> Option<&NativeVersion> {
 		None
 	}
@@ -1,6 +1,7 @@
 const PROTOS: &[&str] = &[
 	"src/schema/api.v1.proto",
-	"src/schema/light.v1.proto"
+	"src/schema/light.v1.proto",
+	"src/schema/bitswap.v1.2.0.proto",
 ];
 
 fn main() {
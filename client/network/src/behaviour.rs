@@ -17,9 +17,11 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
+	bitswap::Bitswap,
 	config::{ProtocolId, Role}, light_client_handler, peer_info, request_responses,
 	discovery::{DiscoveryBehaviour, DiscoveryConfig, DiscoveryOut},
 	protocol::{message::Roles, CustomMessageOutcome, NotificationsSink, Protocol},
+	protocol_bandwidth::ProtocolsBandwidth,
 	ObservedRole, DhtEvent, ExHashT,
 };
 
@@ -29,7 +31,9 @@ use libp2p::NetworkBehaviour;
 use libp2p::core::{Multiaddr, PeerId, PublicKey};
 use libp2p::identify::IdentifyInfo;
 use libp2p::kad::record;
-use libp2p::swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters};
+use libp2p::swarm::{
+	NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters, toggle::Toggle,
+};
 use log::debug;
 use prost::Message;
 use sp_consensus::{BlockOrigin, import_queue::{IncomingBlock, Origin}};
@@ -38,6 +42,7 @@ use std::{
 	borrow::Cow,
 	collections::{HashSet, VecDeque},
 	iter,
+	sync::Arc,
 	task::{Context, Poll},
 	time::Duration,
 };
@@ -61,6 +66,8 @@ pub struct Behaviour<B: BlockT, H: ExHashT> {
 	request_responses: request_responses::RequestResponsesBehaviour,
 	/// Light client request handling.
 	light_client_handler: light_client_handler::LightClientHandler<B>,
+	/// Serves the indexed transactions over bitswap, if enabled.
+	bitswap: Toggle<Bitswap>,
 
 	/// Queue of events to produce for the outside.
 	#[behaviour(ignore)]
@@ -84,6 +91,9 @@ pub enum BehaviourOut<B: BlockT> {
 	/// Started a random iterative Kademlia discovery query.
 	RandomKademliaStarted(ProtocolId),
 
+	/// The given address should be added to the external addresses of our node.
+	ExternalAddress(Multiaddr),
+
 	/// We have received a request from a peer and answered it.
 	///
 	/// This event is generated for statistics purposes.
@@ -180,6 +190,8 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 		block_request_protocol_config: request_responses::ProtocolConfig,
 		// All remaining request protocol configs.
 		mut request_response_protocols: Vec<request_responses::ProtocolConfig>,
+		protocols_bandwidth: Arc<ProtocolsBandwidth>,
+		bitswap: Option<Bitswap>,
 	) -> Result<Self, request_responses::RegisterError> {
 		// Extract protocol name and add to `request_response_protocols`.
 		let block_request_protocol_name = block_request_protocol_config.name.to_string();
@@ -190,8 +202,12 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 			peer_info: peer_info::PeerInfoBehaviour::new(user_agent, local_public_key),
 			discovery: disco_config.finish(),
 			request_responses:
-				request_responses::RequestResponsesBehaviour::new(request_response_protocols.into_iter())?,
+				request_responses::RequestResponsesBehaviour::new(
+					request_response_protocols.into_iter(),
+					protocols_bandwidth,
+				)?,
 			light_client_handler,
+			bitswap: bitswap.into(),
 			events: VecDeque::new(),
 			role,
 
@@ -438,6 +454,9 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<DiscoveryOut>
 					self.events.push_back(BehaviourOut::RandomKademliaStarted(protocol));
 				}
 			}
+			DiscoveryOut::AdvertisedAddress(address) => {
+				self.events.push_back(BehaviourOut::ExternalAddress(address));
+			}
 		}
 	}
 }
@@ -0,0 +1,471 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Bitswap server for the data indexed through the offchain indexing API.
+//!
+//! Only the server side of the protocol is implemented: wantlists received from remotes are
+//! answered with the requested data, or with its presence if only that was requested. The data is
+//! identified by a CIDv1 whose multihash is the blake2b-256 hash of the data, which is the key
+//! under which it is indexed.
+//!
+//! The [`Bitswap`] network behaviour only forwards the received wantlists to a
+//! [`BitswapRequestHandler`], which reads the data from the database in a separate task and sends
+//! the responses back to the behaviour.
+
+use crate::chain::Client;
+use crate::schema::bitswap::{
+	Message as BitswapMessage,
+	message::{
+		wantlist::WantType, Block as MessageBlock, BlockPresence, BlockPresenceType,
+	},
+};
+use cid::Version;
+use futures::{channel::mpsc, future::BoxFuture, prelude::*};
+use libp2p::{
+	core::{
+		Multiaddr, PeerId,
+		connection::ConnectionId,
+		upgrade::{InboundUpgrade, OutboundUpgrade, ReadOneError, UpgradeInfo, read_one, write_one},
+	},
+	swarm::{
+		NetworkBehaviour, NetworkBehaviourAction, NotifyHandler, OneShotHandler, PollParameters,
+	},
+};
+use log::{debug, error, trace};
+use prost::Message;
+use sp_runtime::traits::Block as BlockT;
+use std::{
+	io,
+	iter,
+	sync::Arc,
+	task::{Context, Poll},
+};
+use unsigned_varint::encode as varint_encode;
+use void::Void;
+
+const LOG_TARGET: &str = "bitswap";
+
+/// Name of the protocol.
+const PROTOCOL_NAME: &[u8] = b"/ipfs/bitswap/1.2.0";
+
+/// Maximum size of a bitswap message.
+const MAX_PACKET_SIZE: usize = 2 * 1024 * 1024;
+
+/// Maximum number of entries of a wantlist. Larger wantlists are ignored.
+const MAX_WANTED_BLOCKS: usize = 16;
+
+/// Maximum number of requests waiting to be handled. Requests received while the queue is full
+/// are ignored.
+const MAX_REQUEST_QUEUE: usize = 20;
+
+/// Maximum number of responses waiting to be sent.
+const MAX_RESPONSE_QUEUE: usize = 20;
+
+/// Size reserved in a response for its block presences, which can't be larger than 64 bytes each
+/// as only the supported CIDs are answered.
+const MAX_PRESENCES_SIZE: usize = MAX_WANTED_BLOCKS * 64;
+
+/// Upper bound of the size of the field tag and length prefix of a block in a response.
+const BLOCK_OVERHEAD: usize = 16;
+
+/// Multihash code of blake2b-256.
+const BLAKE2B_256: u64 = 0xb220;
+
+/// Prefix of a CID, sent along with the data of a block.
+struct Prefix {
+	/// The version of the CID.
+	version: Version,
+	/// The codec of the CID.
+	codec: u64,
+	/// The multihash type of the CID.
+	mh_type: u64,
+	/// The multihash length of the CID.
+	mh_len: u8,
+}
+
+impl Prefix {
+	/// Encodes the prefix as concatenated varints.
+	fn to_bytes(&self) -> Vec<u8> {
+		let mut res = Vec::with_capacity(4 * 10);
+		let mut buf = varint_encode::u64_buffer();
+		res.extend_from_slice(varint_encode::u64(self.version.into(), &mut buf));
+		res.extend_from_slice(varint_encode::u64(self.codec, &mut buf));
+		res.extend_from_slice(varint_encode::u64(self.mh_type, &mut buf));
+		res.extend_from_slice(varint_encode::u64(self.mh_len as u64, &mut buf));
+		res
+	}
+}
+
+/// Substream upgrade reading an incoming bitswap message.
+#[derive(Debug, Clone, Default)]
+pub struct BitswapConfig;
+
+impl UpgradeInfo for BitswapConfig {
+	type Info = &'static [u8];
+	type InfoIter = iter::Once<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(PROTOCOL_NAME)
+	}
+}
+
+impl<T> InboundUpgrade<T> for BitswapConfig
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = BitswapMessage;
+	type Error = ReadOneError;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_inbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		async move {
+			let packet = read_one(&mut s, MAX_PACKET_SIZE).await?;
+			BitswapMessage::decode(&packet[..])
+				.map_err(|e| ReadOneError::Io(io::Error::new(io::ErrorKind::Other, e)))
+		}.boxed()
+	}
+}
+
+impl UpgradeInfo for BitswapMessage {
+	type Info = &'static [u8];
+	type InfoIter = iter::Once<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(PROTOCOL_NAME)
+	}
+}
+
+impl<T> OutboundUpgrade<T> for BitswapMessage
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = ();
+	type Error = io::Error;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_outbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		async move {
+			let mut data = Vec::with_capacity(self.encoded_len());
+			self.encode(&mut data).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+			write_one(&mut s, data).await
+		}.boxed()
+	}
+}
+
+/// Event produced by the handler.
+#[derive(Debug)]
+pub enum HandlerEvent {
+	/// Received a message from the remote.
+	Request(BitswapMessage),
+	/// Successfully sent a response.
+	ResponseSent,
+}
+
+impl From<BitswapMessage> for HandlerEvent {
+	fn from(message: BitswapMessage) -> Self {
+		HandlerEvent::Request(message)
+	}
+}
+
+impl From<()> for HandlerEvent {
+	fn from(_: ()) -> Self {
+		HandlerEvent::ResponseSent
+	}
+}
+
+/// Network behaviour answering bitswap requests through a [`BitswapRequestHandler`].
+pub struct Bitswap {
+	request_sender: mpsc::Sender<(PeerId, BitswapMessage)>,
+	response_receiver: mpsc::Receiver<(PeerId, BitswapMessage)>,
+}
+
+/// Handler building the responses to the bitswap requests received by a [`Bitswap`] behaviour.
+pub struct BitswapRequestHandler<B> {
+	client: Arc<dyn Client<B>>,
+	request_receiver: mpsc::Receiver<(PeerId, BitswapMessage)>,
+	response_sender: mpsc::Sender<(PeerId, BitswapMessage)>,
+}
+
+impl<B: BlockT> BitswapRequestHandler<B> {
+	/// Creates a new handler serving the data indexed in `client`, along with the behaviour to
+	/// pass to the network.
+	pub fn new(client: Arc<dyn Client<B>>) -> (Self, Bitswap) {
+		let (request_sender, request_receiver) = mpsc::channel(MAX_REQUEST_QUEUE);
+		let (response_sender, response_receiver) = mpsc::channel(MAX_RESPONSE_QUEUE);
+		let handler = BitswapRequestHandler { client, request_receiver, response_sender };
+		(handler, Bitswap { request_sender, response_receiver })
+	}
+
+	/// Run [`BitswapRequestHandler`].
+	pub async fn run(mut self) {
+		while let Some((peer, request)) = self.request_receiver.next().await {
+			if let Some(response) = self.respond(&peer, request) {
+				if self.response_sender.send((peer, response)).await.is_err() {
+					debug!(target: LOG_TARGET, "Bitswap behaviour has been dropped");
+					return
+				}
+			}
+		}
+	}
+
+	/// Builds the response to the wantlist of `request`. Returns `None` if the request must be
+	/// ignored.
+	///
+	/// Blocks which don't fit in a response of [`MAX_PACKET_SIZE`] bytes alongside the blocks
+	/// already added to it are only reported as present, so that they can be wanted separately.
+	/// Blocks which don't fit in a response on their own are never sent, the transaction storage
+	/// pallet limits the size of the indexed data accordingly.
+	fn respond(&self, peer: &PeerId, request: BitswapMessage) -> Option<BitswapMessage> {
+		let wantlist = match request.wantlist {
+			Some(wantlist) => wantlist,
+			None => {
+				debug!(target: LOG_TARGET, "Unexpected bitswap message from {}", peer);
+				return None
+			}
+		};
+		if wantlist.entries.len() > MAX_WANTED_BLOCKS {
+			trace!(target: LOG_TARGET, "Ignored request from {}: too many entries", peer);
+			return None
+		}
+
+		let mut response = BitswapMessage::default();
+		for entry in wantlist.entries {
+			if entry.cancel {
+				continue
+			}
+
+			let cid = match cid::Cid::read_bytes(entry.block.as_slice()) {
+				Ok(cid) => cid,
+				Err(e) => {
+					trace!(target: LOG_TARGET, "Bad CID {:?} from {}: {:?}", entry.block, peer, e);
+					continue
+				}
+			};
+
+			let mut hash = B::Hash::default();
+			if cid.version() != Version::V1
+				|| cid.hash().code() != BLAKE2B_256
+				|| cid.hash().digest().len() != hash.as_ref().len()
+			{
+				debug!(target: LOG_TARGET, "Ignoring unsupported CID {} from {}", cid, peer);
+				continue
+			}
+			hash.as_mut().copy_from_slice(cid.hash().digest());
+
+			let data = match self.client.indexed_transaction(&hash) {
+				Ok(data) => data,
+				Err(e) => {
+					error!(target: LOG_TARGET, "Error retrieving indexed transaction {}: {}", hash, e);
+					None
+				}
+			};
+
+			match data {
+				Some(data) if entry.want_type == WantType::Block as i32 => {
+					let prefix = Prefix {
+						version: cid.version(),
+						codec: cid.codec(),
+						mh_type: cid.hash().code(),
+						mh_len: cid.hash().size(),
+					};
+					let block = MessageBlock { prefix: prefix.to_bytes(), data };
+					let block_size = block.encoded_len() + BLOCK_OVERHEAD;
+					if response.encoded_len() + block_size + MAX_PRESENCES_SIZE <= MAX_PACKET_SIZE {
+						trace!(target: LOG_TARGET, "Sending {} to {}", cid, peer);
+						response.payload.push(block);
+					} else if block_size + MAX_PRESENCES_SIZE <= MAX_PACKET_SIZE {
+						trace!(target: LOG_TARGET, "Reporting {} to {}: response full", cid, peer);
+						response.block_presences.push(BlockPresence {
+							r#type: BlockPresenceType::Have as i32,
+							cid: cid.to_bytes(),
+						});
+					} else {
+						debug!(target: LOG_TARGET, "Can't send {} to {}: too large", cid, peer);
+						if entry.send_dont_have {
+							response.block_presences.push(BlockPresence {
+								r#type: BlockPresenceType::DontHave as i32,
+								cid: cid.to_bytes(),
+							});
+						}
+					}
+				}
+				Some(_) => response.block_presences.push(BlockPresence {
+					r#type: BlockPresenceType::Have as i32,
+					cid: cid.to_bytes(),
+				}),
+				None if entry.send_dont_have => response.block_presences.push(BlockPresence {
+					r#type: BlockPresenceType::DontHave as i32,
+					cid: cid.to_bytes(),
+				}),
+				None => {}
+			}
+		}
+
+		Some(response)
+	}
+}
+
+impl NetworkBehaviour for Bitswap {
+	type ProtocolsHandler = OneShotHandler<BitswapConfig, BitswapMessage, HandlerEvent>;
+	type OutEvent = Void;
+
+	fn new_handler(&mut self) -> Self::ProtocolsHandler {
+		Default::default()
+	}
+
+	fn addresses_of_peer(&mut self, _peer: &PeerId) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn inject_connected(&mut self, _peer: &PeerId) {
+	}
+
+	fn inject_disconnected(&mut self, _peer: &PeerId) {
+	}
+
+	fn inject_event(&mut self, peer: PeerId, _connection: ConnectionId, event: HandlerEvent) {
+		let request = match event {
+			HandlerEvent::ResponseSent => return,
+			HandlerEvent::Request(request) => request,
+		};
+		trace!(target: LOG_TARGET, "Received request from {}: {:?}", peer, request);
+
+		if let Err(e) = self.request_sender.try_send((peer, request)) {
+			let reason = if e.is_full() { "queue is full" } else { "handler is gone" };
+			debug!(target: LOG_TARGET, "Ignored request from {}: {}", e.into_inner().0, reason);
+		}
+	}
+
+	fn poll(
+		&mut self,
+		cx: &mut Context,
+		_: &mut impl PollParameters,
+	) -> Poll<NetworkBehaviourAction<BitswapMessage, Self::OutEvent>> {
+		if let Poll::Ready(Some((peer_id, message))) = self.response_receiver.poll_next_unpin(cx) {
+			return Poll::Ready(NetworkBehaviourAction::NotifyHandler {
+				peer_id,
+				handler: NotifyHandler::Any,
+				event: message,
+			})
+		}
+		Poll::Pending
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schema::bitswap::message::{Wantlist, wantlist::Entry};
+	use cid::{Cid, multihash::Multihash};
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use sp_runtime::traits::{BlakeTwo256, Hash};
+	use substrate_test_runtime_client::{
+		prelude::*, runtime::{Block, Extrinsic},
+	};
+
+	fn want(cids: &[Cid], want_type: WantType) -> BitswapMessage {
+		BitswapMessage {
+			wantlist: Some(Wantlist {
+				entries: cids.iter().map(|cid| Entry {
+					block: cid.to_bytes(),
+					want_type: want_type as i32,
+					send_dont_have: true,
+					..Default::default()
+				}).collect(),
+				full: false,
+			}),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn prefix_is_encoded_as_varints() {
+		let prefix = Prefix { version: Version::V1, codec: 0x55, mh_type: BLAKE2B_256, mh_len: 32 };
+		assert_eq!(prefix.to_bytes(), vec![0x01, 0x55, 0xa0, 0xe4, 0x02, 0x20]);
+	}
+
+	#[test]
+	fn largest_indexed_data_fits_in_a_response() {
+		let block = MessageBlock {
+			prefix: Prefix { version: Version::V1, codec: 0x55, mh_type: BLAKE2B_256, mh_len: 32 }
+				.to_bytes(),
+			data: vec![0; sp_transaction_storage_proof::MAX_TRANSACTION_SIZE as usize],
+		};
+		let size = BitswapMessage::default().encoded_len() + block.encoded_len() + BLOCK_OVERHEAD;
+		assert!(size + MAX_PRESENCES_SIZE <= MAX_PACKET_SIZE);
+	}
+
+	#[test]
+	fn missing_data_is_reported() {
+		let (bitswap, _) =
+			BitswapRequestHandler::<Block>::new(Arc::new(substrate_test_runtime_client::new()));
+		let peer = PeerId::random();
+		let supported = Cid::new_v1(0x55, Multihash::wrap(BLAKE2B_256, &[1; 32]).unwrap());
+		let sha2 = Cid::new_v1(0x55, Multihash::wrap(0x12, &[1; 32]).unwrap());
+
+		let response = bitswap.respond(
+			&peer,
+			want(&[supported.clone(), sha2], WantType::Block),
+		).unwrap();
+		assert!(response.payload.is_empty());
+		assert_eq!(response.block_presences, vec![BlockPresence {
+			cid: supported.to_bytes(),
+			r#type: BlockPresenceType::DontHave as i32,
+		}]);
+
+		let too_many = vec![supported; MAX_WANTED_BLOCKS + 1];
+		assert!(bitswap.respond(&peer, want(&too_many, WantType::Have)).is_none());
+		assert!(bitswap.respond(&peer, BitswapMessage::default()).is_none());
+	}
+
+	#[test]
+	fn indexed_data_is_served() {
+		let mut client = Arc::new(
+			TestClientBuilder::new().enable_offchain_indexing_api().build()
+		);
+		let data = b"some indexed data".to_vec();
+		let hash = BlakeTwo256::hash(&data);
+		let mut block_builder = client.new_block(Default::default()).unwrap();
+		block_builder.push(Extrinsic::OffchainIndexSet(
+			sp_core::offchain::transaction_index_key(hash.as_ref()),
+			data.clone(),
+		)).unwrap();
+		let block = block_builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		let (bitswap, _) = BitswapRequestHandler::<Block>::new(client);
+		let peer = PeerId::random();
+		let cid = Cid::new_v1(0x55, Multihash::wrap(BLAKE2B_256, hash.as_ref()).unwrap());
+
+		let response = bitswap.respond(&peer, want(&[cid.clone()], WantType::Block)).unwrap();
+		assert!(response.block_presences.is_empty());
+		assert_eq!(response.payload, vec![MessageBlock {
+			prefix: Prefix { version: Version::V1, codec: 0x55, mh_type: BLAKE2B_256, mh_len: 32 }
+				.to_bytes(),
+			data,
+		}]);
+
+		let response = bitswap.respond(&peer, want(&[cid.clone()], WantType::Have)).unwrap();
+		assert!(response.payload.is_empty());
+		assert_eq!(response.block_presences, vec![BlockPresence {
+			cid: cid.to_bytes(),
+			r#type: BlockPresenceType::Have as i32,
+		}]);
+	}
+}
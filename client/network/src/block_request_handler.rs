@@ -46,6 +46,7 @@ pub fn generate_protocol_config(protocol_id: ProtocolId) -> ProtocolConfig {
 		max_response_size: 16 * 1024 * 1024,
 		request_timeout: Duration::from_secs(40),
 		inbound_queue: None,
+		bandwidth_limit: Default::default(),
 	}
 }
 
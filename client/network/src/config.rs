@@ -22,7 +22,9 @@
 //! See the documentation of [`Params`].
 
 pub use crate::chain::Client;
+pub use crate::bitswap::Bitswap;
 pub use crate::on_demand_layer::{AlwaysBadChecker, OnDemand};
+pub use crate::protocol_bandwidth::BandwidthLimit;
 pub use crate::request_responses::{
 	IncomingRequest,
 	OutgoingResponse,
@@ -111,6 +113,13 @@ pub struct Params<B: BlockT, H: ExHashT> {
 	/// [`block_request_handler::BlockRequestHandler::new`] allowing both outgoing and incoming
 	/// requests.
 	pub block_request_protocol_config: RequestResponseConfig,
+
+	/// Behaviour serving the data indexed through the offchain indexing API over bitswap, if
+	/// [`NetworkConfiguration::ipfs_server`] is enabled.
+	///
+	/// Constructed via [`bitswap::BitswapRequestHandler::new`], the handler of which must be run
+	/// separately.
+	pub bitswap: Option<Bitswap>,
 }
 
 /// Role of the local node.
@@ -426,6 +435,9 @@ pub struct NetworkConfiguration {
 	/// a modification of the way the implementation works. Different nodes with different
 	/// configured values remain compatible with each other.
 	pub yamux_window_size: Option<u32>,
+	/// Enable serving the data indexed through the offchain indexing API over the IPFS bitswap
+	/// protocol.
+	pub ipfs_server: bool,
 }
 
 impl NetworkConfiguration {
@@ -451,11 +463,13 @@ impl NetworkConfiguration {
 				enable_mdns: false,
 				allow_private_ipv4: true,
 				wasm_external_transport: None,
+				secure_websocket: None,
 			},
 			max_parallel_downloads: 5,
 			allow_non_globals_in_dht: false,
 			kademlia_disjoint_query_paths: false,
 			yamux_window_size: None,
+			ipfs_server: false,
 		}
 	}
 
@@ -534,6 +548,9 @@ pub struct NonDefaultSetConfig {
 	pub notifications_protocol: Cow<'static, str>,
 	/// Maximum allowed size of single notifications.
 	pub max_notification_size: u64,
+	/// Maximum rate at which notifications are sent and accepted. Notifications exceeding it are
+	/// discarded.
+	pub bandwidth_limit: BandwidthLimit,
 	/// Base configuration.
 	pub set_config: SetConfig,
 }
@@ -560,6 +577,13 @@ pub enum TransportConfig {
 		/// This parameter exists whatever the target platform is, but it is expected to be set to
 		/// `Some` only when compiling for WASM.
 		wasm_external_transport: Option<wasm_ext::ExtTransport>,
+
+		/// If `Some`, the TCP transport also accepts secure WebSocket connections on the listen
+		/// addresses ending with `/wss`. This is what makes a node reachable by light clients
+		/// running in a browser, which can't open plain TCP connections.
+		///
+		/// Irrelevant when compiling for WASM.
+		secure_websocket: Option<SecureWebSocketConfig>,
 	},
 
 	/// Only allow connections within the same process.
@@ -567,6 +591,34 @@ pub enum TransportConfig {
 	MemoryOnly,
 }
 
+/// Configuration of the TLS layer of secure WebSocket listeners.
+#[derive(Clone)]
+pub struct SecureWebSocketConfig {
+	/// Domain name the certificate has been issued for.
+	///
+	/// Browsers refuse to open secure WebSockets to raw IP addresses. Each `/wss` listen address
+	/// is therefore advertised to the rest of the network as `/dns/<domain>/tcp/<port>/wss`.
+	pub domain: String,
+	/// DER-encoded private key of the certificate.
+	pub private_key: Vec<u8>,
+	/// DER-encoded certificate chain, starting with the certificate of this node.
+	pub certificate_chain: Vec<Vec<u8>>,
+	/// DER-encoded certificates trusted when dialing `/wss` addresses, in addition to the
+	/// usual root certificate authorities. Useful for networks using self-signed certificates.
+	pub trusted_certificates: Vec<Vec<u8>>,
+}
+
+impl fmt::Debug for SecureWebSocketConfig {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// The private key is deliberately not printed.
+		f.debug_struct("SecureWebSocketConfig")
+			.field("domain", &self.domain)
+			.field("certificate_chain", &self.certificate_chain.len())
+			.field("trusted_certificates", &self.trusted_certificates.len())
+			.finish()
+	}
+}
+
 /// The policy for connections to non-reserved peers.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NonReservedPeerMode {
@@ -35,6 +35,10 @@
 //! Additionally, the `DiscoveryBehaviour` is also capable of storing and loading value in the
 //! configured DHTs.
 //!
+//! If a secure WebSocket domain is configured, the `DiscoveryBehaviour` also derives, from each of
+//! our `/wss` listen addresses, the `/dns/...` address that browsers can reach us at, and reports
+//! it with `DiscoveryOut::AdvertisedAddress` so that it gets advertised to the network.
+//!
 //! ## Usage
 //!
 //! The `DiscoveryBehaviour` generates events of type `DiscoveryOut`, most notably
@@ -86,6 +90,7 @@ pub struct DiscoveryConfig {
 	enable_mdns: bool,
 	kademlia_disjoint_query_paths: bool,
 	protocol_ids: HashSet<ProtocolId>,
+	secure_websocket_domain: Option<String>,
 }
 
 impl DiscoveryConfig {
@@ -99,7 +104,8 @@ impl DiscoveryConfig {
 			discovery_only_if_under_num: std::u64::MAX,
 			enable_mdns: false,
 			kademlia_disjoint_query_paths: false,
-			protocol_ids: HashSet::new()
+			protocol_ids: HashSet::new(),
+			secure_websocket_domain: None,
 		}
 	}
 
@@ -158,6 +164,13 @@ impl DiscoveryConfig {
 		self
 	}
 
+	/// Advertise our `/wss` listen addresses under the given domain name rather than under the
+	/// IP address we listen on.
+	pub fn with_secure_websocket_domain(&mut self, domain: String) -> &mut Self {
+		self.secure_websocket_domain = Some(domain);
+		self
+	}
+
 	/// Create a `DiscoveryBehaviour` from this config.
 	pub fn finish(self) -> DiscoveryBehaviour {
 		let DiscoveryConfig {
@@ -169,6 +182,7 @@ impl DiscoveryConfig {
 			enable_mdns,
 			kademlia_disjoint_query_paths,
 			protocol_ids,
+			secure_websocket_domain,
 		} = self;
 
 		let kademlias = protocol_ids.into_iter()
@@ -215,6 +229,7 @@ impl DiscoveryConfig {
 				NonZeroUsize::new(MAX_KNOWN_EXTERNAL_ADDRESSES)
 					.expect("value is a constant; constant is non-zero; qed.")
 			),
+			secure_websocket_domain,
 		}
 	}
 }
@@ -248,6 +263,8 @@ pub struct DiscoveryBehaviour {
 	allow_non_globals_in_dht: bool,
 	/// A cache of discovered external addresses. Only used for logging purposes.
 	known_external_addresses: LruHashSet<Multiaddr>,
+	/// Domain name under which our `/wss` listen addresses are advertised, if any.
+	secure_websocket_domain: Option<String>,
 }
 
 impl DiscoveryBehaviour {
@@ -435,6 +452,13 @@ pub enum DiscoveryOut {
 
 	/// Started a random Kademlia query for each DHT identified by the given `ProtocolId`s.
 	RandomKademliaStarted(Vec<ProtocolId>),
+
+	/// We started listening on an address that can't be dialed as is, and the given address
+	/// should be advertised to the network instead.
+	///
+	/// This is the case of `/wss` listen addresses, which browsers only connect to through the
+	/// domain name of their certificate.
+	AdvertisedAddress(Multiaddr),
 }
 
 impl NetworkBehaviour for DiscoveryBehaviour {
@@ -569,6 +593,14 @@ impl NetworkBehaviour for DiscoveryBehaviour {
 	}
 
 	fn inject_new_listen_addr(&mut self, addr: &Multiaddr) {
+		if let Some(domain) = &self.secure_websocket_domain {
+			if let Some(advertised) = secure_websocket_address(addr, domain) {
+				debug!(target: "sub-libp2p",
+					"Advertising secure WebSocket listen address {} as {}", addr, advertised);
+				self.pending_events.push_back(DiscoveryOut::AdvertisedAddress(advertised));
+			}
+		}
+
 		for k in self.kademlias.values_mut() {
 			NetworkBehaviour::inject_new_listen_addr(k, addr)
 		}
@@ -779,6 +811,22 @@ fn protocol_name_from_protocol_id(id: &ProtocolId) -> Vec<u8> {
 	v
 }
 
+/// If `addr` is a secure WebSocket listen address, returns the same address with the IP address
+/// replaced with `domain`.
+fn secure_websocket_address(addr: &Multiaddr, domain: &str) -> Option<Multiaddr> {
+	let mut iter = addr.iter();
+	match (iter.next()?, iter.next()?, iter.next()?, iter.next()) {
+		(Protocol::Ip4(_), Protocol::Tcp(port), Protocol::Wss(path), None) |
+		(Protocol::Ip6(_), Protocol::Tcp(port), Protocol::Wss(path), None) => Some(
+			Multiaddr::empty()
+				.with(Protocol::Dns(domain.into()))
+				.with(Protocol::Tcp(port))
+				.with(Protocol::Wss(path))
+		),
+		_ => None,
+	}
+}
+
 /// [`Mdns::new`] returns a future. Instead of forcing [`DiscoveryConfig::finish`] and all its
 /// callers to be async, lazily instantiate [`Mdns`].
 #[cfg(not(target_os = "unknown"))]
@@ -833,7 +881,9 @@ mod tests {
 	use libp2p::swarm::Swarm;
 	use libp2p::yamux;
 	use std::{collections::HashSet, task::Poll};
-	use super::{DiscoveryConfig, DiscoveryOut, protocol_name_from_protocol_id};
+	use super::{
+		DiscoveryConfig, DiscoveryOut, protocol_name_from_protocol_id, secure_websocket_address,
+	};
 
 	#[test]
 	fn discovery_working() {
@@ -1029,4 +1079,23 @@ mod tests {
 			"Expected remote peer not to be added to `protocol_b` Kademlia instance.",
 		);
 	}
+
+	#[test]
+	fn secure_websocket_listen_addresses_are_advertised_under_domain() {
+		let domain = "node.example.com";
+		let advertised = |addr: &str| secure_websocket_address(&addr.parse().unwrap(), domain);
+
+		assert_eq!(
+			advertised("/ip4/0.0.0.0/tcp/30334/wss"),
+			Some("/dns/node.example.com/tcp/30334/wss".parse().unwrap()),
+		);
+		assert_eq!(
+			advertised("/ip6/::/tcp/443/wss"),
+			Some("/dns/node.example.com/tcp/443/wss".parse().unwrap()),
+		);
+		assert_eq!(advertised("/ip4/0.0.0.0/tcp/30333"), None);
+		assert_eq!(advertised("/ip4/0.0.0.0/tcp/30334/ws"), None);
+		assert_eq!(advertised("/dns/example.com/tcp/30334/wss"), None);
+		assert_eq!(advertised("/memory/5"), None);
+	}
 }
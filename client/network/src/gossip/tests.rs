@@ -117,6 +117,7 @@ fn build_test_full_node(network_config: config::NetworkConfiguration)
 		),
 		metrics_registry: None,
 		block_request_protocol_config,
+		bitswap: None,
 	})
 	.unwrap();
 
@@ -145,6 +146,7 @@ fn build_nodes_one_proto()
 			config::NonDefaultSetConfig {
 				notifications_protocol: PROTOCOL_NAME,
 				max_notification_size: 1024 * 1024,
+				bandwidth_limit: Default::default(),
 				set_config: Default::default()
 			}
 		],
@@ -159,6 +161,7 @@ fn build_nodes_one_proto()
 			config::NonDefaultSetConfig {
 				notifications_protocol: PROTOCOL_NAME,
 				max_notification_size: 1024 * 1024,
+				bandwidth_limit: Default::default(),
 				set_config: config::SetConfig {
 					reserved_nodes: vec![config::MultiaddrWithPeerId {
 						multiaddr: listen_addr,
@@ -116,6 +116,10 @@
 //! - **[`/<protocol_id>/kad`](https://github.com/libp2p/specs/pull/108)**. We periodically open
 //! ephemeral substreams for Kademlia random walk queries. Each Kademlia query is done in a
 //! separate substream.
+//! - **[`/ipfs/bitswap/1.2.0`](https://github.com/ipfs/specs/blob/master/BITSWAP.md)**. Only
+//! enabled if `NetworkConfiguration::ipfs_server` is set. We answer the wantlists of the remote
+//! with the data indexed through the offchain indexing API, identified by CIDv1 with a blake2b-256
+//! multihash. We never request data ourselves.
 //!
 //! Additionally, Substrate uses the following non-libp2p-standard protocols:
 //!
@@ -252,17 +256,20 @@ mod discovery;
 mod light_client_handler;
 mod on_demand_layer;
 mod protocol;
+mod protocol_bandwidth;
 mod request_responses;
 mod schema;
 mod service;
 mod transport;
 mod utils;
 
+pub mod bitswap;
 pub mod block_request_handler;
 pub mod config;
 pub mod error;
 pub mod gossip;
 pub mod network_state;
+pub mod state_request_handler;
 
 #[doc(inline)]
 pub use libp2p::{multiaddr, Multiaddr, PeerId};
@@ -272,7 +279,7 @@ pub use service::{
 	NotificationSenderReady,
 };
 
-pub use sc_peerset::ReputationChange;
+pub use sc_peerset::{BannedPeer, ReputationChange};
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 
 /// The maximum allowed number of established connections per peer.
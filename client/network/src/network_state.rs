@@ -44,6 +44,25 @@ pub struct NetworkState {
 	pub not_connected_peers: HashMap<String, NotConnectedPeer>,
 	/// State of the peerset manager.
 	pub peerset: serde_json::Value,
+	/// Traffic of each notifications and request-response protocol.
+	#[serde(default)]
+	pub protocols: HashMap<String, ProtocolBandwidth>,
+}
+
+/// Part of the `NetworkState` struct. Unstable.
+///
+/// Only accounts for the payloads of the notifications, requests and responses.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolBandwidth {
+	/// Number of bytes received and accepted.
+	pub total_inbound: u64,
+	/// Number of bytes sent.
+	pub total_outbound: u64,
+	/// Number of bytes received but discarded because of the bandwidth limit.
+	pub dropped_inbound: u64,
+	/// Number of bytes not sent because of the bandwidth limit.
+	pub dropped_outbound: u64,
 }
 
 /// Part of the `NetworkState` struct. Unstable.
@@ -57,7 +57,7 @@ use std::borrow::Cow;
 use std::convert::TryFrom as _;
 use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry};
 use std::sync::Arc;
-use std::{io, iter, num::NonZeroUsize, pin::Pin, task::Poll, time};
+use std::{io, iter, mem, num::NonZeroUsize, pin::Pin, task::Poll, time};
 
 mod generic_proto;
 
@@ -103,6 +103,9 @@ const HARDCODED_PEERSETS_TX: sc_peerset::SetId = sc_peerset::SetId::from(1);
 /// Number of hardcoded peersets (the constants right above). Any set whose identifier is equal or
 /// superior to this value corresponds to a user-defined protocol.
 const NUM_HARDCODED_PEERSETS: usize = 2;
+/// Name of the file, within the network configuration directory, where the reputations and the
+/// ban list of the peerset are saved.
+const PEERSET_STATE_FILE: &str = "peerset.json";
 
 /// When light node connects to the full node and the full node is behind light node
 /// for at least `LIGHT_MAXIMAL_BLOCKS_DIFFERENCE` blocks, we consider it not useful
@@ -212,6 +215,9 @@ pub struct Protocol<B: BlockT, H: ExHashT> {
 	propagate_timeout: Pin<Box<dyn Stream<Item = ()> + Send>>,
 	/// Pending list of messages to return from `poll` as a priority.
 	pending_messages: VecDeque<CustomMessageOutcome<B>>,
+	/// Block requests that exceeded the bandwidth limit of the protocol, and are sent again at
+	/// the next `tick_timeout`.
+	rate_limited_block_requests: Vec<(PeerId, message::BlockRequest<B>)>,
 	/// Pending transactions verification tasks.
 	pending_transactions: FuturesUnordered<PendingTransaction<H>>,
 	/// As multiple peers can send us the same transaction, we group
@@ -453,6 +459,8 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 
 			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
 				sets,
+				persistence_path: network_config.net_config_path.as_ref()
+					.map(|path| path.join(PEERSET_STATE_FILE)),
 			})
 		};
 
@@ -511,6 +519,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			tick_timeout: Box::pin(interval(TICK_TIMEOUT)),
 			propagate_timeout: Box::pin(interval(PROPAGATE_TIMEOUT)),
 			pending_messages: VecDeque::new(),
+			rate_limited_block_requests: Vec::new(),
 			pending_transactions: FuturesUnordered::new(),
 			pending_transactions_peers: HashMap::new(),
 			config,
@@ -566,6 +575,11 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns the list of peers banned by the peerset manager.
+	pub fn banned_peers(&mut self) -> Vec<sc_peerset::BannedPeer> {
+		self.behaviour.banned_peers()
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.peers.values().count()
@@ -720,6 +734,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		}
 
 		if let Some(_peer_data) = self.peers.remove(&peer) {
+			self.rate_limited_block_requests.retain(|(id, _)| *id != peer);
 			self.sync.peer_disconnected(&peer);
 			Ok(())
 		} else {
@@ -1578,7 +1593,7 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 						finished_block_requests.push((id.clone(), req, protobuf_response));
 					},
 					Poll::Ready(Ok(Err(e))) => {
-						peer.block_request.take();
+						let (req, _) = peer.block_request.take().unwrap();
 						trace!(target: "sync", "Block request to peer {:?} failed: {:?}.", id, e);
 
 						match e {
@@ -1601,6 +1616,11 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 							| RequestFailure::NotConnected => {
 								self.behaviour.disconnect_peer(id, HARDCODED_PEERSETS_SYNC);
 							},
+							// The request hasn't been sent. The sync with this peer is still
+							// waiting for it, so send it again later.
+							RequestFailure::RateLimited => {
+								self.rate_limited_block_requests.push((id.clone(), req));
+							},
 							RequestFailure::UnknownProtocol => {
 								debug_assert!(false, "Block request protocol should always be known.");
 							}
@@ -1633,6 +1653,11 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 
 		while let Poll::Ready(Some(())) = self.tick_timeout.poll_next_unpin(cx) {
 			self.tick();
+
+			for (id, request) in mem::take(&mut self.rate_limited_block_requests) {
+				let event = prepare_block_request(&mut self.peers, id, request);
+				self.pending_messages.push_back(event);
+			}
 		}
 
 		while let Poll::Ready(Some(())) = self.propagate_timeout.poll_next_unpin(cx) {
@@ -77,6 +77,7 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 					reserved_only: false,
 				}
 			],
+			persistence_path: None,
 		});
 
 		let behaviour = CustomProtoWithAddr {
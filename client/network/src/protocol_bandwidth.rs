@@ -0,0 +1,322 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Accounting and rate limiting of the traffic of each notifications and request-response
+//! protocol.
+//!
+//! Contrary to the [`BandwidthSinks`](crate::transport::BandwidthSinks), which measure the raw
+//! traffic of the transport, the [`ProtocolsBandwidth`] only sees the payloads of the
+//! notifications, requests and responses, and attributes them to the protocol they belong to.
+//!
+//! Limits are enforced with token buckets, one for the protocol as a whole and one for each
+//! peer, in each direction. Each bucket holds at most one second worth of traffic. A payload is
+//! accepted as long as the buckets aren't empty, even if it is larger than what they contain, in
+//! which case the buckets go in debt. This way, payloads larger than the per-second limit can
+//! still go through, just less often. The debt is capped at one second worth of traffic, so that
+//! large responses, which are never discarded, can't stall a protocol for longer than two seconds.
+
+use crate::network_state::ProtocolBandwidth;
+
+use libp2p::PeerId;
+use parking_lot::Mutex;
+use std::{borrow::Cow, collections::HashMap};
+use wasm_timer::Instant;
+
+/// Number of peers above which the buckets of the peers that are idle are discarded.
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// Maximum rate at which the traffic of a protocol is accepted.
+///
+/// Limits apply separately to each direction. Notifications and requests exceeding the limit
+/// are discarded. Responses are never discarded, as they were requested, but count towards the
+/// limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimit {
+	/// Maximum number of bytes per second, summed over all peers. `None` for no limit.
+	pub total: Option<u64>,
+	/// Maximum number of bytes per second exchanged with any individual peer. `None` for no
+	/// limit.
+	pub per_peer: Option<u64>,
+}
+
+/// Direction of a payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	/// Sent by a remote.
+	Inbound,
+	/// Sent by the local node.
+	Outbound,
+}
+
+/// Traffic of all the protocols, shared between the network service and the behaviours.
+#[derive(Debug, Default)]
+pub struct ProtocolsBandwidth {
+	protocols: Mutex<HashMap<Cow<'static, str>, ProtocolState>>,
+}
+
+/// State of a single protocol.
+#[derive(Debug, Default)]
+struct ProtocolState {
+	limit: BandwidthLimit,
+	stats: ProtocolBandwidth,
+	inbound: Buckets,
+	outbound: Buckets,
+}
+
+/// Buckets of a single direction of a protocol.
+#[derive(Debug, Default)]
+struct Buckets {
+	total: Option<TokenBucket>,
+	peers: HashMap<PeerId, TokenBucket>,
+}
+
+impl ProtocolsBandwidth {
+	/// Creates a new instance in which no protocol is limited.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets the limit of the given protocol. Protocols whose limit isn't set aren't limited, but
+	/// their traffic is still accounted.
+	pub fn set_limit(&self, protocol: Cow<'static, str>, limit: BandwidthLimit) {
+		let mut protocols = self.protocols.lock();
+		let state = protocols.entry(protocol).or_default();
+		let now = Instant::now();
+		state.limit = limit;
+		for buckets in [&mut state.inbound, &mut state.outbound].iter_mut() {
+			buckets.total = limit.total.map(|rate| TokenBucket::new(rate, now));
+			buckets.peers.clear();
+		}
+	}
+
+	/// Accounts a notification or a request, and returns `false` if it exceeds the limit, in which
+	/// case it must be discarded.
+	pub fn try_transfer(
+		&self,
+		protocol: &str,
+		peer: &PeerId,
+		direction: Direction,
+		bytes: usize,
+	) -> bool {
+		self.transfer(protocol, peer, direction, bytes as u64, true)
+	}
+
+	/// Accounts a response, which is never discarded but counts towards the limit.
+	pub fn force_transfer(&self, protocol: &str, peer: &PeerId, direction: Direction, bytes: usize) {
+		self.transfer(protocol, peer, direction, bytes as u64, false);
+	}
+
+	/// Returns the traffic of each protocol seen so far.
+	pub fn stats(&self) -> HashMap<String, ProtocolBandwidth> {
+		self.protocols.lock().iter()
+			.map(|(protocol, state)| (protocol.to_string(), state.stats.clone()))
+			.collect()
+	}
+
+	fn transfer(
+		&self,
+		protocol: &str,
+		peer: &PeerId,
+		direction: Direction,
+		bytes: u64,
+		enforce: bool,
+	) -> bool {
+		let mut protocols = self.protocols.lock();
+		if !protocols.contains_key(protocol) {
+			protocols.insert(Cow::Owned(protocol.to_owned()), ProtocolState::default());
+		}
+		let state = protocols.get_mut(protocol).expect("Inserted above; qed");
+
+		let limit = state.limit;
+		let buckets = match direction {
+			Direction::Inbound => &mut state.inbound,
+			Direction::Outbound => &mut state.outbound,
+		};
+
+		let Buckets { total, peers } = buckets;
+
+		let now = Instant::now();
+		if peers.len() >= MAX_TRACKED_PEERS {
+			peers.retain(|_, bucket| !bucket.is_full(now));
+		}
+
+		let mut peer_bucket = limit.per_peer.map(|rate| {
+			peers.entry(peer.clone()).or_insert_with(|| TokenBucket::new(rate, now))
+		});
+		let mut total_bucket = total.as_mut();
+
+		let accepted = !enforce ||
+			(peer_bucket.as_mut().map_or(true, |bucket| bucket.has_capacity(now)) &&
+				total_bucket.as_mut().map_or(true, |bucket| bucket.has_capacity(now)));
+
+		if accepted {
+			if let Some(bucket) = peer_bucket {
+				bucket.consume(bytes, now);
+			}
+			if let Some(bucket) = total_bucket {
+				bucket.consume(bytes, now);
+			}
+		}
+
+		let (transferred, dropped) = match direction {
+			Direction::Inbound => (&mut state.stats.total_inbound, &mut state.stats.dropped_inbound),
+			Direction::Outbound => (&mut state.stats.total_outbound, &mut state.stats.dropped_outbound),
+		};
+		if accepted {
+			*transferred = transferred.saturating_add(bytes);
+		} else {
+			*dropped = dropped.saturating_add(bytes);
+		}
+
+		accepted
+	}
+}
+
+/// Token bucket refilled at a constant rate, and holding at most one second worth of tokens.
+#[derive(Debug)]
+struct TokenBucket {
+	/// Number of tokens added per second. Also the capacity of the bucket.
+	rate: f64,
+	/// Number of tokens in the bucket. Negative if the bucket is in debt, but never below
+	/// `-rate`.
+	available: f64,
+	/// Last time `available` has been updated.
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	fn new(rate: u64, now: Instant) -> Self {
+		TokenBucket {
+			rate: rate as f64,
+			available: rate as f64,
+			last_refill: now,
+		}
+	}
+
+	fn refill(&mut self, now: Instant) {
+		if now > self.last_refill {
+			let elapsed = (now - self.last_refill).as_secs_f64();
+			self.available = (self.available + elapsed * self.rate).min(self.rate);
+			self.last_refill = now;
+		}
+	}
+
+	fn has_capacity(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.available > 0.0
+	}
+
+	fn is_full(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.available >= self.rate
+	}
+
+	fn consume(&mut self, bytes: u64, now: Instant) {
+		self.refill(now);
+		self.available = (self.available - bytes as f64).max(-self.rate);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn limited(total: Option<u64>, per_peer: Option<u64>) -> ProtocolsBandwidth {
+		let bandwidth = ProtocolsBandwidth::new();
+		bandwidth.set_limit("/test/1".into(), BandwidthLimit { total, per_peer });
+		bandwidth
+	}
+
+	#[test]
+	fn unlimited_protocols_are_accounted() {
+		let bandwidth = ProtocolsBandwidth::new();
+		let peer = PeerId::random();
+
+		for _ in 0..10 {
+			assert!(bandwidth.try_transfer("/test/1", &peer, Direction::Inbound, 1024 * 1024));
+		}
+		bandwidth.force_transfer("/test/1", &peer, Direction::Outbound, 5);
+
+		let stats = bandwidth.stats();
+		assert_eq!(stats["/test/1"], ProtocolBandwidth {
+			total_inbound: 10 * 1024 * 1024,
+			total_outbound: 5,
+			dropped_inbound: 0,
+			dropped_outbound: 0,
+		});
+	}
+
+	#[test]
+	fn per_peer_limit_only_affects_that_peer() {
+		let bandwidth = limited(None, Some(100));
+		let greedy = PeerId::random();
+		let other = PeerId::random();
+
+		// A payload larger than the limit goes through once, then the bucket is in debt.
+		assert!(bandwidth.try_transfer("/test/1", &greedy, Direction::Inbound, 150));
+		assert!(!bandwidth.try_transfer("/test/1", &greedy, Direction::Inbound, 1));
+
+		// Other peers and the other direction are unaffected.
+		assert!(bandwidth.try_transfer("/test/1", &other, Direction::Inbound, 100));
+		assert!(bandwidth.try_transfer("/test/1", &greedy, Direction::Outbound, 100));
+
+		let stats = &bandwidth.stats()["/test/1"];
+		assert_eq!(stats.total_inbound, 250);
+		assert_eq!(stats.dropped_inbound, 1);
+		assert_eq!(stats.total_outbound, 100);
+	}
+
+	#[test]
+	fn total_limit_is_shared_between_peers() {
+		let bandwidth = limited(Some(100), None);
+
+		assert!(bandwidth.try_transfer("/test/1", &PeerId::random(), Direction::Outbound, 100));
+		assert!(!bandwidth.try_transfer("/test/1", &PeerId::random(), Direction::Outbound, 10));
+
+		// Responses are never dropped, but still consume the budget.
+		bandwidth.force_transfer("/test/1", &PeerId::random(), Direction::Outbound, 10);
+		assert_eq!(bandwidth.stats()["/test/1"].total_outbound, 110);
+		assert_eq!(bandwidth.stats()["/test/1"].dropped_outbound, 10);
+	}
+
+	#[test]
+	fn debt_is_capped_at_the_bucket_capacity() {
+		let bandwidth = limited(Some(1000), None);
+		let peer = PeerId::random();
+
+		// A huge response only puts the bucket one second worth of traffic in debt.
+		bandwidth.force_transfer("/test/1", &peer, Direction::Outbound, 1_000_000);
+		assert!(!bandwidth.try_transfer("/test/1", &peer, Direction::Outbound, 1));
+
+		std::thread::sleep(std::time::Duration::from_millis(1050));
+		assert!(bandwidth.try_transfer("/test/1", &peer, Direction::Outbound, 1));
+		assert_eq!(bandwidth.stats()["/test/1"].total_outbound, 1_000_001);
+	}
+
+	#[test]
+	fn buckets_refill_over_time() {
+		let bandwidth = limited(Some(1000), None);
+		let peer = PeerId::random();
+
+		assert!(bandwidth.try_transfer("/test/1", &peer, Direction::Inbound, 1000));
+		assert!(!bandwidth.try_transfer("/test/1", &peer, Direction::Inbound, 1));
+
+		std::thread::sleep(std::time::Duration::from_millis(50));
+		assert!(bandwidth.try_transfer("/test/1", &peer, Direction::Inbound, 1));
+	}
+}
@@ -52,10 +52,10 @@ use libp2p::{
 };
 use std::{
 	borrow::Cow, collections::{hash_map::Entry, HashMap}, convert::TryFrom as _, io, iter,
-	pin::Pin, task::{Context, Poll}, time::Duration,
+	pin::Pin, sync::Arc, task::{Context, Poll}, time::Duration,
 };
 use wasm_timer::Instant;
-use crate::ReputationChange;
+use crate::{protocol_bandwidth::{BandwidthLimit, Direction, ProtocolsBandwidth}, ReputationChange};
 
 pub use libp2p::request_response::{InboundFailure, OutboundFailure, RequestId};
 
@@ -103,6 +103,11 @@ pub struct ProtocolConfig {
     /// advertise support for this protocol, but any incoming request will lead to an error being
     /// sent back.
     pub inbound_queue: Option<mpsc::Sender<IncomingRequest>>,
+
+    /// Maximum rate at which requests and responses are sent and accepted.
+    ///
+    /// Requests exceeding it are discarded, while responses only count towards it.
+    pub bandwidth_limit: BandwidthLimit,
 }
 
 /// A single request received by a peer on a request-response protocol.
@@ -220,6 +225,9 @@ pub struct RequestResponsesBehaviour {
 
 	/// Whenever an incoming request arrives, the arrival [`Instant`] is recorded here.
 	pending_responses_arrival_time: HashMap<ProtocolRequestId, Instant>,
+
+	/// Accounting and limits of the traffic of each protocol.
+	bandwidth: Arc<ProtocolsBandwidth>,
 }
 
 /// Generated by the response builder and waiting to be processed.
@@ -234,9 +242,17 @@ struct RequestProcessingOutcome {
 impl RequestResponsesBehaviour {
 	/// Creates a new behaviour. Must be passed a list of supported protocols. Returns an error if
 	/// the same protocol is passed twice.
-	pub fn new(list: impl Iterator<Item = ProtocolConfig>) -> Result<Self, RegisterError> {
+	///
+	/// The bandwidth limit of each protocol is registered in `bandwidth`, which then accounts for
+	/// all the requests and responses.
+	pub fn new(
+		list: impl Iterator<Item = ProtocolConfig>,
+		bandwidth: Arc<ProtocolsBandwidth>,
+	) -> Result<Self, RegisterError> {
 		let mut protocols = HashMap::new();
 		for protocol in list {
+			bandwidth.set_limit(protocol.name.clone(), protocol.bandwidth_limit);
+
 			let mut cfg = RequestResponseConfig::default();
 			cfg.set_connection_keep_alive(Duration::from_secs(10));
 			cfg.set_request_timeout(protocol.request_timeout);
@@ -264,13 +280,14 @@ impl RequestResponsesBehaviour {
 			pending_requests: Default::default(),
 			pending_responses: Default::default(),
 			pending_responses_arrival_time: Default::default(),
+			bandwidth,
 		})
 	}
 
 	/// Initiates sending a request.
 	///
-	/// An error is returned if we are not connected to the target peer or if the protocol doesn't
-	/// match one that has been registered.
+	/// An error is returned if we are not connected to the target peer, if the protocol doesn't
+	/// match one that has been registered, or if the request exceeds its bandwidth limit.
 	pub fn send_request(
 		&mut self,
 		target: &PeerId,
@@ -279,13 +296,7 @@ impl RequestResponsesBehaviour {
 		pending_response: oneshot::Sender<Result<Vec<u8>, RequestFailure>>,
 	) {
 		if let Some((protocol, _)) = self.protocols.get_mut(protocol_name) {
-			if protocol.is_connected(target) {
-				let request_id = protocol.send_request(target, request);
-				self.pending_requests.insert(
-					(protocol_name.to_string().into(), request_id).into(),
-					(Instant::now(), pending_response),
-				);
-			} else {
+			if !protocol.is_connected(target) {
 				if pending_response.send(Err(RequestFailure::NotConnected)).is_err() {
 					log::debug!(
 						target: "sub-libp2p",
@@ -294,6 +305,26 @@ impl RequestResponsesBehaviour {
 						target,
 					);
 				};
+			} else if !self.bandwidth.try_transfer(
+				protocol_name,
+				target,
+				Direction::Outbound,
+				request.len(),
+			) {
+				if pending_response.send(Err(RequestFailure::RateLimited)).is_err() {
+					log::debug!(
+						target: "sub-libp2p",
+						"Bandwidth limit of {:?} exceeded. At the same time local \
+						 node is no longer interested in the result.",
+						protocol_name,
+					);
+				};
+			} else {
+				let request_id = protocol.send_request(target, request);
+				self.pending_requests.insert(
+					(protocol_name.to_string().into(), request_id).into(),
+					(Instant::now(), pending_response),
+				);
 			}
 		} else {
 			if pending_response.send(Err(RequestFailure::UnknownProtocol)).is_err() {
@@ -450,6 +481,12 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
 				if let Ok(payload) = result {
 					if let Some((protocol, _)) = self.protocols.get_mut(&*protocol_name) {
+						self.bandwidth.force_transfer(
+							&protocol_name,
+							&peer,
+							Direction::Outbound,
+							payload.len(),
+						);
 						if let Err(_) = protocol.send_response(inner_channel, Ok(payload)) {
 							// Note: Failure is handled further below when receiving
 							// `InboundFailure` event from `RequestResponse` behaviour.
@@ -527,7 +564,19 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 
 							// Submit the request to the "response builder" passed by the user at
 							// initialization.
-							if let Some(resp_builder) = resp_builder {
+							if !self.bandwidth.try_transfer(
+								protocol,
+								&peer,
+								Direction::Inbound,
+								request.len(),
+							) {
+								// Same as when the response builder is too busy, `tx` is dropped.
+								log::debug!(
+									target: "sub-libp2p",
+									"Dropping request from {:?} on {:?}: bandwidth limit exceeded",
+									peer, protocol,
+								);
+							} else if let Some(resp_builder) = resp_builder {
 								// If the response builder is too busy, silently drop `tx`. This
 								// will be reported by the corresponding `RequestResponse` through
 								// an `InboundFailure::Omission` event.
@@ -568,6 +617,15 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							},
 							..
 						} => {
+							if let Ok(payload) = &response {
+								self.bandwidth.force_transfer(
+									protocol,
+									&peer,
+									Direction::Inbound,
+									payload.len(),
+								);
+							}
+
 							let (started, delivered) = match self.pending_requests.remove(
 								&(protocol.clone(), request_id).into(),
 							) {
@@ -704,6 +762,8 @@ pub enum RequestFailure {
 	Refused,
 	/// The remote replied, but the local node is no longer interested in the response.
 	Obsolete,
+	/// Sending the request would exceed the bandwidth limit of the protocol.
+	RateLimited,
 	/// Problem on the network.
 	#[display(fmt = "Problem on the network")]
 	Network(#[error(ignore)] OutboundFailure),
@@ -871,7 +931,7 @@ mod tests {
 			.multiplex(libp2p::yamux::YamuxConfig::default())
 			.boxed();
 
-		let behaviour = RequestResponsesBehaviour::new(list).unwrap();
+		let behaviour = RequestResponsesBehaviour::new(list, Default::default()).unwrap();
 
 		let mut swarm = Swarm::new(transport, behaviour, keypair.public().into_peer_id());
 		let listen_addr: Multiaddr = format!("/memory/{}", rand::random::<u64>()).parse().unwrap();
@@ -906,6 +966,7 @@ mod tests {
 					max_response_size: 1024 * 1024,
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: Some(tx),
+					bandwidth_limit: Default::default(),
 				};
 
 				build_swarm(iter::once(protocol_config))
@@ -992,6 +1053,7 @@ mod tests {
 					max_response_size: 8,  // <-- important for the test
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: Some(tx),
+					bandwidth_limit: Default::default(),
 				};
 
 				build_swarm(iter::once(protocol_config))
@@ -1081,6 +1143,7 @@ mod tests {
 					max_response_size: 1024 * 1024,
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: None,
+					bandwidth_limit: Default::default(),
 				},
 				ProtocolConfig {
 					name: From::from(protocol_name_2),
@@ -1088,6 +1151,7 @@ mod tests {
 					max_response_size: 1024 * 1024,
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: None,
+					bandwidth_limit: Default::default(),
 				},
 			];
 
@@ -1105,6 +1169,7 @@ mod tests {
 					max_response_size: 1024 * 1024,
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: Some(tx_1),
+					bandwidth_limit: Default::default(),
 				},
 				ProtocolConfig {
 					name: From::from(protocol_name_2),
@@ -1112,6 +1177,7 @@ mod tests {
 					max_response_size: 1024 * 1024,
 					request_timeout: Duration::from_secs(30),
 					inbound_queue: Some(tx_2),
+					bandwidth_limit: Default::default(),
 				},
 			];
 
@@ -24,3 +24,7 @@ pub mod v1 {
 		include!(concat!(env!("OUT_DIR"), "/api.v1.light.rs"));
 	}
 }
+
+pub mod bitswap {
+	include!(concat!(env!("OUT_DIR"), "/bitswap.message.rs"));
+}
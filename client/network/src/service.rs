@@ -49,6 +49,7 @@ use crate::{
 		event::Event,
 		sync::SyncState,
 	},
+	protocol_bandwidth::{Direction, ProtocolsBandwidth},
 	transport, ReputationChange,
 };
 use futures::{channel::oneshot, prelude::*};
@@ -76,7 +77,7 @@ use libp2p::swarm::{
 use log::{error, info, trace, warn};
 use metrics::{Metrics, MetricSources, Histogram, HistogramVec};
 use parking_lot::Mutex;
-use sc_peerset::PeersetHandle;
+use sc_peerset::{BannedPeer, PeersetHandle};
 use sp_consensus::import_queue::{BlockImportError, BlockImportResult, ImportQueue, Link};
 use sp_runtime::traits::{Block as BlockT, NumberFor};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
@@ -96,6 +97,7 @@ use std::{
 		Arc,
 	},
 	task::Poll,
+	time::Duration,
 };
 
 pub use behaviour::{ResponseFailure, InboundFailure, RequestFailure, OutboundFailure};
@@ -117,6 +119,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	local_peer_id: PeerId,
 	/// Bandwidth logging system. Can be queried to know the average bandwidth consumed.
 	bandwidth: Arc<transport::BandwidthSinks>,
+	/// Traffic and bandwidth limits of each notifications and request-response protocol.
+	protocols_bandwidth: Arc<ProtocolsBandwidth>,
 	/// Peerset manager (PSM); manages the reputation of nodes and indicates the network which
 	/// nodes it should be connected to or not.
 	peerset: PeersetHandle,
@@ -247,6 +251,12 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 		let num_connected = Arc::new(AtomicUsize::new(0));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
 
+		// The limits of the request-response protocols are registered by the behaviour.
+		let protocols_bandwidth = Arc::new(ProtocolsBandwidth::new());
+		for set in &params.network_config.extra_sets {
+			protocols_bandwidth.set_limit(set.notifications_protocol.clone(), set.bandwidth_limit);
+		}
+
 		// Build the swarm.
 		let (mut swarm, bandwidth): (Swarm<B, H>, _) = {
 			let user_agent = format!(
@@ -277,9 +287,14 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 						config.with_mdns(false);
 						config.allow_private_ipv4(false);
 					}
-					TransportConfig::Normal { enable_mdns, allow_private_ipv4, .. } => {
+					TransportConfig::Normal {
+						enable_mdns, allow_private_ipv4, ref secure_websocket, ..
+					} => {
 						config.with_mdns(enable_mdns);
 						config.allow_private_ipv4(allow_private_ipv4);
+						if let Some(secure_websocket) = secure_websocket {
+							config.with_secure_websocket_domain(secure_websocket.domain.clone());
+						}
 					}
 				}
 
@@ -287,10 +302,10 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			};
 
 			let (transport, bandwidth) = {
-				let (config_mem, config_wasm) = match params.network_config.transport {
-					TransportConfig::MemoryOnly => (true, None),
-					TransportConfig::Normal { wasm_external_transport, .. } =>
-						(false, wasm_external_transport)
+				let (config_mem, config_wasm, config_wss) = match &params.network_config.transport {
+					TransportConfig::MemoryOnly => (true, None, None),
+					TransportConfig::Normal { wasm_external_transport, secure_websocket, .. } =>
+						(false, wasm_external_transport.clone(), secure_websocket.as_ref())
 				};
 
 				// The yamux buffer size limit is configured to be equal to the maximum frame size
@@ -328,9 +343,10 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 					local_identity,
 					config_mem,
 					config_wasm,
+					config_wss,
 					params.network_config.yamux_window_size,
 					yamux_maximum_buffer_size
-				)
+				)?
 			};
 
 			let behaviour = {
@@ -343,6 +359,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 					discovery_config,
 					params.block_request_protocol_config,
 					params.network_config.request_response_protocols,
+					protocols_bandwidth.clone(),
+					params.bitswap,
 				);
 
 				match result {
@@ -380,6 +398,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			Some(registry) => {
 				Some(metrics::register(registry, MetricSources {
 					bandwidth: bandwidth.clone(),
+					protocols_bandwidth: protocols_bandwidth.clone(),
 					major_syncing: is_major_syncing.clone(),
 					connected_peers: num_connected.clone(),
 				})?)
@@ -404,6 +423,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 
 		let service = Arc::new(NetworkService {
 			bandwidth,
+			protocols_bandwidth,
 			external_addresses: external_addresses.clone(),
 			num_connected: num_connected.clone(),
 			is_major_syncing: is_major_syncing.clone(),
@@ -590,6 +610,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			connected_peers,
 			not_connected_peers,
 			peerset: swarm.user_protocol_mut().peerset_debug_info(),
+			protocols: self.service.protocols_bandwidth.stats(),
 		}
 	}
 
@@ -611,6 +632,11 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 	pub fn add_reserved_peer(&self, peer: String) -> Result<(), String> {
 		self.service.add_reserved_peer(peer)
 	}
+
+	/// Returns the list of peers that are currently banned.
+	pub fn banned_peers(&mut self) -> Vec<BannedPeer> {
+		self.network_service.user_protocol_mut().banned_peers()
+	}
 }
 
 impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
@@ -677,6 +703,16 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 			}
 		};
 
+		if !self.protocols_bandwidth.try_transfer(&protocol, &target, Direction::Outbound, message.len()) {
+			log::debug!(
+				target: "sub-libp2p",
+				"Discarding notification to {:?} on {:?}: bandwidth limit exceeded",
+				target,
+				protocol,
+			);
+			return;
+		}
+
 		if let Some(notifications_sizes_metric) = self.notifications_sizes_metric.as_ref() {
 			notifications_sizes_metric
 				.with_label_values(&["out", &protocol])
@@ -785,6 +821,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		Ok(NotificationSender {
 			sink,
 			protocol_name: protocol,
+			protocols_bandwidth: self.protocols_bandwidth.clone(),
 			notification_size_metric,
 		})
 	}
@@ -874,6 +911,20 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		self.peerset.report_peer(who, cost_benefit);
 	}
 
+	/// Bans a peer for the given duration, or forever if `None`.
+	///
+	/// The peer is disconnected, and neither dialed nor accepted until the ban expires or is
+	/// lifted with [`NetworkService::unban_peer`]. Bans are persisted in the network
+	/// configuration directory, if any.
+	pub fn ban_peer(&self, who: PeerId, duration: Option<Duration>) {
+		self.peerset.ban_peer(who, duration);
+	}
+
+	/// Lifts the ban of a peer previously banned with [`NetworkService::ban_peer`].
+	pub fn unban_peer(&self, who: PeerId) {
+		self.peerset.unban_peer(who);
+	}
+
 	/// Disconnect from a node as soon as possible.
 	///
 	/// This triggers the same effects as if the connection had closed itself spontaneously.
@@ -1162,6 +1213,9 @@ pub struct NotificationSender {
 	/// Name of the protocol on the wire.
 	protocol_name: Cow<'static, str>,
 
+	/// Traffic and bandwidth limits of the protocols.
+	protocols_bandwidth: Arc<ProtocolsBandwidth>,
+
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notification_size_metric: Option<Histogram>,
@@ -1177,6 +1231,7 @@ impl NotificationSender {
 			},
 			peer_id: self.sink.peer_id(),
 			protocol_name: &self.protocol_name,
+			protocols_bandwidth: &self.protocols_bandwidth,
 			notification_size_metric: self.notification_size_metric.clone(),
 		})
 	}
@@ -1193,6 +1248,9 @@ pub struct NotificationSenderReady<'a> {
 	/// Name of the protocol on the wire.
 	protocol_name: &'a Cow<'static, str>,
 
+	/// Traffic and bandwidth limits of the protocols.
+	protocols_bandwidth: &'a ProtocolsBandwidth,
+
 	/// Field extracted from the [`Metrics`] struct and necessary to report the
 	/// notifications-related metrics.
 	notification_size_metric: Option<Histogram>,
@@ -1200,9 +1258,20 @@ pub struct NotificationSenderReady<'a> {
 
 impl<'a> NotificationSenderReady<'a> {
 	/// Consumes this slots reservation and actually queues the notification.
+	///
+	/// The notification isn't queued if it exceeds the bandwidth limit of the protocol.
 	pub fn send(self, notification: impl Into<Vec<u8>>) -> Result<(), NotificationSenderError> {
 		let notification = notification.into();
 
+		if !self.protocols_bandwidth.try_transfer(
+			self.protocol_name,
+			self.peer_id,
+			Direction::Outbound,
+			notification.len(),
+		) {
+			return Err(NotificationSenderError::RateLimited);
+		}
+
 		if let Some(notification_size_metric) = &self.notification_size_metric {
 			notification_size_metric.observe(notification.len() as f64);
 		}
@@ -1233,6 +1302,9 @@ pub enum NotificationSenderError {
 	Closed,
 	/// Protocol name hasn't been registered.
 	BadProtocol,
+	/// The notification has been discarded because it exceeds the bandwidth limit of the
+	/// protocol.
+	RateLimited,
 }
 
 /// Messages sent from the `NetworkService` to the `NetworkWorker`.
@@ -1401,9 +1473,11 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 			}
 
 			// Process the next action coming from the network.
-			let next_event = this.network_service.next_event();
-			futures::pin_mut!(next_event);
-			let poll_value = next_event.poll_unpin(cx);
+			let poll_value = {
+				let next_event = this.network_service.next_event();
+				futures::pin_mut!(next_event);
+				next_event.poll_unpin(cx)
+			};
 
 			match poll_value {
 				Poll::Pending => break,
@@ -1461,6 +1535,7 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 									RequestFailure::UnknownProtocol => "unknown-protocol",
 									RequestFailure::Refused => "refused",
 									RequestFailure::Obsolete => "obsolete",
+									RequestFailure::RateLimited => "rate-limited",
 									RequestFailure::Network(OutboundFailure::DialFailure) =>
 										"dial-failure",
 									RequestFailure::Network(OutboundFailure::Timeout) =>
@@ -1485,6 +1560,13 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 							.inc();
 					}
 				},
+				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::ExternalAddress(address))) => {
+					Swarm::<B, H>::add_external_address(
+						&mut this.network_service,
+						address,
+						AddressScore::Infinite,
+					);
+				},
 				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::NotificationStreamOpened {
 					remote, protocol, notifications_sink, role
 				})) => {
@@ -1550,7 +1632,20 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 						peers_notifications_sinks.remove(&(remote.clone(), protocol));
 					}
 				},
-				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::NotificationsReceived { remote, messages })) => {
+				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::NotificationsReceived { remote, mut messages })) => {
+					let protocols_bandwidth = &this.service.protocols_bandwidth;
+					messages.retain(|(protocol, message)| {
+						protocols_bandwidth.try_transfer(protocol, &remote, Direction::Inbound, message.len())
+					});
+					if messages.is_empty() {
+						trace!(
+							target: "sub-libp2p",
+							"Discarded notifications from {:?}: bandwidth limit exceeded",
+							remote,
+						);
+						continue;
+					}
+
 					if let Some(metrics) = this.metrics.as_ref() {
 						for (protocol, message) in &messages {
 							metrics.notifications_sizes
@@ -1608,11 +1703,11 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 						let reason = match cause {
 							Some(ConnectionError::IO(_)) => "transport-error",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(EitherError::A(EitherError::A(
-								EitherError::A(EitherError::B(EitherError::A(
-								PingFailure::Timeout)))))))) => "ping-timeout",
+								EitherError::A(EitherError::A(EitherError::B(EitherError::A(
+								PingFailure::Timeout))))))))) => "ping-timeout",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(EitherError::A(EitherError::A(
-								EitherError::A(EitherError::A(
-								NotifsHandlerError::SyncNotificationsClogged))))))) => "sync-notifications-clogged",
+								EitherError::A(EitherError::A(EitherError::A(
+								NotifsHandlerError::SyncNotificationsClogged)))))))) => "sync-notifications-clogged",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::Handler(_))) => "protocol-error",
 							Some(ConnectionError::Handler(NodeHandlerWrapperError::KeepAliveTimeout)) => "keep-alive-timeout",
 							None => "actively-closed",
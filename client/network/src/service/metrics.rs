@@ -16,7 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::transport::BandwidthSinks;
+use crate::{protocol_bandwidth::ProtocolsBandwidth, transport::BandwidthSinks};
 use prometheus_endpoint::{
 	self as prometheus,
 	Counter, CounterVec, Gauge, GaugeVec, HistogramOpts,
@@ -36,6 +36,7 @@ pub use prometheus_endpoint::{Histogram, HistogramVec};
 /// Registers all networking metrics with the given registry.
 pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics, PrometheusError> {
 	BandwidthCounters::register(registry, sources.bandwidth)?;
+	ProtocolBandwidthCounters::register(registry, sources.protocols_bandwidth)?;
 	MajorSyncingGauge::register(registry, sources.major_syncing)?;
 	NumConnectedGauge::register(registry, sources.connected_peers)?;
 	Metrics::register(registry)
@@ -44,6 +45,7 @@ pub fn register(registry: &Registry, sources: MetricSources) -> Result<Metrics,
 /// Predefined metric sources that are fed directly into prometheus.
 pub struct MetricSources {
 	pub bandwidth: Arc<BandwidthSinks>,
+	pub protocols_bandwidth: Arc<ProtocolsBandwidth>,
 	pub major_syncing: Arc<AtomicBool>,
 	pub connected_peers: Arc<AtomicUsize>,
 }
@@ -288,6 +290,55 @@ impl MetricSource for BandwidthCounters {
 	}
 }
 
+/// The per-protocol bandwidth counter metrics.
+#[derive(Clone)]
+pub struct ProtocolBandwidthCounters {
+	protocols: Arc<ProtocolsBandwidth>,
+	/// Whether to report the bytes discarded because of the limits instead of the accepted ones.
+	dropped: bool,
+}
+
+impl ProtocolBandwidthCounters {
+	/// Registers the `ProtocolBandwidthCounters` metrics whose values are
+	/// obtained from the given `ProtocolsBandwidth`.
+	fn register(registry: &Registry, protocols: Arc<ProtocolsBandwidth>) -> Result<(), PrometheusError> {
+		prometheus::register(SourcedCounter::new(
+			&Opts::new(
+				"sub_libp2p_protocol_bytes_total",
+				"Total size of the notifications, requests and responses of each protocol"
+			).variable_label("protocol").variable_label("direction"),
+			ProtocolBandwidthCounters { protocols: protocols.clone(), dropped: false },
+		)?, registry)?;
+
+		prometheus::register(SourcedCounter::new(
+			&Opts::new(
+				"sub_libp2p_protocol_dropped_bytes_total",
+				"Total size of the notifications and requests of each protocol discarded because \
+				 of its bandwidth limit"
+			).variable_label("protocol").variable_label("direction"),
+			ProtocolBandwidthCounters { protocols, dropped: true },
+		)?, registry)?;
+
+		Ok(())
+	}
+}
+
+impl MetricSource for ProtocolBandwidthCounters {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		for (protocol, stats) in self.protocols.stats() {
+			if self.dropped {
+				set(&[protocol.as_str(), "in"], stats.dropped_inbound);
+				set(&[protocol.as_str(), "out"], stats.dropped_outbound);
+			} else {
+				set(&[protocol.as_str(), "in"], stats.total_inbound);
+				set(&[protocol.as_str(), "out"], stats.total_outbound);
+			}
+		}
+	}
+}
+
 /// The "major syncing" metric.
 #[derive(Clone)]
 pub struct MajorSyncingGauge(Arc<AtomicBool>);
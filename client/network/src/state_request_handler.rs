@@ -0,0 +1,423 @@
+// Copyright 2021 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helper for handling (i.e. answering) state requests from a remote peer via the
+//! [`crate::request_responses::RequestResponsesBehaviour`], and for downloading the state of a
+//! block from a peer, chunk by chunk.
+//!
+//! A state request asks for the key/value pairs following a given key in the storage of a given
+//! block. The responder returns as many consecutive pairs as fit in its response size limit,
+//! together with a proof that allows the requester to check them against the state root of the
+//! block. Downloading the entire state is done by repeatedly requesting the pairs following the
+//! last key received, until a response is marked as complete.
+
+use codec::{Encode, Decode};
+use crate::chain::Client;
+use crate::config::ProtocolId;
+use crate::request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig};
+use crate::schema::v1::{StateEntry, StateRequest, StateResponse};
+use crate::{ExHashT, NetworkService, PeerId, RequestFailure};
+use futures::channel::{mpsc, oneshot};
+use futures::stream::StreamExt;
+use log::debug;
+use prost::Message;
+use sc_client_api::StorageProof;
+use sp_core::storage::StorageKey;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, HashFor};
+use std::sync::Arc;
+use std::time::Duration;
+
+const LOG_TARGET: &str = "state-request-handler";
+/// Number of key/value pairs read from the database at once.
+const ENTRIES_PER_READ: usize = 256;
+/// Maximum total size of the keys and values of a response. A response always contains at least
+/// one entry, even if it alone exceeds this limit.
+const MAX_RESPONSE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Generates a [`ProtocolConfig`] for the state request protocol, refusing incoming requests.
+pub fn generate_protocol_config(protocol_id: ProtocolId) -> ProtocolConfig {
+	ProtocolConfig {
+		name: generate_protocol_name(&protocol_id).into(),
+		max_request_size: 1024,
+		// Leaves room for a single value larger than `MAX_RESPONSE_BYTES`, such as the runtime
+		// code, and for the proof.
+		max_response_size: 16 * 1024 * 1024,
+		request_timeout: Duration::from_secs(40),
+		inbound_queue: None,
+		bandwidth_limit: Default::default(),
+	}
+}
+
+/// Generate the state protocol name from chain specific protocol identifier.
+pub fn generate_protocol_name(protocol_id: &ProtocolId) -> String {
+	let mut s = String::new();
+	s.push_str("/");
+	s.push_str(protocol_id.as_ref());
+	s.push_str("/state/1");
+	s
+}
+
+/// Handler for incoming state requests from a remote peer.
+pub struct StateRequestHandler<B> {
+	client: Arc<dyn Client<B>>,
+	request_receiver: mpsc::Receiver<IncomingRequest>,
+	max_response_bytes: usize,
+}
+
+impl<B: BlockT> StateRequestHandler<B> {
+	/// Create a new [`StateRequestHandler`].
+	pub fn new(protocol_id: ProtocolId, client: Arc<dyn Client<B>>) -> (Self, ProtocolConfig) {
+		// State requests are only sent by nodes syncing the state, which is rare. Reading and
+		// proving a chunk is however expensive, hence the small queue.
+		let (tx, request_receiver) = mpsc::channel(8);
+
+		let mut protocol_config = generate_protocol_config(protocol_id);
+		protocol_config.inbound_queue = Some(tx);
+
+		let handler = Self {
+			client,
+			request_receiver,
+			max_response_bytes: MAX_RESPONSE_BYTES,
+		};
+
+		(handler, protocol_config)
+	}
+
+	fn handle_request(
+		&self,
+		payload: Vec<u8>,
+		pending_response: oneshot::Sender<OutgoingResponse>
+	) -> Result<(), HandleRequestError> {
+		let request = StateRequest::decode(&payload[..])?;
+		let block = BlockId::<B>::Hash(Decode::decode(&mut request.block.as_ref())?);
+
+		let mut entries = Vec::new();
+		let mut proofs = Vec::new();
+		let mut total_size = 0;
+		let mut complete = false;
+		let mut cursor = if request.start.is_empty() {
+			None
+		} else {
+			Some(StorageKey(request.start))
+		};
+
+		'read: loop {
+			let pairs = if request.no_proof {
+				self.client.read_range(&block, None, cursor.as_ref(), ENTRIES_PER_READ)?
+			} else {
+				let (pairs, proof) = self.client.read_range_proof(
+					&block,
+					None,
+					cursor.as_ref(),
+					ENTRIES_PER_READ,
+				)?;
+				// Pairs past the size limit are discarded below, but remain covered by the
+				// proof. This is harmless, as the proof is still valid for the entries that
+				// are sent.
+				proofs.push(proof);
+				pairs
+			};
+
+			let exhausted = pairs.len() < ENTRIES_PER_READ;
+			for (key, value) in pairs {
+				let size = key.0.len() + value.0.len();
+				if !entries.is_empty() && total_size + size > self.max_response_bytes {
+					break 'read;
+				}
+
+				total_size += size;
+				cursor = Some(key.clone());
+				entries.push(StateEntry { key: key.0, value: value.0 });
+			}
+
+			if exhausted {
+				complete = true;
+				break;
+			}
+			if total_size >= self.max_response_bytes {
+				break;
+			}
+		}
+
+		let res = StateResponse {
+			entries,
+			proof: if request.no_proof {
+				Vec::new()
+			} else {
+				StorageProof::merge(proofs).encode()
+			},
+			complete,
+		};
+
+		let mut data = Vec::with_capacity(res.encoded_len());
+		res.encode(&mut data)?;
+
+		pending_response.send(OutgoingResponse {
+			result: Ok(data),
+			reputation_changes: Vec::new(),
+		}).map_err(|_| HandleRequestError::SendResponse)
+	}
+
+	/// Run [`StateRequestHandler`].
+	pub async fn run(mut self) {
+		while let Some(request) = self.request_receiver.next().await {
+			let IncomingRequest { peer, payload, pending_response } = request;
+
+			match self.handle_request(payload, pending_response) {
+				Ok(()) => debug!(target: LOG_TARGET, "Handled state request from {}.", peer),
+				Err(e) => debug!(
+					target: LOG_TARGET,
+					"Failed to handle state request from {}: {}",
+					peer, e,
+				),
+			}
+		}
+	}
+}
+
+#[derive(derive_more::Display, derive_more::From)]
+enum HandleRequestError {
+	#[display(fmt = "Failed to decode request: {}.", _0)]
+	DecodeProto(prost::DecodeError),
+	#[display(fmt = "Failed to encode response: {}.", _0)]
+	EncodeProto(prost::EncodeError),
+	#[display(fmt = "Failed to decode block hash: {}.", _0)]
+	DecodeScale(codec::Error),
+	Client(sp_blockchain::Error),
+	#[display(fmt = "Failed to send response.")]
+	SendResponse,
+}
+
+/// Consecutive key/value pairs of the state of a block, as returned by a peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChunk {
+	/// Key/value pairs, in lexicographic order of the keys.
+	pub entries: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Proof of the entries. `None` if it wasn't requested.
+	pub proof: Option<StorageProof>,
+	/// True if there is no key after the last entry.
+	pub complete: bool,
+}
+
+impl StateChunk {
+	/// Returns the key to request the next chunk from, or `None` if the state is complete.
+	pub fn next_start(&self) -> Option<&[u8]> {
+		if self.complete {
+			None
+		} else {
+			self.entries.last().map(|(key, _)| &key[..])
+		}
+	}
+
+	/// Checks the entries, and whether the state is complete, against the state root of the
+	/// block. `start` is the key the chunk has been requested from.
+	pub fn verify<B: BlockT>(
+		&self,
+		state_root: B::Hash,
+		start: &[u8],
+	) -> Result<(), StateRequestError> {
+		// An incomplete chunk must make progress, otherwise there is no key to continue from.
+		if !self.complete && self.entries.is_empty() {
+			return Err(StateRequestError::EmptyChunk);
+		}
+
+		let proof = self.proof.clone().ok_or(StateRequestError::MissingProof)?;
+		let start = if start.is_empty() { None } else { Some(start) };
+
+		// When the chunk is complete, reading one more entry must yield nothing.
+		let count = if self.complete { self.entries.len() + 1 } else { self.entries.len() };
+		let proven = sp_state_machine::read_range_proof_check::<HashFor<B>>(
+			state_root,
+			proof,
+			None,
+			start,
+			count,
+		).map_err(|e| StateRequestError::InvalidProof(e.to_string()))?;
+
+		if proven != self.entries {
+			return Err(StateRequestError::InvalidProof(
+				"entries don't match the proof".into(),
+			));
+		}
+
+		Ok(())
+	}
+}
+
+/// Error while downloading a chunk of state from a peer.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum StateRequestError {
+	/// The request failed.
+	#[display(fmt = "Request failed: {}", _0)]
+	Request(RequestFailure),
+	/// The response couldn't be decoded.
+	#[display(fmt = "Failed to decode response: {}", _0)]
+	DecodeProto(prost::DecodeError),
+	/// The proof in the response couldn't be decoded.
+	#[display(fmt = "Failed to decode proof: {}", _0)]
+	DecodeScale(codec::Error),
+	/// The chunk doesn't contain a proof.
+	#[display(fmt = "Missing proof")]
+	#[from(ignore)]
+	MissingProof,
+	/// The chunk has no entries, but isn't marked as complete.
+	#[display(fmt = "Incomplete chunk without entries")]
+	#[from(ignore)]
+	EmptyChunk,
+	/// The proof doesn't match the state root or the entries.
+	#[display(fmt = "Invalid proof: {}", _0)]
+	#[from(ignore)]
+	InvalidProof(String),
+}
+
+impl std::error::Error for StateRequestError {}
+
+/// Requests the key/value pairs following `start` in the state of `block` from `peer`.
+///
+/// The peer must be connected, and the state request protocol must have been registered through
+/// [`NetworkConfiguration::request_response_protocols`](
+/// crate::config::NetworkConfiguration::request_response_protocols). The proof, if requested,
+/// isn't checked; see [`StateChunk::verify`].
+pub async fn request_state_chunk<B: BlockT, H: ExHashT>(
+	network: &NetworkService<B, H>,
+	protocol_id: &ProtocolId,
+	peer: PeerId,
+	block: B::Hash,
+	start: &[u8],
+	with_proof: bool,
+) -> Result<StateChunk, StateRequestError> {
+	let request = StateRequest {
+		block: block.encode(),
+		start: start.to_vec(),
+		no_proof: !with_proof,
+	};
+
+	let mut payload = Vec::with_capacity(request.encoded_len());
+	request.encode(&mut payload)
+		.expect("Vec<u8> provides capacity as needed; qed");
+
+	let response = network.request(peer, generate_protocol_name(protocol_id), payload).await?;
+	decode_response(&response, with_proof)
+}
+
+fn decode_response(response: &[u8], with_proof: bool) -> Result<StateChunk, StateRequestError> {
+	let response = StateResponse::decode(response)?;
+	let proof = if with_proof {
+		Some(Decode::decode(&mut response.proof.as_ref())?)
+	} else {
+		None
+	};
+
+	Ok(StateChunk {
+		entries: response.entries.into_iter().map(|entry| (entry.key, entry.value)).collect(),
+		proof,
+		complete: response.complete,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use sc_client_api::StorageProvider;
+	use sp_blockchain::HeaderBackend;
+	use sp_runtime::traits::Header as _;
+	use substrate_test_runtime_client::runtime::Block;
+
+	fn request_chunk(
+		handler: &StateRequestHandler<Block>,
+		block: <Block as BlockT>::Hash,
+		start: &[u8],
+		with_proof: bool,
+	) -> StateChunk {
+		let request = StateRequest {
+			block: block.encode(),
+			start: start.to_vec(),
+			no_proof: !with_proof,
+		};
+		let mut payload = Vec::new();
+		request.encode(&mut payload).unwrap();
+
+		let (tx, rx) = oneshot::channel();
+		assert!(handler.handle_request(payload, tx).is_ok());
+		let response = futures::executor::block_on(rx).unwrap().result.unwrap();
+		decode_response(&response, with_proof).unwrap()
+	}
+
+	#[test]
+	fn downloads_and_verifies_the_whole_state_in_chunks() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let genesis = client.header(BlockId::Number(0)).unwrap().unwrap();
+		let (mut handler, _) = StateRequestHandler::new(ProtocolId::from("test"), client.clone());
+		handler.max_response_bytes = 1024;
+
+		let mut entries = Vec::new();
+		let mut start = Vec::new();
+		let mut num_chunks = 0;
+		loop {
+			let chunk = request_chunk(&handler, genesis.hash(), &start, true);
+			chunk.verify::<Block>(*genesis.state_root(), &start).unwrap();
+			num_chunks += 1;
+
+			entries.extend(chunk.entries.iter().cloned());
+			match chunk.next_start() {
+				Some(next) => start = next.to_vec(),
+				None => break,
+			}
+		}
+
+		let expected = client.storage_pairs(&BlockId::Number(0), &StorageKey(Vec::new())).unwrap()
+			.into_iter()
+			.map(|(key, value)| (key.0, value.0))
+			.collect::<Vec<_>>();
+		assert_eq!(entries, expected);
+		assert!(num_chunks > 1);
+	}
+
+	#[test]
+	fn tampered_chunks_are_rejected() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let genesis = client.header(BlockId::Number(0)).unwrap().unwrap();
+		let (mut handler, _) = StateRequestHandler::new(ProtocolId::from("test"), client);
+		handler.max_response_bytes = 1024;
+
+		let chunk = request_chunk(&handler, genesis.hash(), &[], true);
+		assert!(!chunk.complete);
+
+		let mut tampered = chunk.clone();
+		tampered.entries[0].1.push(0);
+		assert!(tampered.verify::<Block>(*genesis.state_root(), &[]).is_err());
+
+		let mut truncated = chunk.clone();
+		truncated.complete = true;
+		assert!(truncated.verify::<Block>(*genesis.state_root(), &[]).is_err());
+
+		let mut empty = chunk.clone();
+		empty.entries.clear();
+		assert!(matches!(
+			empty.verify::<Block>(*genesis.state_root(), &[]),
+			Err(StateRequestError::EmptyChunk),
+		));
+
+		let without_proof = request_chunk(&handler, genesis.hash(), &[], false);
+		assert_eq!(without_proof.entries, chunk.entries);
+		assert!(matches!(
+			without_proof.verify::<Block>(*genesis.state_root(), &[]),
+			Err(StateRequestError::MissingProof),
+		));
+	}
+}
@@ -16,6 +16,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
+use crate::config::SecureWebSocketConfig;
 use libp2p::{
 	PeerId, Transport,
 	core::{
@@ -26,7 +27,7 @@ use libp2p::{
 };
 #[cfg(not(target_os = "unknown"))]
 use libp2p::{tcp, dns, websocket};
-use std::{sync::Arc, time::Duration};
+use std::{io, sync::Arc, time::Duration};
 
 pub use self::bandwidth::BandwidthSinks;
 
@@ -43,31 +44,44 @@ pub use self::bandwidth::BandwidthSinks;
 /// high-level protocols combined, or to some generously high value if you are sure that a maximum
 /// size is enforced on all high-level protocols.
 ///
+/// `secure_websocket` enables listening on `/wss` addresses, using the given certificate. Dialing
+/// `/wss` addresses is always possible. Ignored if `memory_only` is true.
+///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
 /// the connections spawned with this transport.
+///
+/// Returns an error if the certificate or private key of `secure_websocket` can't be parsed.
 pub fn build_transport(
 	keypair: identity::Keypair,
 	memory_only: bool,
 	wasm_external_transport: Option<wasm_ext::ExtTransport>,
+	secure_websocket: Option<&SecureWebSocketConfig>,
 	yamux_window_size: Option<u32>,
 	yamux_maximum_buffer_size: usize,
-) -> (Boxed<(PeerId, StreamMuxerBox)>, Arc<BandwidthSinks>) {
+) -> io::Result<(Boxed<(PeerId, StreamMuxerBox)>, Arc<BandwidthSinks>)> {
 	// Build the base layer of the transport.
 	let transport = if let Some(t) = wasm_external_transport {
 		OptionalTransport::some(t)
 	} else {
 		OptionalTransport::none()
 	};
+	#[cfg(target_os = "unknown")]
+	let _ = secure_websocket;
 	#[cfg(not(target_os = "unknown"))]
 	let transport = transport.or_transport(if !memory_only {
 		let desktop_trans = tcp::TcpConfig::new().nodelay(true);
-		let desktop_trans = websocket::WsConfig::new(desktop_trans.clone())
-			.or_transport(desktop_trans);
-		OptionalTransport::some(if let Ok(dns) = dns::DnsConfig::new(desktop_trans.clone()) {
+		let desktop_trans = if let Ok(dns) = dns::DnsConfig::new(desktop_trans.clone()) {
 			EitherTransport::Left(dns)
 		} else {
 			EitherTransport::Right(desktop_trans.map_err(dns::DnsErr::Underlying))
-		})
+		};
+		// The WebSocket transport wraps the DNS transport rather than the other way around, as
+		// it needs the domain name of `/dns/.../wss` addresses to check the remote certificate.
+		let mut ws_trans = websocket::WsConfig::new(desktop_trans.clone());
+		if let Some(config) = secure_websocket {
+			ws_trans.set_tls_config(tls_config(config)?);
+		}
+		OptionalTransport::some(ws_trans.or_transport(desktop_trans))
 	} else {
 		OptionalTransport::none()
 	});
@@ -122,5 +136,28 @@ pub fn build_transport(
 		.timeout(Duration::from_secs(20))
 		.boxed();
 
-	(transport, bandwidth)
+	Ok((transport, bandwidth))
+}
+
+/// Turns the DER-encoded certificates of `config` into the TLS configuration of the
+/// WebSocket transport.
+#[cfg(not(target_os = "unknown"))]
+fn tls_config(config: &SecureWebSocketConfig) -> io::Result<websocket::tls::Config> {
+	let invalid = |err: websocket::tls::Error| io::Error::new(
+		io::ErrorKind::InvalidInput,
+		format!("Invalid secure WebSocket certificate: {}", err),
+	);
+
+	let key = websocket::tls::PrivateKey::new(config.private_key.clone());
+	let certificates = config.certificate_chain.iter()
+		.cloned()
+		.map(websocket::tls::Certificate::new);
+
+	let mut builder = websocket::tls::Config::builder();
+	builder.server(key, certificates).map_err(invalid)?;
+	for certificate in &config.trusted_certificates {
+		builder.add_trust(&websocket::tls::Certificate::new(certificate.clone())).map_err(invalid)?;
+	}
+
+	Ok(builder.finish())
 }
@@ -27,7 +27,7 @@ use std::{
 	task::{Poll, Context as FutureContext}
 };
 
-use libp2p::build_multiaddr;
+use libp2p::{build_multiaddr, multiaddr::Protocol};
 use log::trace;
 use sc_network::block_request_handler::{self, BlockRequestHandler};
 use sp_blockchain::{
@@ -55,7 +55,10 @@ use sc_network::{
 	NetworkWorker, NetworkService, config::{ProtocolId, MultiaddrWithPeerId, NonReservedPeerMode},
 	Multiaddr,
 };
-use sc_network::config::{NetworkConfiguration, NonDefaultSetConfig, TransportConfig};
+use sc_network::config::{
+	BandwidthLimit, NetworkConfiguration, NonDefaultSetConfig, SecureWebSocketConfig,
+	TransportConfig,
+};
 use libp2p::PeerId;
 use parking_lot::Mutex;
 use sp_core::H256;
@@ -609,6 +612,38 @@ pub struct FullPeerConfig {
 	///
 	/// If `None`, it will be connected to all other peers.
 	pub connect_to_peers: Option<Vec<usize>>,
+	/// If true, the peer listens on a local WebSocket address instead of an in-memory one.
+	pub websocket: bool,
+	/// If `Some`, the peer listens on a local secure WebSocket address instead of an in-memory
+	/// one. The certificate must have been issued for `localhost`.
+	pub secure_websocket: Option<SecureWebSocketConfig>,
+	/// Bandwidth limit of the block request protocol.
+	pub block_request_bandwidth_limit: BandwidthLimit,
+}
+
+/// Polls `network` until it listens on a TCP port, and returns the address to dial it at.
+///
+/// `/wss` addresses are returned as `/dns4/localhost/...`, as certificates are checked against a
+/// domain name.
+fn actual_listen_addr(network: &mut NetworkWorker<Block, <Block as BlockT>::Hash>) -> Multiaddr {
+	let listen_addr = futures::executor::block_on(future::poll_fn(|cx| {
+		let _ = network.poll_unpin(cx);
+		match network.listen_addresses().next() {
+			Some(addr) => Poll::Ready(addr.clone()),
+			None => Poll::Pending,
+		}
+	}));
+
+	if listen_addr.iter().any(|protocol| matches!(protocol, Protocol::Wss(_))) {
+		listen_addr.iter()
+			.map(|protocol| match protocol {
+				Protocol::Ip4(_) => Protocol::Dns4("localhost".into()),
+				protocol => protocol,
+			})
+			.collect()
+	} else {
+		listen_addr
+	}
 }
 
 pub trait TestNetFactory: Sized {
@@ -695,21 +730,37 @@ pub trait TestNetFactory: Sized {
 			None,
 		));
 
-		let listen_addr = build_multiaddr![Memory(rand::random::<u64>())];
-
 		let mut network_config = NetworkConfiguration::new(
 			"test-node",
 			"test-client",
 			Default::default(),
 			None,
 		);
-		network_config.transport = TransportConfig::MemoryOnly;
+		let listen_addr = if config.websocket || config.secure_websocket.is_some() {
+			// The OS picks the port, which is read back once the network is listening.
+			let listen_addr = if config.secure_websocket.is_some() {
+				build_multiaddr![Ip4([127, 0, 0, 1]), Tcp(0u16), Wss("/")]
+			} else {
+				build_multiaddr![Ip4([127, 0, 0, 1]), Tcp(0u16), Ws("/")]
+			};
+			network_config.transport = TransportConfig::Normal {
+				enable_mdns: false,
+				allow_private_ipv4: true,
+				wasm_external_transport: None,
+				secure_websocket: config.secure_websocket,
+			};
+			listen_addr
+		} else {
+			network_config.transport = TransportConfig::MemoryOnly;
+			build_multiaddr![Memory(rand::random::<u64>())]
+		};
 		network_config.listen_addresses = vec![listen_addr.clone()];
 		network_config.allow_non_globals_in_dht = true;
 		network_config.extra_sets = config.notifications_protocols.into_iter().map(|p| {
 			NonDefaultSetConfig {
 				notifications_protocol: p,
 				max_notification_size: 1024 * 1024,
+				bandwidth_limit: Default::default(),
 				set_config: Default::default()
 			}
 		}).collect();
@@ -726,12 +777,13 @@ pub trait TestNetFactory: Sized {
 		let protocol_id = ProtocolId::from("test-protocol-name");
 
 		let block_request_protocol_config = {
-			let (handler, protocol_config) = BlockRequestHandler::new(protocol_id.clone(), client.clone());
+			let (handler, mut protocol_config) = BlockRequestHandler::new(protocol_id.clone(), client.clone());
 			self.spawn_task(handler.run().boxed());
+			protocol_config.bandwidth_limit = config.block_request_bandwidth_limit;
 			protocol_config
 		};
 
-		let network = NetworkWorker::new(sc_network::config::Params {
+		let mut network = NetworkWorker::new(sc_network::config::Params {
 			role: Role::Full,
 			executor: None,
 			network_config,
@@ -744,10 +796,17 @@ pub trait TestNetFactory: Sized {
 				.unwrap_or_else(|| Box::new(DefaultBlockAnnounceValidator)),
 			metrics_registry: None,
 			block_request_protocol_config,
+			bitswap: None,
 		}).unwrap();
 
 		trace!(target: "test_network", "Peer identifier: {}", network.service().local_peer_id());
 
+		let listen_addr = if listen_addr.iter().any(|protocol| protocol == Protocol::Tcp(0)) {
+			actual_listen_addr(&mut network)
+		} else {
+			listen_addr
+		};
+
 		self.mut_peers(move |peers| {
 			for peer in peers.iter_mut() {
 				peer.network.add_known_address(
@@ -830,6 +889,7 @@ pub trait TestNetFactory: Sized {
 			block_announce_validator: Box::new(DefaultBlockAnnounceValidator),
 			metrics_registry: None,
 			block_request_protocol_config,
+			bitswap: None,
 		}).unwrap();
 
 		self.mut_peers(|peers| {
@@ -897,3 +897,84 @@ fn block_announce_data_is_propagated() {
 		net.block_until_idle();
 	}
 }
+
+#[test]
+fn syncs_over_websocket() {
+	sp_tracing::try_init_simple();
+	let mut net = TestNet::new(0);
+
+	net.add_full_peer_with_config(FullPeerConfig { websocket: true, ..Default::default() });
+	net.add_full_peer_with_config(FullPeerConfig { websocket: true, ..Default::default() });
+
+	net.peer(0).push_blocks(10, false);
+	net.block_until_sync();
+
+	assert_eq!(net.peer(1).client().info().best_number, 10);
+	assert!(net.peers()[0].blockchain_canon_equals(&net.peers()[1]));
+}
+
+#[test]
+fn syncs_over_secure_websocket() {
+	sp_tracing::try_init_simple();
+	let mut net = TestNet::new(0);
+
+	let certificate = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+	let certificate_der = certificate.serialize_der().unwrap();
+	let config = || sc_network::config::SecureWebSocketConfig {
+		domain: "localhost".into(),
+		private_key: certificate.serialize_private_key_der(),
+		certificate_chain: vec![certificate_der.clone()],
+		trusted_certificates: vec![certificate_der.clone()],
+	};
+
+	net.add_full_peer_with_config(FullPeerConfig {
+		secure_websocket: Some(config()),
+		..Default::default()
+	});
+	net.add_full_peer_with_config(FullPeerConfig {
+		secure_websocket: Some(config()),
+		..Default::default()
+	});
+	assert!(net.peers()[0].listen_addr.iter().any(|protocol| matches!(protocol, Protocol::Wss(_))));
+
+	net.peer(0).push_blocks(10, false);
+	net.block_until_sync();
+
+	assert_eq!(net.peer(1).client().info().best_number, 10);
+	assert!(net.peers()[0].blockchain_canon_equals(&net.peers()[1]));
+}
+
+#[test]
+fn rate_limited_block_requests_are_sent_again_later() {
+	sp_tracing::try_init_simple();
+	let mut net = TestNet::new(1);
+
+	// Each block request is larger than what the limit allows per second, so every request
+	// following the first one exceeds it for a while.
+	net.add_full_peer_with_config(FullPeerConfig {
+		block_request_bandwidth_limit: sc_network::config::BandwidthLimit {
+			total: None,
+			per_peer: Some(8),
+		},
+		..Default::default()
+	});
+
+	net.peer(0).push_blocks(300, false);
+	net.block_until_connected();
+
+	block_on(futures::future::poll_fn::<(), _>(|cx| {
+		net.poll(cx);
+		// The peer is never disconnected because of the rate limit.
+		assert_eq!(net.peer(1).num_peers(), 1);
+		if net.peer(1).client().info().best_number == 300 {
+			Poll::Ready(())
+		} else {
+			Poll::Pending
+		}
+	}));
+
+	let dropped = net.peer(1).network.network_state().protocols.values()
+		.map(|protocol| protocol.dropped_outbound)
+		.sum::<u64>();
+	assert!(dropped > 0);
+}
@@ -22,6 +22,8 @@ use std::{
 	convert::TryFrom,
 	thread::sleep,
 	collections::HashSet,
+	path::PathBuf,
+	time::Duration,
 };
 
 use crate::NetworkProvider;
@@ -32,13 +34,16 @@ use codec::{Encode, Decode};
 use sp_core::OpaquePeerId;
 use sp_core::offchain::{
 	Externalities as OffchainExt, HttpRequestId, Timestamp, HttpRequestStatus, HttpError,
-	OffchainStorage, OpaqueNetworkState, OpaqueMultiaddr, StorageKind,
+	OffchainStorage, OpaqueNetworkState, OpaqueMultiaddr, StorageKind, StorageError,
+	Duration as OffchainDuration,
 };
 pub use sp_offchain::STORAGE_PREFIX;
 pub use http::SharedClient;
 
 #[cfg(not(target_os = "unknown"))]
 mod http;
+#[cfg(not(target_os = "unknown"))]
+mod http_proxy;
 
 #[cfg(target_os = "unknown")]
 use http_dummy as http;
@@ -47,6 +52,18 @@ mod http_dummy;
 
 mod timestamp;
 
+/// Configuration of the HTTP client of the offchain workers.
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+	/// URI of the HTTP proxy the requests go through, e.g. `http://127.0.0.1:3128`.
+	///
+	/// HTTPS requests are tunneled through the proxy with `CONNECT`.
+	pub proxy: Option<String>,
+	/// PEM files containing root certificates trusted in addition to the ones of the operating
+	/// system.
+	pub ca_certificates: Vec<PathBuf>,
+}
+
 /// Asynchronous offchain API.
 ///
 /// NOTE this is done to prevent recursive calls into the runtime (which are not supported currently).
@@ -59,6 +76,19 @@ pub(crate) struct Api<Storage> {
 	is_validator: bool,
 	/// Everything HTTP-related is handled by a different struct.
 	http: http::HttpApi,
+	/// Time at which the offchain worker times out, if it has a timeout. No call blocks past it.
+	deadline: Option<Timestamp>,
+}
+
+impl<Storage> Api<Storage> {
+	/// Restricts a deadline passed by the runtime to the deadline of the worker.
+	fn clamp_deadline(&self, deadline: Option<Timestamp>) -> Option<Timestamp> {
+		match (deadline, self.deadline) {
+			(Some(deadline), Some(worker_deadline)) => Some(deadline.min(worker_deadline)),
+			(deadline, None) => deadline,
+			(None, worker_deadline) => worker_deadline,
+		}
+	}
 }
 
 fn unavailable_yet<R: Default>(name: &str) -> R {
@@ -91,6 +121,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	}
 
 	fn sleep_until(&mut self, deadline: Timestamp) {
+		let deadline = self.clamp_deadline(Some(deadline)).unwrap_or(deadline);
 		sleep(timestamp::timestamp_from_now(deadline));
 	}
 
@@ -134,13 +165,21 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		}
 	}
 
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError> {
+		self.db.indexed_get(at, key)
+	}
+
 	fn http_request_start(
 		&mut self,
 		method: &str,
 		uri: &str,
-		_meta: &[u8]
+		meta: &[u8]
 	) -> Result<HttpRequestId, ()> {
-		self.http.request_start(method, uri)
+		self.http.request_start(method, uri, meta)
 	}
 
 	fn http_request_add_header(
@@ -158,7 +197,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		chunk: &[u8],
 		deadline: Option<Timestamp>
 	) -> Result<(), HttpError> {
-		self.http.request_write_body(request_id, chunk, deadline)
+		self.http.request_write_body(request_id, chunk, self.clamp_deadline(deadline))
 	}
 
 	fn http_response_wait(
@@ -166,7 +205,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		ids: &[HttpRequestId],
 		deadline: Option<Timestamp>
 	) -> Vec<HttpRequestStatus> {
-		self.http.response_wait(ids, deadline)
+		self.http.response_wait(ids, self.clamp_deadline(deadline))
 	}
 
 	fn http_response_headers(
@@ -182,7 +221,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		buffer: &mut [u8],
 		deadline: Option<Timestamp>
 	) -> Result<usize, HttpError> {
-		self.http.response_read_body(request_id, buffer, deadline)
+		self.http.response_read_body(request_id, buffer, self.clamp_deadline(deadline))
 	}
 
 	fn set_authorized_nodes(&mut self, nodes: Vec<OpaquePeerId>, authorized_only: bool) {
@@ -270,19 +309,26 @@ pub(crate) struct AsyncApi {
 
 impl AsyncApi {
 	/// Creates new Offchain extensions API implementation an the asynchronous processing part.
+	///
+	/// If a `timeout` is passed, the calls of the API stop blocking once it has elapsed.
 	pub fn new<S: OffchainStorage>(
 		db: S,
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
 		shared_client: SharedClient,
+		timeout: Option<Duration>,
 	) -> (Api<S>, Self) {
 		let (http_api, http_worker) = http::http(shared_client);
 
+		let deadline = timeout.map(|timeout| {
+			timestamp::now().add(OffchainDuration::from_millis(timeout.as_millis() as u64))
+		});
 		let api = Api {
 			db,
 			network_provider,
 			is_validator,
 			http: http_api,
+			deadline,
 		};
 
 		let async_api = Self {
@@ -340,6 +386,7 @@ mod tests {
 			mock,
 			false,
 			shared_client,
+			None,
 		)
 	}
 
@@ -377,6 +424,28 @@ mod tests {
 		assert!(new_now.unix_millis() - 100 >= now.unix_millis());
 	}
 
+	#[test]
+	fn should_not_block_past_the_worker_timeout() {
+		let db = LocalStorage::new_test();
+		let (mut api, _) = AsyncApi::new(
+			db,
+			Arc::new(TestNetwork()),
+			false,
+			SharedClient::new(),
+			Some(Duration::from_millis(100)),
+		);
+
+		// Arrange.
+		let now = api.timestamp();
+		let deadline = now.add(sp_core::offchain::Duration::from_millis(60_000));
+
+		// Act.
+		api.sleep_until(deadline);
+
+		// Assert.
+		assert!(api.timestamp().unix_millis() < now.unix_millis() + 10_000);
+	}
+
 	#[test]
 	fn should_set_and_get_local_storage() {
 		// given
@@ -27,28 +27,80 @@
 //! (i.e.: the socket should continue being processed) in the background even if the runtime isn't
 //! actively calling any function.
 
-use crate::api::timestamp;
+use crate::api::{timestamp, HttpConfig, http_proxy::ProxyConnector};
 use bytes::buf::ext::{Reader, BufExt};
 use fnv::FnvHashMap;
 use futures::{prelude::*, future, channel::mpsc};
-use log::error;
-use sp_core::offchain::{HttpRequestId, Timestamp, HttpRequestStatus, HttpError};
-use std::{convert::TryFrom, fmt, io::Read as _, pin::Pin, task::{Context, Poll}};
+use futures_timer::Delay;
+use log::{error, warn};
+use sp_core::offchain::{HttpRequestId, HttpRequestMeta, Timestamp, HttpRequestStatus, HttpError};
+use std::{
+	convert::TryFrom, fmt, fs, io::{self, Read as _}, pin::Pin, task::{Context, Poll}, time::Duration,
+};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 use std::sync::Arc;
-use hyper::{Client as HyperClient, Body, client};
+use hyper::{Client as HyperClient, Body};
 use hyper_rustls::HttpsConnector;
 
 /// Wrapper struct used for keeping the hyper_rustls client running.
 #[derive(Clone)]
-pub struct SharedClient(Arc<HyperClient<HttpsConnector<client::HttpConnector>, Body>>);
+pub struct SharedClient(Arc<HyperClient<HttpsConnector<ProxyConnector>, Body>>);
 
 impl SharedClient {
+	/// Creates a client connecting directly to the servers and trusting the root certificates
+	/// of the operating system.
 	pub fn new() -> Self {
-		Self(Arc::new(HyperClient::builder().build(HttpsConnector::new())))
+		Self::with_config(&HttpConfig::default())
+			.expect("Failed to load the root certificates of the operating system")
+	}
+
+	/// Creates a client following the given configuration.
+	///
+	/// Returns an error if the proxy URI is invalid or if the certificates can't be loaded.
+	pub fn with_config(config: &HttpConfig) -> io::Result<Self> {
+		let proxy = match &config.proxy {
+			Some(proxy) => Some(ProxyConnector::parse_proxy(proxy)?),
+			None => None,
+		};
+
+		let tls_config = tls_config(&config.ca_certificates)?;
+		let connector = HttpsConnector::from((ProxyConnector::new(proxy), tls_config));
+		Ok(Self(Arc::new(HyperClient::builder().build(connector))))
 	}
 }
 
+/// Builds the TLS configuration of the client, trusting the root certificates of the operating
+/// system as well as the ones found in the given PEM files.
+fn tls_config(ca_certificates: &[std::path::PathBuf]) -> io::Result<rustls::ClientConfig> {
+	let mut config = rustls::ClientConfig::new();
+	config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	config.root_store = match rustls_native_certs::load_native_certs() {
+		Ok(store) => store,
+		Err((Some(store), err)) => {
+			warn!("Could not load all the root certificates of the operating system: {}", err);
+			store
+		},
+		Err((None, err)) => return Err(err),
+	};
+
+	for path in ca_certificates {
+		let mut file = io::BufReader::new(fs::File::open(path)?);
+		match config.root_store.add_pem_file(&mut file) {
+			Ok((valid, _)) if valid > 0 => {},
+			_ => return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("No valid certificate found in {}", path.display()),
+			)),
+		}
+	}
+
+	if config.root_store.is_empty() {
+		return Err(io::Error::new(io::ErrorKind::NotFound, "No root certificate found"));
+	}
+
+	Ok(config)
+}
+
 /// Creates a pair of [`HttpApi`] and [`HttpWorker`].
 pub fn http(shared_client: SharedClient) -> (HttpApi, HttpWorker) {
 	let (to_worker, from_api) = tracing_unbounded("mpsc_ocw_to_worker");
@@ -101,7 +153,7 @@ enum HttpApiRequest {
 	/// A request has been dispatched but the worker notified us of an error. We report this
 	/// failure to the user as an `IoError` and remove the request from the list as soon as
 	/// possible.
-	Fail(hyper::Error),
+	Fail(HttpWorkerFailure),
 }
 
 /// A request within `HttpApi` that has received a response.
@@ -130,15 +182,22 @@ impl HttpApi {
 	pub fn request_start(
 		&mut self,
 		method: &str,
-		uri: &str
+		uri: &str,
+		meta: &[u8],
 	) -> Result<HttpRequestId, ()> {
 		// Start by building the prototype of the request.
 		// We do this first so that we don't touch anything in `self` if building the prototype
 		// fails.
+		let meta = HttpRequestMeta::decode_meta(meta).map_err(|_| ())?;
 		let (body_sender, body) = hyper::Body::channel();
 		let mut request = hyper::Request::new(body);
 		*request.method_mut() = hyper::Method::from_bytes(method.as_bytes()).map_err(|_| ())?;
 		*request.uri_mut() = hyper::Uri::from_maybe_shared(uri.to_owned()).map_err(|_| ())?;
+		if let Some(timeout) = meta.timeout {
+			// The request itself is what is sent to the worker, so that's where we store the
+			// timeout.
+			request.extensions_mut().insert(RequestTimeout(Duration::from_millis(timeout.millis())));
+		}
 
 		let new_id = self.next_id;
 		debug_assert!(!self.requests.contains_key(&new_id));
@@ -520,6 +579,19 @@ impl fmt::Debug for HttpApiRequest {
 	}
 }
 
+/// Timeout of a request, stored in the extensions of the `hyper::Request`.
+#[derive(Debug, Clone, Copy)]
+struct RequestTimeout(Duration);
+
+/// Reason why the worker has given up on a request.
+#[derive(Debug)]
+enum HttpWorkerFailure {
+	/// The HTTP client has reported an error.
+	Hyper(hyper::Error),
+	/// No response has been received before the timeout of the request.
+	Timeout,
+}
+
 /// Message send from the API to the worker.
 enum ApiToWorker {
 	/// Dispatches a new HTTP request.
@@ -555,7 +627,7 @@ enum WorkerToApi {
 		/// The ID that was passed to the worker.
 		id: HttpRequestId,
 		/// Error that happened.
-		error: hyper::Error,
+		error: HttpWorkerFailure,
 	},
 }
 
@@ -566,7 +638,7 @@ pub struct HttpWorker {
 	/// Used to receive messages from the `HttpApi`.
 	from_api: TracingUnboundedReceiver<ApiToWorker>,
 	/// The engine that runs HTTP requests.
-	http_client: Arc<HyperClient<HttpsConnector<client::HttpConnector>, Body>>,
+	http_client: Arc<HyperClient<HttpsConnector<ProxyConnector>, Body>>,
 	/// HTTP requests that are being worked on by the engine.
 	requests: Vec<(HttpRequestId, HttpWorkerRequest)>,
 }
@@ -574,7 +646,12 @@ pub struct HttpWorker {
 /// HTTP request being processed by the worker.
 enum HttpWorkerRequest {
 	/// Request has been dispatched and is waiting for a response from the Internet.
-	Dispatched(hyper::client::ResponseFuture),
+	Dispatched {
+		/// Resolves to the response, once its headers have been received.
+		future: hyper::client::ResponseFuture,
+		/// Fires when the request times out, if it has a timeout.
+		timeout: Option<Delay>,
+	},
 	/// Progressively reading the body of the response and sending it to the channel.
 	ReadBody {
 		/// Body to read `Chunk`s from. Only used if the channel is ready to accept data.
@@ -598,15 +675,23 @@ impl Future for HttpWorker {
 		for n in (0..me.requests.len()).rev() {
 			let (id, request) = me.requests.swap_remove(n);
 			match request {
-				HttpWorkerRequest::Dispatched(mut future) => {
+				HttpWorkerRequest::Dispatched { mut future, mut timeout } => {
 					// Check for an HTTP response from the Internet.
 					let response = match Future::poll(Pin::new(&mut future), cx) {
 						Poll::Pending => {
-							me.requests.push((id, HttpWorkerRequest::Dispatched(future)));
+							let timed_out = timeout.as_mut()
+								.map_or(false, |timeout| Future::poll(Pin::new(timeout), cx).is_ready());
+							if timed_out {
+								let error = HttpWorkerFailure::Timeout;
+								let _ = me.to_api.unbounded_send(WorkerToApi::Fail { id, error });
+								continue;		// don't insert the request back
+							}
+							me.requests.push((id, HttpWorkerRequest::Dispatched { future, timeout }));
 							continue
 						},
 						Poll::Ready(Ok(response)) => response,
 						Poll::Ready(Err(error)) => {
+							let error = HttpWorkerFailure::Hyper(error);
 							let _ = me.to_api.unbounded_send(WorkerToApi::Fail { id, error });
 							continue;		// don't insert the request back
 						}
@@ -666,9 +751,11 @@ impl Future for HttpWorker {
 			Poll::Pending => {},
 			Poll::Ready(None) => return Poll::Ready(()),	// stops the worker
 			Poll::Ready(Some(ApiToWorker::Dispatch { id, request })) => {
+				let timeout = request.extensions().get::<RequestTimeout>()
+					.map(|RequestTimeout(timeout)| Delay::new(*timeout));
 				let future = me.http_client.request(request);
 				debug_assert!(me.requests.iter().all(|(i, _)| *i != id));
-				me.requests.push((id, HttpWorkerRequest::Dispatched(future)));
+				me.requests.push((id, HttpWorkerRequest::Dispatched { future, timeout }));
 				cx.waker().wake_by_ref();	// reschedule the task to poll the request
 			}
 		}
@@ -688,7 +775,7 @@ impl fmt::Debug for HttpWorker {
 impl fmt::Debug for HttpWorkerRequest {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
-			HttpWorkerRequest::Dispatched(_) =>
+			HttpWorkerRequest::Dispatched { .. } =>
 				f.debug_tuple("HttpWorkerRequest::Dispatched").finish(),
 			HttpWorkerRequest::ReadBody { .. } =>
 				f.debug_tuple("HttpWorkerRequest::Response").finish(),
@@ -699,11 +786,17 @@ impl fmt::Debug for HttpWorkerRequest {
 #[cfg(test)]
 mod tests {
 	use core::convert::Infallible;
-	use crate::api::timestamp;
+	use crate::api::{timestamp, HttpConfig};
 	use super::{http, SharedClient};
-	use sp_core::offchain::{HttpError, HttpRequestId, HttpRequestStatus, Duration};
+	use codec::Encode;
+	use sp_core::offchain::{
+		HttpError, HttpRequestId, HttpRequestMeta, HttpRequestStatus, Duration, Timestamp,
+	};
 	use futures::future;
 	use lazy_static::lazy_static;
+	use parking_lot::Mutex;
+	use std::{io::Write as _, net::SocketAddr, sync::Arc};
+	use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
 	
 	// Using lazy_static to avoid spawning lots of different SharedClients,
 	// as spawning a SharedClient is CPU-intensive and opens lots of fds.
@@ -711,12 +804,29 @@ mod tests {
 		static ref SHARED_CLIENT: SharedClient = SharedClient::new();
 	}
 
+	// Answers the requests received by the test server, depending on their path.
+	async fn mock_server(
+		request: hyper::Request<hyper::Body>,
+	) -> Result<hyper::Response<hyper::Body>, Infallible> {
+		let body = match request.uri().path() {
+			// Waits before answering, in order to test the timeouts.
+			"/slow" => {
+				futures_timer::Delay::new(std::time::Duration::from_secs(10)).await;
+				"Hello World!".to_owned()
+			},
+			// Echoes the URI of the request, which is only absolute when sent to a proxy.
+			"/uri" => request.uri().to_string(),
+			_ => "Hello World!".to_owned(),
+		};
+		Ok(hyper::Response::new(hyper::Body::from(body)))
+	}
+
 	// Returns an `HttpApi` whose worker is ran in the background, and a `SocketAddr` to an HTTP
 	// server that runs in the background as well.
 	macro_rules! build_api_server {
-		() => {{
-			let hyper_client = SHARED_CLIENT.clone();
-			let (api, worker) = http(hyper_client.clone());
+		() => { build_api_server!(SHARED_CLIENT.clone()) };
+		($hyper_client:expr) => {{
+			let (api, worker) = http($hyper_client);
 
 			let (addr_tx, addr_rx) = std::sync::mpsc::channel();
 			std::thread::spawn(move || {
@@ -725,11 +835,7 @@ mod tests {
 				let server = rt.spawn(async move {
 					let server = hyper::Server::bind(&"127.0.0.1:0".parse().unwrap())
 						.serve(hyper::service::make_service_fn(|_| { async move {
-							Ok::<_, Infallible>(hyper::service::service_fn(move |_req| async move {
-								Ok::<_, Infallible>(
-									hyper::Response::new(hyper::Body::from("Hello World!"))
-								)
-							}))
+							Ok::<_, Infallible>(hyper::service::service_fn(mock_server))
 						}}));
 					let _ = addr_tx.send(server.local_addr());
 					server.await.map_err(drop)
@@ -740,6 +846,107 @@ mod tests {
 		}};
 	}
 
+	// Starts an HTTPS server answering with `mock_server`, with a certificate for `localhost`
+	// issued by a self-signed CA. Returns the address of the server and the PEM file of the CA.
+	fn build_tls_server() -> (SocketAddr, tempfile::NamedTempFile) {
+		let mut ca_params = rcgen::CertificateParams::new(Vec::new());
+		ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+		let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+		let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+		let mut ca_file = tempfile::NamedTempFile::new().unwrap();
+		ca_file.write_all(ca.serialize_pem().unwrap().as_bytes()).unwrap();
+
+		let mut tls_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+		tls_config.set_single_cert(
+			vec![rustls::Certificate(cert.serialize_der_with_signer(&ca).unwrap())],
+			rustls::PrivateKey(cert.serialize_private_key_der()),
+		).unwrap();
+		let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(tls_config));
+
+		let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+		std::thread::spawn(move || {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			rt.block_on(async move {
+				let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+				let _ = addr_tx.send(listener.local_addr().unwrap());
+				while let Ok((stream, _)) = listener.accept().await {
+					let acceptor = acceptor.clone();
+					tokio::spawn(async move {
+						if let Ok(stream) = acceptor.accept(stream).await {
+							let service = hyper::service::service_fn(mock_server);
+							let _ = hyper::server::conn::Http::new()
+								.serve_connection(stream, service)
+								.await;
+						}
+					});
+				}
+			});
+		});
+		(addr_rx.recv().unwrap(), ca_file)
+	}
+
+	// Starts a proxy only supporting `CONNECT`. Returns the address of the proxy and the targets
+	// of the tunnels it opened.
+	fn build_connect_proxy() -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+		let targets = Arc::new(Mutex::new(Vec::new()));
+		let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+		let proxy_targets = targets.clone();
+		std::thread::spawn(move || {
+			let mut rt = tokio::runtime::Runtime::new().unwrap();
+			rt.block_on(async move {
+				let mut listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+				let _ = addr_tx.send(listener.local_addr().unwrap());
+				while let Ok((mut stream, _)) = listener.accept().await {
+					let targets = proxy_targets.clone();
+					tokio::spawn(async move {
+						let mut head = Vec::new();
+						while !head.ends_with(b"\r\n\r\n") {
+							let mut byte = [0u8];
+							if stream.read(&mut byte).await.unwrap_or(0) == 0 {
+								return
+							}
+							head.push(byte[0]);
+						}
+						let head = String::from_utf8_lossy(&head).into_owned();
+						let target = match head.strip_prefix("CONNECT ") {
+							Some(rest) => rest.split(' ').next().unwrap_or_default().to_owned(),
+							None => return,
+						};
+						targets.lock().push(target.clone());
+
+						let server = match tokio::net::TcpStream::connect(&target[..]).await {
+							Ok(server) => server,
+							Err(_) => return,
+						};
+						let response = b"HTTP/1.1 200 Connection established\r\n\r\n";
+						if stream.write_all(response).await.is_err() {
+							return
+						}
+						let (mut client_read, mut client_write) = tokio::io::split(stream);
+						let (mut server_read, mut server_write) = tokio::io::split(server);
+						let _ = future::join(
+							tokio::io::copy(&mut client_read, &mut server_write),
+							tokio::io::copy(&mut server_read, &mut client_write),
+						).await;
+					});
+				}
+			});
+		});
+		(addr_rx.recv().unwrap(), targets)
+	}
+
+	// Reads the whole body of the response to the given request.
+	fn read_body(api: &mut super::HttpApi, id: HttpRequestId, deadline: Timestamp) -> Vec<u8> {
+		let mut body = Vec::new();
+		let mut buf = [0; 512];
+		loop {
+			match api.response_read_body(id, &mut buf, Some(deadline)).unwrap() {
+				0 => return body,
+				n => body.extend_from_slice(&buf[..n]),
+			}
+		}
+	}
+
 	#[test]
 	fn basic_localhost() {
 		let deadline = timestamp::now().add(Duration::from_millis(10_000));
@@ -748,7 +955,7 @@ mod tests {
 
 		let (mut api, addr) = build_api_server!();
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[], Some(deadline)).unwrap();
 
 		match api.response_wait(&[id], Some(deadline))[0] {
@@ -764,16 +971,126 @@ mod tests {
 		assert_eq!(&buf[..n], b"Hello World!");
 	}
 
+	#[test]
+	fn request_timeout() {
+		let deadline = timestamp::now().add(Duration::from_millis(5_000));
+		let (mut api, addr) = build_api_server!();
+
+		let meta = HttpRequestMeta { timeout: Some(Duration::from_millis(100)) }.encode();
+		let slow = api.request_start("GET", &format!("http://{}/slow", addr), &meta).unwrap();
+		let fast = api.request_start("GET", &format!("http://{}", addr), &meta).unwrap();
+
+		// The timeout aborts the request long before the deadline.
+		assert_eq!(
+			api.response_wait(&[slow, fast], Some(deadline)),
+			vec![HttpRequestStatus::IoError, HttpRequestStatus::Finished(200)],
+		);
+		assert!(timestamp::now() < deadline);
+		assert_eq!(read_body(&mut api, fast, deadline), b"Hello World!");
+	}
+
+	#[test]
+	fn request_through_proxy() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+
+		// The test server acts as a proxy: requests are sent to it whatever their host.
+		let (_, proxy_addr) = build_api_server!();
+		let config = HttpConfig {
+			proxy: Some(format!("http://{}", proxy_addr)),
+			..Default::default()
+		};
+		let (mut api, _) = build_api_server!(SharedClient::with_config(&config).unwrap());
+
+		let id = api.request_start("GET", "http://example.invalid/uri", &[]).unwrap();
+		match api.response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => {},
+			v => panic!("Connecting through the proxy failed: {:?}", v)
+		}
+		assert_eq!(read_body(&mut api, id, deadline), b"http://example.invalid/uri");
+
+		// Without proxy, the server only sees the path.
+		let (mut api, addr) = build_api_server!();
+		let id = api.request_start("GET", &format!("http://{}/uri", addr), &[]).unwrap();
+		assert_eq!(read_body(&mut api, id, deadline), b"/uri");
+	}
+
+	#[test]
+	fn https_request_with_custom_ca() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let (server_addr, ca_file) = build_tls_server();
+		let uri = format!("https://localhost:{}/uri", server_addr.port());
+
+		// The certificate of the server is only trusted once the CA is configured.
+		let (mut api, _) = build_api_server!();
+		let id = api.request_start("GET", &uri, &[]).unwrap();
+		assert_eq!(api.response_wait(&[id], Some(deadline)), vec![HttpRequestStatus::IoError]);
+
+		let config = HttpConfig {
+			ca_certificates: vec![ca_file.path().into()],
+			..Default::default()
+		};
+		let (mut api, _) = build_api_server!(SharedClient::with_config(&config).unwrap());
+		let id = api.request_start("GET", &uri, &[]).unwrap();
+		match api.response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => {},
+			v => panic!("Connecting to the TLS server failed: {:?}", v)
+		}
+		assert_eq!(read_body(&mut api, id, deadline), b"/uri");
+	}
+
+	#[test]
+	fn https_request_through_proxy() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let (server_addr, ca_file) = build_tls_server();
+		let (proxy_addr, targets) = build_connect_proxy();
+		let config = HttpConfig {
+			proxy: Some(format!("http://{}", proxy_addr)),
+			ca_certificates: vec![ca_file.path().into()],
+		};
+		let (mut api, _) = build_api_server!(SharedClient::with_config(&config).unwrap());
+
+		// The TLS session is established with the server through the tunnel, which the server
+		// can't tell apart from a direct connection.
+		let uri = format!("https://localhost:{}/uri", server_addr.port());
+		let id = api.request_start("GET", &uri, &[]).unwrap();
+		match api.response_wait(&[id], Some(deadline))[0] {
+			HttpRequestStatus::Finished(200) => {},
+			v => panic!("Connecting through the proxy failed: {:?}", v)
+		}
+		assert_eq!(read_body(&mut api, id, deadline), b"/uri");
+		assert_eq!(*targets.lock(), vec![format!("localhost:{}", server_addr.port())]);
+	}
+
+	#[test]
+	fn invalid_http_config() {
+		let invalid_proxy = HttpConfig {
+			proxy: Some("socks5://127.0.0.1:1080".into()),
+			..Default::default()
+		};
+		assert!(SharedClient::with_config(&invalid_proxy).is_err());
+
+		let missing_certificate = HttpConfig {
+			ca_certificates: vec!["/this/file/does/not/exist.pem".into()],
+			..Default::default()
+		};
+		assert!(SharedClient::with_config(&missing_certificate).is_err());
+	}
+
 	#[test]
 	fn request_start_invalid_call() {
 		let (mut api, addr) = build_api_server!();
 
-		match api.request_start("\0", &format!("http://{}", addr)) {
+		match api.request_start("\0", &format!("http://{}", addr), &[]) {
+			Err(()) => {}
+			Ok(_) => panic!()
+		};
+
+		match api.request_start("GET", "http://\0localhost", &[]) {
 			Err(()) => {}
 			Ok(_) => panic!()
 		};
 
-		match api.request_start("GET", "http://\0localhost") {
+		match api.request_start("GET", &format!("http://{}", addr), &[0xff]) {
 			Err(()) => {}
 			Ok(_) => panic!()
 		};
@@ -788,19 +1105,19 @@ mod tests {
 			Ok(_) => panic!()
 		};
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		match api.request_add_header(id, "\0", "bar") {
 			Err(()) => {}
 			Ok(_) => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		match api.request_add_header(id, "Foo", "\0") {
 			Err(()) => {}
 			Ok(_) => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_add_header(id, "Foo", "Bar").unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		match api.request_add_header(id, "Foo2", "Bar") {
@@ -808,14 +1125,14 @@ mod tests {
 			Ok(_) => panic!()
 		};
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		api.response_headers(id);
 		match api.request_add_header(id, "Foo2", "Bar") {
 			Err(()) => {}
 			Ok(_) => panic!()
 		};
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		api.response_read_body(id, &mut [], None).unwrap();
 		match api.request_add_header(id, "Foo2", "Bar") {
 			Err(()) => {}
@@ -837,7 +1154,7 @@ mod tests {
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.request_write_body(id, &[], None).unwrap();
@@ -846,7 +1163,7 @@ mod tests {
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.request_write_body(id, &[], None).unwrap();
@@ -855,7 +1172,7 @@ mod tests {
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.response_wait(&[id], None);
 		match api.request_write_body(id, &[], None) {
@@ -863,7 +1180,7 @@ mod tests {
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[1, 2, 3, 4], None).unwrap();
 		api.response_wait(&[id], None);
 		match api.request_write_body(id, &[1, 2, 3, 4], None) {
@@ -871,28 +1188,28 @@ mod tests {
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.response_headers(id);
 		match api.request_write_body(id, &[1, 2, 3, 4], None) {
 			Err(HttpError::Invalid) => {}
 			_ => panic!()
 		};
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		api.response_headers(id);
 		match api.request_write_body(id, &[], None) {
 			Err(HttpError::Invalid) => {}
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.response_read_body(id, &mut [], None).unwrap();
 		match api.request_write_body(id, &[1, 2, 3, 4], None) {
 			Err(HttpError::Invalid) => {}
 			_ => panic!()
 		};
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.response_read_body(id, &mut [], None).unwrap();
 		match api.request_write_body(id, &[], None) {
 			Err(HttpError::Invalid) => {}
@@ -905,20 +1222,20 @@ mod tests {
 		let (mut api, addr) = build_api_server!();
 		assert_eq!(api.response_headers(HttpRequestId(0xdead)), &[]);
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		assert_eq!(api.response_headers(id), &[]);
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_write_body(id, &[], None).unwrap();
 		while api.response_headers(id).is_empty() {
 			std::thread::sleep(std::time::Duration::from_millis(100));
 		}
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		api.response_wait(&[id], None);
 		assert_ne!(api.response_headers(id), &[]);
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		let mut buf = [0; 128];
 		while api.response_read_body(id, &mut buf, None).unwrap() != 0 {}
 		assert_eq!(api.response_headers(id), &[]);
@@ -928,14 +1245,14 @@ mod tests {
 	fn response_header_invalid_call() {
 		let (mut api, addr) = build_api_server!();
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		assert_eq!(api.response_headers(id), &[]);
 
-		let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 		api.request_add_header(id, "Foo", "Bar").unwrap();
 		assert_eq!(api.response_headers(id), &[]);
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		api.request_add_header(id, "Foo", "Bar").unwrap();
 		api.request_write_body(id, &[], None).unwrap();
 		// Note: this test actually sends out the request, and is supposed to test a situation
@@ -955,7 +1272,7 @@ mod tests {
 			_ => panic!()
 		}
 
-		let id = api.request_start("GET", &format!("http://{}", addr)).unwrap();
+		let id = api.request_start("GET", &format!("http://{}", addr), &[]).unwrap();
 		while api.response_read_body(id, &mut buf, None).unwrap() != 0 {}
 		match api.response_read_body(id, &mut buf, None) {
 			Err(HttpError::Invalid) => {}
@@ -972,7 +1289,7 @@ mod tests {
 		let (mut api, addr) = build_api_server!();
 
 		for _ in 0..50 {
-			let id = api.request_start("POST", &format!("http://{}", addr)).unwrap();
+			let id = api.request_start("POST", &format!("http://{}", addr), &[]).unwrap();
 
 			for _ in 0..250 {
 				match rand::random::<u8>() % 6 {
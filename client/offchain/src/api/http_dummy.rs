@@ -18,6 +18,7 @@
 
 //! Contains the same API as the `http` module, except that everything returns an error.
 
+use crate::api::HttpConfig;
 use sp_core::offchain::{HttpRequestId, Timestamp, HttpRequestStatus, HttpError};
 use std::{future::Future, pin::Pin, task::Context, task::Poll};
 
@@ -29,6 +30,10 @@ impl SharedClient {
 	pub fn new() -> Self {
 		Self
 	}
+
+	pub fn with_config(_: &HttpConfig) -> std::io::Result<Self> {
+		Ok(Self)
+	}
 }
 
 /// Creates a pair of [`HttpApi`] and [`HttpWorker`].
@@ -49,7 +54,8 @@ impl HttpApi {
 	pub fn request_start(
 		&mut self,
 		_: &str,
-		_: &str
+		_: &str,
+		_: &[u8],
 	) -> Result<HttpRequestId, ()> {
 		/// Because this always returns an error, none of the other methods should ever be called.
 		Err(())
@@ -0,0 +1,172 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Connector of the HTTP client of the offchain workers, optionally going through an HTTP proxy.
+//!
+//! When a proxy is configured, every connection is opened to the proxy instead of the server.
+//! Plain HTTP requests are then sent to the proxy as is, with their absolute URI, while a tunnel
+//! to the server is opened with `CONNECT` for HTTPS requests, so that the TLS session is
+//! established end-to-end with the server.
+
+use futures::prelude::*;
+use hyper::{
+	Uri,
+	client::{HttpConnector, connect::{Connected, Connection}},
+	http::uri::Scheme,
+	service::Service,
+};
+use std::{error, io, pin::Pin, task::{Context, Poll}};
+use tokio::{io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _}, net::TcpStream};
+
+/// Maximum size of the head of the response of the proxy to a `CONNECT` request.
+const MAX_CONNECT_RESPONSE_SIZE: usize = 8 * 1024;
+
+/// Opens the TCP connections of the HTTP client.
+///
+/// `https` URIs must be handled by a TLS connector wrapping this one.
+#[derive(Clone)]
+pub struct ProxyConnector {
+	inner: HttpConnector,
+	proxy: Option<Uri>,
+}
+
+impl ProxyConnector {
+	/// Creates a connector going through the given proxy, if any.
+	pub fn new(proxy: Option<Uri>) -> Self {
+		let mut inner = HttpConnector::new();
+		inner.enforce_http(false);
+		ProxyConnector { inner, proxy }
+	}
+
+	/// Parses the URI of a proxy. Only HTTP proxies are supported.
+	pub fn parse_proxy(proxy: &str) -> io::Result<Uri> {
+		let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+
+		let uri = proxy.parse::<Uri>()
+			.map_err(|err| invalid(format!("Invalid proxy URI {}: {}", proxy, err)))?;
+		if uri.scheme() != Some(&Scheme::HTTP) || uri.host().is_none() {
+			return Err(invalid(format!("Proxy URI must be of the form http://host:port: {}", proxy)));
+		}
+
+		Ok(uri)
+	}
+}
+
+impl Service<Uri> for ProxyConnector {
+	type Response = ProxyStream;
+	type Error = Box<dyn error::Error + Send + Sync>;
+	type Future = Pin<Box<dyn Future<Output = Result<ProxyStream, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, dst: Uri) -> Self::Future {
+		let proxy = match &self.proxy {
+			Some(proxy) => proxy.clone(),
+			None => {
+				let connecting = self.inner.call(dst);
+				return Box::pin(async move {
+					Ok(ProxyStream { inner: connecting.await?, proxied: false })
+				})
+			},
+		};
+
+		let connecting = self.inner.call(proxy);
+		Box::pin(async move {
+			let mut stream = connecting.await?;
+			if dst.scheme() == Some(&Scheme::HTTPS) {
+				tunnel(&mut stream, &dst).await?;
+				// Through the tunnel, we talk to the server as if we were directly connected.
+				Ok(ProxyStream { inner: stream, proxied: false })
+			} else {
+				Ok(ProxyStream { inner: stream, proxied: true })
+			}
+		})
+	}
+}
+
+/// Asks the proxy at the other end of `stream` to open a tunnel to the host of `dst`.
+async fn tunnel(stream: &mut TcpStream, dst: &Uri) -> io::Result<()> {
+	let host = dst.host()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URI without host"))?;
+	let authority = format!("{}:{}", host, dst.port_u16().unwrap_or(443));
+	let request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", authority);
+	stream.write_all(request.as_bytes()).await?;
+
+	// The head of the response is read byte by byte, in order to not consume anything past it.
+	let mut head = Vec::new();
+	while !head.ends_with(b"\r\n\r\n") {
+		if head.len() >= MAX_CONNECT_RESPONSE_SIZE {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "Proxy response is too large"));
+		}
+
+		let mut byte = [0u8];
+		if stream.read(&mut byte).await? == 0 {
+			return Err(io::ErrorKind::UnexpectedEof.into());
+		}
+		head.push(byte[0]);
+	}
+
+	let status_line = head.split(|b| *b == b'\r').next().unwrap_or_default();
+	let mut parts = status_line.split(|b| *b == b' ');
+	match (parts.next(), parts.next()) {
+		(Some(b"HTTP/1.1"), Some(b"200")) | (Some(b"HTTP/1.0"), Some(b"200")) => Ok(()),
+		_ => Err(io::Error::new(
+			io::ErrorKind::Other,
+			format!("Proxy refused to connect to {}: {}", authority, String::from_utf8_lossy(status_line)),
+		)),
+	}
+}
+
+/// Connection opened by the [`ProxyConnector`].
+pub struct ProxyStream {
+	inner: TcpStream,
+	/// True if the requests must be sent to the proxy with their absolute URI.
+	proxied: bool,
+}
+
+impl Connection for ProxyStream {
+	fn connected(&self) -> Connected {
+		self.inner.connected().proxy(self.proxied)
+	}
+}
+
+impl AsyncRead for ProxyStream {
+	fn poll_read(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context,
+		buf: &mut [u8],
+	) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.inner).poll_read(cx, buf)
+	}
+}
+
+impl AsyncWrite for ProxyStream {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.inner).poll_write(cx, buf)
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.inner).poll_flush(cx)
+	}
+
+	fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.inner).poll_shutdown(cx)
+	}
+}
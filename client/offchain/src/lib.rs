@@ -36,8 +36,8 @@
 #![warn(missing_docs)]
 
 use std::{
-	fmt, marker::PhantomData, sync::Arc,
-	collections::HashSet,
+	fmt, marker::PhantomData, sync::{Arc, atomic::{AtomicUsize, Ordering}},
+	collections::HashSet, time::Duration,
 };
 
 use parking_lot::Mutex;
@@ -53,6 +53,8 @@ use futures::{prelude::*, future::ready};
 mod api;
 use api::SharedClient;
 
+pub use api::HttpConfig;
+
 pub use sp_offchain::{OffchainWorkerApi, STORAGE_PREFIX};
 
 /// NetworkProvider provides [`OffchainWorkers`] with all necessary hooks into the
@@ -63,6 +65,13 @@ pub trait NetworkProvider: NetworkStateInfo {
 
 	/// Set the authorized only flag.
 	fn set_authorized_only(&self, reserved_only: bool);
+
+	/// Returns true if the node is catching up with the rest of the network.
+	///
+	/// Defaults to `false`, in which case offchain workers are never skipped.
+	fn is_major_syncing(&self) -> bool {
+		false
+	}
 }
 
 impl<B, H> NetworkProvider for NetworkService<B, H>
@@ -77,6 +86,49 @@ where
 	fn set_authorized_only(&self, reserved_only: bool) {
 		self.set_authorized_only(reserved_only)
 	}
+
+	fn is_major_syncing(&self) -> bool {
+		self.is_major_syncing()
+	}
+}
+
+/// Blocks the offchain workers are run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkerTrigger {
+	/// Every block that becomes the new best block when imported.
+	NewBest,
+	/// Every block that is finalized. Blocks finalized together only trigger a single run, on
+	/// the last of them.
+	Finalized,
+}
+
+/// Policy deciding when offchain workers are run.
+#[derive(Debug, Clone)]
+pub struct SchedulingPolicy {
+	/// Blocks the offchain workers are run on.
+	pub trigger: WorkerTrigger,
+	/// If true, the offchain workers aren't run while the node is catching up with the network.
+	/// They are run on every block by default.
+	pub skip_while_major_syncing: bool,
+	/// Maximum number of offchain workers running at the same time, if any. Blocks imported
+	/// while that many workers are running are skipped.
+	pub max_concurrent_workers: Option<usize>,
+	/// Maximum duration of the run of the offchain workers of a block, if any.
+	///
+	/// Once it has elapsed, the calls to the offchain API stop blocking and the HTTP requests
+	/// are aborted. The execution of the runtime itself can't be interrupted.
+	pub timeout: Option<Duration>,
+}
+
+impl Default for SchedulingPolicy {
+	fn default() -> Self {
+		SchedulingPolicy {
+			trigger: WorkerTrigger::NewBest,
+			skip_while_major_syncing: false,
+			max_concurrent_workers: None,
+			timeout: None,
+		}
+	}
 }
 
 /// An offchain workers manager.
@@ -86,20 +138,50 @@ pub struct OffchainWorkers<Client, Storage, Block: traits::Block> {
 	_block: PhantomData<Block>,
 	thread_pool: Mutex<ThreadPool>,
 	shared_client: SharedClient,
+	policy: SchedulingPolicy,
+	/// Number of offchain workers currently running, shared with the threads running them.
+	running_workers: Arc<AtomicUsize>,
 }
 
 impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Block> {
 	/// Creates new `OffchainWorkers`.
 	pub fn new(client: Arc<Client>, db: Storage) -> Self {
-		let shared_client = SharedClient::new();
+		Self::with_shared_client(client, db, SharedClient::new())
+	}
+
+	/// Creates new `OffchainWorkers` whose HTTP requests follow the given configuration.
+	///
+	/// Returns an error if the proxy URI is invalid or if the certificates can't be loaded.
+	pub fn with_http_config(
+		client: Arc<Client>,
+		db: Storage,
+		http_config: &HttpConfig,
+	) -> std::io::Result<Self> {
+		Ok(Self::with_shared_client(client, db, SharedClient::with_config(http_config)?))
+	}
+
+	fn with_shared_client(client: Arc<Client>, db: Storage, shared_client: SharedClient) -> Self {
 		Self {
 			client,
 			db,
 			_block: PhantomData,
 			thread_pool: Mutex::new(ThreadPool::new(num_cpus::get())),
 			shared_client,
+			policy: SchedulingPolicy::default(),
+			running_workers: Arc::new(AtomicUsize::new(0)),
 		}
 	}
+
+	/// Sets the policy deciding when the offchain workers are run.
+	pub fn with_scheduling_policy(mut self, policy: SchedulingPolicy) -> Self {
+		self.policy = policy;
+		self
+	}
+
+	/// Returns the policy deciding when the offchain workers are run.
+	pub fn scheduling_policy(&self) -> &SchedulingPolicy {
+		&self.policy
+	}
 }
 
 impl<Client, Storage, Block: traits::Block> fmt::Debug for OffchainWorkers<
@@ -149,16 +231,37 @@ impl<Client, Storage, Block> OffchainWorkers<
 		};
 		debug!("Checking offchain workers at {:?}: version:{}", at, version);
 		if version > 0 {
+			let running_worker = match self.try_start_worker() {
+				Some(running_worker) => running_worker,
+				None => {
+					debug!("Skipping offchain workers at {:?}: too many workers running", at);
+					return futures::future::Either::Right(futures::future::ready(()))
+				},
+			};
 			let (api, runner) = api::AsyncApi::new(
 				self.db.clone(),
 				network_provider,
 				is_validator,
 				self.shared_client.clone(),
+				self.policy.timeout,
 			);
+			let process = match self.policy.timeout {
+				Some(timeout) => {
+					let at = at.clone();
+					futures::future::select(
+						runner.process().boxed(),
+						futures_timer::Delay::new(timeout),
+					).map(move |outcome| if let futures::future::Either::Right(_) = outcome {
+						warn!("Offchain workers at {:?} timed out", at);
+					}).boxed()
+				},
+				None => runner.process().boxed(),
+			};
 			debug!("Spawning offchain workers at {:?}", at);
 			let header = header.clone();
 			let client = self.client.clone();
 			self.spawn_worker(move || {
+				let _running_worker = running_worker;
 				let runtime = client.runtime_api();
 				let api = Box::new(api);
 				debug!("Running offchain workers at {:?}", at);
@@ -177,12 +280,25 @@ impl<Client, Storage, Block> OffchainWorkers<
 					log::error!("Error running offchain workers at {:?}: {:?}", at, e);
 				}
 			});
-			futures::future::Either::Left(runner.process())
+			futures::future::Either::Left(process)
 		} else {
 			futures::future::Either::Right(futures::future::ready(()))
 		}
 	}
 
+	/// Registers a new running worker, unless the maximum number of concurrent workers is
+	/// reached. The worker is considered running until the returned value is dropped.
+	fn try_start_worker(&self) -> Option<RunningWorker> {
+		let max = self.policy.max_concurrent_workers;
+		self.running_workers.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |running| {
+			match max {
+				Some(max) if running >= max => None,
+				_ => Some(running + 1),
+			}
+		}).ok()?;
+		Some(RunningWorker(self.running_workers.clone()))
+	}
+
 	/// Spawns a new offchain worker.
 	///
 	/// We spawn offchain workers for each block in a separate thread,
@@ -196,6 +312,15 @@ impl<Client, Storage, Block> OffchainWorkers<
 	}
 }
 
+/// Decrements the number of running workers when dropped.
+struct RunningWorker(Arc<AtomicUsize>);
+
+impl Drop for RunningWorker {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
 /// Inform the offchain worker about new imported blocks
 pub async fn notification_future<Client, Storage, Block, Spawner>(
 	is_validator: bool,
@@ -211,22 +336,41 @@ pub async fn notification_future<Client, Storage, Block, Spawner>(
 		Storage: OffchainStorage + 'static,
 		Spawner: SpawnNamed
 {
-	client.import_notification_stream().for_each(move |n| {
-		if n.is_new_best {
+	let policy = offchain.scheduling_policy().clone();
+	let headers = match policy.trigger {
+		WorkerTrigger::NewBest => client.import_notification_stream()
+			.filter_map(|n| ready(if n.is_new_best {
+				Some(n.header)
+			} else {
+				log::debug!(
+					target: "sc_offchain",
+					"Skipping offchain workers for non-canon block: {:?}",
+					n.header,
+				);
+				None
+			}))
+			.boxed(),
+		WorkerTrigger::Finalized => client.finality_notification_stream()
+			.map(|n| n.header)
+			.boxed(),
+	};
+
+	headers.for_each(move |header| {
+		if policy.skip_while_major_syncing && network_provider.is_major_syncing() {
+			log::debug!(
+				target: "sc_offchain",
+				"Skipping offchain workers while major syncing: {:?}",
+				header,
+			);
+		} else {
 			spawner.spawn(
 				"offchain-on-block",
 				offchain.on_block_imported(
-					&n.header,
+					&header,
 					network_provider.clone(),
 					is_validator,
 				).boxed(),
 			);
-		} else {
-			log::debug!(
-				target: "sc_offchain",
-				"Skipping offchain workers for non-canon block: {:?}",
-				n.header,
-			)
 		}
 
 		ready(())
@@ -247,8 +391,13 @@ mod tests {
 	use sp_consensus::BlockOrigin;
 	use sc_client_api::Backend as _;
 	use sc_block_builder::BlockBuilderProvider as _;
+	use std::{sync::atomic::AtomicBool, task::Poll};
+	use substrate_test_runtime_client::ClientExt;
 
-	struct TestNetwork();
+	#[derive(Default)]
+	struct TestNetwork {
+		major_syncing: AtomicBool,
+	}
 
 	impl NetworkStateInfo for TestNetwork {
 		fn external_addresses(&self) -> Vec<Multiaddr> {
@@ -268,6 +417,45 @@ mod tests {
 		fn set_authorized_only(&self, _reserved_only: bool) {
 			unimplemented!()
 		}
+
+		fn is_major_syncing(&self) -> bool {
+			self.major_syncing.load(Ordering::SeqCst)
+		}
+	}
+
+	// Counts the spawned tasks, without running them.
+	#[derive(Clone, Default)]
+	struct CountingSpawner(Arc<AtomicUsize>);
+
+	impl CountingSpawner {
+		fn count(&self) -> usize {
+			self.0.load(Ordering::SeqCst)
+		}
+	}
+
+	impl SpawnNamed for CountingSpawner {
+		fn spawn_blocking(&self, _: &'static str, _: futures::future::BoxFuture<'static, ()>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+
+		fn spawn(&self, _: &'static str, _: futures::future::BoxFuture<'static, ()>) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	// Processes the notifications received so far by a `notification_future`.
+	fn process_notifications(notifications: &mut (impl Future<Output = ()> + Unpin)) {
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert!(notifications.poll_unpin(cx).is_pending());
+			Poll::Ready(())
+		}));
+	}
+
+	fn import_block(client: &mut Arc<TestClient>) -> <Block as traits::Block>::Hash {
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash = block.header.hash();
+		client.import(BlockOrigin::Own, block).unwrap();
+		hash
 	}
 
 	struct TestPool(
@@ -300,7 +488,7 @@ mod tests {
 			client.clone(),
 		));
 		let db = sc_client_db::offchain::LocalStorage::new_test();
-		let network = Arc::new(TestNetwork());
+		let network = Arc::new(TestNetwork::default());
 		let header = client.header(&BlockId::number(0)).unwrap().unwrap();
 
 		// when
@@ -314,6 +502,142 @@ mod tests {
 		assert_eq!(pool.0.ready().next().unwrap().is_propagable(), false);
 	}
 
+	#[test]
+	fn should_limit_concurrent_workers() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let db = sc_client_db::offchain::LocalStorage::new_test();
+		let offchain: OffchainWorkers<_, _, Block> = OffchainWorkers::new(client, db)
+			.with_scheduling_policy(SchedulingPolicy {
+				max_concurrent_workers: Some(2),
+				..Default::default()
+			});
+
+		let first = offchain.try_start_worker().unwrap();
+		let second = offchain.try_start_worker().unwrap();
+		assert!(offchain.try_start_worker().is_none());
+
+		// Workers that are done no longer count.
+		drop(first);
+		let _third = offchain.try_start_worker().unwrap();
+		assert!(offchain.try_start_worker().is_none());
+		drop(second);
+		assert!(offchain.try_start_worker().is_some());
+	}
+
+	#[test]
+	fn should_only_run_on_finalized_blocks_with_finalized_trigger() {
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let db = sc_client_db::offchain::LocalStorage::new_test();
+		let offchain = OffchainWorkers::new(client.clone(), db)
+			.with_scheduling_policy(SchedulingPolicy {
+				trigger: WorkerTrigger::Finalized,
+				..Default::default()
+			});
+		let spawner = CountingSpawner::default();
+		let mut notifications = notification_future(
+			false,
+			client.clone(),
+			Arc::new(offchain),
+			spawner.clone(),
+			Arc::new(TestNetwork::default()),
+		).boxed();
+		process_notifications(&mut notifications);
+
+		import_block(&mut client);
+		let second = import_block(&mut client);
+		process_notifications(&mut notifications);
+		assert_eq!(spawner.count(), 0);
+
+		// Blocks finalized together only trigger a single run.
+		client.finalize_block(BlockId::Hash(second), None).unwrap();
+		process_notifications(&mut notifications);
+		assert_eq!(spawner.count(), 1);
+	}
+
+	#[test]
+	fn should_skip_blocks_while_major_syncing() {
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let db = sc_client_db::offchain::LocalStorage::new_test();
+		let offchain = OffchainWorkers::new(client.clone(), db)
+			.with_scheduling_policy(SchedulingPolicy {
+				skip_while_major_syncing: true,
+				..Default::default()
+			});
+		let network = Arc::new(TestNetwork::default());
+		let spawner = CountingSpawner::default();
+		let mut notifications = notification_future(
+			false,
+			client.clone(),
+			Arc::new(offchain),
+			spawner.clone(),
+			network.clone(),
+		).boxed();
+		process_notifications(&mut notifications);
+
+		network.major_syncing.store(true, Ordering::SeqCst);
+		import_block(&mut client);
+		process_notifications(&mut notifications);
+		assert_eq!(spawner.count(), 0);
+
+		network.major_syncing.store(false, Ordering::SeqCst);
+		import_block(&mut client);
+		process_notifications(&mut notifications);
+		assert_eq!(spawner.count(), 1);
+	}
+
+	#[test]
+	fn should_run_while_major_syncing_by_default() {
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let db = sc_client_db::offchain::LocalStorage::new_test();
+		let offchain = Arc::new(OffchainWorkers::new(client.clone(), db));
+		let network = Arc::new(TestNetwork::default());
+		let spawner = CountingSpawner::default();
+		let mut notifications = notification_future(
+			false,
+			client.clone(),
+			offchain,
+			spawner.clone(),
+			network.clone(),
+		).boxed();
+		process_notifications(&mut notifications);
+
+		network.major_syncing.store(true, Ordering::SeqCst);
+		import_block(&mut client);
+		process_notifications(&mut notifications);
+		assert_eq!(spawner.count(), 1);
+	}
+
+	#[test]
+	fn should_stop_waiting_for_workers_after_timeout() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let db = sc_client_db::offchain::LocalStorage::new_test();
+		let timeout = Duration::from_millis(100);
+		let offchain = OffchainWorkers::new(client.clone(), db)
+			.with_scheduling_policy(SchedulingPolicy {
+				timeout: Some(timeout),
+				..Default::default()
+			});
+		let header = client.header(&BlockId::number(0)).unwrap().unwrap();
+
+		// Keep all the threads busy, so that the worker never even starts.
+		let mut busy = Vec::new();
+		for _ in 0..num_cpus::get() {
+			let (release, released) = std::sync::mpsc::channel::<()>();
+			offchain.spawn_worker(move || { let _ = released.recv(); });
+			busy.push(release);
+		}
+
+		let started = std::time::Instant::now();
+		futures::executor::block_on(
+			offchain.on_block_imported(&header, Arc::new(TestNetwork::default()), false)
+		);
+		let elapsed = started.elapsed();
+		assert!(elapsed >= timeout);
+		// the worker never starts, so only the timeout ends the wait.
+		assert!(elapsed < timeout + Duration::from_secs(5), "waited for {:?}", elapsed);
+		drop(busy);
+	}
+
 	#[test]
 	fn offchain_index_set_and_clear_works() {
 		sp_tracing::try_init_simple();
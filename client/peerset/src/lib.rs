@@ -31,15 +31,20 @@
 //!
 //! In addition, for each, set, the peerset also holds a list of reserved nodes towards which it
 //! will at all time try to maintain a connection with.
+//!
+//! Nodes can also be explicitly banned, either forever or for a given duration. Banned nodes are
+//! neither connected to nor accepted, even if they are reserved. If a persistence path is
+//! configured, the ban list and the reputations are saved to disk and restored on startup.
 
 mod peersstate;
+mod persistence;
 
 use std::{collections::HashSet, collections::VecDeque};
-use futures::prelude::*;
+use futures::{channel::oneshot, prelude::*};
 use log::{debug, error, trace};
 use serde_json::json;
-use std::{collections::HashMap, pin::Pin, task::{Context, Poll}, time::Duration};
-use wasm_timer::Instant;
+use std::{collections::HashMap, path::PathBuf, pin::Pin, task::{Context, Poll}, time::Duration};
+use wasm_timer::{Instant, SystemTime, UNIX_EPOCH};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 pub use libp2p::PeerId;
@@ -51,6 +56,8 @@ const DISCONNECT_REPUTATION_CHANGE: i32 = -256;
 /// Amount of time between the moment we disconnect from a node and the moment we remove it from
 /// the list.
 const FORGET_AFTER: Duration = Duration::from_secs(3600);
+/// Interval at which the reputations are saved to disk, if a persistence path is configured.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(300);
 
 #[derive(Debug)]
 enum Action {
@@ -61,6 +68,9 @@ enum Action {
 	ReportPeer(PeerId, ReputationChange),
 	AddToPeersSet(SetId, PeerId),
 	RemoveFromPeersSet(SetId, PeerId),
+	BanPeer(PeerId, Option<Duration>),
+	UnbanPeer(PeerId),
+	BannedPeers(oneshot::Sender<Vec<BannedPeer>>),
 }
 
 /// Identifier of a set in the peerset.
@@ -111,6 +121,15 @@ impl ReputationChange {
 	}
 }
 
+/// Node that has been banned with [`PeersetHandle::ban_peer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedPeer {
+	/// Identity of the node.
+	pub peer_id: PeerId,
+	/// UNIX timestamp, in seconds, at which the ban expires. `None` if the ban is permanent.
+	pub until: Option<u64>,
+}
+
 /// Shared handle to the peer set manager (PSM). Distributed around the code.
 #[derive(Debug, Clone)]
 pub struct PeersetHandle {
@@ -161,6 +180,31 @@ impl PeersetHandle {
 	pub fn remove_from_peers_set(&self, set_id: SetId, peer_id: PeerId) {
 		let _ = self.tx.unbounded_send(Action::RemoveFromPeersSet(set_id, peer_id));
 	}
+
+	/// Bans a peer for the given duration, or forever if `None`. The peer is disconnected from
+	/// all sets, including the ones it is reserved in, and can't connect again until the ban is
+	/// lifted.
+	///
+	/// Banning a peer that is already banned replaces the expiry of the previous ban.
+	pub fn ban_peer(&self, peer_id: PeerId, duration: Option<Duration>) {
+		let _ = self.tx.unbounded_send(Action::BanPeer(peer_id, duration));
+	}
+
+	/// Lifts the ban of a peer and resets its reputation.
+	///
+	/// Has no effect if the peer isn't banned.
+	pub fn unban_peer(&self, peer_id: PeerId) {
+		let _ = self.tx.unbounded_send(Action::UnbanPeer(peer_id));
+	}
+
+	/// Returns the list of peers that are currently banned.
+	pub async fn banned_peers(self) -> Result<Vec<BannedPeer>, ()> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self.tx.unbounded_send(Action::BannedPeers(tx));
+
+		// The channel can only be closed if the peerset no longer exists.
+		rx.await.map_err(|_| ())
+	}
 }
 
 /// Message that can be sent by the peer set manager (PSM).
@@ -203,6 +247,10 @@ impl From<u64> for IncomingIndex {
 pub struct PeersetConfig {
 	/// List of sets of nodes the peerset manages.
 	pub sets: Vec<SetConfig>,
+
+	/// File in which the reputations and the ban list are saved, and from which they are
+	/// restored on startup. `None` to keep them in memory only.
+	pub persistence_path: Option<PathBuf>,
 }
 
 /// Configuration for a single set of nodes.
@@ -252,6 +300,12 @@ pub struct Peerset {
 	created: Instant,
 	/// Last time when we updated the reputations of connected nodes.
 	latest_time_update: Instant,
+	/// Banned nodes, with the UNIX timestamp (in seconds) at which the ban expires, if any.
+	banned: HashMap<PeerId, Option<u64>>,
+	/// Saves the reputations and the ban list, if a persistence path is configured.
+	persistence: Option<persistence::Writer>,
+	/// Last time the reputations and the ban list were saved.
+	latest_persist: Instant,
 }
 
 impl Peerset {
@@ -279,9 +333,24 @@ impl Peerset {
 				message_queue: VecDeque::new(),
 				created: now,
 				latest_time_update: now,
+				banned: HashMap::new(),
+				persistence: config.persistence_path.clone().map(persistence::Writer::new),
+				latest_persist: now,
 			}
 		};
 
+		if let Some(path) = &config.persistence_path {
+			let persisted = persistence::load(path);
+			for (peer_id, reputation) in persisted.reputations {
+				peerset.data.peer_reputation(peer_id).set_reputation(reputation);
+			}
+			for (peer_id, until) in persisted.bans {
+				peerset.data.peer_reputation(peer_id.clone()).set_reputation(i32::min_value());
+				peerset.banned.insert(peer_id, until);
+			}
+			peerset.expire_bans();
+		}
+
 		for (set, set_config) in config.sets.into_iter().enumerate() {
 			for node in set_config.reserved_nodes {
 				peerset.data.add_no_slot_node(set, node);
@@ -407,6 +476,14 @@ impl Peerset {
 	}
 
 	fn on_report_peer(&mut self, peer_id: PeerId, change: ReputationChange) {
+		// The reputation of banned nodes stays at its minimum until the ban is lifted.
+		if self.banned.contains_key(&peer_id) {
+			trace!(target: "peerset", "Report {}: {:+} ignored, node is banned. Reason: {}",
+				peer_id, change.value, change.reason
+			);
+			return;
+		}
+
 		// We want reputations to be up-to-date before adjusting them.
 		self.update_time();
 
@@ -456,6 +533,12 @@ impl Peerset {
 		// empirically determine a value of `k` that looks correct.
 		for _ in 0..secs_diff {
 			for peer_id in self.data.peers().cloned().collect::<Vec<_>>() {
+				// The reputation of banned nodes stays at its minimum for as long as they are
+				// banned.
+				if self.banned.contains_key(&peer_id) {
+					continue;
+				}
+
 				// We use `k = 0.98`, so we divide by `50`. With that value, it takes 34.3 seconds
 				// to reduce the reputation by half.
 				fn reput_tick(reput: i32) -> i32 {
@@ -498,6 +581,97 @@ impl Peerset {
 				}
 			}
 		}
+
+		self.expire_bans();
+
+		if self.persistence.is_some() && now - self.latest_persist >= PERSIST_INTERVAL {
+			self.persist();
+		}
+	}
+
+	fn on_ban_peer(&mut self, peer_id: PeerId, duration: Option<Duration>) {
+		let until = duration.map(|duration| unix_time().saturating_add(duration.as_secs()));
+		debug!(target: "peerset", "Banning {} until {:?}", peer_id, until);
+
+		self.banned.insert(peer_id.clone(), until);
+		self.data.peer_reputation(peer_id.clone()).set_reputation(i32::min_value());
+
+		for set_index in 0..self.data.num_sets() {
+			if let peersstate::Peer::Connected(peer) = self.data.peer(set_index, &peer_id) {
+				let peer = peer.disconnect();
+				self.message_queue.push_back(Message::Drop {
+					set_id: SetId(set_index),
+					peer_id: peer.into_peer_id(),
+				});
+			}
+		}
+
+		self.persist();
+	}
+
+	fn on_unban_peer(&mut self, peer_id: PeerId) {
+		if self.banned.remove(&peer_id).is_none() {
+			return;
+		}
+
+		debug!(target: "peerset", "Unbanning {}", peer_id);
+		self.data.peer_reputation(peer_id).set_reputation(0);
+		self.persist();
+		self.alloc_slots();
+	}
+
+	/// Lifts the bans that have expired.
+	fn expire_bans(&mut self) {
+		let now = unix_time();
+		let expired = self.banned.iter()
+			.filter(|(_, until)| until.map_or(false, |until| until <= now))
+			.map(|(peer_id, _)| peer_id.clone())
+			.collect::<Vec<_>>();
+
+		for peer_id in expired {
+			debug!(target: "peerset", "Ban of {} expired", peer_id);
+			self.banned.remove(&peer_id);
+			self.data.peer_reputation(peer_id).set_reputation(0);
+		}
+	}
+
+	/// Saves the reputations and the ban list in the background, if a persistence path is
+	/// configured.
+	fn persist(&mut self) {
+		self.latest_persist = Instant::now();
+
+		if self.persistence.is_none() {
+			return;
+		}
+
+		let state = persistence::PersistedState {
+			reputations: self.data.peers().cloned().collect::<Vec<_>>().into_iter()
+				.map(|peer_id| {
+					let reputation = self.data.peer_reputation(peer_id.clone()).reputation();
+					(peer_id, reputation)
+				})
+				.collect(),
+			bans: self.banned.iter()
+				.map(|(peer_id, until)| (peer_id.clone(), *until))
+				.collect(),
+		};
+
+		if let Some(persistence) = &self.persistence {
+			persistence.save(state);
+		}
+	}
+
+	/// Returns the list of nodes that are currently banned.
+	///
+	/// > **Note**: This has the same effect as [`PeersetHandle::banned_peers`].
+	pub fn banned_peers(&mut self) -> Vec<BannedPeer> {
+		self.expire_bans();
+
+		let mut banned = self.banned.iter()
+			.map(|(peer_id, until)| BannedPeer { peer_id: peer_id.clone(), until: *until })
+			.collect::<Vec<_>>();
+		banned.sort_by_key(|banned| banned.peer_id.to_base58());
+		banned
 	}
 
 	/// Try to fill available out slots with nodes.
@@ -507,6 +681,10 @@ impl Peerset {
 		// Try to connect to all the reserved nodes that we are not connected to.
 		for set_index in 0..self.data.num_sets() {
 			for reserved_node in &self.reserved_nodes[set_index].0 {
+				if self.banned.contains_key(reserved_node) {
+					continue;
+				}
+
 				let entry = match self.data.peer(set_index, reserved_node) {
 					peersstate::Peer::Unknown(n) => n.discover(),
 					peersstate::Peer::NotConnected(n) => n,
@@ -541,6 +719,12 @@ impl Peerset {
 
 			// Try to grab the next node to attempt to connect to.
 			while let Some(next) = self.data.highest_not_connected_peer(set_index) {
+				// Banned nodes have the lowest possible reputation, so none of the remaining
+				// nodes is worth connecting to either.
+				if self.banned.contains_key(next.peer_id()) {
+					break;
+				}
+
 				// Don't connect to nodes with an abysmal reputation.
 				if next.reputation() < BANNED_THRESHOLD {
 					break;
@@ -579,6 +763,11 @@ impl Peerset {
 			}
 		}
 
+		if self.banned.contains_key(&peer_id) {
+			self.message_queue.push_back(Message::Reject(index));
+			return;
+		}
+
 		let not_connected = match self.data.peer(set_id.0, &peer_id) {
 			// If we're already connected, don't answer, as the docs mention.
 			peersstate::Peer::Connected(_) => return,
@@ -699,16 +888,37 @@ impl Stream for Peerset {
 					self.add_to_peers_set(sets_name, peer_id),
 				Action::RemoveFromPeersSet(sets_name, peer_id) =>
 					self.on_remove_from_peers_set(sets_name, peer_id),
+				Action::BanPeer(peer_id, duration) =>
+					self.on_ban_peer(peer_id, duration),
+				Action::UnbanPeer(peer_id) =>
+					self.on_unban_peer(peer_id),
+				Action::BannedPeers(pending_response) => {
+					let _ = pending_response.send(self.banned_peers());
+				},
 			}
 		}
 	}
 }
 
+impl Drop for Peerset {
+	fn drop(&mut self) {
+		self.persist();
+	}
+}
+
+/// Number of seconds elapsed since the UNIX epoch.
+fn unix_time() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
 #[cfg(test)]
 mod tests {
 	use libp2p::PeerId;
 	use futures::prelude::*;
-	use super::{PeersetConfig, Peerset, Message, IncomingIndex, ReputationChange, SetConfig, SetId, BANNED_THRESHOLD};
+	use super::{
+		PeersetConfig, Peerset, Message, IncomingIndex, ReputationChange, SetConfig, SetId,
+		BannedPeer, BANNED_THRESHOLD,
+	};
 	use std::{pin::Pin, task::Poll, thread, time::Duration};
 
 	fn assert_messages(mut peerset: Peerset, messages: Vec<Message>) -> Peerset {
@@ -739,6 +949,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			persistence_path: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -769,6 +980,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			persistence_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -797,6 +1009,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			persistence_path: None,
 		};
 
 		let (mut peerset, _) = Peerset::from_config(config);
@@ -820,6 +1033,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			persistence_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -843,6 +1057,7 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			persistence_path: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -875,4 +1090,124 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
+
+	fn set_config(reserved_nodes: Vec<PeerId>) -> SetConfig {
+		SetConfig {
+			in_peers: 25,
+			out_peers: 25,
+			bootnodes: vec![],
+			reserved_nodes: reserved_nodes.into_iter().collect(),
+			reserved_only: false,
+		}
+	}
+
+	#[test]
+	fn test_peerset_ban_and_unban() {
+		let reserved = PeerId::random();
+		let (peerset, handle) = Peerset::from_config(PeersetConfig {
+			sets: vec![set_config(vec![reserved.clone()])],
+			persistence_path: None,
+		});
+		let mut peerset = assert_messages(peerset, vec![
+			Message::Connect { set_id: SetId::from(0), peer_id: reserved.clone() },
+		]);
+
+		// Even reserved peers get disconnected when banned.
+		handle.ban_peer(reserved.clone(), None);
+		peerset = assert_messages(peerset, vec![
+			Message::Drop { set_id: SetId::from(0), peer_id: reserved.clone() },
+		]);
+		assert_eq!(peerset.banned_peers(), vec![BannedPeer { peer_id: reserved.clone(), until: None }]);
+
+		// The reputation of banned peers doesn't recover over time.
+		thread::sleep(Duration::from_millis(1500));
+		peerset.incoming(SetId::from(0), reserved.clone(), IncomingIndex(1));
+		peerset = assert_messages(peerset, vec![Message::Reject(IncomingIndex(1))]);
+
+		// Once unbanned, the reserved peer is connected to again.
+		handle.unban_peer(reserved.clone());
+		peerset = assert_messages(peerset, vec![
+			Message::Connect { set_id: SetId::from(0), peer_id: reserved },
+		]);
+		assert!(peerset.banned_peers().is_empty());
+	}
+
+	#[test]
+	fn test_peerset_reports_do_not_affect_banned_peers() {
+		let peer_id = PeerId::random();
+		let (mut peerset, handle) = Peerset::from_config(PeersetConfig {
+			sets: vec![set_config(vec![])],
+			persistence_path: None,
+		});
+
+		handle.ban_peer(peer_id.clone(), None);
+		handle.report_peer(peer_id.clone(), ReputationChange::new(i32::max_value(), ""));
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			Poll::Ready(())
+		}));
+
+		assert_eq!(peerset.data.peer_reputation(peer_id.clone()).reputation(), i32::min_value());
+		peerset.incoming(SetId::from(0), peer_id, IncomingIndex(1));
+		assert_messages(peerset, vec![Message::Reject(IncomingIndex(1))]);
+	}
+
+	#[test]
+	fn test_peerset_ban_expires() {
+		let peer_id = PeerId::random();
+		let (mut peerset, handle) = Peerset::from_config(PeersetConfig {
+			sets: vec![set_config(vec![])],
+			persistence_path: None,
+		});
+
+		handle.ban_peer(peer_id.clone(), Some(Duration::from_secs(1)));
+		let mut banned_peers = handle.clone().banned_peers().boxed();
+		let banned_peers = futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			banned_peers.poll_unpin(cx)
+		}));
+		assert_eq!(banned_peers.unwrap().len(), 1);
+
+		thread::sleep(Duration::from_millis(2100));
+		assert!(peerset.banned_peers().is_empty());
+
+		peerset.incoming(SetId::from(0), peer_id, IncomingIndex(1));
+		assert_messages(peerset, vec![Message::Accept(IncomingIndex(1))]);
+	}
+
+	#[test]
+	fn test_peerset_bans_and_reputations_are_persisted() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peerset.json");
+		let banned = PeerId::random();
+		let misbehaving = PeerId::random();
+
+		{
+			let (mut peerset, handle) = Peerset::from_config(PeersetConfig {
+				sets: vec![set_config(vec![])],
+				persistence_path: Some(path.clone()),
+			});
+			handle.ban_peer(banned.clone(), Some(Duration::from_secs(3600)));
+			handle.report_peer(misbehaving.clone(), ReputationChange::new(-1000, ""));
+			futures::executor::block_on(futures::future::poll_fn(|cx| {
+				assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+				Poll::Ready(())
+			}));
+			// The state is saved when the peerset is dropped.
+		}
+
+		let (mut peerset, _handle) = Peerset::from_config(PeersetConfig {
+			sets: vec![set_config(vec![])],
+			persistence_path: Some(path),
+		});
+
+		let banned_peers = peerset.banned_peers();
+		assert_eq!(banned_peers.len(), 1);
+		assert_eq!(banned_peers[0].peer_id, banned);
+		assert!(banned_peers[0].until.is_some());
+		assert!(peerset.data.peer_reputation(misbehaving).reputation() < 0);
+
+		peerset.incoming(SetId::from(0), banned, IncomingIndex(1));
+		assert_messages(peerset, vec![Message::Reject(IncomingIndex(1))]);
+	}
 }
@@ -456,6 +456,11 @@ impl<'a> NotConnectedPeer<'a> {
 		self.peer_id.into_owned()
 	}
 
+	/// Get the `PeerId` associated to this `NotConnectedPeer`.
+	pub fn peer_id(&self) -> &PeerId {
+		&self.peer_id
+	}
+
 	/// Bumps the value that `last_connected_or_discovered` would return to now, even if we
 	/// didn't connect or disconnect.
 	pub fn bump_last_connected_or_discovered(&mut self) {
@@ -0,0 +1,234 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk storage of the reputations and of the ban list, so that they survive restarts.
+//!
+//! The state is stored as a single JSON file, which is entirely rewritten every time it is
+//! saved. Peers are identified by their base58-encoded `PeerId`. Entries that fail to parse are
+//! ignored rather than preventing the node from starting.
+//!
+//! The file is written by a [`Writer`] on a background thread, so that the peerset is never
+//! blocked on disk I/O.
+
+use libp2p::PeerId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap, fs, io, path::{Path, PathBuf}, sync::mpsc, thread,
+};
+
+/// Content of the file.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredState {
+	/// Non-zero reputations of the nodes we know about.
+	#[serde(default)]
+	reputations: BTreeMap<String, i32>,
+	/// Banned nodes, with the UNIX timestamp (in seconds) at which the ban expires, if any.
+	#[serde(default)]
+	bans: BTreeMap<String, Option<u64>>,
+}
+
+/// State of the peerset restored from disk.
+#[derive(Debug, Default, PartialEq)]
+pub struct PersistedState {
+	/// Reputations of the nodes we know about.
+	pub reputations: Vec<(PeerId, i32)>,
+	/// Banned nodes, with the UNIX timestamp (in seconds) at which the ban expires, if any.
+	pub bans: Vec<(PeerId, Option<u64>)>,
+}
+
+/// Loads the state stored at `path`. Returns an empty state if the file doesn't exist or is
+/// corrupted.
+pub fn load(path: &Path) -> PersistedState {
+	let stored: StoredState = match fs::read(path) {
+		Ok(bytes) => match serde_json::from_slice(&bytes) {
+			Ok(stored) => stored,
+			Err(err) => {
+				warn!(target: "peerset", "Ignoring corrupted peerset state {}: {}", path.display(), err);
+				return PersistedState::default()
+			}
+		},
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return PersistedState::default(),
+		Err(err) => {
+			warn!(target: "peerset", "Failed to read peerset state {}: {}", path.display(), err);
+			return PersistedState::default()
+		}
+	};
+
+	fn parse<T>(entries: BTreeMap<String, T>) -> Vec<(PeerId, T)> {
+		entries.into_iter()
+			.filter_map(|(peer_id, value)| match peer_id.parse() {
+				Ok(peer_id) => Some((peer_id, value)),
+				Err(_) => {
+					warn!(target: "peerset", "Ignoring invalid peer id in peerset state: {}", peer_id);
+					None
+				}
+			})
+			.collect()
+	}
+
+	PersistedState {
+		reputations: parse(stored.reputations),
+		bans: parse(stored.bans),
+	}
+}
+
+/// Overwrites the state stored at `path`.
+pub fn save(path: &Path, state: &PersistedState) -> io::Result<()> {
+	let stored = StoredState {
+		reputations: state.reputations.iter()
+			.filter(|(_, reputation)| *reputation != 0)
+			.map(|(peer_id, reputation)| (peer_id.to_base58(), *reputation))
+			.collect(),
+		bans: state.bans.iter()
+			.map(|(peer_id, until)| (peer_id.to_base58(), *until))
+			.collect(),
+	};
+
+	let bytes = serde_json::to_vec(&stored)
+		.map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+	// Write to a temporary file first, so that a crash while saving doesn't lose the state.
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, bytes)?;
+	fs::rename(tmp_path, path)
+}
+
+/// Saves states on a background thread.
+///
+/// States that are queued while a previous one is being written are coalesced, only the most
+/// recent one is written. Dropping the `Writer` waits for the pending state to be written.
+pub struct Writer {
+	tx: Option<mpsc::Sender<PersistedState>>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Writer {
+	/// Starts the thread writing the states to `path`.
+	pub fn new(path: PathBuf) -> Self {
+		let (tx, rx) = mpsc::channel::<PersistedState>();
+		let thread = thread::Builder::new()
+			.name("peerset-persistence".into())
+			.spawn(move || {
+				while let Ok(mut state) = rx.recv() {
+					while let Ok(newer) = rx.try_recv() {
+						state = newer;
+					}
+
+					if let Err(err) = save(&path, &state) {
+						warn!(
+							target: "peerset",
+							"Failed to save peerset state to {}: {}", path.display(), err,
+						);
+					}
+				}
+			});
+
+		match thread {
+			Ok(thread) => Writer { tx: Some(tx), thread: Some(thread) },
+			Err(err) => {
+				warn!(target: "peerset", "Failed to start the peerset persistence thread: {}", err);
+				Writer { tx: None, thread: None }
+			}
+		}
+	}
+
+	/// Queues `state` to be written.
+	pub fn save(&self, state: PersistedState) {
+		if let Some(tx) = &self.tx {
+			let _ = tx.send(state);
+		}
+	}
+}
+
+impl Drop for Writer {
+	fn drop(&mut self) {
+		// Closing the channel stops the thread once the queued state has been written.
+		self.tx.take();
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn state_roundtrips() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peerset.json");
+		let banned = PeerId::random();
+		let banned_forever = PeerId::random();
+
+		let state = PersistedState {
+			reputations: vec![(banned.clone(), i32::min_value())],
+			bans: vec![(banned, Some(1_700_000_000)), (banned_forever, None)],
+		};
+		save(&path, &state).unwrap();
+
+		let mut loaded = load(&path);
+		loaded.bans.sort_by_key(|(_, until)| std::cmp::Reverse(*until));
+		assert_eq!(loaded, state);
+	}
+
+	#[test]
+	fn writer_saves_the_latest_state() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peerset.json");
+		let peer_id = PeerId::random();
+
+		let writer = Writer::new(path.clone());
+		writer.save(PersistedState { reputations: vec![(peer_id.clone(), -1)], bans: Vec::new() });
+		writer.save(PersistedState { reputations: vec![(peer_id.clone(), -2)], bans: Vec::new() });
+		drop(writer);
+
+		assert_eq!(load(&path).reputations, vec![(peer_id, -2)]);
+	}
+
+	#[test]
+	fn missing_or_corrupted_state_is_empty() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peerset.json");
+		assert_eq!(load(&path), PersistedState::default());
+
+		fs::write(&path, b"not json").unwrap();
+		assert_eq!(load(&path), PersistedState::default());
+	}
+
+	#[test]
+	fn zero_reputations_and_invalid_peer_ids_are_dropped() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("peerset.json");
+		let peer_id = PeerId::random();
+
+		save(&path, &PersistedState {
+			reputations: vec![(peer_id.clone(), 0)],
+			bans: Vec::new(),
+		}).unwrap();
+		assert_eq!(load(&path), PersistedState::default());
+
+		fs::write(&path, format!(
+			r#"{{"reputations":{{"{}":-5,"invalid":-5}},"bans":{{}}}}"#,
+			peer_id.to_base58(),
+		)).unwrap();
+		assert_eq!(load(&path).reputations, vec![(peer_id, -5)]);
+	}
+}
@@ -64,6 +64,7 @@ fn test_once() {
 				reserved_only: Uniform::new_inclusive(0, 10).sample(&mut rng) == 0,
 			},
 		],
+		persistence_path: None,
 	});
 
 	futures::executor::block_on(futures::future::poll_fn(move |cx| {
@@ -31,12 +31,16 @@ pub enum Error {
 	UnavailableStorageKind,
 	/// Call to an unsafe RPC was denied.
 	UnsafeRpcCalled(crate::policy::UnsafeRpcError),
+	/// The offchain storage failed to process the request.
+	#[display(fmt="Offchain storage error: {}", _0)]
+	Storage(sp_core::offchain::StorageError),
 }
 
 impl std::error::Error for Error {
 	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match self {
 			Self::UnsafeRpcCalled(err) => Some(err),
+			Self::Storage(err) => Some(err),
 			_ => None,
 		}
 	}
@@ -54,6 +58,11 @@ impl From<Error> for rpc::Error {
 				data: None,
 			},
 			Error::UnsafeRpcCalled(e) => e.into(),
+			Error::Storage(e) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("Offchain storage error: {}", e),
+				data: None,
+			},
 		}
 	}
 }
@@ -28,7 +28,7 @@ pub use self::gen_client::Client as OffchainClient;
 
 /// Substrate offchain RPC API
 #[rpc]
-pub trait OffchainApi {
+pub trait OffchainApi<Hash> {
 	/// Set offchain local storage under given key and prefix.
 	#[rpc(name = "offchain_localStorageSet")]
 	fn set_local_storage(&self, kind: StorageKind, key: Bytes, value: Bytes) -> Result<()>;
@@ -36,4 +36,24 @@ pub trait OffchainApi {
 	/// Get offchain local storage under given key and prefix.
 	#[rpc(name = "offchain_localStorageGet")]
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<Option<Bytes>>;
+
+	/// Get the keys of offchain local storage starting with given prefix, in lexicographic order.
+	#[rpc(name = "offchain_localStorageKeys")]
+	fn local_storage_keys(&self, kind: StorageKind, prefix: Bytes) -> Result<Vec<Bytes>>;
+
+	/// Remove all the entries of offchain local storage whose key starts with given prefix, all
+	/// at once.
+	///
+	/// Returns the number of removed entries.
+	#[rpc(name = "offchain_localStorageClearPrefix")]
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> Result<u64>;
+
+	/// Get the value written under given key through offchain indexing, as seen by the block
+	/// with the given hash, or by the best block if none is given.
+	///
+	/// Only the changes made by that block and its ancestors are taken into account. Only the
+	/// finalized blocks which are not pruned yet and the blocks which are not finalized yet are
+	/// known, an error is returned for any other block.
+	#[rpc(name = "offchain_indexedGet")]
+	fn indexed_get(&self, key: Bytes, at: Option<Hash>) -> Result<Option<Bytes>>;
 }
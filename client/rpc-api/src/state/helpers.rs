@@ -18,7 +18,7 @@
 
 //! Substrate state API helpers.
 
-use sp_core::Bytes;
+use sp_core::{Bytes, storage::{StorageKey, StorageData, PrefixedStorageKey}};
 use serde::{Serialize, Deserialize};
 
 /// ReadProof struct returned by the RPC
@@ -30,3 +30,55 @@ pub struct ReadProof<Hash> {
 	/// A proof used to prove that storage entries are included in the storage trie
 	pub proof: Vec<Bytes>,
 }
+
+/// StoragePairsPage struct returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoragePairsPage<Hash> {
+	/// Block hash the pairs were read at
+	pub at: Hash,
+	/// Key/value pairs of the page, in lexicographic order of the keys
+	pub pairs: Vec<(StorageKey, StorageData)>,
+	/// A proof that the pairs are included in the storage trie, if requested
+	pub proof: Option<Vec<Bytes>>,
+}
+
+/// Storage value read while executing a block, as returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReadTrace {
+	/// Child trie the key belongs to, `None` for the top-level trie
+	pub child_storage_key: Option<PrefixedStorageKey>,
+	/// The key that was read
+	pub key: StorageKey,
+	/// The value found in the parent state
+	pub value: Option<StorageData>,
+}
+
+/// Storage value written while executing a block, as returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageWriteTrace {
+	/// Child trie the key belongs to, `None` for the top-level trie
+	pub child_storage_key: Option<PrefixedStorageKey>,
+	/// The key that was written
+	pub key: StorageKey,
+	/// The value in the parent state
+	pub old_value: Option<StorageData>,
+	/// The value after executing the block, `None` if it was deleted
+	pub new_value: Option<StorageData>,
+	/// Indices of the extrinsics that modified the value
+	pub extrinsics: Vec<u32>,
+}
+
+/// BlockTrace struct returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the traced block
+	pub block: Hash,
+	/// Values read from the parent state
+	pub reads: Vec<StorageReadTrace>,
+	/// Values written by the block
+	pub writes: Vec<StorageWriteTrace>,
+}
@@ -30,7 +30,7 @@ use sp_version::RuntimeVersion;
 use self::error::FutureResult;
 
 pub use self::gen_client::Client as StateClient;
-pub use self::helpers::ReadProof;
+pub use self::helpers::{ReadProof, StoragePairsPage, BlockTrace, StorageReadTrace, StorageWriteTrace};
 
 /// Substrate state API
 #[rpc]
@@ -63,6 +63,20 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>,
 	) -> FutureResult<Vec<StorageKey>>;
 
+	/// Returns the key/value pairs with prefix with pagination support.
+	/// Up to `count` pairs will be returned.
+	/// If `start_key` is passed, return next pairs in storage in lexicographic order.
+	/// If `with_proof` is `true`, a proof of the returned pairs is included.
+	#[rpc(name = "state_getPairsPaged")]
+	fn storage_pairs_paged(
+		&self,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		hash: Option<Hash>,
+		with_proof: Option<bool>,
+	) -> FutureResult<StoragePairsPage<Hash>>;
+
 	/// Returns a storage entry at a specific block's state.
 	#[rpc(name = "state_getStorage", alias("state_getStorageAt"))]
 	fn storage(&self, key: StorageKey, hash: Option<Hash>) -> FutureResult<Option<StorageData>>;
@@ -107,6 +121,11 @@ pub trait StateApi<Hash> {
 	#[rpc(name = "state_getReadProof")]
 	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
 
+	/// Re-executes a block and returns every storage entry it read from its parent
+	/// state and every storage entry it wrote, along with the extrinsics that wrote them.
+	#[rpc(name = "state_traceBlock")]
+	fn trace_block(&self, block: Hash) -> FutureResult<BlockTrace<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
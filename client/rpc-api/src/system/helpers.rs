@@ -73,6 +73,17 @@ pub struct PeerInfo<Hash, Number> {
 	pub best_number: Number,
 }
 
+/// Information about a banned peer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPeerInfo {
+	/// Peer ID
+	pub peer_id: String,
+	/// UNIX timestamp, in seconds, at which the ban expires. Missing if the ban is permanent.
+	#[serde(default = "Default::default", skip_serializing_if = "Option::is_none")]
+	pub until: Option<u64>,
+}
+
 /// The role the node is running as
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum NodeRole {
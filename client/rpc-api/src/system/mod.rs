@@ -27,7 +27,7 @@ use futures::{future::BoxFuture, compat::Compat};
 
 use self::error::Result as SystemResult;
 
-pub use self::helpers::{SystemInfo, Health, PeerInfo, NodeRole, SyncState};
+pub use self::helpers::{SystemInfo, Health, PeerInfo, BannedPeerInfo, NodeRole, SyncState};
 pub use self::gen_client::Client as SystemClient;
 
 /// Substrate system RPC API
@@ -100,6 +100,23 @@ pub trait SystemApi<Hash, Number> {
 	fn system_remove_reserved_peer(&self, peer_id: String)
 		-> Compat<BoxFuture<'static, Result<(), jsonrpc_core::Error>>>;
 
+	/// Bans a peer for the given number of seconds, or forever if no duration is given. The
+	/// peer is disconnected and neither dialed nor accepted until the ban expires or is lifted
+	/// with `system_unbanPeer`. The string should encode only the PeerId.
+	#[rpc(name = "system_banPeer", returns = "()")]
+	fn system_ban_peer(&self, peer_id: String, duration: Option<u64>)
+		-> Compat<BoxFuture<'static, Result<(), jsonrpc_core::Error>>>;
+
+	/// Lifts the ban of a peer. The string should encode only the PeerId.
+	#[rpc(name = "system_unbanPeer", returns = "()")]
+	fn system_unban_peer(&self, peer_id: String)
+		-> Compat<BoxFuture<'static, Result<(), jsonrpc_core::Error>>>;
+
+	/// Returns the peers that are currently banned.
+	#[rpc(name = "system_bannedPeers", returns = "Vec<BannedPeerInfo>")]
+	fn system_banned_peers(&self)
+		-> Compat<BoxFuture<'static, jsonrpc_core::Result<Vec<BannedPeerInfo>>>>;
+
 	/// Returns the roles the node is running as.
 	#[rpc(name = "system_nodeRoles", returns = "Vec<NodeRole>")]
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>>;
@@ -29,28 +29,38 @@ use sp_core::{
 	Bytes,
 	offchain::{OffchainStorage, StorageKind},
 };
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::Block as BlockT;
 use parking_lot::RwLock;
-use std::sync::Arc;
+use std::{marker::PhantomData, sync::Arc};
 
 /// Offchain API
-#[derive(Debug)]
-pub struct Offchain<T: OffchainStorage> {
+pub struct Offchain<T: OffchainStorage, Block, Client> {
 	/// Offchain storage
 	storage: Arc<RwLock<T>>,
+	/// Client used to find the best block.
+	client: Arc<Client>,
 	deny_unsafe: DenyUnsafe,
+	_block: PhantomData<Block>,
 }
 
-impl<T: OffchainStorage> Offchain<T> {
+impl<T: OffchainStorage, Block, Client> Offchain<T, Block, Client> {
 	/// Create new instance of Offchain API.
-	pub fn new(storage: T, deny_unsafe: DenyUnsafe) -> Self {
+	pub fn new(storage: T, client: Arc<Client>, deny_unsafe: DenyUnsafe) -> Self {
 		Offchain {
 			storage: Arc::new(RwLock::new(storage)),
+			client,
 			deny_unsafe,
+			_block: PhantomData,
 		}
 	}
 }
 
-impl<T: OffchainStorage + 'static> OffchainApi for Offchain<T> {
+impl<T, Block, Client> OffchainApi<Block::Hash> for Offchain<T, Block, Client> where
+	T: OffchainStorage + 'static,
+	Block: BlockT,
+	Client: HeaderBackend<Block> + Send + Sync + 'static,
+{
 	/// Set offchain local storage under given key and prefix.
 	fn set_local_storage(&self, kind: StorageKind, key: Bytes, value: Bytes) -> Result<()> {
 		self.deny_unsafe.check_if_safe()?;
@@ -73,4 +83,45 @@ impl<T: OffchainStorage + 'static> OffchainApi for Offchain<T> {
 		};
 		Ok(self.storage.read().get(prefix, &*key).map(Into::into))
 	}
+
+	/// Get the keys of offchain local storage starting with given prefix.
+	fn local_storage_keys(&self, kind: StorageKind, prefix: Bytes) -> Result<Vec<Bytes>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let storage_prefix = match kind {
+			StorageKind::PERSISTENT => sp_offchain::STORAGE_PREFIX,
+			StorageKind::LOCAL => return Err(Error::UnavailableStorageKind),
+		};
+		let mut keys: Vec<_> = self.storage.read()
+			.iter_prefix(storage_prefix, &*prefix)
+			.map_err(Error::Storage)?
+			.into_iter()
+			.map(|(key, _)| key)
+			.collect();
+		keys.sort();
+		Ok(keys.into_iter().map(Into::into).collect())
+	}
+
+	/// Remove all the entries of offchain local storage whose key starts with given prefix.
+	fn clear_local_storage_prefix(&self, kind: StorageKind, prefix: Bytes) -> Result<u64> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let storage_prefix = match kind {
+			StorageKind::PERSISTENT => sp_offchain::STORAGE_PREFIX,
+			StorageKind::LOCAL => return Err(Error::UnavailableStorageKind),
+		};
+		self.storage.write().clear_prefix(storage_prefix, &*prefix).map_err(Error::Storage)
+	}
+
+	/// Get the value written under given key through offchain indexing at given block, or at the
+	/// best block.
+	fn indexed_get(&self, key: Bytes, at: Option<Block::Hash>) -> Result<Option<Bytes>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		self.storage.read()
+			.indexed_get(at.as_ref(), &*key)
+			.map(|value| value.map(Into::into))
+			.map_err(Error::Storage)
+	}
 }
@@ -18,12 +18,83 @@
 
 use super::*;
 use assert_matches::assert_matches;
-use sp_core::{Bytes, offchain::storage::InMemOffchainStorage};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::Backend as _;
+use sp_core::{Bytes, offchain::{storage::InMemOffchainStorage, StorageError}};
+use sp_runtime::{generic::BlockId, traits::Header as _};
+use substrate_test_runtime_client::{
+	prelude::*,
+	sp_consensus::BlockOrigin,
+	runtime::{Block, Extrinsic, H256},
+};
+
+fn new_offchain<T: OffchainStorage>(
+	storage: T,
+	deny_unsafe: DenyUnsafe,
+) -> Offchain<T, Block, TestClient> {
+	Offchain::new(storage, Arc::new(substrate_test_runtime_client::new()), deny_unsafe)
+}
+
+// Imports a block on top of `parent`, writing `value` under `key` through offchain indexing.
+fn import_indexing_block(
+	client: &mut Arc<TestClient>,
+	parent: H256,
+	key: &[u8],
+	value: &[u8],
+) -> H256 {
+	let mut builder = client.new_block_at(&BlockId::Hash(parent), Default::default(), false)
+		.unwrap();
+	builder.push(Extrinsic::OffchainIndexSet(key.to_vec(), value.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+	hash
+}
+
+// Offchain storage which can't be iterated over, like the one backed by ParityDb.
+#[derive(Clone, Default)]
+struct NonIterableStorage(InMemOffchainStorage);
+
+impl OffchainStorage for NonIterableStorage {
+	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
+		self.0.set(prefix, key, value)
+	}
+
+	fn remove(&mut self, prefix: &[u8], key: &[u8]) {
+		self.0.remove(prefix, key)
+	}
+
+	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		self.0.get(prefix, key)
+	}
+
+	fn iter_prefix(
+		&self,
+		_: &[u8],
+		_: &[u8],
+	) -> std::result::Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+		Err(StorageError::Unsupported)
+	}
+
+	fn clear_prefix(&mut self, _: &[u8], _: &[u8]) -> std::result::Result<u64, StorageError> {
+		Err(StorageError::Unsupported)
+	}
+
+	fn compare_and_set(
+		&mut self,
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		self.0.compare_and_set(prefix, key, old_value, new_value)
+	}
+}
 
 #[test]
 fn local_storage_should_work() {
 	let storage = InMemOffchainStorage::default();
-	let offchain = Offchain::new(storage, DenyUnsafe::No);
+	let offchain = new_offchain(storage, DenyUnsafe::No);
 	let key = Bytes(b"offchain_storage".to_vec());
 	let value = Bytes(b"offchain_value".to_vec());
 
@@ -37,10 +108,126 @@ fn local_storage_should_work() {
 	);
 }
 
+#[test]
+fn local_storage_prefix_calls_should_work() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = new_offchain(storage, DenyUnsafe::No);
+	for key in &[&b"oracle/b"[..], b"oracle/a", b"other"] {
+		offchain.set_local_storage(StorageKind::PERSISTENT, Bytes(key.to_vec()), Bytes(vec![1]))
+			.unwrap();
+	}
+
+	assert_matches!(
+		offchain.local_storage_keys(StorageKind::PERSISTENT, Bytes(b"oracle/".to_vec())),
+		Ok(ref keys) if *keys == vec![Bytes(b"oracle/a".to_vec()), Bytes(b"oracle/b".to_vec())]
+	);
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, Bytes(b"oracle/".to_vec())),
+		Ok(2)
+	);
+	assert_matches!(
+		offchain.local_storage_keys(StorageKind::PERSISTENT, Bytes(Vec::new())),
+		Ok(ref keys) if *keys == vec![Bytes(b"other".to_vec())]
+	);
+	assert_matches!(
+		offchain.local_storage_keys(StorageKind::LOCAL, Bytes(Vec::new())),
+		Err(Error::UnavailableStorageKind)
+	);
+}
+
+#[test]
+fn local_storage_prefix_calls_report_storage_errors() {
+	let offchain = new_offchain(NonIterableStorage::default(), DenyUnsafe::No);
+	offchain.set_local_storage(StorageKind::PERSISTENT, Bytes(b"key".to_vec()), Bytes(vec![1]))
+		.unwrap();
+
+	assert_matches!(
+		offchain.local_storage_keys(StorageKind::PERSISTENT, Bytes(Vec::new())),
+		Err(Error::Storage(StorageError::Unsupported))
+	);
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, Bytes(Vec::new())),
+		Err(Error::Storage(StorageError::Unsupported))
+	);
+	assert_matches!(
+		offchain.get_local_storage(StorageKind::PERSISTENT, Bytes(b"key".to_vec())),
+		Ok(Some(_))
+	);
+}
+
+#[test]
+fn indexed_get_should_work() {
+	let storage = InMemOffchainStorage::default();
+	let offchain = new_offchain(storage, DenyUnsafe::No);
+	let key = Bytes(b"indexed".to_vec());
+	let value = Bytes(b"indexed_value".to_vec());
+
+	assert_matches!(offchain.indexed_get(key.clone(), None), Ok(None));
+	offchain.set_local_storage(StorageKind::PERSISTENT, key.clone(), value.clone()).unwrap();
+	assert_matches!(
+		offchain.indexed_get(key, Some(H256::repeat_byte(1))),
+		Ok(Some(ref v)) if *v == value
+	);
+}
+
+#[test]
+fn indexed_get_should_be_fork_aware() {
+	let (client, backend) = TestClientBuilder::new()
+		.enable_offchain_indexing_api()
+		.build_with_backend();
+	let mut client = Arc::new(client);
+	let genesis = client.genesis_hash();
+	let key = b"indexed";
+	let a1 = import_indexing_block(&mut client, genesis, key, b"a1");
+	let b1 = import_indexing_block(&mut client, genesis, key, b"b1");
+	let offchain = Offchain::new(
+		backend.offchain_storage().unwrap(),
+		client.clone(),
+		DenyUnsafe::No,
+	);
+	let key = Bytes(key.to_vec());
+
+	// `a1` stays the best block, as it has been imported first.
+	assert_matches!(
+		offchain.indexed_get(key.clone(), None),
+		Ok(Some(ref v)) if *v == Bytes(b"a1".to_vec())
+	);
+	assert_matches!(
+		offchain.indexed_get(key.clone(), Some(a1)),
+		Ok(Some(ref v)) if *v == Bytes(b"a1".to_vec())
+	);
+	assert_matches!(
+		offchain.indexed_get(key.clone(), Some(b1)),
+		Ok(Some(ref v)) if *v == Bytes(b"b1".to_vec())
+	);
+	assert_matches!(offchain.indexed_get(key.clone(), Some(genesis)), Ok(None));
+	assert_matches!(
+		offchain.indexed_get(key.clone(), Some(H256::repeat_byte(0x42))),
+		Err(Error::Storage(StorageError::UnknownBlock))
+	);
+
+	// the views of finalized blocks are kept, while the discarded forks are unknown.
+	let a2 = import_indexing_block(&mut client, a1, b"indexed", b"a2");
+	client.finalize_block(BlockId::Hash(a2), None).unwrap();
+	assert_matches!(offchain.indexed_get(key.clone(), Some(genesis)), Ok(None));
+	assert_matches!(
+		offchain.indexed_get(key.clone(), Some(a1)),
+		Ok(Some(ref v)) if *v == Bytes(b"a1".to_vec())
+	);
+	assert_matches!(
+		offchain.indexed_get(key.clone(), None),
+		Ok(Some(ref v)) if *v == Bytes(b"a2".to_vec())
+	);
+	assert_matches!(
+		offchain.indexed_get(key, Some(b1)),
+		Err(Error::Storage(StorageError::UnknownBlock))
+	);
+}
+
 #[test]
 fn offchain_calls_considered_unsafe() {
 	let storage = InMemOffchainStorage::default();
-	let offchain = Offchain::new(storage, DenyUnsafe::Yes);
+	let offchain = new_offchain(storage, DenyUnsafe::Yes);
 	let key = Bytes(b"offchain_storage".to_vec());
 	let value = Bytes(b"offchain_value".to_vec());
 
@@ -52,4 +239,16 @@ fn offchain_calls_considered_unsafe() {
 		offchain.get_local_storage(StorageKind::PERSISTENT, key),
 		Err(Error::UnsafeRpcCalled(_))
 	);
+	assert_matches!(
+		offchain.local_storage_keys(StorageKind::PERSISTENT, Bytes(Vec::new())),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+	assert_matches!(
+		offchain.clear_local_storage_prefix(StorageKind::PERSISTENT, Bytes(Vec::new())),
+		Err(Error::UnsafeRpcCalled(_))
+	);
+	assert_matches!(
+		offchain.indexed_get(Bytes(Vec::new()), None),
+		Err(Error::UnsafeRpcCalled(_))
+	);
 }
@@ -26,7 +26,7 @@ use log::warn;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId, manager::SubscriptionManager};
 use rpc::{Result as RpcResult, futures::{stream, Future, Sink, Stream, future::result}};
 
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{ReadProof, StoragePairsPage, BlockTrace, StorageReadTrace, StorageWriteTrace};
 use sc_client_api::backend::Backend;
 use sp_blockchain::{Result as ClientResult, Error as ClientError, HeaderMetadata, CachedHeaderMetadata, HeaderBackend};
 use sc_client_api::BlockchainEvents;
@@ -43,7 +43,7 @@ use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt};
 
 use super::{StateBackend, ChildStateBackend, error::{FutureResult, Error, Result}, client_err};
 use std::marker::PhantomData;
-use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider, ProofProvider};
+use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider, ProofProvider, StorageTraceProvider};
 
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
@@ -222,6 +222,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	Block: BlockT + 'static,
 	BE: Backend<Block> + 'static,
 	Client: ExecutorProvider<Block> + StorageProvider<Block, BE> + ProofProvider<Block> + HeaderBackend<Block>
+		+ StorageTraceProvider<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error> + BlockchainEvents<Block>
 		+ CallApiAt<Block, Error = sp_blockchain::Error> + ProvideRuntimeApi<Block>
 		+ Send + Sync + 'static,
@@ -289,6 +290,45 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 				.map_err(client_err)))
 	}
 
+	fn storage_pairs_paged(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: Option<StorageKey>,
+		count: u32,
+		start_key: Option<StorageKey>,
+		with_proof: bool,
+	) -> FutureResult<StoragePairsPage<Block::Hash>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| {
+					let id = BlockId::Hash(block);
+					if with_proof {
+						self.client
+							.read_range_proof(&id, prefix.as_ref(), start_key.as_ref(), count as usize)
+							.map(|(pairs, proof)| StoragePairsPage {
+								at: block,
+								pairs,
+								proof: Some(proof.iter_nodes().map(|node| node.into()).collect()),
+							})
+					} else {
+						self.client
+							.storage_keys_iter(&id, prefix.as_ref(), start_key.as_ref())?
+							.take(count as usize)
+							.map(|key| self.client
+								.storage(&id, &key)
+								.map(|value| value.map(|value| (key, value)))
+							)
+							.collect::<sp_blockchain::Result<Vec<_>>>()
+							.map(|pairs| StoragePairsPage {
+								at: block,
+								pairs: pairs.into_iter().flatten().collect(),
+								proof: None,
+							})
+					}
+				})
+				.map_err(client_err)))
+	}
+
 	fn storage(
 		&self,
 		block: Option<Block::Hash>,
@@ -403,6 +443,28 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		))
 	}
 
+	fn trace_block(&self, block: Block::Hash) -> FutureResult<BlockTrace<Block::Hash>> {
+		Box::new(result(
+			self.client.trace_block_storage(&BlockId::Hash(block))
+				.map(|trace| BlockTrace {
+					block,
+					reads: trace.reads.into_iter().map(|read| StorageReadTrace {
+						child_storage_key: read.child_storage_key,
+						key: read.key,
+						value: read.value,
+					}).collect(),
+					writes: trace.writes.into_iter().map(|write| StorageWriteTrace {
+						child_storage_key: write.child_storage_key,
+						key: write.key,
+						old_value: write.old_value,
+						new_value: write.new_value,
+						extrinsics: write.extrinsics,
+					}).collect(),
+				})
+				.map_err(client_err),
+		))
+	}
+
 	fn subscribe_runtime_version(
 		&self,
 		_meta: crate::Metadata,
@@ -136,6 +136,63 @@ fn should_return_child_storage() {
 	);
 }
 
+#[test]
+fn should_return_storage_pairs_paged() {
+	let client = Arc::new(TestClientBuilder::new()
+		.add_extra_storage(b":map:acc1".to_vec(), vec![1, 2])
+		.add_extra_storage(b":map:acc2".to_vec(), vec![1, 2, 3])
+		.add_extra_storage(b":map:acc3".to_vec(), vec![1, 2, 3, 4])
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let state_root = client.header(&BlockId::Hash(genesis_hash)).unwrap().unwrap().state_root;
+	let (api, _child) = new_full(
+		client,
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+	);
+	let prefix = StorageKey(b":map:".to_vec());
+
+	let page = api.storage_pairs_paged(Some(prefix.clone()), 2, None, None, None).wait().unwrap();
+	assert_eq!(page.at, genesis_hash);
+	assert_eq!(
+		page.pairs,
+		vec![
+			(StorageKey(b":map:acc1".to_vec()), StorageData(vec![1, 2])),
+			(StorageKey(b":map:acc2".to_vec()), StorageData(vec![1, 2, 3])),
+		],
+	);
+	assert_eq!(page.proof, None);
+
+	let start_key = page.pairs.last().map(|(key, _)| key.clone());
+	let page = api.storage_pairs_paged(
+		Some(prefix.clone()),
+		2,
+		start_key.clone(),
+		Some(genesis_hash),
+		Some(true),
+	).wait().unwrap();
+	assert_eq!(
+		page.pairs,
+		vec![(StorageKey(b":map:acc3".to_vec()), StorageData(vec![1, 2, 3, 4]))],
+	);
+	let proof = sp_state_machine::StorageProof::new(
+		page.proof.unwrap().into_iter().map(|node| node.0).collect(),
+	);
+	let checked = sp_state_machine::read_range_proof_check::<sp_runtime::traits::BlakeTwo256>(
+		state_root,
+		proof,
+		Some(&prefix.0),
+		start_key.as_ref().map(|key| &key.0[..]),
+		2,
+	).unwrap();
+	assert_eq!(checked, vec![(b":map:acc3".to_vec(), vec![1, 2, 3, 4])]);
+
+	assert_matches!(
+		api.storage_pairs_paged(Some(prefix), STORAGE_KEYS_PAGED_MAX_COUNT + 1, None, None, None).wait(),
+		Err(Error::InvalidCount { .. })
+	);
+}
+
 #[test]
 fn should_call_contract() {
 	let client = Arc::new(substrate_test_runtime_client::new());
@@ -235,6 +292,44 @@ fn should_send_initial_storage_changes_and_notifications() {
 	assert_eq!(executor::block_on(next.into_future().compat()).unwrap().0, None);
 }
 
+#[test]
+fn should_trace_block() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let (api, _child) = new_full(
+		client.clone(),
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::No,
+	);
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(vec![1], Some(vec![1])).unwrap();
+	builder.push_storage_change(vec![2], Some(vec![2])).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let trace = api.trace_block(block_hash).wait().unwrap();
+	assert_eq!(trace.block, block_hash);
+
+	let write_of = |key: &[u8]| trace.writes.iter()
+		.find(|w| w.child_storage_key.is_none() && w.key.0 == key)
+		.unwrap();
+	assert_eq!(write_of(&[1]).old_value, None);
+	assert_eq!(write_of(&[1]).new_value, Some(StorageData(vec![1])));
+	assert_eq!(write_of(&[1]).extrinsics, vec![0]);
+	assert_eq!(write_of(&[2]).new_value, Some(StorageData(vec![2])));
+	assert_eq!(write_of(&[2]).extrinsics, vec![1]);
+	assert!(!trace.reads.is_empty());
+
+	// tracing re-executes the block, so it is only allowed for trusted callers
+	let (api, _child) = new_full(
+		client,
+		SubscriptionManager::new(Arc::new(TaskExecutor)),
+		DenyUnsafe::Yes,
+	);
+	assert_matches!(api.trace_block(block_hash).wait(), Err(Error::UnsafeRpcCalled(_)));
+}
+
 #[test]
 fn should_query_storage() {
 	fn run_tests(mut client: Arc<TestClient>, has_changes_trie_config: bool) {
@@ -90,6 +90,7 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 						connected_peers: Default::default(),
 						not_connected_peers: Default::default(),
 						peerset: serde_json::Value::Null,
+						protocols: Default::default(),
 					}).unwrap());
 				},
 				Request::NetworkAddReservedPeer(peer, sender) => {
@@ -104,6 +105,19 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 						Err(s) => sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
 					};
 				}
+				Request::NetworkBanPeer(peer, _, sender) |
+				Request::NetworkUnbanPeer(peer, sender) => {
+					let _ = match peer.parse::<PeerId>() {
+						Ok(_) => sender.send(Ok(())),
+						Err(s) => sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
+					};
+				}
+				Request::NetworkBannedPeers(sender) => {
+					let _ = sender.send(vec![BannedPeerInfo {
+						peer_id: status.peer_id.to_base58(),
+						until: Some(1_700_000_000),
+					}]);
+				}
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				}
@@ -289,6 +303,7 @@ fn system_network_state() {
 			connected_peers: Default::default(),
 			not_connected_peers: Default::default(),
 			peerset: serde_json::Value::Null,
+			protocols: Default::default(),
 		}
 	);
 }
@@ -337,6 +352,36 @@ fn system_network_remove_reserved() {
 	assert!(runtime.block_on(bad_fut).is_err());
 }
 
+#[test]
+fn system_network_ban_and_unban_peer() {
+	let good_peer_id = "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	let bad_peer_id = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	assert_eq!(runtime.block_on(api(None).system_ban_peer(good_peer_id.into(), Some(60))), Ok(()));
+	assert_eq!(runtime.block_on(api(None).system_ban_peer(good_peer_id.into(), None)), Ok(()));
+	assert!(runtime.block_on(api(None).system_ban_peer(bad_peer_id.into(), None)).is_err());
+	assert_eq!(runtime.block_on(api(None).system_unban_peer(good_peer_id.into())), Ok(()));
+	assert!(runtime.block_on(api(None).system_unban_peer(bad_peer_id.into())).is_err());
+}
+
+#[test]
+fn system_banned_peers() {
+	let peer_id = PeerId::random();
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let banned = runtime.block_on(api(Status {
+		peer_id: peer_id.clone(),
+		..Default::default()
+	}).system_banned_peers()).unwrap();
+
+	assert_eq!(banned, vec![BannedPeerInfo { peer_id: peer_id.to_base58(), until: Some(1_700_000_000) }]);
+	assert_eq!(
+		::serde_json::to_string(&banned).unwrap(),
+		format!(r#"[{{"peerId":"{}","until":1700000000}}]"#, peer_id.to_base58()),
+	);
+}
+
 #[test]
 fn test_add_reset_log_filter() {
 	const EXPECTED_BEFORE_ADD: &'static str = "EXPECTED_BEFORE_ADD";
@@ -42,7 +42,9 @@ use sc_keystore::LocalKeystore;
 use log::{info, warn};
 use sc_network::config::{Role, OnDemand};
 use sc_network::NetworkService;
+use sc_network::bitswap::BitswapRequestHandler;
 use sc_network::block_request_handler::{self, BlockRequestHandler};
+use sc_network::state_request_handler::{self, StateRequestHandler};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
 	Block as BlockT, HashFor, Zero, BlockIdTo,
@@ -71,6 +73,7 @@ use sc_client_api::{
 	BlockBackend, BlockchainEvents,
 	backend::StorageProvider,
 	proof_provider::ProofProvider,
+	storage_trace::StorageTraceProvider,
 	execution_extensions::ExecutionExtensions
 };
 use sp_blockchain::{HeaderMetadata, HeaderBackend};
@@ -515,7 +518,7 @@ pub fn build_offchain_workers<TBl, TBackend, TCl>(
 	spawn_handle: SpawnTaskHandle,
 	client: Arc<TCl>,
 	network: Arc<NetworkService<TBl, <TBl as BlockT>::Hash>>,
-) -> Option<Arc<sc_offchain::OffchainWorkers<TCl, TBackend::OffchainStorage, TBl>>>
+) -> Result<Option<Arc<sc_offchain::OffchainWorkers<TCl, TBackend::OffchainStorage, TBl>>>, Error>
 	where
 		TBl: BlockT, TBackend: sc_client_api::Backend<TBl>,
 		<TBackend as sc_client_api::Backend<TBl>>::OffchainStorage: 'static,
@@ -524,7 +527,16 @@ pub fn build_offchain_workers<TBl, TBackend, TCl>(
 {
 	let offchain_workers = match backend.offchain_storage() {
 		Some(db) => {
-			Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone(), db)))
+			let http_config = sc_offchain::HttpConfig {
+				proxy: config.offchain_worker.http_proxy.clone(),
+				ca_certificates: config.offchain_worker.http_ca_certificates.clone(),
+			};
+			let offchain_workers = sc_offchain::OffchainWorkers::with_http_config(
+				client.clone(),
+				db,
+				&http_config,
+			)?.with_scheduling_policy(config.offchain_worker.scheduling_policy.clone());
+			Some(Arc::new(offchain_workers))
 		},
 		None => {
 			warn!("Offchain workers disabled, due to lack of offchain storage support in backend.");
@@ -546,7 +558,7 @@ pub fn build_offchain_workers<TBl, TBackend, TCl>(
 		);
 	}
 
-	offchain_workers
+	Ok(offchain_workers)
 }
 
 /// Spawn the tasks that are required to run a node.
@@ -556,6 +568,7 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	where
 		TCl: ProvideRuntimeApi<TBl> + HeaderMetadata<TBl, Error=sp_blockchain::Error> + Chain<TBl> +
 		BlockBackend<TBl> + BlockIdTo<TBl, Error=sp_blockchain::Error> + ProofProvider<TBl> +
+		StorageTraceProvider<TBl> +
 		HeaderBackend<TBl> + BlockchainEvents<TBl> + ExecutorProvider<TBl> + UsageProvider<TBl> +
 		StorageProvider<TBl, TBackend> + CallApiAt<TBl, Error=sp_blockchain::Error> +
 		Send + 'static,
@@ -747,7 +760,7 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 		TBl: BlockT,
 		TCl: ProvideRuntimeApi<TBl> + BlockchainEvents<TBl> + HeaderBackend<TBl> +
 		HeaderMetadata<TBl, Error=sp_blockchain::Error> + ExecutorProvider<TBl> +
-		CallApiAt<TBl, Error=sp_blockchain::Error> + ProofProvider<TBl> +
+		CallApiAt<TBl, Error=sp_blockchain::Error> + ProofProvider<TBl> + StorageTraceProvider<TBl> +
 		StorageProvider<TBl, TBackend> + BlockBackend<TBl> + Send + Sync + 'static,
 		TExPool: MaintainedTransactionPool<Block=TBl, Hash = <TBl as BlockT>::Hash> + 'static,
 		TBackend: sc_client_api::backend::Backend<TBl> + 'static,
@@ -798,6 +811,11 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 		(chain, state, child_state)
 	};
 
+	let maybe_offchain_rpc = offchain_storage.map(|storage| {
+		let offchain = sc_rpc::offchain::Offchain::new(storage, client.clone(), deny_unsafe);
+		offchain::OffchainApi::to_delegate(offchain)
+	});
+
 	let author = sc_rpc::author::Author::new(
 		client,
 		transaction_pool,
@@ -807,11 +825,6 @@ fn gen_handler<TBl, TBackend, TExPool, TRpc, TCl>(
 	);
 	let system = system::System::new(system_info, system_rpc_tx, deny_unsafe);
 
-	let maybe_offchain_rpc = offchain_storage.map(|storage| {
-		let offchain = sc_rpc::offchain::Offchain::new(storage, deny_unsafe);
-		offchain::OffchainApi::to_delegate(offchain)
-	});
-
 	sc_rpc_server::rpc_handler(
 		(
 			state::StateApi::to_delegate(state),
@@ -900,6 +913,28 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 		}
 	};
 
+	let mut network_config = config.network.clone();
+	network_config.request_response_protocols.push(if matches!(config.role, Role::Light) {
+		// Allow outgoing requests but deny incoming requests.
+		state_request_handler::generate_protocol_config(protocol_id.clone())
+	} else {
+		// Allow both outgoing and incoming requests.
+		let (handler, protocol_config) = StateRequestHandler::new(
+			protocol_id.clone(),
+			client.clone(),
+		);
+		spawn_handle.spawn("state_request_handler", handler.run());
+		protocol_config
+	});
+
+	let bitswap = if config.network.ipfs_server {
+		let (handler, bitswap) = BitswapRequestHandler::new(client.clone());
+		spawn_handle.spawn("bitswap_request_handler", handler.run());
+		Some(bitswap)
+	} else {
+		None
+	};
+
 	let network_params = sc_network::config::Params {
 		role: config.role.clone(),
 		executor: {
@@ -908,7 +943,7 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 				spawn_handle.spawn("libp2p-node", fut);
 			}))
 		},
-		network_config: config.network.clone(),
+		network_config,
 		chain: client.clone(),
 		on_demand: on_demand,
 		transaction_pool: transaction_pool_adapter as _,
@@ -917,6 +952,7 @@ pub fn build_network<TBl, TExPool, TImpQu, TCl>(
 		block_announce_validator,
 		metrics_registry: config.prometheus_config.as_ref().map(|config| config.registry.clone()),
 		block_request_protocol_config,
+		bitswap,
 	};
 
 	let has_bootnodes = !network_params.network_config.boot_nodes.is_empty();
@@ -23,7 +23,7 @@ use sp_runtime::{
 };
 use sp_state_machine::{
 	self, OverlayedChanges, Ext, ExecutionManager, StateMachine, ExecutionStrategy,
-	backend::Backend as _, StorageProof,
+	backend::Backend as _, StorageProof, ReadRecordingBackend, RecordedReads,
 };
 use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use sp_externalities::Extensions;
@@ -294,6 +294,45 @@ where
 		.map_err(Into::into)
 	}
 
+	fn call_recording_reads(
+		&self,
+		id: &BlockId<Block>,
+		overlay: &mut OverlayedChanges,
+		method: &str,
+		call_data: &[u8],
+		strategy: ExecutionStrategy,
+		extensions: Option<Extensions>,
+	) -> sp_blockchain::Result<(Vec<u8>, RecordedReads)> {
+		let changes_trie = backend::changes_tries_state_at_block(
+			id, self.backend.changes_trie_storage()
+		)?;
+		let state = self.backend.state_at(*id)?;
+		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = state_runtime_code.runtime_code()
+			.map_err(sp_blockchain::Error::RuntimeCode)?;
+		let runtime_code = self.check_override(runtime_code, id)?;
+
+		// Recording starts only after the runtime code has been fetched, so that `:code`
+		// only shows up in the reads if the call itself accesses it.
+		let recording_state = ReadRecordingBackend::new(&state);
+		let return_data = StateMachine::new(
+			&recording_state,
+			changes_trie,
+			overlay,
+			&self.executor,
+			method,
+			call_data,
+			extensions.unwrap_or_default(),
+			&runtime_code,
+			self.spawn_handle.clone(),
+		).execute_using_consensus_failure_handler::<_, NeverNativeValue, fn() -> _>(
+			strategy.get_manager(),
+			None,
+		)?;
+
+		Ok((return_data.into_encoded(), recording_state.into_reads()))
+	}
+
 	fn native_runtime_version(&self) -> Option<&NativeVersion> {
 		Some(self.executor.native_version())
 	}
@@ -45,9 +45,9 @@ use sp_runtime::{
 	},
 };
 use sp_state_machine::{
-	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
-	ChangesTrieConfigurationRange, key_changes, key_changes_proof,
+	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId, OverlayedChanges,
+	prove_read, prove_child_read, prove_range_read, read_range_on_backend, ChangesTrieRootsStorage,
+	ChangesTrieStorage, ChangesTrieConfigurationRange, key_changes, key_changes_proof,
 };
 use sc_executor::RuntimeVersion;
 use sp_consensus::{
@@ -82,7 +82,8 @@ use sc_client_api::{
 	execution_extensions::ExecutionExtensions,
 	notifications::{StorageNotifications, StorageEventStream},
 	KeyIterator, CallExecutor, ExecutorProvider, ProofProvider,
-	cht, UsageProvider
+	cht, UsageProvider,
+	storage_trace::{StorageTraceProvider, BlockStorageTrace, StorageRead, StorageWrite},
 };
 use sp_utils::mpsc::{TracingUnboundedSender, tracing_unbounded};
 use sp_blockchain::Error;
@@ -1211,6 +1212,45 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	fn read_range_proof(
+		&self,
+		id: &BlockId<Block>,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> sp_blockchain::Result<(Vec<(StorageKey, StorageData)>, StorageProof)> {
+		let state = self.state_at(id)?;
+		let (pairs, proof) = prove_range_read(
+			state,
+			prefix.map(|prefix| &prefix.0[..]),
+			start_key.map(|key| &key.0[..]),
+			count,
+		)?;
+		let pairs = pairs.into_iter()
+			.map(|(key, value)| (StorageKey(key), StorageData(value)))
+			.collect();
+		Ok((pairs, proof))
+	}
+
+	fn read_range(
+		&self,
+		id: &BlockId<Block>,
+		prefix: Option<&StorageKey>,
+		start_key: Option<&StorageKey>,
+		count: usize,
+	) -> sp_blockchain::Result<Vec<(StorageKey, StorageData)>> {
+		let state = self.state_at(id)?;
+		let pairs = read_range_on_backend(
+			&state,
+			prefix.map(|prefix| &prefix.0[..]),
+			start_key.map(|key| &key.0[..]),
+			count,
+		)?;
+		Ok(pairs.into_iter()
+			.map(|(key, value)| (StorageKey(key), StorageData(value)))
+			.collect())
+	}
+
 	fn execution_proof(
 		&self,
 		id: &BlockId<Block>,
@@ -1265,6 +1305,70 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 }
 
 
+impl<B, E, Block, RA> StorageTraceProvider<Block> for Client<B, E, Block, RA> where
+	B: backend::Backend<Block>,
+	E: CallExecutor<Block>,
+	Block: BlockT,
+{
+	fn trace_block_storage(&self, id: &BlockId<Block>) -> sp_blockchain::Result<BlockStorageTrace> {
+		let header = self.backend.blockchain().expect_header(*id)?;
+		let extrinsics = self.body(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("Missing body of block {:?}", id)))?;
+		let parent = BlockId::Hash(*header.parent_hash());
+
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_collect_extrinsics(true);
+		let (_, recorded_reads) = self.executor.call_recording_reads(
+			&parent,
+			&mut overlay,
+			"Core_execute_block",
+			&Block::new(header, extrinsics).encode(),
+			self.execution_extensions.strategies().importing,
+			None,
+		)?;
+
+		let prefixed_child_key = |storage_key: &[u8]| {
+			ChildInfo::new_default(storage_key).prefixed_storage_key()
+		};
+		let reads = recorded_reads.into_iter()
+			.map(|((child_storage_key, key), value)| StorageRead {
+				child_storage_key: child_storage_key.as_ref().map(|k| prefixed_child_key(k)),
+				key: StorageKey(key),
+				value: value.map(StorageData),
+			})
+			.collect();
+
+		let state = self.state_at(&parent)?;
+		let state_err = |e| Error::from_state(Box::new(e));
+		let mut writes = Vec::new();
+		for (key, entry) in overlay.changes() {
+			writes.push(StorageWrite {
+				child_storage_key: None,
+				key: StorageKey(key.clone()),
+				old_value: state.storage(key).map_err(state_err)?.map(StorageData),
+				new_value: entry.value().cloned().map(StorageData),
+				extrinsics: entry.extrinsics().into_iter().collect(),
+			});
+		}
+		let mut children = overlay.children().collect::<Vec<_>>();
+		children.sort_by(|(_, a), (_, b)| a.storage_key().cmp(b.storage_key()));
+		for (changes, child_info) in children {
+			for (key, entry) in changes {
+				writes.push(StorageWrite {
+					child_storage_key: Some(child_info.prefixed_storage_key()),
+					key: StorageKey(key.clone()),
+					old_value: state.child_storage(child_info, key).map_err(state_err)?.map(StorageData),
+					new_value: entry.value().cloned().map(StorageData),
+					extrinsics: entry.extrinsics().into_iter().collect(),
+				});
+			}
+		}
+
+		Ok(BlockStorageTrace { reads, writes })
+	}
+}
+
+
 impl<B, E, Block, RA> BlockBuilderProvider<B, Block, Self> for Client<B, E, Block, RA>
 	where
 		B: backend::Backend<Block> + Send + Sync + 'static,
@@ -1919,6 +2023,21 @@ impl<B, E, Block, RA> BlockBackend<Block> for Client<B, E, Block, RA>
 	fn block_hash(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Option<Block::Hash>> {
 		self.backend.blockchain().hash(number)
 	}
+
+	fn indexed_transaction(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.backend.blockchain().indexed_transaction(hash)
+	}
+}
+
+impl<B, E, Block, RA> sp_transaction_storage_proof::IndexedTransactions<Block> for Client<B, E, Block, RA>
+	where
+		B: backend::Backend<Block>,
+		E: CallExecutor<Block>,
+		Block: BlockT,
+{
+	fn indexed_transaction(&self, hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.backend.blockchain().indexed_transaction(hash)
+	}
 }
 
 impl<B, E, Block, RA> backend::AuxStore for Client<B, E, Block, RA>
@@ -29,6 +29,9 @@ use sc_client_api::execution_extensions::ExecutionStrategies;
 
 use std::{io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_offchain::{
+	SchedulingPolicy as OffchainSchedulingPolicy, WorkerTrigger as OffchainWorkerTrigger,
+};
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::SecretString;
 pub use sc_telemetry::TelemetryEndpoints;
@@ -172,6 +175,13 @@ pub struct OffchainWorkerConfig {
 	pub enabled: bool,
 	/// allow writes from the runtime to the offchain worker database.
 	pub indexing_enabled: bool,
+	/// URI of the HTTP proxy the HTTP requests of the offchain workers go through.
+	pub http_proxy: Option<String>,
+	/// PEM files containing root certificates trusted by the HTTP client of the offchain workers,
+	/// in addition to the ones of the operating system.
+	pub http_ca_certificates: Vec<PathBuf>,
+	/// Policy deciding when the offchain workers are run.
+	pub scheduling_policy: OffchainSchedulingPolicy,
 }
 
 /// Configuration of the Prometheus endpoint.
@@ -55,7 +55,7 @@ use sp_blockchain::{
 };
 use std::panic::UnwindSafe;
 use std::cell::RefCell;
-use sp_state_machine::{OverlayedChanges, ExecutionManager};
+use sp_state_machine::{OverlayedChanges, ExecutionManager, RecordedReads};
 use parity_scale_codec::{Decode, Encode};
 use super::prepare_client_with_key_changes;
 use substrate_test_runtime_client::{
@@ -252,6 +252,18 @@ impl CallExecutor<Block> for DummyCallExecutor {
 		unreachable!()
 	}
 
+	fn call_recording_reads(
+		&self,
+		_id: &BlockId<Block>,
+		_overlay: &mut OverlayedChanges,
+		_method: &str,
+		_call_data: &[u8],
+		_strategy: ExecutionStrategy,
+		_extensions: Option<Extensions>,
+	) -> Result<(Vec<u8>, RecordedReads), ClientError> {
+		unreachable!()
+	}
+
 	fn native_runtime_version(&self) -> Option<&NativeVersion> {
 		unreachable!()
 	}
@@ -81,7 +81,8 @@
 //! * `force_destroy`: Destroys an asset class.
 //!
 //! ### Privileged Functions
-//! * `destroy`: Destroys an entire asset class; called by the asset class's Owner.
+//! * `destroy`: Destroys an entire asset class, possibly across several calls; called by the asset
+//!   class's Owner.
 //! * `mint`: Increases the asset balance of an account; called by the asset class's Issuer.
 //! * `burn`: Decreases the asset balance of an account; called by the asset class's Admin.
 //! * `force_transfer`: Transfers between arbitrary accounts; called by the asset class's Admin.
@@ -120,7 +121,7 @@ use sp_runtime::{RuntimeDebug, traits::{
 use codec::{Encode, Decode, HasCompact};
 use frame_support::{Parameter, decl_module, decl_event, decl_storage, decl_error, ensure,
 	traits::{Currency, ReservableCurrency, EnsureOrigin, Get, BalanceStatus::Reserved},
-	dispatch::{DispatchResult, DispatchError},
+	dispatch::{DispatchResult, DispatchError}, storage::KillOutcome,
 };
 use frame_system::ensure_signed;
 pub use weights::WeightInfo;
@@ -274,6 +275,9 @@ decl_event! {
 		AssetThawed(AssetId),
 		/// An asset class was destroyed.
 		Destroyed(AssetId),
+		/// Some zombie accounts of an asset class were removed, but others remain.
+		/// \[asset_id, remaining_zombies\]
+		PartiallyDestroyed(AssetId, u32),
 		/// Some asset class was force-created. \[asset_id, owner\]
 		ForceCreated(AssetId, AccountId),
 		/// The maximum amount of zombies allowed has changed. \[asset_id, max_zombies\]
@@ -436,9 +440,14 @@ decl_module! {
 		/// - `id`: The identifier of the asset to be destroyed. This must identify an existing
 		/// asset.
 		///
-		/// Emits `Destroyed` event when successful.
+		/// - `zombies_witness`: The maximum number of zombie accounts to remove in this call.
+		///
+		/// If more than `zombies_witness` zombie accounts exist, only that many are removed, the
+		/// asset class is frozen and the call must be repeated until all accounts are gone.
+		///
+		/// Emits `PartiallyDestroyed` event if zombie accounts remain, `Destroyed` event otherwise.
 		///
-		/// Weight: `O(z)` where `z` is the number of zombie accounts.
+		/// Weight: `O(z)` where `z` is `zombies_witness`.
 		#[weight = T::WeightInfo::destroy(*zombies_witness)]
 		fn destroy(origin,
 			#[compact] id: T::AssetId,
@@ -447,17 +456,32 @@ decl_module! {
 			let origin = ensure_signed(origin)?;
 
 			Asset::<T>::try_mutate_exists(id, |maybe_details| {
-				let details = maybe_details.take().ok_or(Error::<T>::Unknown)?;
+				let mut details = maybe_details.take().ok_or(Error::<T>::Unknown)?;
 				ensure!(details.owner == origin, Error::<T>::NoPermission);
 				ensure!(details.accounts == details.zombies, Error::<T>::RefsLeft);
-				ensure!(details.zombies <= zombies_witness, Error::<T>::BadWitness);
-
-				let metadata = Metadata::<T>::take(&id);
-				T::Currency::unreserve(&details.owner, details.deposit.saturating_add(metadata.deposit));
-
-				*maybe_details = None;
-				Account::<T>::remove_prefix(&id);
-				Self::deposit_event(RawEvent::Destroyed(id));
+				ensure!(details.zombies == 0 || zombies_witness > 0, Error::<T>::BadWitness);
+
+				match Account::<T>::remove_prefix(&id, Some(zombies_witness)) {
+					KillOutcome::AllRemoved(_) => {
+						let metadata = Metadata::<T>::take(&id);
+						T::Currency::unreserve(
+							&details.owner,
+							details.deposit.saturating_add(metadata.deposit),
+						);
+						Self::deposit_event(RawEvent::Destroyed(id));
+					}
+					KillOutcome::SomeRemaining(removed) => {
+						details.zombies = details.zombies.saturating_sub(removed);
+						details.accounts = details.accounts.saturating_sub(removed);
+						details.is_frozen = true;
+						Self::deposit_event(RawEvent::PartiallyDestroyed(id, details.zombies));
+						*maybe_details = Some(details);
+					}
+					// The prefix of an asset's accounts never covers a child storage root.
+					KillOutcome::Refused => {
+						return Err(DispatchError::Other("Asset accounts are not removable"))
+					}
+				}
 				Ok(())
 			})
 		}
@@ -488,7 +512,7 @@ decl_module! {
 				T::Currency::unreserve(&details.owner, details.deposit.saturating_add(metadata.deposit));
 
 				*maybe_details = None;
-				Account::<T>::remove_prefix(&id);
+				Account::<T>::remove_prefix(&id, None);
 				Self::deposit_event(RawEvent::Destroyed(id));
 				Ok(())
 			})
@@ -1253,6 +1277,66 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn destroy_across_multiple_calls_should_work() {
+		let mut ext = new_test_ext();
+		ext.execute_with(|| {
+			Balances::make_free_balance_be(&1, 100);
+			assert_ok!(Assets::create(Origin::signed(1), 0, 1, 10, 1));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 10, 100));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 20, 100));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 30, 100));
+		});
+		ext.commit_all().unwrap();
+		ext.execute_with(|| {
+			assert_ok!(Assets::destroy(Origin::signed(1), 0, 2));
+			assert_eq!(Account::<Test>::iter_prefix(0).count(), 1);
+			let details = Asset::<Test>::get(0).unwrap();
+			assert_eq!(details.zombies, 1);
+			assert!(details.is_frozen);
+			assert_eq!(Balances::reserved_balance(&1), 11);
+		});
+		ext.commit_all().unwrap();
+		ext.execute_with(|| {
+			assert_ok!(Assets::destroy(Origin::signed(1), 0, 2));
+			assert!(!Asset::<Test>::contains_key(0));
+			assert_eq!(Account::<Test>::iter_prefix(0).count(), 0);
+			assert_eq!(Balances::reserved_balance(&1), 0);
+		});
+	}
+
+	#[test]
+	fn destroy_across_multiple_calls_in_the_same_block_should_work() {
+		let mut ext = new_test_ext();
+		ext.execute_with(|| {
+			Balances::make_free_balance_be(&1, 100);
+			assert_ok!(Assets::create(Origin::signed(1), 0, 1, 10, 1));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 10, 100));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 20, 100));
+			assert_ok!(Assets::mint(Origin::signed(1), 0, 30, 100));
+		});
+		ext.commit_all().unwrap();
+		ext.execute_with(|| {
+			assert_ok!(Assets::destroy(Origin::signed(1), 0, 1));
+			let details = Asset::<Test>::get(0).unwrap();
+			assert_eq!(details.zombies, 2);
+			assert_eq!(details.accounts, 2);
+			assert_eq!(Account::<Test>::iter_prefix(0).count(), 2);
+
+			// Accounts removed by the previous call are neither removed nor counted again.
+			assert_ok!(Assets::destroy(Origin::signed(1), 0, 1));
+			let details = Asset::<Test>::get(0).unwrap();
+			assert_eq!(details.zombies, 1);
+			assert_eq!(details.accounts, 1);
+			assert_eq!(Account::<Test>::iter_prefix(0).count(), 1);
+
+			assert_ok!(Assets::destroy(Origin::signed(1), 0, 1));
+			assert!(!Asset::<Test>::contains_key(0));
+			assert_eq!(Account::<Test>::iter_prefix(0).count(), 0);
+			assert_eq!(Balances::reserved_balance(&1), 0);
+		});
+	}
+
 	#[test]
 	fn max_zombies_should_work() {
 		new_test_ext().execute_with(|| {
@@ -29,6 +29,13 @@
 //! ### Public Functions
 //!
 //! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration.
+//! - `submit_unsigned_equivocation_report` - Submit an unsigned equivocation report from an
+//! offchain context (e.g. the block import pipeline of an authoring node).
+//!
+//! ### Dispatchable Functions
+//!
+//! - `report_equivocation` - Report an authority that authored two blocks in the same slot.
+//! - `report_equivocation_unsigned` - Same as above, but only for local or in-block submission.
 //!
 //! ## Related Modules
 //!
@@ -49,26 +56,82 @@ use pallet_timestamp;
 use sp_std::{result, prelude::*};
 use codec::{Encode, Decode};
 use frame_support::{
-	decl_storage, decl_module, Parameter, traits::{Get, FindAuthor},
+	decl_storage, decl_module, decl_error, Parameter,
+	dispatch::DispatchResultWithPostInfo,
+	traits::{Get, FindAuthor, KeyOwnerProofSystem},
+	weights::{Pays, Weight},
 	ConsensusEngineId,
 };
+use frame_system::{ensure_none, ensure_signed};
 use sp_runtime::{
-	RuntimeAppPublic,
+	RuntimeAppPublic, KeyTypeId,
 	traits::{SaturatedConversion, Saturating, Zero, Member, IsMember}, generic::DigestItem,
 };
+use sp_session::{GetSessionNumber, GetValidatorCount};
+use sp_staking::SessionIndex;
 use sp_timestamp::OnTimestampSet;
 use sp_inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
 use sp_consensus_aura::{
-	AURA_ENGINE_ID, ConsensusLog, AuthorityIndex,
+	AURA_ENGINE_ID, ConsensusLog, AuthorityIndex, EquivocationProof, SlotNumber,
 	inherents::{INHERENT_IDENTIFIER, AuraInherentData},
 };
 
+mod equivocation;
+mod default_weights;
+
 mod mock;
 mod tests;
 
+pub use equivocation::{AuraEquivocationOffence, EquivocationHandler, HandleEquivocation};
+
 pub trait Config: pallet_timestamp::Config {
 	/// The identifier type for an authority.
 	type AuthorityId: Member + Parameter + RuntimeAppPublic + Default;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, Self::AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, defines methods to report an
+	/// offence (after the equivocation has been validated) and for submitting a
+	/// transaction to report an equivocation (from an offchain context).
+	/// NOTE: when enabling equivocation handling (i.e. this type isn't set to
+	/// `()`) you must use this pallet's `ValidateUnsigned` in the runtime
+	/// definition.
+	type HandleEquivocation: HandleEquivocation<Self>;
+
+	/// The number of sessions, including the current one, whose first slot is kept. Equivocations
+	/// can only be reported for these sessions.
+	type ReportableSessions: Get<SessionIndex>;
+
+	type WeightInfo: WeightInfo;
+}
+
+pub trait WeightInfo {
+	fn report_equivocation(validator_count: u32) -> Weight;
+}
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
+	}
 }
 
 decl_storage! {
@@ -78,15 +141,71 @@ decl_storage! {
 
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
+
+		/// The first slot of the last `ReportableSessions` sessions, used to check that the slot
+		/// of an equivocation report matches the session of its key ownership proof.
+		SessionStartSlot get(fn session_start_slot):
+			map hasher(twox_64_concat) SessionIndex => Option<SlotNumber>;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
-		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+		build(|config| {
+			Module::<T>::initialize_authorities(&config.authorities);
+			SessionStartSlot::insert(0, 0);
+		})
 	}
 }
 
 decl_module! {
-	pub struct Module<T: Config> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// The number of sessions, including the current one, for which equivocations can be
+		/// reported.
+		const ReportableSessions: SessionIndex = T::ReportableSessions::get();
+
+		type Error = Error<T>;
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		#[weight = <T as Config>::WeightInfo::report_equivocation(key_owner_proof.validator_count())]
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			let reporter = ensure_signed(origin)?;
+
+			Self::do_report_equivocation(
+				Some(reporter),
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		/// This extrinsic must be called unsigned and it is expected that only
+		/// block authors will call it (validated in `ValidateUnsigned`), as such
+		/// if the block author is defined it will be defined as the equivocation
+		/// reporter.
+		#[weight = <T as Config>::WeightInfo::report_equivocation(key_owner_proof.validator_count())]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+
+			Self::do_report_equivocation(
+				T::HandleEquivocation::block_author(),
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+	}
 }
 
 impl<T: Config> Module<T> {
@@ -106,13 +225,90 @@ impl<T: Config> Module<T> {
 			<Authorities<T>>::put(authorities);
 		}
 	}
+
+	/// The slot of the block currently being executed, as found in its pre-digest.
+	fn current_slot() -> Option<SlotNumber> {
+		<frame_system::Module<T>>::digest()
+			.logs
+			.iter()
+			.filter_map(|log| log.as_pre_runtime())
+			.find(|(id, _)| *id == AURA_ENGINE_ID)
+			.and_then(|(_, mut data)| SlotNumber::decode(&mut data).ok())
+	}
+
+	fn do_report_equivocation(
+		reporter: Option<T::AccountId>,
+		equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResultWithPostInfo {
+		let offender = equivocation_proof.offender.clone();
+		let slot_number = equivocation_proof.slot_number;
+
+		// validate the equivocation proof
+		if !sp_consensus_aura::check_equivocation_proof(equivocation_proof) {
+			return Err(Error::<T>::InvalidEquivocationProof.into());
+		}
+
+		let validator_set_count = key_owner_proof.validator_count();
+		let session_index = key_owner_proof.session();
+
+		// check that the slot number is consistent with the session index
+		// in the key ownership proof (i.e. slot is within that session)
+		let session_start = SessionStartSlot::get(session_index)
+			.ok_or(Error::<T>::InvalidKeyOwnershipProof)?;
+		let session_end = SessionStartSlot::get(session_index + 1);
+
+		if slot_number < session_start || session_end.map_or(false, |end| slot_number >= end) {
+			return Err(Error::<T>::InvalidKeyOwnershipProof.into());
+		}
+
+		// check the membership proof and extract the offender's id
+		let key = (T::AuthorityId::ID, offender);
+		let offender = T::KeyOwnerProofSystem::check_proof(key, key_owner_proof)
+			.ok_or(Error::<T>::InvalidKeyOwnershipProof)?;
+
+		let offence = AuraEquivocationOffence {
+			slot: slot_number,
+			validator_set_count,
+			offender,
+			session_index,
+		};
+
+		let reporters = match reporter {
+			Some(id) => vec![id],
+			None => vec![],
+		};
+
+		T::HandleEquivocation::report_offence(reporters, offence)
+			.map_err(|_| Error::<T>::DuplicateOffenceReport)?;
+
+		// waive the fee since the report is valid and beneficial
+		Ok(Pays::No.into())
+	}
+
+	/// Submits an extrinsic to report an equivocation. This method will create
+	/// an unsigned extrinsic with a call to `report_equivocation_unsigned` and
+	/// will push the transaction to the pool. Only useful in an offchain
+	/// context.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_unsigned_equivocation_report(
+			equivocation_proof,
+			key_owner_proof,
+		)
+		.ok()
+	}
 }
 
 impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
 	type Public = T::AuthorityId;
 }
 
-impl<T: Config> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+impl<T: Config> pallet_session::OneSessionHandler<T::AccountId> for Module<T>
+	where T: pallet_session::Config
+{
 	type Key = T::AuthorityId;
 
 	fn on_genesis_session<'a, I: 'a>(validators: I)
@@ -120,11 +316,23 @@ impl<T: Config> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 	{
 		let authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
 		Self::initialize_authorities(&authorities);
+		SessionStartSlot::insert(0, 0);
 	}
 
 	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
 		where I: Iterator<Item=(&'a T::AccountId, T::AuthorityId)>
 	{
+		// the session module already moved to the new session.
+		let session_index = <pallet_session::Module<T>>::current_index();
+		if let Some(slot) = Self::current_slot() {
+			SessionStartSlot::insert(session_index, slot);
+		}
+
+		// sessions older than the reportable ones can not be reported anymore.
+		if let Some(pruned) = session_index.checked_sub(T::ReportableSessions::get()) {
+			SessionStartSlot::remove(pruned);
+		}
+
 		// instant changes
 		if changed {
 			let next_authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
@@ -19,15 +19,24 @@
 
 #![cfg(test)]
 
-use crate as pallet_aura;
-use sp_consensus_aura::ed25519::AuthorityId;
+use std::cell::RefCell;
+use codec::Encode;
+use crate::{self as pallet_aura, AuraEquivocationOffence, EquivocationHandler};
+use sp_consensus_aura::{
+	AURA_ENGINE_ID, EquivocationProof, SlotNumber,
+	ed25519::{AuthorityId, AuthorityPair},
+};
 use sp_runtime::{
-	traits::IdentityLookup,
-	testing::{Header, UintAuthorityId},
+	impl_opaque_keys, KeyTypeId, Perbill,
+	traits::{ConvertInto, Header as _, IdentityLookup, IsMember, OpaqueKeys},
+	testing::{Digest, DigestItem, Header, TestXt, UintAuthorityId},
 };
-use frame_support::parameter_types;
+use sp_session::MembershipProof;
+use sp_staking::{SessionIndex, offence::{Offence, OffenceError, ReportOffence}};
+use frame_support::{parameter_types, traits::KeyOwnerProofSystem};
+use frame_system::InitKind;
 use sp_io;
-use sp_core::H256;
+use sp_core::{H256, U256, crypto::Pair};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -40,7 +49,9 @@ frame_support::construct_runtime!(
 	{
 		System: frame_system::{Module, Call, Config, Storage, Event<T>},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
-		Aura: pallet_aura::{Module, Call, Storage, Config<T>, Inherent},
+		Authorship: pallet_authorship::{Module, Call, Storage, Inherent},
+		Session: pallet_session::{Module, Call, Storage, Event},
+		Aura: pallet_aura::{Module, Call, Storage, Config<T>, Inherent, ValidateUnsigned},
 	}
 );
 
@@ -83,8 +94,162 @@ impl pallet_timestamp::Config for Test {
 	type WeightInfo = ();
 }
 
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = TestXt<Call, ()>;
+}
+
+impl pallet_authorship::Config for Test {
+	type FindAuthor = ();
+	type UncleGenerations = ();
+	type FilterUncle = ();
+	type EventHandler = ();
+}
+
+impl_opaque_keys! {
+	pub struct MockSessionKeys {
+		pub aura: Aura,
+	}
+}
+
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(17);
+	pub const ReportableSessions: SessionIndex = 2;
+}
+
+impl pallet_session::Config for Test {
+	type Event = Event;
+	type ValidatorId = u64;
+	type ValidatorIdOf = ConvertInto;
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
+	type SessionManager = ();
+	type SessionHandler = <MockSessionKeys as OpaqueKeys>::KeyTypeIdProviders;
+	type Keys = MockSessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+	type WeightInfo = ();
+}
+
 impl pallet_aura::Config for Test {
 	type AuthorityId = AuthorityId;
+	type KeyOwnerProof = MembershipProof;
+	type KeyOwnerIdentification = AuthorityId;
+	type KeyOwnerProofSystem = MockKeyOwnerProofSystem;
+	type HandleEquivocation = EquivocationHandler<AuthorityId, OffenceRecorder>;
+	type ReportableSessions = ReportableSessions;
+	type WeightInfo = ();
+}
+
+/// A key ownership proof system that only knows about the current authorities.
+pub struct MockKeyOwnerProofSystem;
+
+impl KeyOwnerProofSystem<(KeyTypeId, AuthorityId)> for MockKeyOwnerProofSystem {
+	type Proof = MembershipProof;
+	type IdentificationTuple = AuthorityId;
+
+	fn prove(key: (KeyTypeId, AuthorityId)) -> Option<Self::Proof> {
+		if !Aura::is_member(&key.1) {
+			return None;
+		}
+
+		Some(MembershipProof {
+			session: Session::current_index(),
+			trie_nodes: Vec::new(),
+			validator_count: Aura::authorities().len() as u32,
+		})
+	}
+
+	fn check_proof(key: (KeyTypeId, AuthorityId), _proof: Self::Proof) -> Option<AuthorityId> {
+		if key.0 == sp_core::crypto::key_types::AURA && Aura::is_member(&key.1) {
+			Some(key.1)
+		} else {
+			None
+		}
+	}
+}
+
+thread_local! {
+	pub static OFFENCES: RefCell<Vec<(Vec<u64>, SessionIndex, SlotNumber, AuthorityId)>> =
+		RefCell::new(Vec::new());
+}
+
+/// Records all reported offences, rejecting duplicates.
+pub struct OffenceRecorder;
+
+impl ReportOffence<u64, AuthorityId, AuraEquivocationOffence<AuthorityId>> for OffenceRecorder {
+	fn report_offence(
+		reporters: Vec<u64>,
+		offence: AuraEquivocationOffence<AuthorityId>,
+	) -> Result<(), OffenceError> {
+		if Self::is_known_offence(&offence.offenders(), &offence.time_slot()) {
+			return Err(OffenceError::DuplicateReport);
+		}
+
+		OFFENCES.with(|offences| offences.borrow_mut().push((
+			reporters,
+			offence.session_index,
+			offence.slot,
+			offence.offender,
+		)));
+
+		Ok(())
+	}
+
+	fn is_known_offence(offenders: &[AuthorityId], time_slot: &SlotNumber) -> bool {
+		OFFENCES.with(|offences| offenders.iter().all(|offender| {
+			offences.borrow().iter().any(|(_, _, slot, o)| slot == time_slot && o == offender)
+		}))
+	}
+}
+
+pub fn offences() -> Vec<(Vec<u64>, SessionIndex, SlotNumber, AuthorityId)> {
+	OFFENCES.with(|offences| offences.borrow().clone())
+}
+
+pub fn make_pre_digest(slot_number: SlotNumber) -> Digest {
+	Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot_number.encode())] }
+}
+
+/// Start a new session at the given block and slot.
+pub fn start_session(block_number: u64, slot_number: SlotNumber) {
+	System::initialize(
+		&block_number,
+		&System::parent_hash(),
+		&make_pre_digest(slot_number),
+		InitKind::Full,
+	);
+	Session::rotate_session();
+}
+
+/// Build a proof that the given authority sealed two different headers at the same slot.
+pub fn generate_equivocation_proof(
+	offender: &AuthorityPair,
+	slot_number: SlotNumber,
+) -> EquivocationProof<Header, AuthorityId> {
+	let make_header = |state_root| {
+		let mut header = Header::new(
+			System::block_number(),
+			H256::default(),
+			state_root,
+			System::parent_hash(),
+			make_pre_digest(slot_number),
+		);
+		let signature = offender.sign(header.hash().as_ref());
+		header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature.encode()));
+		header
+	};
+
+	EquivocationProof {
+		offender: offender.public(),
+		slot_number,
+		first_header: make_header(H256::from_low_u64_be(1)),
+		second_header: make_header(H256::from_low_u64_be(2)),
+	}
 }
 
 pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
@@ -94,3 +259,18 @@ pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
 	}.assimilate_storage(&mut t).unwrap();
 	t.into()
 }
+
+pub fn new_test_ext_with_pairs(
+	authorities_len: usize,
+) -> (Vec<AuthorityPair>, sp_io::TestExternalities) {
+	let pairs = (0..authorities_len).map(|i| {
+		AuthorityPair::from_seed(&U256::from(i).into())
+	}).collect::<Vec<_>>();
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_aura::GenesisConfig::<Test>{
+		authorities: pairs.iter().map(|p| p.public()).collect(),
+	}.assimilate_storage(&mut t).unwrap();
+
+	(pairs, t.into())
+}
@@ -19,7 +19,13 @@
 
 #![cfg(test)]
 
-use crate::mock::{Aura, new_test_ext};
+use crate::{Call, Error, mock::*};
+use frame_support::{assert_err, assert_ok, unsigned::ValidateUnsigned, weights::Pays};
+use sp_core::crypto::Pair;
+use sp_runtime::transaction_validity::{
+	InvalidTransaction, TransactionSource, TransactionValidityError,
+};
+use sp_session::MembershipProof;
 
 #[test]
 fn initial_values() {
@@ -28,3 +34,235 @@ fn initial_values() {
 		assert_eq!(Aura::authorities().len(), 4);
 	});
 }
+
+#[test]
+fn report_equivocation_works() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_session(1, 10);
+		assert_eq!(Session::current_index(), 1);
+		assert_eq!(Aura::session_start_slot(1), Some(10));
+
+		let equivocation_proof = generate_equivocation_proof(&pairs[1], 12);
+		let key_owner_proof = MembershipProof { session: 1, trie_nodes: vec![], validator_count: 3 };
+
+		let post_info = Aura::report_equivocation(
+			Origin::signed(1),
+			equivocation_proof,
+			key_owner_proof,
+		).unwrap();
+
+		assert_eq!(post_info.pays_fee, Pays::No);
+		assert_eq!(offences(), vec![(vec![1], 1, 12, pairs[1].public())]);
+	});
+}
+
+#[test]
+fn report_equivocation_invalid_equivocation_proof() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		let key_owner_proof = MembershipProof { session: 0, trie_nodes: vec![], validator_count: 3 };
+
+		let assert_invalid_equivocation_proof = |equivocation_proof| {
+			assert_err!(
+				Aura::report_equivocation(
+					Origin::signed(1),
+					equivocation_proof,
+					key_owner_proof.clone(),
+				),
+				Error::<Test>::InvalidEquivocationProof,
+			);
+		};
+
+		// both headers are the same
+		let mut equivocation_proof = generate_equivocation_proof(&pairs[0], 5);
+		equivocation_proof.second_header = equivocation_proof.first_header.clone();
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the slot number in the proof doesn't match the headers
+		let mut equivocation_proof = generate_equivocation_proof(&pairs[0], 5);
+		equivocation_proof.slot_number = 6;
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the headers target different slots
+		let mut equivocation_proof = generate_equivocation_proof(&pairs[0], 5);
+		equivocation_proof.second_header = generate_equivocation_proof(&pairs[0], 6).second_header;
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		// the headers were signed by a different authority
+		let mut equivocation_proof = generate_equivocation_proof(&pairs[0], 5);
+		equivocation_proof.offender = pairs[1].public();
+		assert_invalid_equivocation_proof(equivocation_proof);
+
+		assert!(offences().is_empty());
+	});
+}
+
+#[test]
+fn session_start_slots_are_pruned() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		assert_eq!(Aura::session_start_slot(0), Some(0));
+
+		start_session(1, 10);
+		assert_eq!(Aura::session_start_slot(0), Some(0));
+		assert_eq!(Aura::session_start_slot(1), Some(10));
+
+		// only the last `ReportableSessions` sessions are kept.
+		start_session(2, 20);
+		assert_eq!(Aura::session_start_slot(0), None);
+		assert_eq!(Aura::session_start_slot(1), Some(10));
+		assert_eq!(Aura::session_start_slot(2), Some(20));
+
+		start_session(3, 30);
+		assert_eq!(Aura::session_start_slot(1), None);
+		assert_eq!(Aura::session_start_slot(2), Some(20));
+		assert_eq!(Aura::session_start_slot(3), Some(30));
+
+		// equivocations of pruned sessions can not be reported anymore.
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&pairs[0], 15),
+				MembershipProof { session: 1, trie_nodes: vec![], validator_count: 3 },
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+		assert_ok!(Aura::report_equivocation(
+			Origin::signed(1),
+			generate_equivocation_proof(&pairs[0], 25),
+			MembershipProof { session: 2, trie_nodes: vec![], validator_count: 3 },
+		));
+	});
+}
+
+#[test]
+fn report_equivocation_invalid_key_owner_proof() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_session(1, 10);
+
+		let key_owner_proof = |session| {
+			MembershipProof { session, trie_nodes: vec![], validator_count: 3 }
+		};
+
+		// the slot belongs to session 0
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&pairs[0], 9),
+				key_owner_proof(1),
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// the slot belongs to session 1
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&pairs[0], 10),
+				key_owner_proof(0),
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// the session is not known yet
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&pairs[0], 10),
+				key_owner_proof(2),
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// the offender is not an authority
+		let outsider = sp_consensus_aura::ed25519::AuthorityPair::from_seed(&[42; 32]);
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&outsider, 10),
+				key_owner_proof(1),
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// a slot from the previous session with the matching proof
+		assert_ok!(Aura::report_equivocation(
+			Origin::signed(1),
+			generate_equivocation_proof(&pairs[0], 9),
+			key_owner_proof(0),
+		));
+	});
+}
+
+#[test]
+fn report_equivocation_rejects_duplicates() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		let key_owner_proof = MembershipProof { session: 0, trie_nodes: vec![], validator_count: 3 };
+
+		assert_ok!(Aura::report_equivocation_unsigned(
+			Origin::none(),
+			generate_equivocation_proof(&pairs[2], 3),
+			key_owner_proof.clone(),
+		));
+
+		assert_err!(
+			Aura::report_equivocation(
+				Origin::signed(1),
+				generate_equivocation_proof(&pairs[2], 3),
+				key_owner_proof,
+			),
+			Error::<Test>::DuplicateOffenceReport,
+		);
+
+		assert_eq!(offences().len(), 1);
+	});
+}
+
+#[test]
+fn report_equivocation_validate_unsigned_prevents_duplicates() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		let equivocation_proof = generate_equivocation_proof(&pairs[0], 4);
+		let key_owner_proof = MembershipProof { session: 0, trie_nodes: vec![], validator_count: 3 };
+		let call = Call::report_equivocation_unsigned(
+			equivocation_proof.clone(),
+			key_owner_proof.clone(),
+		);
+
+		// only local/inblock reports are allowed
+		assert_eq!(
+			<Aura as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into(),
+		);
+
+		// the transaction is valid when passed as local
+		let valid = <Aura as ValidateUnsigned>::validate_unsigned(TransactionSource::Local, &call)
+			.unwrap();
+		assert!(!valid.propagate);
+
+		// the pre dispatch checks should also pass
+		assert_ok!(<Aura as ValidateUnsigned>::pre_dispatch(&call));
+
+		// we submit the report
+		assert_ok!(Aura::report_equivocation_unsigned(
+			Origin::none(),
+			equivocation_proof,
+			key_owner_proof,
+		));
+
+		// the report should now be considered stale and the transaction is invalid
+		assert_err!(
+			<Aura as ValidateUnsigned>::pre_dispatch(&call),
+			TransactionValidityError::Invalid(InvalidTransaction::Stale),
+		);
+	});
+}
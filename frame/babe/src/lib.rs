@@ -454,10 +454,17 @@ impl<T: Config> Module<T> {
 		// by the session module to be called before this.
 		debug_assert!(Self::initialized().is_some());
 
-		// Update epoch index
+		// Update epoch index.
+		//
+		// NOTE: the epoch index is derived from the current slot, so it is not necessarily
+		// contiguous if no blocks were authored for a whole epoch or more. When skipping
+		// from epoch N to e.g. N + 4, the authorities and randomness announced for epoch
+		// N + 1 are used, and the randomness collected during epoch N is used for N + 5.
+		// The client reuses the announced epoch data in the same way.
 		let epoch_index = EpochIndex::get()
 			.checked_add(1)
-			.expect("epoch indices will never reach 2^64 before the death of the universe; qed");
+			.expect("epoch indices will never reach 2^64 before the death of the universe; qed")
+			.max(Self::epoch_index_for(CurrentSlot::get()));
 
 		EpochIndex::put(epoch_index);
 		Authorities::put(authorities);
@@ -512,7 +519,7 @@ impl<T: Config> Module<T> {
 	/// announced).
 	pub fn next_epoch() -> Epoch {
 		let next_epoch_index = EpochIndex::get().checked_add(1).expect(
-			"epoch index is u64; it is always only incremented by one; \
+			"epoch index is u64; it only grows by the number of elapsed epochs; \
 			 if u64 is not enough we should crash for safety; qed.",
 		);
 
@@ -525,6 +532,15 @@ impl<T: Config> Module<T> {
 		}
 	}
 
+	// the index of the epoch the given slot belongs to. only guaranteed to
+	// give correct results after `do_initialize` of the first block in the chain.
+	fn epoch_index_for(slot_number: SlotNumber) -> u64 {
+		slot_number
+			.saturating_sub(GenesisSlot::get())
+			.checked_div(T::EpochDuration::get())
+			.unwrap_or(0)
+	}
+
 	fn epoch_start(epoch_index: u64) -> SlotNumber {
 		// (epoch_index * epoch_duration) + genesis_slot
 
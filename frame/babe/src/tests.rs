@@ -220,6 +220,42 @@ fn can_predict_next_epoch_change() {
 	})
 }
 
+#[test]
+fn skipped_epochs_reuse_announced_epoch_data() {
+	new_test_ext(1).execute_with(|| {
+		assert_eq!(<Test as Config>::EpochDuration::get(), 3);
+		// this sets the genesis slot to 6;
+		go_to_block(1, 6);
+		progress_to_block(3);
+
+		assert_eq!(Babe::epoch_index(), 0);
+		let announced = Babe::next_epoch();
+		assert_eq!(announced.epoch_index, 1);
+
+		// no blocks are authored for several epochs, the chain resumes at slot 22 which
+		// belongs to epoch #5 (starting at slot 21).
+		go_to_block(4, 22);
+
+		let current_epoch = Babe::current_epoch();
+		assert_eq!(current_epoch.epoch_index, 5);
+		assert_eq!(current_epoch.start_slot, 21);
+
+		// the data announced for epoch #1 is used for epoch #5.
+		assert_eq!(current_epoch.authorities, announced.authorities);
+		assert_eq!(current_epoch.randomness, announced.randomness);
+
+		let next_epoch = Babe::next_epoch();
+		assert_eq!(next_epoch.epoch_index, 6);
+		assert_eq!(next_epoch.start_slot, 24);
+
+		// and the chain keeps changing epochs as usual.
+		assert!(!Babe::should_epoch_change(5));
+		progress_to_block(6);
+		assert_eq!(Babe::epoch_index(), 6);
+		assert_eq!(Babe::current_epoch_start(), 24);
+	})
+}
+
 #[test]
 fn can_enact_next_config() {
 	new_test_ext(1).execute_with(|| {
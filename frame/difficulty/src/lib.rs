@@ -0,0 +1,203 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Difficulty Module
+//!
+//! The Difficulty module keeps the proof of work difficulty of a chain in the runtime.
+//!
+//! ## Overview
+//!
+//! At the end of every block the module records the difficulty the block was mined at,
+//! together with its timestamp, and computes the difficulty of the next block using the
+//! configured [`RetargetAlgorithm`]. Only the last `Window` solve times are kept.
+//!
+//! [`Lwma`] provides a linearly weighted moving average retarget, which reacts quickly
+//! to hash rate changes while being hard to manipulate with timestamps.
+//!
+//! ## Interface
+//!
+//! ### Public Functions
+//!
+//! - `difficulty` - The difficulty the next block has to be mined at.
+//!
+//! The client reads the difficulty through `sp_consensus_pow::DifficultyApi`, which the
+//! runtime implements by returning `Difficulty::difficulty()`. `PowAlgorithm::difficulty`
+//! implementations can then use `sc_consensus_pow::runtime_difficulty`.
+//!
+//! ## Related Modules
+//!
+//! - [Timestamp](../pallet_timestamp/index.html): The timestamp of each block is used to
+//! measure the solve times.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Encode, Decode};
+use frame_support::{decl_module, decl_storage, traits::Get, weights::Weight};
+use sp_core::U256;
+use sp_runtime::{RuntimeDebug, traits::{SaturatedConversion, Saturating, UniqueSaturatedInto}};
+use sp_std::prelude::*;
+
+mod mock;
+mod tests;
+
+/// The difficulty type used by the module.
+pub type Difficulty = U256;
+
+/// The difficulty a block was mined at, and its timestamp.
+#[derive(Encode, Decode, Clone, Copy, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct DifficultyAndTimestamp<Moment> {
+	/// Difficulty of the block.
+	pub difficulty: Difficulty,
+	/// Timestamp of the block.
+	pub timestamp: Moment,
+}
+
+/// An algorithm computing the difficulty of the next block.
+pub trait RetargetAlgorithm<Moment> {
+	/// Compute the next difficulty from the given history, ordered from the oldest to
+	/// the most recent block. The history is never empty.
+	fn next_difficulty(
+		history: &[DifficultyAndTimestamp<Moment>],
+		target_block_time: Moment,
+	) -> Difficulty;
+}
+
+/// Never adjust the difficulty.
+impl<Moment> RetargetAlgorithm<Moment> for () {
+	fn next_difficulty(
+		history: &[DifficultyAndTimestamp<Moment>],
+		_target_block_time: Moment,
+	) -> Difficulty {
+		history.last().map(|entry| entry.difficulty).unwrap_or_default()
+	}
+}
+
+/// Linearly weighted moving average difficulty adjustment.
+///
+/// Solve times are weighted by how recent they are, and clamped between one and
+/// six target block times so that a single bogus timestamp can only have a bounded
+/// effect on the result.
+pub struct Lwma;
+
+impl<Moment> RetargetAlgorithm<Moment> for Lwma where
+	Moment: Copy + Saturating + UniqueSaturatedInto<u64>,
+{
+	fn next_difficulty(
+		history: &[DifficultyAndTimestamp<Moment>],
+		target_block_time: Moment,
+	) -> Difficulty {
+		let target = target_block_time.saturated_into::<u64>().max(1);
+
+		if history.len() < 2 {
+			return history.last().map(|entry| entry.difficulty).unwrap_or_default();
+		}
+
+		let mut weighted_solve_times = 0u128;
+		let mut sum_difficulties = Difficulty::zero();
+
+		for (i, pair) in history.windows(2).enumerate() {
+			let solve_time = pair[1].timestamp
+				.saturating_sub(pair[0].timestamp)
+				.saturated_into::<u64>()
+				.max(1)
+				.min(6 * target);
+
+			weighted_solve_times += (i as u128 + 1) * solve_time as u128;
+			sum_difficulties = sum_difficulties.saturating_add(pair[1].difficulty);
+		}
+
+		// next = average difficulty * target / weighted average solve time, where the
+		// weights 1..=n sum to n(n + 1) / 2.
+		let n = (history.len() - 1) as u64;
+
+		sum_difficulties
+			.saturating_mul(U256::from(target))
+			.saturating_mul(U256::from(n + 1))
+			/ U256::from(2 * weighted_solve_times)
+	}
+}
+
+pub trait Config: pallet_timestamp::Config {
+	/// The block time the difficulty adjustment aims for.
+	type TargetBlockTime: Get<Self::Moment>;
+
+	/// The number of most recent solve times taken into account.
+	type Window: Get<u32>;
+
+	/// The lowest difficulty the adjustment can result in.
+	type MinDifficulty: Get<Difficulty>;
+
+	/// The algorithm computing the difficulty of the next block.
+	type RetargetAlgorithm: RetargetAlgorithm<Self::Moment>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Difficulty {
+		/// The difficulty the next block has to be mined at.
+		pub CurrentDifficulty get(fn difficulty) config(initial_difficulty): Difficulty;
+
+		/// Difficulty and timestamp of the most recent blocks, oldest first.
+		pub History get(fn history): Vec<DifficultyAndTimestamp<T::Moment>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// The block time the difficulty adjustment aims for.
+		const TargetBlockTime: T::Moment = T::TargetBlockTime::get();
+
+		/// The number of most recent solve times taken into account.
+		const Window: u32 = T::Window::get();
+
+		fn on_initialize() -> Weight {
+			// weight of `on_finalize`
+			T::DbWeight::get().reads_writes(3, 2)
+		}
+
+		fn on_finalize() {
+			let timestamp = <pallet_timestamp::Module<T>>::get();
+			Self::note_block(timestamp);
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Record the difficulty and timestamp of the current block and retarget the
+	/// difficulty of the next one.
+	fn note_block(timestamp: T::Moment) {
+		let mut history = Self::history();
+		history.push(DifficultyAndTimestamp {
+			difficulty: Self::difficulty(),
+			timestamp,
+		});
+
+		// `n` solve times are measured between `n + 1` blocks.
+		let max_len = T::Window::get().max(1) as usize + 1;
+		if history.len() > max_len {
+			let excess = history.len() - max_len;
+			history.drain(..excess);
+		}
+
+		let next_difficulty = T::RetargetAlgorithm::next_difficulty(
+			&history,
+			T::TargetBlockTime::get(),
+		).max(T::MinDifficulty::get());
+
+		CurrentDifficulty::put(next_difficulty);
+		<History<T>>::put(history);
+	}
+}
@@ -0,0 +1,103 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use crate::{self as pallet_difficulty, Config, Difficulty, Lwma};
+use sp_runtime::{testing::Header, traits::IdentityLookup};
+use frame_support::{parameter_types, traits::OnFinalize};
+use sp_core::H256;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
+		DifficultyModule: pallet_difficulty::{Module, Call, Storage, Config},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MinimumPeriod: u64 = 1;
+	pub const TargetBlockTime: u64 = 10;
+	pub const Window: u32 = 4;
+	pub MinDifficulty: Difficulty = 100.into();
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Version = ();
+	type Hashing = sp_runtime::traits::BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+impl Config for Test {
+	type TargetBlockTime = TargetBlockTime;
+	type Window = Window;
+	type MinDifficulty = MinDifficulty;
+	type RetargetAlgorithm = Lwma;
+}
+
+/// Finalize the next block, produced `block_time` after the current one.
+pub fn next_block(block_time: u64) {
+	let number = System::block_number() + 1;
+	System::set_block_number(number);
+	Timestamp::set_timestamp(Timestamp::get() + block_time);
+	DifficultyModule::on_finalize(number);
+}
+
+pub fn new_test_ext(initial_difficulty: u64) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_difficulty::GenesisConfig {
+		initial_difficulty: initial_difficulty.into(),
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
+}
@@ -0,0 +1,119 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use crate::{DifficultyAndTimestamp, Difficulty, Lwma, RetargetAlgorithm, mock::*};
+use frame_support::traits::Get;
+
+fn history(entries: &[(u64, u64)]) -> Vec<DifficultyAndTimestamp<u64>> {
+	entries.iter().map(|(difficulty, timestamp)| DifficultyAndTimestamp {
+		difficulty: (*difficulty).into(),
+		timestamp: *timestamp,
+	}).collect()
+}
+
+#[test]
+fn lwma_keeps_difficulty_on_target() {
+	let history = history(&[(1000, 0), (1000, 10), (1000, 20), (1000, 30)]);
+	assert_eq!(Lwma::next_difficulty(&history, 10), Difficulty::from(1000));
+}
+
+#[test]
+fn lwma_follows_block_time() {
+	// blocks twice as fast as the target
+	let fast = history(&[(1000, 0), (1000, 5), (1000, 10), (1000, 15)]);
+	assert_eq!(Lwma::next_difficulty(&fast, 10), Difficulty::from(2000));
+
+	// blocks twice as slow as the target
+	let slow = history(&[(1000, 0), (1000, 20), (1000, 40), (1000, 60)]);
+	assert_eq!(Lwma::next_difficulty(&slow, 10), Difficulty::from(500));
+}
+
+#[test]
+fn lwma_weights_recent_blocks_more() {
+	let recently_fast = history(&[(1000, 0), (1000, 20), (1000, 40), (1000, 45)]);
+	let recently_slow = history(&[(1000, 0), (1000, 5), (1000, 25), (1000, 45)]);
+
+	assert!(Lwma::next_difficulty(&recently_fast, 10) > Lwma::next_difficulty(&recently_slow, 10));
+}
+
+#[test]
+fn lwma_clamps_solve_times() {
+	// an out of order timestamp counts as a one second solve time
+	let backwards = history(&[(1000, 100), (1000, 50)]);
+	assert_eq!(Lwma::next_difficulty(&backwards, 10), Difficulty::from(10_000));
+
+	// a huge gap counts as six target block times
+	let gap = history(&[(1000, 0), (1000, 1_000_000)]);
+	assert_eq!(Lwma::next_difficulty(&gap, 10), Difficulty::from(166));
+}
+
+#[test]
+fn no_retarget_keeps_the_last_difficulty() {
+	let fast = history(&[(1000, 0), (1200, 1), (1400, 2)]);
+	assert_eq!(<() as RetargetAlgorithm<u64>>::next_difficulty(&fast, 10), Difficulty::from(1400));
+}
+
+#[test]
+fn difficulty_is_retargeted_every_block() {
+	new_test_ext(1000).execute_with(|| {
+		assert_eq!(DifficultyModule::difficulty(), Difficulty::from(1000));
+
+		// a single block gives no solve time yet
+		next_block(10);
+		assert_eq!(DifficultyModule::difficulty(), Difficulty::from(1000));
+		assert_eq!(DifficultyModule::history().len(), 1);
+
+		for _ in 0..3 {
+			next_block(10);
+		}
+		assert_eq!(DifficultyModule::difficulty(), Difficulty::from(1000));
+
+		for _ in 0..3 {
+			next_block(5);
+		}
+		assert!(DifficultyModule::difficulty() > Difficulty::from(1000));
+	});
+}
+
+#[test]
+fn history_is_bounded_by_the_window() {
+	new_test_ext(1000).execute_with(|| {
+		for _ in 0..10 {
+			next_block(10);
+		}
+
+		let history = DifficultyModule::history();
+		assert_eq!(history.len(), Window::get() as usize + 1);
+		assert_eq!(history.last().unwrap().timestamp, 100);
+		assert_eq!(history.first().unwrap().timestamp, 60);
+	});
+}
+
+#[test]
+fn difficulty_does_not_drop_below_minimum() {
+	new_test_ext(200).execute_with(|| {
+		for _ in 0..10 {
+			next_block(1_000);
+		}
+
+		assert_eq!(DifficultyModule::difficulty(), MinDifficulty::get());
+	});
+}
@@ -149,7 +149,7 @@ fn clean<T: Config>() {
 	<Members<T>>::kill();
 	<Candidates<T>>::kill();
 	<RunnersUp<T>>::kill();
-	<Voting<T>>::remove_all();
+	<Voting<T>>::remove_all(None);
 }
 
 benchmarks! {
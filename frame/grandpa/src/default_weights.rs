@@ -51,4 +51,9 @@ impl crate::WeightInfo for () {
 		(3 * WEIGHT_PER_MICROS)
 			.saturating_add(DbWeight::get().writes(1))
 	}
+
+	fn set_finality_delay() -> Weight {
+		(3 * WEIGHT_PER_MICROS)
+			.saturating_add(DbWeight::get().reads_writes(1, 1))
+	}
 }
@@ -36,8 +36,8 @@ use sp_std::prelude::*;
 use codec::{self as codec, Decode, Encode};
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
 use fg_primitives::{
-	ConsensusLog, EquivocationProof, ScheduledChange, SetId, GRANDPA_AUTHORITIES_KEY,
-	GRANDPA_ENGINE_ID,
+	ConsensusLog, EquivocationProof, FinalityDelay, ScheduledChange, SetId,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID,
 };
 use frame_support::{
 	decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResultWithPostInfo,
@@ -105,6 +105,7 @@ pub trait Config: frame_system::Config {
 pub trait WeightInfo {
 	fn report_equivocation(validator_count: u32) -> Weight;
 	fn note_stalled() -> Weight;
+	fn set_finality_delay() -> Weight;
 }
 
 /// A stored pending change, old format.
@@ -184,6 +185,10 @@ decl_event! {
 		Paused,
 		/// Current authority set has been resumed.
 		Resumed,
+		/// Governance asked voters to stop voting on new blocks.
+		FinalityVotingPaused,
+		/// Governance asked voters to resume voting on new blocks.
+		FinalityVotingResumed,
 	}
 }
 
@@ -231,6 +236,12 @@ decl_storage! {
 		///
 		/// TWOX-NOTE: `SetId` is not under user control.
 		SetIdSession get(fn session_for_set): map hasher(twox_64_concat) SetId => Option<SessionIndex>;
+
+		/// The minimum number of blocks GRANDPA votes should lag behind the best block.
+		MinFinalityLag get(fn min_finality_lag): T::BlockNumber;
+
+		/// `true` if GRANDPA voters should stop voting on new blocks.
+		FinalityVotingPaused get(fn finality_voting_paused): bool;
 	}
 	add_extra_genesis {
 		config(authorities): AuthorityList;
@@ -307,6 +318,37 @@ decl_module! {
 			Self::on_stalled(delay, best_finalized_block_number)
 		}
 
+		/// Set the minimum number of blocks GRANDPA votes should lag behind the best
+		/// block. This is only enforced by voters using the runtime finality delay
+		/// voting rule, and takes effect from the next voting round.
+		/// Only callable by root.
+		#[weight = T::WeightInfo::set_finality_delay()]
+		fn set_min_finality_lag(origin, min_lag: T::BlockNumber) {
+			ensure_root(origin)?;
+
+			MinFinalityLag::<T>::put(min_lag);
+		}
+
+		/// Ask GRANDPA voters to stop voting on, or resume voting on, new blocks.
+		/// Unlike `schedule_pause` this doesn't change the authority set and takes
+		/// effect immediately, but is only enforced by voters using the runtime
+		/// finality delay voting rule. Only callable by root.
+		#[weight = T::WeightInfo::set_finality_delay()]
+		fn set_finality_voting_paused(origin, paused: bool) {
+			ensure_root(origin)?;
+
+			if paused == FinalityVotingPaused::get() {
+				return Ok(());
+			}
+
+			FinalityVotingPaused::put(paused);
+			if paused {
+				Self::deposit_event(Event::FinalityVotingPaused);
+			} else {
+				Self::deposit_event(Event::FinalityVotingResumed);
+			}
+		}
+
 		fn on_finalize(block_number: T::BlockNumber) {
 			// check for scheduled pending authority set changes
 			if let Some(pending_change) = <PendingChange<T>>::get() {
@@ -378,6 +420,14 @@ impl<T: Config> Module<T> {
 		storage::unhashed::get_or_default::<VersionedAuthorityList>(GRANDPA_AUTHORITIES_KEY).into()
 	}
 
+	/// Get the finality restrictions currently set by governance.
+	pub fn finality_delay() -> FinalityDelay<T::BlockNumber> {
+		FinalityDelay {
+			min_lag: Self::min_finality_lag(),
+			paused: Self::finality_voting_paused(),
+		}
+	}
+
 	/// Set the current set of authorities, along with their respective weights.
 	fn set_grandpa_authorities(authorities: &AuthorityList) {
 		storage::unhashed::put(
@@ -290,6 +290,41 @@ fn schedule_resume_only_when_paused() {
 	});
 }
 
+#[test]
+fn finality_delay_is_set_by_root() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		initialize_block(1, Default::default());
+
+		assert_eq!(Grandpa::finality_delay(), FinalityDelay { min_lag: 0, paused: false });
+
+		// only root can change the finality restrictions
+		assert!(Grandpa::set_min_finality_lag(Origin::signed(1), 10).is_err());
+		assert!(Grandpa::set_finality_voting_paused(Origin::signed(1), true).is_err());
+
+		assert_ok!(Grandpa::set_min_finality_lag(Origin::root(), 10));
+		assert_eq!(Grandpa::finality_delay(), FinalityDelay { min_lag: 10, paused: false });
+
+		assert_ok!(Grandpa::set_finality_voting_paused(Origin::root(), true));
+		assert_eq!(Grandpa::finality_delay(), FinalityDelay { min_lag: 10, paused: true });
+		assert_eq!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(Event::FinalityVotingPaused.into()),
+		);
+
+		// setting the same flag again doesn't emit another event
+		let events = System::events().len();
+		assert_ok!(Grandpa::set_finality_voting_paused(Origin::root(), true));
+		assert_eq!(System::events().len(), events);
+
+		assert_ok!(Grandpa::set_finality_voting_paused(Origin::root(), false));
+		assert_eq!(Grandpa::finality_delay(), FinalityDelay { min_lag: 10, paused: false });
+		assert_eq!(
+			System::events().last().map(|record| record.event.clone()),
+			Some(Event::FinalityVotingResumed.into()),
+		);
+	});
+}
+
 #[test]
 fn time_slot_have_sane_ord() {
 	// Ensure that `Ord` implementation is sane.
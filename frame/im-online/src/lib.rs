@@ -653,8 +653,8 @@ impl<T: Config> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		// Remove all received heartbeats and number of authored blocks from the
 		// current session, they have already been processed and won't be needed
 		// anymore.
-		<ReceivedHeartbeats>::remove_prefix(&<pallet_session::Module<T>>::current_index());
-		<AuthoredBlocks<T>>::remove_prefix(&<pallet_session::Module<T>>::current_index());
+		<ReceivedHeartbeats>::remove_prefix(&<pallet_session::Module<T>>::current_index(), None);
+		<AuthoredBlocks<T>>::remove_prefix(&<pallet_session::Module<T>>::current_index(), None);
 
 		if offenders.is_empty() {
 			Self::deposit_event(RawEvent::AllGood);
@@ -0,0 +1,121 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the Sassafras pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::benchmarks;
+use frame_system::RawOrigin;
+use sp_consensus_sassafras::{Transcript, VRFOutput, VRFProof};
+
+use crate::Module as Sassafras;
+
+/// The maximum number of tickets submitted at once.
+const MAX_TICKETS: u32 = 100;
+
+/// Deterministic randomness for the VRF proofs, as system randomness is not available in the
+/// runtime. Insecure, but the proofs are only used to benchmark their verification.
+struct ZeroRng;
+
+impl rand_core::RngCore for ZeroRng {
+	fn next_u32(&mut self) -> u32 {
+		0
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		0
+	}
+
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		dest.iter_mut().for_each(|b| *b = 0);
+	}
+
+	fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+		self.fill_bytes(dest);
+		Ok(())
+	}
+}
+
+impl rand_core::CryptoRng for ZeroRng {}
+
+/// Create `count` authorities and a valid ticket for the next epoch from each of them.
+fn create_tickets<T: Config>(count: u32) -> Vec<Ticket> {
+	let randomness = Randomness::get();
+	let next_epoch = EpochIndex::get() + 1;
+
+	let mut authorities = Vec::new();
+	let mut tickets = Vec::new();
+	for authority_index in 0..count {
+		let mut seed = [0u8; 32];
+		seed[..4].copy_from_slice(&authority_index.to_le_bytes());
+		let keypair = schnorrkel::MiniSecretKey::from_bytes(&seed)
+			.expect("seed has the length of a mini secret key; qed")
+			.expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+
+		let transcript = sp_consensus_sassafras::make_ticket_transcript(&randomness, 0, next_epoch);
+		let extra = schnorrkel::context::attach_rng(Transcript::new(b"VRF"), ZeroRng);
+		let (inout, proof, _) = keypair.vrf_sign_extra(transcript, extra);
+
+		authorities.push(AuthorityId::decode(&mut &keypair.public.to_bytes()[..])
+			.expect("public keys are 32 bytes; qed"));
+		tickets.push(Ticket {
+			authority_index,
+			attempt: 0,
+			vrf_output: VRFOutput(inout.to_output()),
+			vrf_proof: VRFProof(proof),
+		});
+	}
+
+	Authorities::put(authorities);
+	tickets
+}
+
+benchmarks! {
+	submit_tickets {
+		let t in 1 .. MAX_TICKETS;
+		let tickets = create_tickets::<T>(t);
+
+		// Worst case: the tickets of the next epoch are full and every new ticket is sorted in,
+		// pushing out an existing one. The existing tickets don't need to be valid.
+		let mut worst_ticket = tickets.iter()
+			.max_by_key(|ticket| ticket.score())
+			.expect("at least one ticket is created; qed")
+			.clone();
+		worst_ticket.authority_index = MAX_TICKETS;
+		NextTickets::put(vec![worst_ticket; T::EpochDuration::get() as usize]);
+	}: _(RawOrigin::None, tickets)
+	verify {
+		assert_eq!(NextTickets::decode_len(), Some(T::EpochDuration::get() as usize));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::*;
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext(0).execute_with(|| {
+			assert_ok!(test_benchmark_submit_tickets::<Test>());
+		})
+	}
+}
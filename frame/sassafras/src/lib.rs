@@ -0,0 +1,497 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consensus extension module for Sassafras consensus.
+//!
+//! During the first half of every epoch authorities submit VRF tickets for the next epoch
+//! through unsigned extrinsics. The module keeps the tickets with the lowest VRF outputs, at
+//! most one per slot, and once the next epoch starts it assigns them to its slots in order.
+//! Every slot thus has exactly one known author; slots left without a ticket fall back to
+//! round-robin assignment.
+//!
+//! The authority set is currently fixed at genesis.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(unused_must_use, unsafe_code, unused_variables)]
+
+use codec::Decode;
+use frame_support::{
+	debug, decl_error, decl_module, decl_storage,
+	traits::{FindAuthor, Get},
+	weights::Weight,
+};
+use frame_system::{
+	ensure_none,
+	offchain::{SendTransactionTypes, SubmitTransaction},
+};
+use sp_runtime::{
+	traits::{IsMember, SaturatedConversion, Saturating},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
+	},
+	ConsensusEngineId,
+};
+use sp_std::{prelude::*, result};
+use sp_timestamp::OnTimestampSet;
+
+use sp_consensus_sassafras::{
+	digests::PreDigest,
+	inherents::{SassafrasInherentData, INHERENT_IDENTIFIER},
+	AuthorityIndex, SassafrasConfiguration, SlotNumber, Ticket, SASSAFRAS_ENGINE_ID,
+};
+use sp_inherents::{InherentData, InherentIdentifier, MakeFatalError, ProvideInherent};
+
+pub use sp_consensus_sassafras::{AuthorityId, RANDOMNESS_LENGTH};
+
+#[cfg(all(feature = "std", test))]
+mod mock;
+#[cfg(all(feature = "std", test))]
+mod tests;
+
+mod benchmarking;
+pub mod weights;
+
+pub use weights::WeightInfo;
+
+pub trait Config: pallet_timestamp::Config + SendTransactionTypes<Call<Self>> {
+	/// The amount of time, in slots, that each epoch should last.
+	/// NOTE: Currently it is not possible to change the epoch duration after
+	/// the chain has started. Attempting to do so will brick block production.
+	type EpochDuration: Get<SlotNumber>;
+
+	/// The maximum number of tickets each authority may submit for an epoch.
+	type MaxAttempts: Get<u32>;
+
+	/// A configuration for base priority of unsigned ticket transactions.
+	type UnsignedPriority: Get<TransactionPriority>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Sassafras {
+		/// Current epoch index.
+		pub EpochIndex get(fn epoch_index): u64;
+
+		/// Current epoch authorities.
+		pub Authorities get(fn authorities): Vec<AuthorityId>;
+
+		/// The slot at which the first epoch actually started. This is 0
+		/// until the first block of the chain.
+		pub GenesisSlot get(fn genesis_slot): SlotNumber;
+
+		/// Current slot number.
+		pub CurrentSlot get(fn current_slot): SlotNumber;
+
+		/// The epoch randomness, used by the tickets submitted for the next epoch.
+		pub Randomness get(fn randomness): sp_consensus_sassafras::Randomness;
+
+		/// Randomness under construction from the tickets submitted during this epoch, used
+		/// as the randomness of the next epoch.
+		NextRandomness: sp_consensus_sassafras::Randomness;
+
+		/// The slot schedule of the current epoch: tickets sorted by score, the `n`th ticket
+		/// claiming the `n`th slot of the epoch.
+		pub Tickets get(fn tickets): Vec<Ticket>;
+
+		/// Tickets submitted for the next epoch, kept sorted by score and truncated to the
+		/// epoch length.
+		pub NextTickets get(fn next_tickets): Vec<Ticket>;
+	}
+	add_extra_genesis {
+		config(authorities): Vec<AuthorityId>;
+		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// A ticket was submitted by an unknown authority.
+		UnknownAuthority,
+		/// A ticket attempt is not lower than the configured maximum.
+		AttemptTooHigh,
+		/// A ticket VRF proof is invalid.
+		InvalidTicket,
+		/// A ticket for the same authority and attempt was already submitted.
+		DuplicateTicket,
+		/// Tickets are only accepted during the first half of an epoch.
+		SubmissionClosed,
+	}
+}
+
+decl_module! {
+	/// The Sassafras Pallet
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		/// The number of **slots** that an epoch takes.
+		const EpochDuration: u64 = T::EpochDuration::get();
+
+		/// The maximum number of tickets each authority may submit for an epoch.
+		const MaxAttempts: u32 = T::MaxAttempts::get();
+
+		type Error = Error<T>;
+
+		fn integrity_test() {
+			assert!(
+				T::EpochDuration::get() >= 2,
+				"Epochs must last at least two slots to leave time for the ticket submission.",
+			);
+		}
+
+		/// Initialization
+		fn on_initialize(_now: T::BlockNumber) -> Weight {
+			// reading the digest.
+			let weight = T::DbWeight::get().reads(1);
+
+			match Self::find_pre_digest() {
+				Some(pre_digest) => weight.saturating_add(Self::initialize_slot(pre_digest.slot_number)),
+				None => weight,
+			}
+		}
+
+		/// Submit tickets for the next epoch. Only unsigned extrinsics are accepted, the
+		/// tickets are authenticated by their VRF proofs.
+		#[weight = T::WeightInfo::submit_tickets(tickets.len() as u32)]
+		fn submit_tickets(origin, tickets: Vec<Ticket>) {
+			ensure_none(origin)?;
+
+			Self::check_tickets(&tickets)?;
+
+			let mut next_tickets = NextTickets::get();
+			let mut next_randomness = NextRandomness::get();
+			let epoch_length = T::EpochDuration::get() as usize;
+
+			for ticket in tickets {
+				next_randomness = Self::accumulate_randomness(&next_randomness, &ticket);
+
+				let score = ticket.score();
+				let position = next_tickets
+					.binary_search_by(|t| t.score().cmp(&score))
+					.unwrap_or_else(|p| p);
+
+				if position < epoch_length {
+					next_tickets.insert(position, ticket);
+					next_tickets.truncate(epoch_length);
+				}
+			}
+
+			NextTickets::put(next_tickets);
+			NextRandomness::put(next_randomness);
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Determine the Sassafras slot duration based on the Timestamp module configuration.
+	pub fn slot_duration() -> T::Moment {
+		// we double the minimum block-period so each author can always propose within
+		// the majority of their slot.
+		<T as pallet_timestamp::Config>::MinimumPeriod::get().saturating_mul(2u32.into())
+	}
+
+	/// The configuration of the current epoch.
+	pub fn configuration() -> SassafrasConfiguration {
+		SassafrasConfiguration {
+			slot_duration: Self::slot_duration().saturated_into(),
+			epoch_length: T::EpochDuration::get(),
+			epoch_index: EpochIndex::get(),
+			authorities: Authorities::get(),
+			randomness: Randomness::get(),
+			max_attempts: T::MaxAttempts::get(),
+		}
+	}
+
+	/// The number of slots at the start of every epoch during which tickets for the next epoch
+	/// are accepted.
+	///
+	/// This is the first half of the epoch, but at least one slot.
+	pub fn submission_period() -> SlotNumber {
+		(T::EpochDuration::get() / 2).max(1)
+	}
+
+	/// The first slot of the given epoch.
+	pub fn epoch_start(epoch_index: u64) -> SlotNumber {
+		epoch_index
+			.saturating_mul(T::EpochDuration::get())
+			.saturating_add(GenesisSlot::get())
+	}
+
+	/// The index of the authority assigned to the given slot.
+	///
+	/// Slots of the current and next epoch are taken from the sorted tickets, any other slot
+	/// and slots without a ticket are assigned round-robin.
+	pub fn slot_author(slot_number: SlotNumber) -> Option<AuthorityIndex> {
+		let authorities_len = Authorities::decode_len().unwrap_or(0) as u64;
+		if authorities_len == 0 {
+			return None;
+		}
+
+		let round_robin = (slot_number % authorities_len) as AuthorityIndex;
+
+		// before the first block no epoch has started yet.
+		if GenesisSlot::get() == 0 {
+			return Some(round_robin);
+		}
+
+		let current_epoch = EpochIndex::get();
+		let current_start = Self::epoch_start(current_epoch);
+		if slot_number < current_start {
+			return Some(round_robin);
+		}
+
+		let epoch_offset = (slot_number - current_start) / T::EpochDuration::get();
+		let tickets = match epoch_offset {
+			0 => Tickets::get(),
+			1 => NextTickets::get(),
+			_ => return Some(round_robin),
+		};
+
+		let slot_offset = (slot_number - Self::epoch_start(current_epoch + epoch_offset)) as usize;
+		let author = tickets
+			.get(slot_offset)
+			.map(|ticket| ticket.authority_index)
+			.unwrap_or(round_robin);
+
+		Some(author)
+	}
+
+	/// Submits an unsigned extrinsic with the given tickets for the next epoch. Meant to be
+	/// called from the client through the runtime API.
+	pub fn submit_unsigned_tickets(tickets: Vec<Ticket>) -> Option<()> {
+		let call = Call::submit_tickets(tickets);
+
+		match SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into()) {
+			Ok(()) => {
+				debug::info!(target: "sassafras", "Submitted Sassafras tickets.");
+				Some(())
+			}
+			Err(e) => {
+				debug::error!(target: "sassafras", "Error submitting tickets: {:?}", e);
+				None
+			}
+		}
+	}
+
+	fn initialize_authorities(authorities: &[AuthorityId]) {
+		if !authorities.is_empty() {
+			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
+			Authorities::put(authorities);
+		}
+	}
+
+	fn find_pre_digest() -> Option<PreDigest> {
+		<frame_system::Module<T>>::digest()
+			.logs
+			.iter()
+			.filter_map(|s| s.as_pre_runtime())
+			.filter_map(|(id, mut data)| if id == SASSAFRAS_ENGINE_ID {
+				PreDigest::decode(&mut data).ok()
+			} else {
+				None
+			})
+			.next()
+	}
+
+	/// Track the slot of the block being initialized, entering a new epoch if the slot belongs
+	/// to one. Returns the weight consumed.
+	///
+	/// Called on initialization with the slot of the block's pre-digest. Runtimes that do not
+	/// run the `on_initialize` hook, e.g. test runtimes, may call this directly instead.
+	pub fn initialize_slot(current_slot: SlotNumber) -> Weight {
+		let db_weight = T::DbWeight::get();
+		// reading the genesis slot and the epoch index, writing the current slot.
+		let mut weight = db_weight.reads_writes(2, 1);
+
+		// on the first non-zero block (i.e. block #1)
+		// this is where the first epoch (epoch #0) actually starts.
+		if GenesisSlot::get() == 0 {
+			GenesisSlot::put(current_slot);
+			debug_assert_ne!(GenesisSlot::get(), 0);
+			weight = weight.saturating_add(db_weight.writes(1));
+		}
+
+		CurrentSlot::put(current_slot);
+
+		let epoch_index = current_slot
+			.saturating_sub(GenesisSlot::get())
+			/ T::EpochDuration::get();
+
+		let last_epoch_index = EpochIndex::get();
+		if epoch_index <= last_epoch_index {
+			return weight;
+		}
+
+		// rotating the tickets, the epoch index and the randomness.
+		weight = weight.saturating_add(db_weight.reads_writes(2, 5));
+
+		// tickets are only valid for the epoch right after the one they were submitted in,
+		// if whole epochs were skipped every slot falls back to round-robin.
+		if epoch_index == last_epoch_index + 1 {
+			Tickets::put(NextTickets::take());
+		} else {
+			NextTickets::kill();
+			Tickets::kill();
+		}
+
+		EpochIndex::put(epoch_index);
+		Randomness::put(NextRandomness::get());
+
+		weight
+	}
+
+	/// Check that the given tickets may be added to the tickets of the next epoch.
+	fn check_tickets(tickets: &[Ticket]) -> result::Result<(), Error<T>> {
+		let epoch_index = EpochIndex::get();
+		let slots_into_epoch = CurrentSlot::get().saturating_sub(Self::epoch_start(epoch_index));
+		if slots_into_epoch >= Self::submission_period() {
+			return Err(Error::<T>::SubmissionClosed);
+		}
+
+		let authorities = Authorities::get();
+		let randomness = Randomness::get();
+		let next_tickets = NextTickets::get();
+
+		for (i, ticket) in tickets.iter().enumerate() {
+			if ticket.attempt >= T::MaxAttempts::get() {
+				return Err(Error::<T>::AttemptTooHigh);
+			}
+
+			let is_duplicate = |t: &Ticket| {
+				t.authority_index == ticket.authority_index && t.attempt == ticket.attempt
+			};
+			if next_tickets.iter().any(is_duplicate) || tickets[..i].iter().any(is_duplicate) {
+				return Err(Error::<T>::DuplicateTicket);
+			}
+
+			let authority = authorities
+				.get(ticket.authority_index as usize)
+				.ok_or(Error::<T>::UnknownAuthority)?;
+
+			// check the VRF proof last as it is the most expensive check.
+			if !ticket.verify(authority, &randomness, epoch_index + 1) {
+				return Err(Error::<T>::InvalidTicket);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn accumulate_randomness(
+		randomness: &sp_consensus_sassafras::Randomness,
+		ticket: &Ticket,
+	) -> sp_consensus_sassafras::Randomness {
+		let mut s = Vec::with_capacity(RANDOMNESS_LENGTH * 2);
+		s.extend_from_slice(randomness);
+		s.extend_from_slice(&ticket.score());
+
+		sp_io::hashing::blake2_256(&s)
+	}
+}
+
+impl<T: Config> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::submit_tickets(tickets) = call {
+			if tickets.is_empty() {
+				return InvalidTransaction::Call.into();
+			}
+
+			if let Err(e) = Self::check_tickets(tickets) {
+				return match e {
+					Error::<T>::SubmissionClosed | Error::<T>::DuplicateTicket =>
+						InvalidTransaction::Stale.into(),
+					_ => InvalidTransaction::BadProof.into(),
+				};
+			}
+
+			let next_epoch = EpochIndex::get() + 1;
+			let mut builder = ValidTransaction::with_tag_prefix("Sassafras")
+				.priority(T::UnsignedPriority::get())
+				// tickets are not accepted past the first half of the epoch.
+				.longevity(Self::submission_period())
+				// tickets must reach the block authors, who are likely not the ticket owners.
+				.propagate(true);
+
+			for ticket in tickets {
+				builder = builder.and_provides((next_epoch, ticket.authority_index, ticket.attempt));
+			}
+
+			builder.build()
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
+impl<T: Config> FindAuthor<u32> for Module<T> {
+	fn find_author<'a, I>(digests: I) -> Option<u32> where
+		I: 'a + IntoIterator<Item=(ConsensusEngineId, &'a [u8])>
+	{
+		for (id, mut data) in digests.into_iter() {
+			if id == SASSAFRAS_ENGINE_ID {
+				let pre_digest: PreDigest = PreDigest::decode(&mut data).ok()?;
+				return Some(pre_digest.authority_index)
+			}
+		}
+
+		None
+	}
+}
+
+impl<T: Config> IsMember<AuthorityId> for Module<T> {
+	fn is_member(authority_id: &AuthorityId) -> bool {
+		Self::authorities()
+			.iter()
+			.any(|id| id == authority_id)
+	}
+}
+
+impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = AuthorityId;
+}
+
+impl<T: Config> OnTimestampSet<T::Moment> for Module<T> {
+	fn on_timestamp_set(_moment: T::Moment) { }
+}
+
+impl<T: Config> ProvideInherent for Module<T> {
+	type Call = pallet_timestamp::Call<T>;
+	type Error = MakeFatalError<sp_inherents::Error>;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+	fn create_inherent(_: &InherentData) -> Option<Self::Call> {
+		None
+	}
+
+	fn check_inherent(call: &Self::Call, data: &InherentData) -> result::Result<(), Self::Error> {
+		let timestamp = match call {
+			pallet_timestamp::Call::set(ref timestamp) => timestamp.clone(),
+			_ => return Ok(()),
+		};
+
+		let timestamp_based_slot = (timestamp / Self::slot_duration()).saturated_into::<u64>();
+		let seal_slot = data.sassafras_inherent_data()?;
+
+		if timestamp_based_slot == seal_slot {
+			Ok(())
+		} else {
+			Err(sp_inherents::Error::from("timestamp set in block doesn't match slot in seal").into())
+		}
+	}
+}
@@ -0,0 +1,188 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities
+
+use codec::Encode;
+use crate::{self as pallet_sassafras, Config};
+use sp_runtime::{
+	testing::{Digest, DigestItem, Header, TestXt},
+	traits::{Header as _, IdentityLookup},
+	transaction_validity::TransactionPriority,
+};
+use frame_system::InitKind;
+use frame_support::{
+	parameter_types,
+	traits::{OnFinalize, OnInitialize},
+	weights::constants::RocksDbWeight,
+};
+use sp_core::{H256, U256, crypto::{IsWrappedBy, Pair}};
+use sp_consensus_sassafras::{
+	AuthorityId, AuthorityIndex, AuthorityPair, SlotNumber, Ticket, VRFOutput, VRFProof,
+	digests::PreDigest, SASSAFRAS_ENGINE_ID,
+};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
+		Sassafras: pallet_sassafras::{Module, Call, Storage, Config, Inherent, ValidateUnsigned},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const EpochDuration: u64 = 10;
+	pub const MaxAttempts: u32 = 2;
+	pub const MinimumPeriod: u64 = 1;
+	pub const UnsignedPriority: TransactionPriority = 1 << 20;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = RocksDbWeight;
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Version = ();
+	type Hashing = sp_runtime::traits::BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type PalletInfo = PalletInfo;
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+}
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = TestXt<Call, ()>;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = Sassafras;
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+impl Config for Test {
+	type EpochDuration = EpochDuration;
+	type MaxAttempts = MaxAttempts;
+	type UnsignedPriority = UnsignedPriority;
+	type WeightInfo = ();
+}
+
+pub fn make_pre_digest(authority_index: AuthorityIndex, slot_number: SlotNumber) -> Digest {
+	let digest_data = PreDigest { authority_index, slot_number };
+	let log = DigestItem::PreRuntime(SASSAFRAS_ENGINE_ID, digest_data.encode());
+	Digest { logs: vec![log] }
+}
+
+pub fn go_to_block(n: u64, s: u64) {
+	Sassafras::on_finalize(System::block_number());
+	System::on_finalize(System::block_number());
+
+	let parent_hash = if System::block_number() > 1 {
+		let hdr = System::finalize();
+		hdr.hash()
+	} else {
+		System::parent_hash()
+	};
+
+	let pre_digest = make_pre_digest(0, s);
+
+	System::initialize(&n, &parent_hash, &pre_digest, InitKind::Full);
+	System::set_block_number(n);
+
+	System::on_initialize(n);
+	Sassafras::on_initialize(n);
+}
+
+/// Slots will grow accordingly to blocks
+pub fn progress_to_block(n: u64) {
+	let mut slot = Sassafras::current_slot() + 1;
+	for i in System::block_number() + 1..=n {
+		go_to_block(i, slot);
+		slot += 1;
+	}
+}
+
+/// Make a ticket for the next epoch signed by the given authority pair.
+pub fn make_ticket(
+	authority_index: AuthorityIndex,
+	attempt: u32,
+	pair: &AuthorityPair,
+) -> Ticket {
+	let pair = sp_core::sr25519::Pair::from_ref(pair).as_ref();
+	let transcript = sp_consensus_sassafras::make_ticket_transcript(
+		&Sassafras::randomness(),
+		attempt,
+		Sassafras::epoch_index() + 1,
+	);
+	let vrf_inout = pair.vrf_sign(transcript);
+
+	Ticket {
+		authority_index,
+		attempt,
+		vrf_output: VRFOutput(vrf_inout.0.to_output()),
+		vrf_proof: VRFProof(vrf_inout.1),
+	}
+}
+
+pub fn new_test_ext(authorities_len: usize) -> sp_io::TestExternalities {
+	new_test_ext_with_pairs(authorities_len).1
+}
+
+pub fn new_test_ext_with_pairs(
+	authorities_len: usize,
+) -> (Vec<AuthorityPair>, sp_io::TestExternalities) {
+	let pairs = (0..authorities_len).map(|i| {
+		AuthorityPair::from_seed(&U256::from(i).into())
+	}).collect::<Vec<_>>();
+
+	let authorities: Vec<AuthorityId> = pairs.iter().map(|p| p.public()).collect();
+
+	let mut t = frame_system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap();
+
+	pallet_sassafras::GenesisConfig { authorities }
+		.assimilate_storage::<Test>(&mut t)
+		.unwrap();
+
+	(pairs, t.into())
+}
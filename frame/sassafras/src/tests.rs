@@ -0,0 +1,324 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consensus extension module tests for Sassafras consensus.
+
+use super::{Call, *};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	unsigned::ValidateUnsigned,
+	StorageValue,
+};
+use mock::*;
+use sp_runtime::transaction_validity::TransactionValidityError;
+
+#[test]
+fn initial_values() {
+	new_test_ext(4).execute_with(|| {
+		assert_eq!(Sassafras::authorities().len(), 4);
+		assert_eq!(Sassafras::epoch_index(), 0);
+		assert_eq!(Sassafras::genesis_slot(), 0);
+		assert!(Sassafras::tickets().is_empty());
+	})
+}
+
+#[test]
+fn first_block_sets_genesis_slot() {
+	new_test_ext(4).execute_with(|| {
+		go_to_block(1, 100);
+
+		assert_eq!(Sassafras::genesis_slot(), 100);
+		assert_eq!(Sassafras::current_slot(), 100);
+		assert_eq!(Sassafras::epoch_index(), 0);
+		assert_eq!(Sassafras::epoch_start(1), 110);
+	})
+}
+
+#[test]
+fn slot_author_falls_back_to_round_robin_without_tickets() {
+	new_test_ext(4).execute_with(|| {
+		assert_eq!(Sassafras::slot_author(5), Some(1));
+
+		go_to_block(1, 100);
+
+		for slot in 100..130 {
+			assert_eq!(Sassafras::slot_author(slot), Some((slot % 4) as u32));
+		}
+	});
+
+	new_test_ext(0).execute_with(|| {
+		assert_eq!(Sassafras::slot_author(5), None);
+	})
+}
+
+#[test]
+fn submitted_tickets_are_sorted_by_score() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		let tickets = pairs.iter().enumerate().flat_map(|(i, pair)| {
+			vec![make_ticket(i as u32, 0, pair), make_ticket(i as u32, 1, pair)]
+		}).collect::<Vec<_>>();
+
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), tickets[..3].to_vec()));
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), tickets[3..].to_vec()));
+
+		let next_tickets = Sassafras::next_tickets();
+		assert_eq!(next_tickets.len(), 8);
+		assert!(next_tickets.windows(2).all(|w| w[0].score() < w[1].score()));
+
+		// randomness for the next epoch is being accumulated
+		assert_ne!(NextRandomness::get(), Sassafras::randomness());
+	})
+}
+
+#[test]
+fn next_tickets_are_truncated_to_epoch_length() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(8);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		let mut tickets = pairs.iter().enumerate().flat_map(|(i, pair)| {
+			vec![make_ticket(i as u32, 0, pair), make_ticket(i as u32, 1, pair)]
+		}).collect::<Vec<_>>();
+
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), tickets.clone()));
+
+		tickets.sort_by_key(|t| t.score());
+		tickets.truncate(EpochDuration::get() as usize);
+
+		assert_eq!(Sassafras::next_tickets(), tickets);
+	})
+}
+
+#[test]
+fn invalid_tickets_are_rejected() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		// attempt too high
+		let ticket = make_ticket(0, MaxAttempts::get(), &pairs[0]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::AttemptTooHigh,
+		);
+
+		// unknown authority
+		let ticket = make_ticket(4, 0, &pairs[0]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::UnknownAuthority,
+		);
+
+		// signed by a different authority
+		let ticket = make_ticket(1, 0, &pairs[0]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::InvalidTicket,
+		);
+
+		// duplicate in the same call
+		let ticket = make_ticket(0, 0, &pairs[0]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket.clone(), ticket.clone()]),
+			Error::<Test>::DuplicateTicket,
+		);
+
+		// duplicate of an already submitted ticket
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), vec![ticket.clone()]));
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::DuplicateTicket,
+		);
+
+		// signed origin
+		let ticket = make_ticket(1, 0, &pairs[1]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::signed(1), vec![ticket]),
+			sp_runtime::DispatchError::BadOrigin,
+		);
+	})
+}
+
+#[test]
+fn tickets_for_a_previous_epoch_are_rejected() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+		let ticket = make_ticket(0, 0, &pairs[0]);
+
+		go_to_block(2, 110);
+		assert_eq!(Sassafras::epoch_index(), 1);
+
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::InvalidTicket,
+		);
+	})
+}
+
+#[test]
+fn submission_closes_halfway_through_the_epoch() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+		go_to_block(2, 104);
+
+		let ticket = make_ticket(0, 0, &pairs[0]);
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), vec![ticket]));
+
+		go_to_block(3, 105);
+
+		let ticket = make_ticket(1, 0, &pairs[1]);
+		assert_noop!(
+			Sassafras::submit_tickets(Origin::none(), vec![ticket]),
+			Error::<Test>::SubmissionClosed,
+		);
+	})
+}
+
+#[test]
+fn tickets_define_the_schedule_of_the_next_epoch() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		let tickets = vec![
+			make_ticket(2, 0, &pairs[2]),
+			make_ticket(3, 1, &pairs[3]),
+			make_ticket(1, 0, &pairs[1]),
+		];
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), tickets));
+
+		let mut sorted = Sassafras::next_tickets();
+		sorted.sort_by_key(|t| t.score());
+
+		// the schedule of the next epoch is already known.
+		for (i, ticket) in sorted.iter().enumerate() {
+			assert_eq!(Sassafras::slot_author(110 + i as u64), Some(ticket.authority_index));
+		}
+		assert_eq!(Sassafras::slot_author(113), Some(1));
+
+		let next_randomness = NextRandomness::get();
+
+		progress_to_block(11);
+		assert_eq!(Sassafras::current_slot(), 110);
+		assert_eq!(Sassafras::epoch_index(), 1);
+		assert_eq!(Sassafras::tickets(), sorted);
+		assert!(Sassafras::next_tickets().is_empty());
+		assert_eq!(Sassafras::randomness(), next_randomness);
+
+		for (i, ticket) in sorted.iter().enumerate() {
+			assert_eq!(Sassafras::slot_author(110 + i as u64), Some(ticket.authority_index));
+		}
+		// slots without a ticket fall back to round-robin
+		assert_eq!(Sassafras::slot_author(115), Some(3));
+		// as well as the whole next epoch, since no tickets were submitted for it
+		assert_eq!(Sassafras::slot_author(120), Some(0));
+	})
+}
+
+#[test]
+fn skipped_epochs_discard_tickets() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		let ticket = make_ticket(2, 0, &pairs[2]);
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), vec![ticket]));
+
+		go_to_block(2, 121);
+
+		assert_eq!(Sassafras::epoch_index(), 2);
+		assert!(Sassafras::tickets().is_empty());
+		assert!(Sassafras::next_tickets().is_empty());
+		assert_eq!(Sassafras::slot_author(120), Some(0));
+	})
+}
+
+#[test]
+fn validate_unsigned_checks_tickets() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(4);
+
+	ext.execute_with(|| {
+		go_to_block(1, 100);
+
+		let ticket = make_ticket(0, 0, &pairs[0]);
+		let call = Call::submit_tickets(vec![ticket.clone()]);
+
+		// tickets are valid from any source and are propagated.
+		for source in &[TransactionSource::External, TransactionSource::Local] {
+			let valid = <Sassafras as ValidateUnsigned>::validate_unsigned(*source, &call).unwrap();
+			assert!(valid.propagate);
+			assert_eq!(valid.provides, vec![("Sassafras", (1u64, 0u32, 0u32)).encode()]);
+		}
+
+		let bad_call = Call::submit_tickets(vec![make_ticket(1, 0, &pairs[0])]);
+		assert_eq!(
+			<Sassafras as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &bad_call),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::BadProof)),
+		);
+
+		assert_eq!(
+			<Sassafras as ValidateUnsigned>::validate_unsigned(
+				TransactionSource::External,
+				&Call::submit_tickets(vec![]),
+			),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Call)),
+		);
+
+		// once included the same ticket becomes stale.
+		assert_ok!(Sassafras::submit_tickets(Origin::none(), vec![ticket]));
+		assert_eq!(
+			<Sassafras as ValidateUnsigned>::validate_unsigned(TransactionSource::External, &call),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Stale)),
+		);
+	})
+}
+
+#[test]
+fn find_author_reads_the_pre_digest() {
+	let digest = make_pre_digest(3, 100);
+	let digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+
+	assert_eq!(<Sassafras as FindAuthor<u32>>::find_author(digests), Some(3));
+}
+
+#[test]
+fn initialize_weight_accounts_for_epoch_changes() {
+	new_test_ext(4).execute_with(|| {
+		let db_weight = <Test as frame_system::Config>::DbWeight::get();
+
+		// the first block also sets the genesis slot.
+		assert_eq!(Sassafras::initialize_slot(100), db_weight.reads_writes(2, 2));
+		assert_eq!(Sassafras::initialize_slot(101), db_weight.reads_writes(2, 1));
+
+		// entering the next epoch rotates the tickets and the randomness.
+		assert_eq!(Sassafras::initialize_slot(110), db_weight.reads_writes(4, 6));
+		assert_eq!(Sassafras::epoch_index(), 1);
+	})
+}
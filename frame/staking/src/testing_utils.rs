@@ -30,8 +30,8 @@ const SEED: u32 = 0;
 
 /// This function removes all validators and nominators from storage.
 pub fn clear_validators_and_nominators<T: Config>() {
-	Validators::<T>::remove_all();
-	Nominators::<T>::remove_all();
+	Validators::<T>::remove_all(None);
+	Nominators::<T>::remove_all(None);
 }
 
 /// Grab a funded user.
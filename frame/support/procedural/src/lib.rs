@@ -320,8 +320,10 @@ pub fn pallet(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 /// Execute the annotated function in a new storage transaction.
 ///
-/// The return type of the annotated function must be `Result`. All changes to storage performed
-/// by the annotated function are discarded if it returns `Err`, or committed if `Ok`.
+/// The return type of the annotated function must be `Result`, with an error type that can be
+/// created from a `DispatchError`. All changes to storage performed by the annotated function are
+/// discarded if it returns `Err`, or committed if `Ok`. If the maximum number of nested
+/// transactions is reached, the function is not executed and an error is returned instead.
 ///
 /// # Example
 ///
@@ -20,8 +20,9 @@
 use sp_std::prelude::*;
 use codec::{FullCodec, FullEncode, Encode, EncodeLike, Decode};
 use crate::hash::{Twox128, StorageHasher};
-use sp_runtime::generic::{Digest, DigestItem};
+use sp_runtime::{DispatchError, generic::{Digest, DigestItem}};
 pub use sp_runtime::TransactionOutcome;
+pub use sp_io::KillOutcome;
 
 pub mod unhashed;
 pub mod hashed;
@@ -31,47 +32,9 @@ pub mod generator;
 pub mod migration;
 pub mod types;
 
-#[cfg(all(feature = "std", any(test, debug_assertions)))]
-mod debug_helper {
-	use std::cell::RefCell;
+pub mod transactional;
 
-	thread_local! {
-		static TRANSACTION_LEVEL: RefCell<u32> = RefCell::new(0);
-	}
-
-	pub fn require_transaction() {
-		let level = TRANSACTION_LEVEL.with(|v| *v.borrow());
-		if level == 0 {
-			panic!("Require transaction not called within with_transaction");
-		}
-	}
-
-	pub struct TransactionLevelGuard;
-
-	impl Drop for TransactionLevelGuard {
-		fn drop(&mut self) {
-			TRANSACTION_LEVEL.with(|v| *v.borrow_mut() -= 1);
-		}
-	}
-
-	/// Increments the transaction level.
-	///
-	/// Returns a guard that when dropped decrements the transaction level automatically.
-	pub fn inc_transaction_level() -> TransactionLevelGuard {
-		TRANSACTION_LEVEL.with(|v| {
-			let mut val = v.borrow_mut();
-			*val += 1;
-			if *val > 10 {
-				crate::debug::warn!(
-					"Detected with_transaction with nest level {}. Nested usage of with_transaction is not recommended.",
-					*val
-				);
-			}
-		});
-
-		TransactionLevelGuard
-	}
-}
+pub use transactional::{TransactionalError, TRANSACTIONAL_LIMIT, is_transactional};
 
 /// Assert this method is called within a storage transaction.
 /// This will **panic** if is not called within a storage transaction.
@@ -79,7 +42,7 @@ mod debug_helper {
 /// This assertion is enabled for native execution and when `debug_assertions` are enabled.
 pub fn require_transaction() {
 	#[cfg(all(feature = "std", any(test, debug_assertions)))]
-	debug_helper::require_transaction();
+	assert!(is_transactional(), "Require transaction not called within with_transaction");
 }
 
 /// Execute the supplied function in a new storage transaction.
@@ -87,8 +50,37 @@ pub fn require_transaction() {
 /// All changes to storage performed by the supplied function are discarded if the returned
 /// outcome is `TransactionOutcome::Rollback`.
 ///
-/// Transactions can be nested to any depth. Commits happen to the parent transaction.
-pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+/// Transactions can be nested up to [`TRANSACTIONAL_LIMIT`] levels, commits happen to the
+/// parent transaction. If opening the transaction would exceed the limit, `f` is not called
+/// and [`TransactionalError::LimitReached`] is returned.
+pub fn with_transaction<T, E>(f: impl FnOnce() -> TransactionOutcome<Result<T, E>>) -> Result<T, E>
+where
+	E: From<DispatchError>,
+{
+	let _guard = transactional::inc_transaction_level()
+		.map_err(|e| E::from(e.into()))?;
+
+	execute_in_transaction(f)
+}
+
+/// Same as [`with_transaction`] but without enforcing the nesting limit.
+///
+/// Nesting beyond [`TRANSACTIONAL_LIMIT`] levels is still possible but emits a warning, as it
+/// usually indicates an unbounded recursion. Prefer [`with_transaction`] where possible.
+pub fn with_transaction_unchecked<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	let guard = transactional::inc_transaction_level();
+	if guard.is_err() {
+		crate::debug::warn!(
+			"Detected with_transaction_unchecked beyond the limit of {} nested transactions.",
+			TRANSACTIONAL_LIMIT,
+		);
+	}
+
+	execute_in_transaction(f)
+}
+
+/// Run `f` in a new storage layer, committing or rolling it back depending on the outcome.
+fn execute_in_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
 	use sp_io::storage::{
 		start_transaction, commit_transaction, rollback_transaction,
 	};
@@ -96,9 +88,6 @@ pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
 
 	start_transaction();
 
-	#[cfg(all(feature = "std", any(test, debug_assertions)))]
-	let _guard = debug_helper::inc_transaction_level();
-
 	match f() {
 		Commit(res) => { commit_transaction(); res },
 		Rollback(res) => { rollback_transaction(); res },
@@ -419,7 +408,11 @@ pub trait StorageDoubleMap<K1: FullEncode, K2: FullEncode, V: FullCodec> {
 		KArg2: EncodeLike<K2>;
 
 	/// Remove all values under the first key.
-	fn remove_prefix<KArg1>(k1: KArg1) where KArg1: ?Sized + EncodeLike<K1>;
+	///
+	/// At most `limit` values are removed from the backend if it is `Some`, the returned
+	/// outcome tells whether values remain under the first key.
+	fn remove_prefix<KArg1>(k1: KArg1, limit: Option<u32>) -> sp_io::KillOutcome
+		where KArg1: ?Sized + EncodeLike<K1>;
 
 	/// Iterate over values that share the first key.
 	fn iter_prefix_values<KArg1>(k1: KArg1) -> PrefixIterator<V>
@@ -584,8 +577,11 @@ pub trait StoragePrefixedMap<Value: FullCodec> {
 	}
 
 	/// Remove all value of the storage.
-	fn remove_all() {
-		sp_io::storage::clear_prefix(&Self::final_prefix())
+	///
+	/// At most `limit` values are removed from the backend if it is `Some`, the returned
+	/// outcome tells whether values remain.
+	fn remove_all(limit: Option<u32>) -> sp_io::KillOutcome {
+		sp_io::storage::clear_prefix(&Self::final_prefix(), limit)
 	}
 
 	/// Iter over all value of the storage.
@@ -690,6 +686,7 @@ mod test {
 	use sp_core::hashing::twox_128;
 	use sp_io::TestExternalities;
 	use generator::StorageValue as _;
+	use crate::assert_ok;
 
 	#[test]
 	fn prefixed_map_works() {
@@ -735,7 +732,7 @@ mod test {
 			assert_eq!(MyStorage::iter_values().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
 
 			// test removal
-			MyStorage::remove_all();
+			MyStorage::remove_all(None);
 			assert!(MyStorage::iter_values().collect::<Vec<_>>().is_empty());
 
 			// test migration
@@ -745,7 +742,7 @@ mod test {
 			assert!(MyStorage::iter_values().collect::<Vec<_>>().is_empty());
 			MyStorage::translate_values(|v: u32| Some(v as u64));
 			assert_eq!(MyStorage::iter_values().collect::<Vec<_>>(), vec![1, 2]);
-			MyStorage::remove_all();
+			MyStorage::remove_all(None);
 
 			// test migration 2
 			unhashed::put(&[&k[..], &vec![1][..]].concat(), &1u128);
@@ -757,7 +754,7 @@ mod test {
 			assert_eq!(MyStorage::iter_values().collect::<Vec<_>>(), vec![1, 2, 3]);
 			MyStorage::translate_values(|v: u128| Some(v as u64));
 			assert_eq!(MyStorage::iter_values().collect::<Vec<_>>(), vec![1, 2, 3]);
-			MyStorage::remove_all();
+			MyStorage::remove_all(None);
 
 			// test that other values are not modified.
 			assert_eq!(unhashed::get(&key_before[..]), Some(32u64));
@@ -813,15 +810,40 @@ mod test {
 	#[test]
 	fn require_transaction_should_not_panic_in_with_transaction() {
 		TestExternalities::default().execute_with(|| {
-			with_transaction(|| {
+			assert_ok!(with_transaction(|| {
 				require_transaction();
-				TransactionOutcome::Commit(())
-			});
+				TransactionOutcome::Commit(Ok::<_, DispatchError>(()))
+			}));
+
+			assert_ok!(with_transaction(|| {
+				require_transaction();
+				TransactionOutcome::Rollback(Ok::<_, DispatchError>(()))
+			}));
 
-			with_transaction(|| {
+			with_transaction_unchecked(|| {
 				require_transaction();
-				TransactionOutcome::Rollback(())
+				TransactionOutcome::Commit(())
 			});
 		});
 	}
+
+	#[test]
+	fn transaction_level_is_tracked_and_removed() {
+		TestExternalities::default().execute_with(|| {
+			assert!(!is_transactional());
+
+			assert_ok!(with_transaction(|| {
+				assert_eq!(transactional::transaction_level(), 1);
+				let res = with_transaction(|| {
+					assert_eq!(transactional::transaction_level(), 2);
+					TransactionOutcome::Rollback(Ok::<_, DispatchError>(()))
+				});
+				assert_eq!(transactional::transaction_level(), 1);
+				TransactionOutcome::Commit(res)
+			}));
+
+			assert!(!is_transactional());
+			assert!(!unhashed::exists(transactional::TRANSACTION_LEVEL_KEY));
+		});
+	}
 }
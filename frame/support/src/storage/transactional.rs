@@ -0,0 +1,91 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tracking of the storage transaction nesting level.
+//!
+//! The level is kept in storage under [`TRANSACTION_LEVEL_KEY`], so it is the same for native
+//! and wasm execution. It is only written outside of the transaction layer it accounts for, so
+//! rolling back a layer never touches the level, and it is removed again once the outermost
+//! layer is closed.
+
+use sp_runtime::DispatchError;
+
+/// The type that is used to store the number of transactional layers.
+pub type Layer = u32;
+
+/// The storage key that holds the current number of transactional layers.
+pub const TRANSACTION_LEVEL_KEY: &[u8] = b":transaction_level:";
+
+/// The maximum number of nested transactional layers.
+pub const TRANSACTIONAL_LIMIT: Layer = 255;
+
+/// Errors of the storage transaction API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionalError {
+	/// Opening the transaction would exceed [`TRANSACTIONAL_LIMIT`] nested layers.
+	LimitReached,
+}
+
+impl From<TransactionalError> for &'static str {
+	fn from(e: TransactionalError) -> &'static str {
+		match e {
+			TransactionalError::LimitReached => "Too many transactional layers have been spawned",
+		}
+	}
+}
+
+impl From<TransactionalError> for DispatchError {
+	fn from(e: TransactionalError) -> DispatchError {
+		DispatchError::Other(e.into())
+	}
+}
+
+/// Returns the current number of nested transactional layers.
+pub fn transaction_level() -> Layer {
+	super::unhashed::get_or_default::<Layer>(TRANSACTION_LEVEL_KEY)
+}
+
+/// Returns `true` if called within a storage transaction.
+pub fn is_transactional() -> bool {
+	transaction_level() > 0
+}
+
+/// Guard that decrements the transaction level when dropped.
+pub(crate) struct LayerGuard;
+
+impl Drop for LayerGuard {
+	fn drop(&mut self) {
+		match transaction_level() {
+			0 => crate::debug::warn!("Unbalanced transaction level detected, level is already 0."),
+			1 => super::unhashed::kill(TRANSACTION_LEVEL_KEY),
+			level => super::unhashed::put::<Layer>(TRANSACTION_LEVEL_KEY, &(level - 1)),
+		}
+	}
+}
+
+/// Increments the transaction level.
+///
+/// Returns a guard that decrements the level when dropped, or an error if the level is
+/// already at [`TRANSACTIONAL_LIMIT`].
+pub(crate) fn inc_transaction_level() -> Result<LayerGuard, TransactionalError> {
+	let level = transaction_level();
+	if level >= TRANSACTIONAL_LIMIT {
+		return Err(TransactionalError::LimitReached)
+	}
+	super::unhashed::put::<Layer>(TRANSACTION_LEVEL_KEY, &(level + 1));
+	Ok(LayerGuard)
+}
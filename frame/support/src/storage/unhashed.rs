@@ -92,8 +92,11 @@ pub fn kill(key: &[u8]) {
 }
 
 /// Ensure keys with the given `prefix` have no entries in storage.
-pub fn kill_prefix(prefix: &[u8]) {
-	sp_io::storage::clear_prefix(prefix);
+///
+/// Removes at most `limit` entries from the backend if it is `Some`, the returned outcome tells
+/// whether entries with the prefix remain.
+pub fn kill_prefix(prefix: &[u8], limit: Option<u32>) -> sp_io::KillOutcome {
+	sp_io::storage::clear_prefix(prefix, limit)
 }
 
 /// Get a Vec of bytes from storage.
@@ -17,7 +17,10 @@
 
 use frame_support::{
 	assert_ok, assert_noop, transactional, StorageMap, StorageValue,
-	dispatch::{DispatchError, DispatchResult}, storage::{with_transaction, TransactionOutcome::*},
+	dispatch::{DispatchError, DispatchResult}, storage::{
+		with_transaction, with_transaction_unchecked, TransactionOutcome::*, TransactionalError,
+		TRANSACTIONAL_LIMIT,
+	},
 };
 use sp_io::TestExternalities;
 use sp_std::result;
@@ -66,12 +69,12 @@ fn storage_transaction_basic_commit() {
 		assert_eq!(Value::get(), 0);
 		assert!(!Map::contains_key("val0"));
 
-		with_transaction(|| {
+		let _: DispatchResult = with_transaction(|| {
 			Value::set(99);
 			Map::insert("val0", 99);
 			assert_eq!(Value::get(), 99);
 			assert_eq!(Map::get("val0"), 99);
-			Commit(())
+			Commit(Ok(()))
 		});
 
 		assert_eq!(Value::get(), 99);
@@ -86,12 +89,12 @@ fn storage_transaction_basic_rollback() {
 		assert_eq!(Value::get(), 0);
 		assert_eq!(Map::get("val0"), 0);
 
-		with_transaction(|| {
+		let _: DispatchResult = with_transaction(|| {
 			Value::set(99);
 			Map::insert("val0", 99);
 			assert_eq!(Value::get(), 99);
 			assert_eq!(Map::get("val0"), 99);
-			Rollback(())
+			Rollback(Ok(()))
 		});
 
 		assert_eq!(Value::get(), 0);
@@ -105,12 +108,12 @@ fn storage_transaction_rollback_then_commit() {
 		Value::set(1);
 		Map::insert("val1", 1);
 
-		with_transaction(|| {
+		let _: DispatchResult = with_transaction(|| {
 			Value::set(2);
 			Map::insert("val1", 2);
 			Map::insert("val2", 2);
 
-			with_transaction(|| {
+			let _: DispatchResult = with_transaction(|| {
 				Value::set(3);
 				Map::insert("val1", 3);
 				Map::insert("val2", 3);
@@ -121,7 +124,7 @@ fn storage_transaction_rollback_then_commit() {
 				assert_eq!(Map::get("val2"), 3);
 				assert_eq!(Map::get("val3"), 3);
 
-				Rollback(())
+				Rollback(Ok(()))
 			});
 
 			assert_eq!(Value::get(), 2);
@@ -129,7 +132,7 @@ fn storage_transaction_rollback_then_commit() {
 			assert_eq!(Map::get("val2"), 2);
 			assert_eq!(Map::get("val3"), 0);
 
-			Commit(())
+			Commit(Ok(()))
 		});
 
 		assert_eq!(Value::get(), 2);
@@ -145,12 +148,12 @@ fn storage_transaction_commit_then_rollback() {
 		Value::set(1);
 		Map::insert("val1", 1);
 
-		with_transaction(|| {
+		let _: DispatchResult = with_transaction(|| {
 			Value::set(2);
 			Map::insert("val1", 2);
 			Map::insert("val2", 2);
 
-			with_transaction(|| {
+			let _: DispatchResult = with_transaction(|| {
 				Value::set(3);
 				Map::insert("val1", 3);
 				Map::insert("val2", 3);
@@ -161,7 +164,7 @@ fn storage_transaction_commit_then_rollback() {
 				assert_eq!(Map::get("val2"), 3);
 				assert_eq!(Map::get("val3"), 3);
 
-				Commit(())
+				Commit(Ok(()))
 			});
 
 			assert_eq!(Value::get(), 3);
@@ -169,7 +172,7 @@ fn storage_transaction_commit_then_rollback() {
 			assert_eq!(Map::get("val2"), 3);
 			assert_eq!(Map::get("val3"), 3);
 
-			Rollback(())
+			Rollback(Ok(()))
 		});
 
 		assert_eq!(Value::get(), 1);
@@ -217,3 +220,69 @@ fn transactional_annotation_in_decl_module() {
 		assert_noop!(<Module<Runtime>>::value_rollbacks(origin, 3), "nah");
 	});
 }
+
+/// Opens `depth` nested transactions, incrementing `Value` in each of them and calling `inner`
+/// in the innermost one.
+fn nested_transactions(depth: u32, inner: &dyn Fn()) -> DispatchResult {
+	with_transaction(|| {
+		Value::mutate(|v| *v += 1);
+		let res = if depth > 1 {
+			nested_transactions(depth - 1, inner)
+		} else {
+			inner();
+			Ok(())
+		};
+		if res.is_ok() { Commit(res) } else { Rollback(res) }
+	})
+}
+
+#[test]
+fn storage_transaction_nesting_up_to_limit() {
+	TestExternalities::default().execute_with(|| {
+		assert_ok!(nested_transactions(TRANSACTIONAL_LIMIT, &|| {}));
+		assert_eq!(Value::get(), TRANSACTIONAL_LIMIT);
+		assert!(!frame_support::storage::is_transactional());
+	});
+}
+
+#[test]
+fn storage_transaction_nesting_beyond_limit_fails() {
+	TestExternalities::default().execute_with(|| {
+		assert_noop!(
+			nested_transactions(TRANSACTIONAL_LIMIT + 1, &|| {}),
+			TransactionalError::LimitReached,
+		);
+		assert!(!frame_support::storage::is_transactional());
+
+		// Annotated functions fail the same way when nested too deep.
+		#[transactional]
+		fn set_value(v: u32) -> DispatchResult {
+			Value::set(v);
+			Ok(())
+		}
+
+		let res = nested_transactions(TRANSACTIONAL_LIMIT, &|| {
+			assert_noop!(set_value(42), TransactionalError::LimitReached);
+		});
+		assert_ok!(res);
+		assert_eq!(Value::get(), TRANSACTIONAL_LIMIT);
+	});
+}
+
+#[test]
+fn unchecked_storage_transaction_ignores_limit() {
+	TestExternalities::default().execute_with(|| {
+		assert_ok!(nested_transactions(TRANSACTIONAL_LIMIT, &|| {
+			with_transaction_unchecked(|| {
+				Map::insert("val", 1);
+				Commit(())
+			});
+			with_transaction_unchecked(|| {
+				Map::insert("val", 2);
+				Rollback(())
+			});
+		}));
+		assert_eq!(Map::get("val"), 1);
+		assert!(!frame_support::storage::is_transactional());
+	});
+}
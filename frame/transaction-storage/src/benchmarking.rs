@@ -0,0 +1,146 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Benchmarks for the Transaction Storage pallet.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_system::{EventRecord, RawOrigin};
+use sp_runtime::traits::Bounded;
+use sp_trie::TrieMut;
+
+use crate::Module as TransactionStorage;
+
+fn assert_last_event<T: Config>(generic_event: <T as Config>::Event) {
+	let events = frame_system::Module::<T>::events();
+	let system_event: <T as frame_system::Config>::Event = generic_event.into();
+	// compare to the last event record
+	let EventRecord { event, .. } = &events[events.len() - 1];
+	assert_eq!(event, &system_event);
+}
+
+/// A caller able to pay the fees of any piece of data.
+fn funded_caller<T: Config>() -> T::AccountId {
+	let caller: T::AccountId = whitelisted_caller();
+	let _ = T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+	caller
+}
+
+/// Fill the data of the current block up to one piece short of `MaxBlockTransactions`, so that
+/// the benchmarked call adds the last one.
+fn fill_block_transactions<T: Config>() {
+	let info = TransactionInfo { size: 1, block_chunks: 1, ..Default::default() };
+	BlockTransactions::put(vec![info; T::MaxBlockTransactions::get() as usize - 1]);
+}
+
+/// Build the trie of the chunks of `data`, and the proof of the chunk with the given index.
+fn chunk_proof(data: &[u8], index: u32) -> (H256, TransactionStorageProof) {
+	type Layout = sp_trie::Layout<BlakeTwo256>;
+
+	let mut db = sp_trie::MemoryDB::<BlakeTwo256>::default();
+	let mut root = Default::default();
+	{
+		let mut trie = sp_trie::TrieDBMut::<Layout>::new(&mut db, &mut root);
+		for (i, chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+			trie.insert(&encode_index(i as u32), chunk).expect("inserts into a memory db; qed");
+		}
+	}
+	let proof = sp_trie::generate_trie_proof::<Layout, _, _, _>(&db, root, &[encode_index(index)])
+		.expect("the chunk is in the trie; qed");
+	let chunk = data.chunks(CHUNK_SIZE).nth(index as usize)
+		.expect("the index is lower than the number of chunks; qed")
+		.to_vec();
+	(root, TransactionStorageProof { chunk, proof })
+}
+
+benchmarks! {
+	store {
+		let l in 1 .. T::MaxTransactionSize::get();
+		let caller = funded_caller::<T>();
+		fill_block_transactions::<T>();
+		let data = vec![0u8; l as usize];
+		let content_hash: H256 = sp_io::hashing::blake2_256(&data).into();
+	}: _(RawOrigin::Signed(caller), data)
+	verify {
+		assert_last_event::<T>(Event::Stored(content_hash).into());
+	}
+
+	renew {
+		let caller = funded_caller::<T>();
+		let info = TransactionInfo {
+			size: T::MaxTransactionSize::get(),
+			block_chunks: num_chunks(T::MaxTransactionSize::get()),
+			..Default::default()
+		};
+		let content_hash = info.content_hash;
+		let block = T::BlockNumber::one();
+		<Transactions<T>>::insert(block, vec![info]);
+		fill_block_transactions::<T>();
+	}: _(RawOrigin::Signed(caller), block, 0)
+	verify {
+		assert_last_event::<T>(Event::Renewed(content_hash).into());
+	}
+
+	// Worst case: the proven block stores `MaxBlockTransactions` pieces of data of
+	// `MaxTransactionSize` bytes, so that the proof is as deep as possible, and the parent hash
+	// selects the last chunk of the last one, so that all of them are searched.
+	check_proof {
+		let target = T::BlockNumber::one();
+		let number = target + TransactionStorage::<T>::storage_period();
+		frame_system::Module::<T>::set_block_number(number);
+
+		let data = vec![0u8; T::MaxTransactionSize::get() as usize];
+		let chunks = num_chunks(data.len() as u32);
+		let count = T::MaxBlockTransactions::get();
+		let total_chunks = chunks * count;
+		let mut parent_hash = T::Hash::default();
+		parent_hash.as_mut()[..8].copy_from_slice(&(total_chunks as u64 - 1).to_be_bytes());
+		frame_system::Module::<T>::set_parent_hash(parent_hash);
+		let (chunk_root, proof) = chunk_proof(&data, chunks - 1);
+
+		let transactions = (1..=count).map(|i| TransactionInfo {
+			chunk_root,
+			content_hash: sp_io::hashing::blake2_256(&data).into(),
+			size: data.len() as u32,
+			block_chunks: chunks * i,
+		}).collect::<Vec<_>>();
+		<Transactions<T>>::insert(target, transactions);
+		<ChunkCount<T>>::insert(target, total_chunks);
+	}: _(RawOrigin::None, proof)
+	verify {
+		assert_last_event::<T>(Event::ProofChecked.into());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::*;
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_store::<Test>());
+			assert_ok!(test_benchmark_renew::<Test>());
+			assert_ok!(test_benchmark_check_proof::<Test>());
+		});
+	}
+}
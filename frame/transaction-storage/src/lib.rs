@@ -0,0 +1,431 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Transaction Storage Module
+//!
+//! The Transaction Storage module stores arbitrary data in the database of the nodes, and
+//! makes block authors prove that they keep it.
+//!
+//! ## Overview
+//!
+//! Data submitted with `store` isn't kept in the runtime state. It is written through the
+//! offchain indexing API under [`sp_core::offchain::transaction_index_key`], which the client
+//! stores in a dedicated database column, keyed by the blake2-256 hash of the data. Nodes
+//! therefore only keep the data if they run with offchain indexing enabled.
+//!
+//! The runtime only keeps, for each block, the content hash, size and chunk root of the data
+//! stored in it. The data is split in chunks of [`CHUNK_SIZE`] bytes, and the chunk root is the
+//! root of the trie of those chunks, keyed by their index.
+//!
+//! Data is stored for `StoragePeriod` blocks, after which it's removed from the database,
+//! unless it has been renewed with `renew`, which stores it again for another period.
+//!
+//! Each block must prove that its author stores the data submitted exactly `StoragePeriod`
+//! blocks earlier. The proof is provided through the `check_proof` inherent, built by
+//! [`sp_transaction_storage_proof::InherentDataProvider`], and covers a chunk selected using
+//! the hash of the parent block as randomness. A block missing the proof is invalid.
+//!
+//! Removals from the database are only applied once the removing block is finalized, so data
+//! removed by a block of a fork that is later abandoned is kept.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! - `store` - Store some data for `StoragePeriod` blocks.
+//! - `renew` - Store data that was stored in a previous block for another period.
+//! - `check_proof` - Check the storage proof of the block. Inherent.
+//!
+//! The node reads the data to prove through [`TransactionStorageApi`], which the runtime
+//! implements by calling `storage_period` and `block_transactions`.
+//!
+//! [`TransactionStorageApi`]: sp_transaction_storage_proof::TransactionStorageApi
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Encode, Decode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	dispatch::DispatchResult,
+	traits::{Currency, ExistenceRequirement, Get, OnUnbalanced, WithdrawReasons},
+	weights::{DispatchClass, Weight},
+};
+use frame_system::{ensure_none, ensure_signed};
+use sp_core::H256;
+use sp_inherents::{InherentData, InherentIdentifier, ProvideInherent};
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{BlakeTwo256, One, Saturating, Zero},
+};
+use sp_std::{prelude::*, result};
+use sp_transaction_storage_proof::{
+	encode_index, num_chunks, random_chunk, InherentError, TransactionStorageProof,
+	TransactionStorageProofInherentData, CHUNK_SIZE, INHERENT_IDENTIFIER,
+};
+
+pub use sp_transaction_storage_proof::{DEFAULT_STORAGE_PERIOD, MAX_TRANSACTION_SIZE};
+
+mod mock;
+mod tests;
+
+mod benchmarking;
+pub mod weights;
+
+pub use weights::WeightInfo;
+
+type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::NegativeImbalance;
+
+/// Data stored in a block.
+#[derive(Encode, Decode, Clone, Default, Eq, PartialEq, RuntimeDebug)]
+pub struct TransactionInfo {
+	/// Root of the trie of the chunks of the data.
+	pub chunk_root: H256,
+	/// Blake2-256 hash of the data, under which it is indexed.
+	pub content_hash: H256,
+	/// Size of the data, in bytes.
+	pub size: u32,
+	/// Total number of chunks of the data stored in the block up to and including this one.
+	/// Used to find the data a chunk selected across the whole block belongs to.
+	pub block_chunks: u32,
+}
+
+pub trait Config: frame_system::Config {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Config>::Event>;
+
+	/// The currency the fees are paid in.
+	type Currency: Currency<Self::AccountId>;
+
+	/// What to do with the fees.
+	type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Fee paid for each stored or renewed piece of data.
+	type EntryFee: Get<BalanceOf<Self>>;
+
+	/// Fee paid for each byte of stored or renewed data.
+	type ByteFee: Get<BalanceOf<Self>>;
+
+	/// Maximum size of a piece of data. Must not exceed [`MAX_TRANSACTION_SIZE`], the size of
+	/// the data that can be served over bitswap.
+	type MaxTransactionSize: Get<u32>;
+
+	/// Maximum number of pieces of data stored or renewed in a block.
+	type MaxBlockTransactions: Get<u32>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as TransactionStorage {
+		/// Data stored in each block of the last storage period.
+		pub Transactions get(fn transactions):
+			map hasher(twox_64_concat) T::BlockNumber => Option<Vec<TransactionInfo>>;
+
+		/// Total number of chunks of the data stored in each block of the last storage period.
+		pub ChunkCount get(fn chunk_count): map hasher(twox_64_concat) T::BlockNumber => u32;
+
+		/// Number of blocks for which the data is stored.
+		pub StoragePeriod get(fn storage_period) config(): T::BlockNumber;
+
+		/// Number of entries of `Transactions` referring to each piece of data. The data is
+		/// removed from the database once it drops to zero.
+		RefCount: map hasher(identity) H256 => u32;
+
+		/// Data stored in the current block.
+		BlockTransactions: Vec<TransactionInfo>;
+
+		/// Whether the storage proof has been checked in the current block.
+		ProofChecked: bool;
+	}
+	add_extra_genesis {
+		build(|config: &GenesisConfig<T>| {
+			assert!(!config.storage_period.is_zero(), "Storage period must not be zero");
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// Data was stored. \[content_hash\]
+		Stored(H256),
+		/// Data was renewed. \[content_hash\]
+		Renewed(H256),
+		/// The storage proof of the block was checked.
+		ProofChecked,
+	}
+);
+
+decl_error! {
+	/// Error for the transaction storage module.
+	pub enum Error for Module<T: Config> {
+		/// The data is empty.
+		EmptyTransaction,
+		/// The data is larger than `MaxTransactionSize`.
+		TransactionTooLarge,
+		/// `MaxBlockTransactions` pieces of data were already stored or renewed in this block.
+		TooManyTransactions,
+		/// The data to renew isn't stored.
+		RenewedNotFound,
+		/// The storage proof was already checked in this block.
+		DoubleCheck,
+		/// No data has to be proven in this block.
+		UnexpectedProof,
+		/// The storage proof is invalid.
+		InvalidProof,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Fee paid for each stored or renewed piece of data.
+		const EntryFee: BalanceOf<T> = T::EntryFee::get();
+
+		/// Fee paid for each byte of stored or renewed data.
+		const ByteFee: BalanceOf<T> = T::ByteFee::get();
+
+		/// Maximum size of a piece of data.
+		const MaxTransactionSize: u32 = T::MaxTransactionSize::get();
+
+		/// Maximum number of pieces of data stored or renewed in a block.
+		const MaxBlockTransactions: u32 = T::MaxBlockTransactions::get();
+
+		fn integrity_test() {
+			assert!(
+				T::MaxTransactionSize::get() <= MAX_TRANSACTION_SIZE,
+				"MaxTransactionSize must not exceed MAX_TRANSACTION_SIZE",
+			);
+		}
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			// Data stored one block before the one proven in this block has expired.
+			let obsolete = n.saturating_sub(Self::storage_period().saturating_add(One::one()));
+			let mut removed = 0;
+			if !obsolete.is_zero() {
+				if let Some(transactions) = <Transactions<T>>::take(obsolete) {
+					removed = transactions.len() as u64;
+					for info in transactions {
+						Self::release(info.content_hash);
+					}
+				}
+				<ChunkCount<T>>::remove(obsolete);
+			}
+
+			// weight of `on_finalize` included
+			T::DbWeight::get().reads_writes(5 + removed, 4 + removed)
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			assert!(
+				ProofChecked::take() || Self::proven_block(n).is_none(),
+				"Storage proof must be checked once in the block",
+			);
+
+			let transactions = BlockTransactions::take();
+			let total_chunks = transactions.last().map_or(0, |info| info.block_chunks);
+			if total_chunks != 0 {
+				<ChunkCount<T>>::insert(n, total_chunks);
+				<Transactions<T>>::insert(n, transactions);
+			}
+		}
+
+		/// Store some data for `StoragePeriod` blocks.
+		///
+		/// The data is indexed under its blake2-256 hash, and a fee of `EntryFee` plus
+		/// `ByteFee` per byte is charged.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		#[weight = T::WeightInfo::store(data.len() as u32)]
+		fn store(origin, data: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!data.is_empty(), Error::<T>::EmptyTransaction);
+			ensure!(
+				data.len() <= T::MaxTransactionSize::get() as usize,
+				Error::<T>::TransactionTooLarge,
+			);
+
+			let chunks = data.chunks(CHUNK_SIZE).map(|chunk| chunk.to_vec()).collect();
+			let chunk_root = sp_io::trie::blake2_256_ordered_root(chunks);
+			let content_hash: H256 = sp_io::hashing::blake2_256(&data).into();
+
+			Self::note_transaction(&sender, TransactionInfo {
+				chunk_root,
+				content_hash,
+				size: data.len() as u32,
+				block_chunks: 0,
+			})?;
+
+			if RefCount::mutate(content_hash, |count| { *count += 1; *count }) == 1 {
+				sp_io::offchain_index::set(
+					&sp_core::offchain::transaction_index_key(content_hash.as_ref()),
+					&data,
+				);
+			}
+
+			Self::deposit_event(Event::Stored(content_hash));
+		}
+
+		/// Store the data stored at position `index` of block `block` for another
+		/// `StoragePeriod` blocks.
+		///
+		/// The same fees as `store` are charged.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		#[weight = T::WeightInfo::renew()]
+		fn renew(origin, block: T::BlockNumber, index: u32) {
+			let sender = ensure_signed(origin)?;
+			let info = <Transactions<T>>::get(block)
+				.and_then(|transactions| transactions.get(index as usize).cloned())
+				.ok_or(Error::<T>::RenewedNotFound)?;
+
+			let content_hash = info.content_hash;
+			Self::note_transaction(&sender, info)?;
+			RefCount::mutate(content_hash, |count| *count += 1);
+
+			Self::deposit_event(Event::Renewed(content_hash));
+		}
+
+		/// Check the proof that the chunk selected for this block is stored.
+		///
+		/// The dispatch origin for this call must be _None_.
+		///
+		/// This inherent must be included in every block storing data a storage period after.
+		#[weight = (T::WeightInfo::check_proof(), DispatchClass::Mandatory)]
+		fn check_proof(origin, proof: TransactionStorageProof) {
+			ensure_none(origin)?;
+			ensure!(!ProofChecked::get(), Error::<T>::DoubleCheck);
+
+			let number = <frame_system::Module<T>>::block_number();
+			let (target, total_chunks) = Self::proven_block(number)
+				.ok_or(Error::<T>::UnexpectedProof)?;
+			let transactions = <Transactions<T>>::get(target)
+				.ok_or(Error::<T>::UnexpectedProof)?;
+
+			let parent_hash = <frame_system::Module<T>>::parent_hash();
+			let selected = random_chunk(parent_hash.as_ref(), total_chunks);
+			let position = transactions.iter()
+				.position(|info| info.block_chunks > selected)
+				.ok_or(Error::<T>::InvalidProof)?;
+			let first_chunk = match position {
+				0 => 0,
+				_ => transactions[position - 1].block_chunks,
+			};
+
+			ensure!(
+				sp_trie::verify_trie_proof::<sp_trie::Layout<BlakeTwo256>, _, _, _>(
+					&transactions[position].chunk_root,
+					&proof.proof,
+					&[(encode_index(selected - first_chunk), Some(&proof.chunk))],
+				).is_ok(),
+				Error::<T>::InvalidProof,
+			);
+
+			ProofChecked::put(true);
+			Self::deposit_event(Event::ProofChecked);
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Content hashes and sizes of the data stored in the given block, for
+	/// `TransactionStorageApi::block_transactions`.
+	pub fn block_transactions(block: T::BlockNumber) -> Vec<([u8; 32], u32)> {
+		<Transactions<T>>::get(block)
+			.unwrap_or_default()
+			.into_iter()
+			.map(|info| (info.content_hash.into(), info.size))
+			.collect()
+	}
+
+	/// The block whose data must be proven in block `n`, and its total number of chunks, if
+	/// there is anything to prove.
+	fn proven_block(n: T::BlockNumber) -> Option<(T::BlockNumber, u32)> {
+		let period = Self::storage_period();
+		if n <= period {
+			return None;
+		}
+		let target = n - period;
+		match <ChunkCount<T>>::get(target) {
+			0 => None,
+			total_chunks => Some((target, total_chunks)),
+		}
+	}
+
+	/// Charge the fees of `info` to `sender` and add it to the data of the current block.
+	fn note_transaction(sender: &T::AccountId, mut info: TransactionInfo) -> DispatchResult {
+		let mut transactions = BlockTransactions::get();
+		ensure!(
+			transactions.len() < T::MaxBlockTransactions::get() as usize,
+			Error::<T>::TooManyTransactions,
+		);
+
+		let fee = T::EntryFee::get().saturating_add(
+			T::ByteFee::get().saturating_mul(info.size.into()),
+		);
+		let imbalance = T::Currency::withdraw(
+			sender,
+			fee,
+			WithdrawReasons::TRANSACTION_PAYMENT,
+			ExistenceRequirement::KeepAlive,
+		)?;
+		T::FeeDestination::on_unbalanced(imbalance);
+
+		let previous_chunks = transactions.last().map_or(0, |info| info.block_chunks);
+		info.block_chunks = previous_chunks + num_chunks(info.size);
+		transactions.push(info);
+		BlockTransactions::put(transactions);
+		Ok(())
+	}
+
+	/// Drop a reference to the given data, and remove it from the database if it was the last.
+	fn release(content_hash: H256) {
+		let remaining = RefCount::mutate_exists(content_hash, |count| {
+			let remaining = count.take().unwrap_or(1).saturating_sub(1);
+			if remaining != 0 {
+				*count = Some(remaining);
+			}
+			remaining
+		});
+		if remaining == 0 {
+			sp_io::offchain_index::clear(
+				&sp_core::offchain::transaction_index_key(content_hash.as_ref()),
+			);
+		}
+	}
+}
+
+impl<T: Config> ProvideInherent for Module<T> {
+	type Call = Call<T>;
+	type Error = InherentError;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+		let proof = data.storage_proof()
+			.expect("Gets and decodes the storage proof inherent data")?;
+		Some(Call::check_proof(proof))
+	}
+
+	fn check_inherent(_call: &Self::Call, _data: &InherentData) -> result::Result<(), Self::Error> {
+		// The proof is checked when the call is dispatched.
+		Ok(())
+	}
+}
@@ -0,0 +1,130 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use crate::{self as pallet_transaction_storage, Config};
+use sp_runtime::{testing::Header, traits::IdentityLookup};
+use frame_support::{parameter_types, traits::{OnFinalize, OnInitialize}};
+use sp_core::H256;
+use sp_transaction_storage_proof::TransactionStorageProof;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system::{Module, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Module, Call, Config<T>, Storage, Event<T>},
+		TransactionStorage: pallet_transaction_storage::{Module, Call, Storage, Config<T>, Inherent, Event},
+	}
+);
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const ExistentialDeposit: u64 = 1;
+	pub const EntryFee: u64 = 100;
+	pub const ByteFee: u64 = 1;
+	pub const MaxTransactionSize: u32 = 1024;
+	pub const MaxBlockTransactions: u32 = 4;
+}
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = ();
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Version = ();
+	type Hashing = sp_runtime::traits::BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = Event;
+	type BlockHashCount = BlockHashCount;
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ();
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
+impl Config for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type FeeDestination = ();
+	type EntryFee = EntryFee;
+	type ByteFee = ByteFee;
+	type MaxTransactionSize = MaxTransactionSize;
+	type MaxBlockTransactions = MaxBlockTransactions;
+	type WeightInfo = ();
+}
+
+/// Number of blocks for which data is stored in tests.
+pub const STORAGE_PERIOD: u64 = 10;
+
+/// Finalize the current block, including the proof returned by `proof` if any, and initialize
+/// the following ones until block `n`.
+pub fn run_to_block(n: u64, proof: impl Fn() -> Option<TransactionStorageProof>) {
+	while System::block_number() < n {
+		if let Some(proof) = proof() {
+			TransactionStorage::check_proof(Origin::none(), proof).unwrap();
+		}
+		TransactionStorage::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+
+		let number = System::block_number() + 1;
+		System::set_block_number(number);
+		System::set_parent_hash(H256::repeat_byte(number as u8));
+		System::on_initialize(number);
+		TransactionStorage::on_initialize(number);
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 1_000_000), (2, 100)],
+	}.assimilate_storage(&mut t).unwrap();
+	pallet_transaction_storage::GenesisConfig::<Test> {
+		storage_period: STORAGE_PERIOD,
+	}.assimilate_storage(&mut t).unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
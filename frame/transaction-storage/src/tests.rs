@@ -0,0 +1,162 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use crate::{Error, mock::*};
+use frame_support::{assert_noop, assert_ok};
+use sp_core::{H256, offchain::transaction_index_key};
+use sp_transaction_storage_proof::build_proof;
+
+fn indexed(ext: &mut sp_io::TestExternalities, data: &[u8]) -> Option<Vec<u8>> {
+	ext.persist_offchain_overlay();
+	let key = transaction_index_key(&sp_io::hashing::blake2_256(data));
+	ext.offchain_db().get(&key)
+}
+
+#[test]
+fn stored_data_is_indexed() {
+	let mut ext = new_test_ext();
+	let data = vec![42u8; 600];
+	ext.execute_with(|| {
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		assert_eq!(Balances::free_balance(1), 1_000_000 - 100 - 600);
+		run_to_block(2, || None);
+
+		let content_hash = H256::from(sp_io::hashing::blake2_256(&data));
+		let transactions = TransactionStorage::transactions(1).unwrap();
+		assert_eq!(transactions.len(), 1);
+		assert_eq!(transactions[0].content_hash, content_hash);
+		assert_eq!(transactions[0].block_chunks, 3);
+		assert_eq!(TransactionStorage::chunk_count(1), 3);
+		assert_eq!(TransactionStorage::block_transactions(1), vec![(content_hash.into(), 600)]);
+	});
+	assert_eq!(indexed(&mut ext, &data), Some(data));
+}
+
+#[test]
+fn invalid_data_is_rejected() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(1), Vec::new()),
+			Error::<Test>::EmptyTransaction,
+		);
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(1), vec![0; 1025]),
+			Error::<Test>::TransactionTooLarge,
+		);
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(2), vec![0; 10]),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+		for i in 0..4 {
+			assert_ok!(TransactionStorage::store(Origin::signed(1), vec![i]));
+		}
+		assert_noop!(
+			TransactionStorage::store(Origin::signed(1), vec![4]),
+			Error::<Test>::TooManyTransactions,
+		);
+	});
+}
+
+#[test]
+fn data_expires_after_storage_period() {
+	let mut ext = new_test_ext();
+	let data = vec![1u8; 300];
+	ext.execute_with(|| {
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+	});
+	assert_eq!(indexed(&mut ext, &data), Some(data.clone()));
+
+	ext.execute_with(|| {
+		run_to_block(1 + STORAGE_PERIOD, || None);
+		let proof = build_proof(System::parent_hash().as_ref(), &[data.clone()]).unwrap();
+		assert!(proof.is_some());
+		run_to_block(2 + STORAGE_PERIOD, || proof.clone());
+		assert!(TransactionStorage::transactions(1).is_none());
+		assert_eq!(TransactionStorage::chunk_count(1), 0);
+	});
+	assert_eq!(indexed(&mut ext, &data), None);
+}
+
+#[test]
+fn renewed_data_is_kept() {
+	let mut ext = new_test_ext();
+	let data = vec![1u8; 300];
+	ext.execute_with(|| {
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		run_to_block(5, || None);
+		assert_ok!(TransactionStorage::renew(Origin::signed(1), 1, 0));
+		assert_noop!(
+			TransactionStorage::renew(Origin::signed(1), 1, 1),
+			Error::<Test>::RenewedNotFound,
+		);
+
+		run_to_block(1 + STORAGE_PERIOD, || None);
+		let proof = build_proof(System::parent_hash().as_ref(), &[data.clone()]).unwrap();
+		run_to_block(2 + STORAGE_PERIOD, || proof.clone());
+		assert!(TransactionStorage::transactions(1).is_none());
+		assert_eq!(TransactionStorage::transactions(5).unwrap()[0].size, 300);
+	});
+	assert_eq!(indexed(&mut ext, &data), Some(data));
+}
+
+#[test]
+fn proof_of_wrong_chunk_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let data = vec![1u8; 300];
+		assert_ok!(TransactionStorage::store(Origin::signed(1), data.clone()));
+		run_to_block(1 + STORAGE_PERIOD, || None);
+
+		// The parent hash selects the second chunk.
+		let proof = build_proof(&[0; 32], &[data.clone()]).unwrap().unwrap();
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::none(), proof),
+			Error::<Test>::InvalidProof,
+		);
+
+		let mut proof = build_proof(System::parent_hash().as_ref(), &[data]).unwrap().unwrap();
+		proof.chunk[0] = 2;
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::none(), proof),
+			Error::<Test>::InvalidProof,
+		);
+	});
+}
+
+#[test]
+fn proof_is_only_accepted_when_expected() {
+	new_test_ext().execute_with(|| {
+		let data = vec![1u8; 300];
+		let proof = build_proof(&[0; 32], &[data]).unwrap().unwrap();
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::none(), proof),
+			Error::<Test>::UnexpectedProof,
+		);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Storage proof must be checked once in the block")]
+fn missing_proof_panics() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TransactionStorage::store(Origin::signed(1), vec![1u8; 300]));
+		run_to_block(2 + STORAGE_PERIOD, || None);
+	});
+}
@@ -67,6 +67,21 @@ benchmarks! {
 	verify {
 		assert_last_event::<T>(Event::BatchCompleted.into())
 	}
+
+	// Worst case: every call fails, so each one is rolled back and deposits an `ItemFailed` event.
+	force_batch {
+		let c in 0 .. 1000;
+		let mut calls: Vec<<T as Config>::Call> = Vec::new();
+		for i in 0 .. c {
+			let call = frame_system::Call::set_heap_pages(0).into();
+			calls.push(call);
+		}
+		let caller = whitelisted_caller();
+	}: _(RawOrigin::Signed(caller), calls)
+	verify {
+		let expected = if c == 0 { Event::BatchCompleted } else { Event::BatchCompletedWithErrors };
+		assert_last_event::<T>(expected.into())
+	}
 }
 
 #[cfg(test)]
@@ -81,6 +96,7 @@ mod tests {
 			assert_ok!(test_benchmark_batch::<Test>());
 			assert_ok!(test_benchmark_as_derivative::<Test>());
 			assert_ok!(test_benchmark_batch_all::<Test>());
+			assert_ok!(test_benchmark_force_batch::<Test>());
 		});
 	}
 }
@@ -45,6 +45,9 @@
 //!
 //! #### For batch dispatch
 //! * `batch` - Dispatch multiple calls from the sender's origin.
+//! * `batch_all` - Dispatch multiple calls from the sender's origin, atomically.
+//! * `force_batch` - Dispatch multiple calls from the sender's origin, rolling back only the
+//!   calls that failed.
 //!
 //! #### For pseudonymal dispatch
 //! * `as_derivative` - Dispatch a call from a derivative signed origin.
@@ -65,6 +68,7 @@ use sp_core::TypeId;
 use sp_io::hashing::blake2_256;
 use frame_support::{decl_module, decl_event, decl_storage, Parameter, transactional};
 use frame_support::{
+	storage::{with_transaction, TransactionOutcome},
 	traits::{OriginTrait, UnfilteredDispatchable, Get},
 	weights::{Weight, GetDispatchInfo, DispatchClass, extract_actual_weight},
 	dispatch::{PostDispatchInfo, DispatchResultWithPostInfo},
@@ -99,6 +103,11 @@ decl_event! {
 		BatchInterrupted(u32, DispatchError),
 		/// Batch of dispatches completed fully with no error.
 		BatchCompleted,
+		/// Batch of dispatches completed but some calls failed and were rolled back.
+		BatchCompletedWithErrors,
+		/// A call of a forced batch failed and its changes were rolled back. Index of the failing
+		/// dispatch given, as well as the error. \[index, error\]
+		ItemFailed(u32, DispatchError),
 	}
 }
 
@@ -278,6 +287,81 @@ decl_module! {
 			let base_weight = T::WeightInfo::batch_all(calls_len as u32);
 			Ok(Some(base_weight + weight).into())
 		}
+
+		/// Send a batch of dispatch calls, rolling back the changes of each call that fails.
+		///
+		/// Unlike `batch`, a failing call does not interrupt the batch and leaves no changes
+		/// behind, as every call is dispatched in its own storage transaction.
+		///
+		/// May be called from any origin.
+		///
+		/// - `calls`: The calls to be dispatched from the same origin.
+		///
+		/// If origin is root then call are dispatch without checking origin filter. (This includes
+		/// bypassing `frame_system::Config::BaseCallFilter`).
+		///
+		/// # <weight>
+		/// - Complexity: O(C) where C is the number of calls to be batched.
+		/// # </weight>
+		///
+		/// This will return `Ok` in all circumstances. An `ItemFailed` event is deposited for every
+		/// call that failed, followed by `BatchCompletedWithErrors` if any call failed, or by
+		/// `BatchCompleted` otherwise.
+		#[weight = {
+			let dispatch_infos = calls.iter().map(|call| call.get_dispatch_info()).collect::<Vec<_>>();
+			let dispatch_weight = dispatch_infos.iter()
+				.map(|di| di.weight)
+				.fold(0, |total: Weight, weight: Weight| total.saturating_add(weight))
+				.saturating_add(T::WeightInfo::force_batch(calls.len() as u32));
+			let dispatch_class = {
+				let all_operational = dispatch_infos.iter()
+					.map(|di| di.class)
+					.all(|class| class == DispatchClass::Operational);
+				if all_operational {
+					DispatchClass::Operational
+				} else {
+					DispatchClass::Normal
+				}
+			};
+			(dispatch_weight, dispatch_class)
+		}]
+		fn force_batch(origin, calls: Vec<<T as Config>::Call>) -> DispatchResultWithPostInfo {
+			let is_root = ensure_root(origin.clone()).is_ok();
+			let calls_len = calls.len();
+			// Track the actual weight of each of the batch calls.
+			let mut weight: Weight = 0;
+			let mut has_error = false;
+			for (index, call) in calls.into_iter().enumerate() {
+				let info = call.get_dispatch_info();
+				// Dispatch each call in its own transaction so a failing call leaves no changes.
+				let result = with_transaction(|| {
+					// If origin is root, bypass any dispatch filter; root can call anything.
+					let result = if is_root {
+						call.dispatch_bypass_filter(origin.clone())
+					} else {
+						call.dispatch(origin.clone())
+					};
+					if result.is_ok() {
+						TransactionOutcome::Commit(result)
+					} else {
+						TransactionOutcome::Rollback(result)
+					}
+				});
+				// Add the weight of this call.
+				weight = weight.saturating_add(extract_actual_weight(&result, &info));
+				if let Err(e) = result {
+					has_error = true;
+					Self::deposit_event(Event::ItemFailed(index as u32, e.error));
+				}
+			}
+			if has_error {
+				Self::deposit_event(Event::BatchCompletedWithErrors);
+			} else {
+				Self::deposit_event(Event::BatchCompleted);
+			}
+			let base_weight = T::WeightInfo::force_batch(calls_len as u32);
+			Ok(Some(base_weight + weight).into())
+		}
 	}
 }
 
@@ -35,7 +35,7 @@ use crate as utility;
 // example module to test behaviors.
 pub mod example {
 	use super::*;
-	use frame_support::dispatch::WithPostDispatchInfo;
+	use frame_support::dispatch::{DispatchResult, WithPostDispatchInfo};
 	pub trait Config: frame_system::Config { }
 
 	decl_module! {
@@ -62,6 +62,12 @@ pub mod example {
 					Ok(end_weight.into())
 				}
 			}
+
+			#[weight = 0]
+			fn put_and_fail(_origin, value: u32) -> DispatchResult {
+				storage::unhashed::put(b":example:value", &value);
+				Err("Failed after writing.".into())
+			}
 		}
 	}
 }
@@ -525,3 +531,70 @@ fn batch_all_handles_weight_refund() {
 		);
 	});
 }
+
+#[test]
+fn force_batch_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Balances::free_balance(1), 10);
+		assert_eq!(Balances::free_balance(2), 10);
+		assert_ok!(
+			Utility::force_batch(Origin::signed(1), vec![
+				Call::Balances(BalancesCall::transfer(2, 5)),
+				Call::Balances(BalancesCall::transfer(2, 10)),
+				Call::Balances(BalancesCall::transfer(2, 5)),
+			]),
+		);
+		assert_eq!(Balances::free_balance(1), 0);
+		assert_eq!(Balances::free_balance(2), 20);
+		assert!(System::events().iter().any(|r| matches!(
+			r.event,
+			Event::utility(utility::Event::ItemFailed(1, DispatchError::Module { .. })),
+		)));
+		expect_event(utility::Event::BatchCompletedWithErrors);
+
+		assert_ok!(
+			Utility::force_batch(Origin::signed(2), vec![
+				Call::Balances(BalancesCall::transfer(1, 5)),
+			]),
+		);
+		expect_event(utility::Event::BatchCompleted);
+	});
+}
+
+#[test]
+fn force_batch_rolls_back_failed_calls_only() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(
+			Utility::force_batch(Origin::signed(1), vec![
+				Call::Balances(BalancesCall::transfer(2, 5)),
+				Call::Example(ExampleCall::put_and_fail(42)),
+			]),
+		);
+		expect_event(utility::Event::BatchCompletedWithErrors);
+		// The failing call left no changes behind, the successful one is kept.
+		assert_eq!(storage::unhashed::get::<u32>(b":example:value"), None);
+		assert_eq!(Balances::free_balance(1), 5);
+		assert_eq!(Balances::free_balance(2), 15);
+	});
+}
+
+#[test]
+fn force_batch_handles_weight_refund() {
+	new_test_ext().execute_with(|| {
+		let start_weight = 100;
+		let end_weight = 75;
+		let diff = start_weight - end_weight;
+
+		// Refund weight of failed and successful calls, without interrupting the batch
+		let good_call = Call::Example(ExampleCall::foobar(false, start_weight, Some(end_weight)));
+		let bad_call = Call::Example(ExampleCall::foobar(true, start_weight, Some(end_weight)));
+		let batch_calls = vec![good_call, bad_call.clone(), bad_call];
+		let batch_len = batch_calls.len() as Weight;
+		let call = Call::Utility(UtilityCall::force_batch(batch_calls));
+		let info = call.get_dispatch_info();
+		let result = call.dispatch(Origin::signed(1));
+		assert_ok!(result);
+		expect_event(utility::Event::BatchCompletedWithErrors);
+		assert_eq!(extract_actual_weight(&result, &info), info.weight - diff * batch_len);
+	});
+}
@@ -46,6 +46,7 @@ pub trait WeightInfo {
 	fn batch(c: u32, ) -> Weight;
 	fn as_derivative() -> Weight;
 	fn batch_all(c: u32, ) -> Weight;
+	fn force_batch(c: u32, ) -> Weight;
 	
 }
 
@@ -66,6 +67,11 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add((2_738_000 as Weight).saturating_mul(c as Weight))
 			
 	}
+	fn force_batch(c: u32, ) -> Weight {
+		(20_536_000 as Weight)
+			.saturating_add((3_164_000 as Weight).saturating_mul(c as Weight))
+			
+	}
 	
 }
 
@@ -85,5 +91,10 @@ impl WeightInfo for () {
 			.saturating_add((2_738_000 as Weight).saturating_mul(c as Weight))
 			
 	}
+	fn force_batch(c: u32, ) -> Weight {
+		(20_536_000 as Weight)
+			.saturating_add((3_164_000 as Weight).saturating_mul(c as Weight))
+			
+	}
 	
 }
@@ -99,6 +99,12 @@ pub trait Backend<Block: BlockT>: HeaderBackend<Block> + HeaderMetadata<Block, E
 	/// Return hashes of all blocks that are children of the block with `parent_hash`.
 	fn children(&self, parent_hash: Block::Hash) -> Result<Vec<Block::Hash>>;
 
+	/// Get the data indexed through the offchain indexing API under the given content hash.
+	/// Returns `None` if it isn't stored.
+	fn indexed_transaction(&self, _hash: &Block::Hash) -> Result<Option<Vec<u8>>> {
+		Ok(None)
+	}
+
 	/// Get the most recent block hash of the best (longest) chains
 	/// that contain block with the given `target_hash`.
 	///
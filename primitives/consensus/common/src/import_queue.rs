@@ -26,7 +26,7 @@
 //! instantiated. The `BasicQueue` and `BasicVerifier` traits allow serial
 //! queues to be instantiated simply.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use sp_runtime::{Justification, traits::{Block as BlockT, Header as _, NumberFor}};
 
@@ -93,6 +93,25 @@ pub trait Verifier<B: BlockT>: Send + Sync {
 		justification: Option<Justification>,
 		body: Option<Vec<B::Extrinsic>>,
 	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String>;
+
+	/// Whether verifying a block requires its parent to be imported, e.g. because the parent's
+	/// state or epoch data is read.
+	///
+	/// Verifiers returning `false` allow the blocks of a batch to be verified concurrently, before
+	/// any of them is imported.
+	fn requires_imported_parent(&self) -> bool {
+		true
+	}
+
+	/// Perform the checks of [`Verifier::verify`] that don't require the parent of `header` to be
+	/// imported, e.g. checking the seal against the authorities known at `ancestor`, the closest
+	/// imported ancestor of `header`.
+	///
+	/// Called concurrently for the blocks of a batch that [require their parent to be
+	/// imported](Verifier::requires_imported_parent), before `verify` is called for them in order.
+	/// Verifiers can remember the successful checks to skip them in `verify`, as long as the
+	/// actual parent leads to the same result.
+	fn pre_verify(&self, _header: &B::Header, _ancestor: &B::Hash) {}
 }
 
 /// Blocks import queue API.
@@ -179,6 +198,44 @@ pub(crate) fn import_single_block_metered<B: BlockT, V: Verifier<B>, Transaction
 	block: IncomingBlock<B>,
 	verifier: &mut V,
 	metrics: Option<Metrics>,
+) -> Result<BlockImportResult<NumberFor<B>>, BlockImportError> {
+	let verification_metrics = metrics.clone();
+	import_single_block_with_verification(
+		import_handle,
+		block,
+		metrics,
+		Duration::default(),
+		|header, justification, body| {
+			let started = wasm_timer::Instant::now();
+			let result = verifier.verify(block_origin, header, justification, body);
+			if let Some(metrics) = verification_metrics.as_ref() {
+				metrics.report_verification(result.is_ok(), started.elapsed());
+			}
+			result
+		},
+	)
+}
+
+/// Result of verifying a single block, as returned by [`Verifier::verify`].
+pub(crate) type VerificationResult<B> =
+	Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String>;
+
+/// Single block import function with metering, using `verify` to obtain the import parameters.
+///
+/// `verify` is only called if the block is not already imported. Reporting the verification
+/// time is left to `verify`, as it may have been computed ahead of time. `verification_time` is
+/// the time spent verifying the block ahead of time, it is included in the reported verification
+/// and import time.
+pub(crate) fn import_single_block_with_verification<B: BlockT, Transaction>(
+	import_handle: &mut dyn BlockImport<B, Transaction = Transaction, Error = ConsensusError>,
+	block: IncomingBlock<B>,
+	metrics: Option<Metrics>,
+	verification_time: Duration,
+	verify: impl FnOnce(
+		B::Header,
+		Option<Justification>,
+		Option<Vec<B::Extrinsic>>,
+	) -> VerificationResult<B>,
 ) -> Result<BlockImportResult<NumberFor<B>>, BlockImportError> {
 	let peer = block.origin;
 
@@ -238,32 +295,27 @@ pub(crate) fn import_single_block_metered<B: BlockT, V: Verifier<B>, Transaction
 	}
 
 	let started = wasm_timer::Instant::now();
-	let (mut import_block, maybe_keys) = verifier.verify(block_origin, header, justification, block.body)
+	let (mut import_block, maybe_keys) = verify(header, justification, block.body)
 		.map_err(|msg| {
 			if let Some(ref peer) = peer {
 				trace!(target: "sync", "Verifying {}({}) from {} failed: {}", number, hash, peer, msg);
 			} else {
 				trace!(target: "sync", "Verifying {}({}) failed: {}", number, hash, msg);
 			}
-			if let Some(metrics) = metrics.as_ref() {
-				metrics.report_verification(false, started.elapsed());
-			}
 			BlockImportError::VerificationFailed(peer.clone(), msg)
 		})?;
 
-	if let Some(metrics) = metrics.as_ref() {
-		metrics.report_verification(true, started.elapsed());
-	}
-
 	let mut cache = HashMap::new();
 	if let Some(keys) = maybe_keys {
 		cache.extend(keys.into_iter());
 	}
 	import_block.allow_missing_state = block.allow_missing_state;
 
+	let import_started = wasm_timer::Instant::now();
 	let imported = import_handle.import_block(import_block.convert_transaction(), cache);
 	if let Some(metrics) = metrics.as_ref() {
-		metrics.report_verification_and_import(started.elapsed());
+		metrics.report_import_time(import_started.elapsed());
+		metrics.report_verification_and_import(verification_time + started.elapsed());
 	}
 	import_handler(imported)
 }
@@ -15,8 +15,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{pin::Pin, time::Duration, marker::PhantomData};
-use futures::{prelude::*, task::Context, task::Poll};
+use std::{collections::HashMap, pin::Pin, time::Duration, marker::PhantomData};
+use futures::{prelude::*, task::Context, task::Poll, channel::oneshot, executor::ThreadPool};
 use futures_timer::Delay;
 use sp_runtime::{Justification, traits::{Block as BlockT, Header as HeaderT, NumberFor}};
 use sp_utils::mpsc::{TracingUnboundedSender, tracing_unbounded, TracingUnboundedReceiver};
@@ -27,7 +27,8 @@ use crate::{
 	import_queue::{
 		BlockImportResult, BlockImportError, Verifier, BoxBlockImport,
 		BoxJustificationImport, ImportQueue, Link, Origin,
-		IncomingBlock, import_single_block_metered,
+		IncomingBlock, VerificationResult, import_single_block_metered,
+		import_single_block_with_verification,
 		buffered_link::{self, BufferedLinkSender, BufferedLinkReceiver},
 	},
 	metrics::Metrics,
@@ -64,6 +65,61 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 		justification_import: Option<BoxJustificationImport<B>>,
 		spawner: &impl sp_core::traits::SpawnNamed,
 		prometheus_registry: Option<&Registry>,
+	) -> Self {
+		Self::new_inner(
+			verifier,
+			block_import,
+			justification_import,
+			spawner,
+			prometheus_registry,
+			None,
+		)
+	}
+
+	/// Instantiate a new basic queue that verifies the blocks of each batch concurrently on
+	/// `verification_threads` threads, before importing them sequentially.
+	///
+	/// Every block is verified by its own clone of `verifier`. If the verifier
+	/// [requires the parent to be imported](Verifier::requires_imported_parent), only blocks whose
+	/// parent is not part of the same batch are verified ahead of time, the others are verified in
+	/// order once their parent is imported. Falls back to sequential verification if the thread
+	/// pool can not be created.
+	pub fn with_parallel_verification<V: 'static + Verifier<B> + Clone>(
+		verifier: V,
+		block_import: BoxBlockImport<B, Transaction>,
+		justification_import: Option<BoxJustificationImport<B>>,
+		spawner: &impl sp_core::traits::SpawnNamed,
+		prometheus_registry: Option<&Registry>,
+		verification_threads: usize,
+	) -> Self {
+		let parallel_verification = ParallelVerification::new(
+			verifier.clone(),
+			verification_threads,
+		).map_err(|err| {
+			log::warn!(
+				target: "sync",
+				"Failed to create block verification thread pool, verifying sequentially: {}",
+				err,
+			);
+		}).ok();
+
+		Self::new_inner(
+			verifier,
+			block_import,
+			justification_import,
+			spawner,
+			prometheus_registry,
+			parallel_verification,
+		)
+	}
+
+	fn new_inner<V: 'static + Verifier<B>>(
+		verifier: V,
+		block_import: BoxBlockImport<B, Transaction>,
+		justification_import: Option<BoxJustificationImport<B>>,
+		spawner: &impl sp_core::traits::SpawnNamed,
+		prometheus_registry: Option<&Registry>,
+		parallel_verification: Option<ParallelVerification<B>>,
 	) -> Self {
 		let (result_sender, result_port) = buffered_link::buffered_link();
 
@@ -81,6 +137,7 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 			block_import,
 			justification_import,
 			metrics,
+			parallel_verification,
 		);
 
 		spawner.spawn_blocking("basic-block-import-worker", future.boxed());
@@ -146,6 +203,93 @@ mod worker_messages {
 	pub struct ImportJustification<B: BlockT>(pub Origin, pub B::Hash, pub NumberFor<B>, pub Justification);
 }
 
+/// Verification of the blocks of a batch on a thread pool, ahead of their sequential import.
+struct ParallelVerification<B: BlockT> {
+	/// The threads running the verification.
+	pool: ThreadPool,
+	/// Creates a verifier for a single block.
+	new_verifier: Box<dyn Fn() -> Box<dyn Verifier<B>> + Send + Sync>,
+	/// Whether the verifier requires the parent of a block to be imported.
+	requires_imported_parent: bool,
+}
+
+impl<B: BlockT> ParallelVerification<B> {
+	fn new<V: 'static + Verifier<B> + Clone>(
+		verifier: V,
+		threads: usize,
+	) -> std::io::Result<Self> {
+		let pool = ThreadPool::builder()
+			.pool_size(threads.max(1))
+			.name_prefix("block-verification-")
+			.create()?;
+
+		Ok(Self {
+			pool,
+			requires_imported_parent: verifier.requires_imported_parent(),
+			new_verifier: Box::new(move || Box::new(verifier.clone())),
+		})
+	}
+
+	/// Start verifying the given block on the thread pool.
+	///
+	/// Returns `None` if the block has no header, it will then fail to import anyway.
+	fn spawn_verification(
+		&self,
+		origin: BlockOrigin,
+		block: &IncomingBlock<B>,
+		metrics: Option<Metrics>,
+	) -> Option<PendingVerification<B>> {
+		let header = block.header.clone()?;
+		let justification = block.justification.clone();
+		let body = block.body.clone();
+		let mut verifier = (self.new_verifier)();
+		let (result_sender, result_receiver) = oneshot::channel();
+
+		self.pool.spawn_ok(async move {
+			let started = wasm_timer::Instant::now();
+			let result = verifier.verify(origin, header, justification, body);
+			let verification_time = started.elapsed();
+			if let Some(metrics) = metrics.as_ref() {
+				metrics.report_verification(result.is_ok(), verification_time);
+			}
+			// The receiver is dropped if the import of an earlier block failed.
+			let _ = result_sender.send((result, verification_time));
+		});
+
+		Some(PendingVerification::Verified(result_receiver))
+	}
+
+	/// Start [pre-verifying](Verifier::pre_verify) the given block on the thread pool, against its
+	/// closest imported ancestor.
+	///
+	/// Returns `None` if the block has no header, it will then fail to import anyway.
+	fn spawn_pre_verification(
+		&self,
+		block: &IncomingBlock<B>,
+		ancestor: B::Hash,
+	) -> Option<PendingVerification<B>> {
+		let header = block.header.clone()?;
+		let verifier = (self.new_verifier)();
+		let (done_sender, done_receiver) = oneshot::channel();
+
+		self.pool.spawn_ok(async move {
+			verifier.pre_verify(&header, &ancestor);
+			let _ = done_sender.send(());
+		});
+
+		Some(PendingVerification::PreVerified(done_receiver))
+	}
+}
+
+/// Verification of a block started ahead of its import.
+enum PendingVerification<B: BlockT> {
+	/// The block is fully verified, along with the time it took.
+	Verified(oneshot::Receiver<(VerificationResult<B>, Duration)>),
+	/// The checks independent of the parent are done, the block still has to be verified once its
+	/// parent is imported.
+	PreVerified(oneshot::Receiver<()>),
+}
+
 /// The process of importing blocks.
 ///
 /// This polls the `block_import_receiver` for new blocks to import and than awaits on importing these blocks.
@@ -160,6 +304,7 @@ async fn block_import_process<B: BlockT, Transaction: Send>(
 	mut block_import_receiver: TracingUnboundedReceiver<worker_messages::ImportBlocks<B>>,
 	metrics: Option<Metrics>,
 	delay_between_blocks: Duration,
+	parallel_verification: Option<ParallelVerification<B>>,
 ) {
 	loop {
 		let worker_messages::ImportBlocks(origin, blocks) = match block_import_receiver.next().await {
@@ -172,6 +317,7 @@ async fn block_import_process<B: BlockT, Transaction: Send>(
 			origin,
 			blocks,
 			&mut verifier,
+			parallel_verification.as_ref(),
 			delay_between_blocks,
 			metrics.clone(),
 		).await;
@@ -193,6 +339,7 @@ impl<B: BlockT> BlockImportWorker<B> {
 		block_import: BoxBlockImport<B, Transaction>,
 		justification_import: Option<BoxJustificationImport<B>>,
 		metrics: Option<Metrics>,
+		parallel_verification: Option<ParallelVerification<B>>,
 	) -> (
 		impl Future<Output = ()> + Send,
 		TracingUnboundedSender<worker_messages::ImportJustification<B>>,
@@ -229,6 +376,7 @@ impl<B: BlockT> BlockImportWorker<B> {
 				block_import_port,
 				worker.metrics.clone(),
 				delay_between_blocks,
+				parallel_verification,
 			);
 			futures::pin_mut!(block_import_process);
 
@@ -303,12 +451,18 @@ struct ImportManyBlocksResult<B: BlockT> {
 
 /// Import several blocks at once, returning import result for each block.
 ///
+/// If `parallel_verification` is given, blocks are verified concurrently up front, unless the
+/// verifier requires their parent to be imported and the parent is part of this batch. These
+/// blocks are only [pre-verified](Verifier::pre_verify) up front and verified with `verifier` once
+/// their parent is imported. The blocks are always imported in order.
+///
 /// This will yield after each imported block once, to ensure that other futures can be called as well.
 async fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	import_handle: &mut BoxBlockImport<B, Transaction>,
 	blocks_origin: BlockOrigin,
 	blocks: Vec<IncomingBlock<B>>,
 	verifier: &mut V,
+	parallel_verification: Option<&ParallelVerification<B>>,
 	delay_between_blocks: Duration,
 	metrics: Option<Metrics>,
 ) -> ImportManyBlocksResult<B> {
@@ -325,6 +479,41 @@ async fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 
 	trace!(target: "sync", "Starting import of {} blocks {}", count, blocks_range);
 
+	let mut pending_verifications = parallel_verification.map(|parallel_verification| {
+		// The closest ancestor of the blocks in this batch that is not part of it, only tracked if
+		// the verifier requires the parent of a block to be imported.
+		let mut ancestors = HashMap::new();
+
+		blocks.iter()
+			.map(|block| {
+				let parent_hash = *block.header.as_ref()?.parent_hash();
+				if !parallel_verification.requires_imported_parent {
+					return parallel_verification.spawn_verification(
+						blocks_origin.clone(),
+						block,
+						metrics.clone(),
+					)
+				}
+
+				match ancestors.get(&parent_hash).cloned() {
+					Some(ancestor) => {
+						ancestors.insert(block.hash, ancestor);
+						parallel_verification.spawn_pre_verification(block, ancestor)
+					},
+					None => {
+						ancestors.insert(block.hash, parent_hash);
+						parallel_verification.spawn_verification(
+							blocks_origin.clone(),
+							block,
+							metrics.clone(),
+						)
+					},
+				}
+			})
+			.collect::<Vec<_>>()
+			.into_iter()
+	});
+
 	let mut imported = 0;
 	let mut results = vec![];
 	let mut has_error = false;
@@ -343,9 +532,30 @@ async fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 
 		let block_number = block.header.as_ref().map(|h| h.number().clone());
 		let block_hash = block.hash;
+		let pending_verification = pending_verifications.as_mut().and_then(|p| p.next()).flatten();
 		let import_result = if has_error {
 			Err(BlockImportError::Cancelled)
+		} else if let Some(PendingVerification::Verified(verification)) = pending_verification {
+			let (verification, verification_time) = verification.await
+				.unwrap_or_else(|_| (
+					Err("Block verification task was cancelled".into()),
+					Duration::default(),
+				));
+
+			// The actual import, with the verification done ahead of time.
+			import_single_block_with_verification(
+				import_handle,
+				block,
+				metrics.clone(),
+				verification_time,
+				|_, _, _| verification,
+			)
 		} else {
+			if let Some(PendingVerification::PreVerified(pre_verification)) = pending_verification {
+				// The verification can still succeed if the pre-verification task was cancelled.
+				let _ = pre_verification.await;
+			}
+
 			// The actual import.
 			import_single_block_metered(
 				import_handle,
@@ -418,8 +628,9 @@ mod tests {
 		BlockCheckParams, BlockImport, BlockImportParams, ImportResult, JustificationImport,
 	};
 	use futures::{executor::block_on, Future};
+	use parking_lot::Mutex;
 	use sp_test_primitives::{Block, BlockNumber, Extrinsic, Hash, Header};
-	use std::collections::HashMap;
+	use std::{collections::{HashMap, HashSet}, sync::Arc};
 
 	impl Verifier<Block> for () {
 		fn verify(
@@ -505,7 +716,7 @@ mod tests {
 		let (result_sender, mut result_port) = buffered_link::buffered_link();
 
 		let (worker, mut finality_sender, mut block_import_sender) =
-			BlockImportWorker::new(result_sender, (), Box::new(()), Some(Box::new(())), None);
+			BlockImportWorker::new(result_sender, (), Box::new(()), Some(Box::new(())), None, None);
 		futures::pin_mut!(worker);
 
 		let mut import_block = |n| {
@@ -593,4 +804,238 @@ mod tests {
 			]
 		);
 	}
+
+	#[test]
+	fn parallel_verification_imports_blocks_in_order() {
+		let parallel_verification = ParallelVerification::new((), 2).unwrap();
+		let mut block_import: BoxBlockImport<Block, Extrinsic> = Box::new(());
+
+		let blocks = (1..=5).map(|n| {
+			let header = Header {
+				parent_hash: Hash::random(),
+				number: n,
+				extrinsics_root: Hash::random(),
+				state_root: Default::default(),
+				digest: Default::default(),
+			};
+
+			IncomingBlock {
+				hash: header.hash(),
+				header: Some(header),
+				body: None,
+				justification: None,
+				origin: None,
+				allow_missing_state: false,
+				import_existing: false,
+			}
+		}).collect::<Vec<_>>();
+		let hashes = blocks.iter().map(|b| b.hash).collect::<Vec<_>>();
+
+		let res = block_on(import_many_blocks(
+			&mut block_import,
+			BlockOrigin::NetworkInitialSync,
+			blocks,
+			&mut (),
+			Some(&parallel_verification),
+			Duration::default(),
+			None,
+		));
+
+		assert_eq!(res.imported, 5);
+		assert_eq!(res.block_count, 5);
+		assert_eq!(
+			res.results.into_iter().map(|(r, h)| match r {
+				Ok(BlockImportResult::ImportedUnknown(n, _, _)) => (n, h),
+				r => panic!("unexpected import result: {:?}", r),
+			}).collect::<Vec<_>>(),
+			(1..=5).zip(hashes).collect::<Vec<_>>(),
+		);
+	}
+
+	/// The blocks imported so far, shared between [`ParentDependentVerifier`] and
+	/// [`RecordingBlockImport`].
+	#[derive(Clone, Default)]
+	struct ImportedBlocks(Arc<Mutex<HashSet<Hash>>>);
+
+	/// A verifier that, like the Aura and BABE ones, fails if the parent is not imported yet.
+	///
+	/// Records the blocks it pre-verifies along with the ancestor they were pre-verified against.
+	#[derive(Clone)]
+	struct ParentDependentVerifier(ImportedBlocks, Arc<Mutex<Vec<(BlockNumber, Hash)>>>);
+
+	impl Verifier<Block> for ParentDependentVerifier {
+		fn verify(
+			&mut self,
+			origin: BlockOrigin,
+			header: Header,
+			_justification: Option<Justification>,
+			_body: Option<Vec<Extrinsic>>,
+		) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+			if !(self.0).0.lock().contains(&header.parent_hash) {
+				return Err(format!("Parent of block {} is not imported", header.number));
+			}
+
+			Ok((BlockImportParams::new(origin, header), None))
+		}
+
+		fn pre_verify(&self, header: &Header, ancestor: &Hash) {
+			self.1.lock().push((header.number, *ancestor));
+		}
+	}
+
+	struct RecordingBlockImport(ImportedBlocks);
+
+	impl BlockImport<Block> for RecordingBlockImport {
+		type Error = crate::Error;
+		type Transaction = Extrinsic;
+
+		fn check_block(
+			&mut self,
+			_block: BlockCheckParams<Block>,
+		) -> Result<ImportResult, Self::Error> {
+			Ok(ImportResult::imported(false))
+		}
+
+		fn import_block(
+			&mut self,
+			block: BlockImportParams<Block, Self::Transaction>,
+			_cache: HashMap<CacheKeyId, Vec<u8>>,
+		) -> Result<ImportResult, Self::Error> {
+			(self.0).0.lock().insert(block.header.hash());
+			Ok(ImportResult::imported(true))
+		}
+	}
+
+	#[test]
+	fn parallel_verification_waits_for_parent_if_required() {
+		let imported_blocks = ImportedBlocks::default();
+		let genesis_hash = Hash::random();
+		imported_blocks.0.lock().insert(genesis_hash);
+
+		let mut verifier = ParentDependentVerifier(imported_blocks.clone(), Default::default());
+		let parallel_verification = ParallelVerification::new(verifier.clone(), 2).unwrap();
+		let mut block_import: BoxBlockImport<Block, Extrinsic> =
+			Box::new(RecordingBlockImport(imported_blocks.clone()));
+
+		let mut parent_hash = genesis_hash;
+		let blocks = (1..=5).map(|n| {
+			let header = Header {
+				parent_hash,
+				number: n,
+				extrinsics_root: Hash::random(),
+				state_root: Default::default(),
+				digest: Default::default(),
+			};
+			parent_hash = header.hash();
+
+			IncomingBlock {
+				hash: header.hash(),
+				header: Some(header),
+				body: None,
+				justification: None,
+				origin: None,
+				allow_missing_state: false,
+				import_existing: false,
+			}
+		}).collect::<Vec<_>>();
+
+		let res = block_on(import_many_blocks(
+			&mut block_import,
+			BlockOrigin::NetworkInitialSync,
+			blocks,
+			&mut verifier,
+			Some(&parallel_verification),
+			Duration::default(),
+			None,
+		));
+
+		assert_eq!(res.imported, 5);
+		assert!(res.results.iter().all(|(r, _)| r.is_ok()));
+		assert_eq!(imported_blocks.0.lock().len(), 6);
+
+		// all blocks but the first were pre-verified against the imported parent of the batch.
+		let mut pre_verified = verifier.1.lock().clone();
+		pre_verified.sort();
+		assert_eq!(pre_verified, (2..=5).map(|n| (n, genesis_hash)).collect::<Vec<_>>());
+	}
+
+	/// A verifier that only succeeds if `expected` blocks are being verified at the same time.
+	#[derive(Clone)]
+	struct ConcurrentVerifier {
+		verifying: Arc<(std::sync::Mutex<usize>, std::sync::Condvar)>,
+		expected: usize,
+	}
+
+	impl Verifier<Block> for ConcurrentVerifier {
+		fn verify(
+			&mut self,
+			origin: BlockOrigin,
+			header: Header,
+			_justification: Option<Justification>,
+			_body: Option<Vec<Extrinsic>>,
+		) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+			let (verifying, all_verifying) = &*self.verifying;
+			let mut verifying = verifying.lock().unwrap();
+			*verifying += 1;
+			all_verifying.notify_all();
+
+			let (verifying, timeout) = all_verifying.wait_timeout_while(
+				verifying,
+				Duration::from_secs(10),
+				|verifying| *verifying < self.expected,
+			).unwrap();
+			if timeout.timed_out() {
+				return Err(format!("Block {} was verified alone ({})", header.number, *verifying));
+			}
+
+			Ok((BlockImportParams::new(origin, header), None))
+		}
+	}
+
+	#[test]
+	fn parallel_verification_verifies_siblings_at_the_same_time() {
+		let imported_blocks = ImportedBlocks::default();
+		let genesis_hash = Hash::random();
+		imported_blocks.0.lock().insert(genesis_hash);
+
+		let mut verifier = ConcurrentVerifier { verifying: Default::default(), expected: 3 };
+		let parallel_verification = ParallelVerification::new(verifier.clone(), 3).unwrap();
+		let mut block_import: BoxBlockImport<Block, Extrinsic> =
+			Box::new(RecordingBlockImport(imported_blocks.clone()));
+
+		// three forks on top of the same, already imported, parent.
+		let blocks = (0..3).map(|_| {
+			let header = Header {
+				parent_hash: genesis_hash,
+				number: 1,
+				extrinsics_root: Hash::random(),
+				state_root: Default::default(),
+				digest: Default::default(),
+			};
+
+			IncomingBlock {
+				hash: header.hash(),
+				header: Some(header),
+				body: None,
+				justification: None,
+				origin: None,
+				allow_missing_state: false,
+				import_existing: false,
+			}
+		}).collect::<Vec<_>>();
+
+		let res = block_on(import_many_blocks(
+			&mut block_import,
+			BlockOrigin::NetworkBroadcast,
+			blocks,
+			&mut verifier,
+			Some(&parallel_verification),
+			Duration::default(),
+			None,
+		));
+
+		assert_eq!(res.imported, 3);
+		assert!(res.results.iter().all(|(r, _)| r.is_ok()));
+		assert_eq!(imported_blocks.0.lock().len(), 4);
+	}
 }
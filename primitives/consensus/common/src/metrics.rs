@@ -31,6 +31,7 @@ pub(crate) struct Metrics {
 	pub import_queue_processed: CounterVec<U64>,
 	pub block_verification_time: HistogramVec,
 	pub block_verification_and_import_time: Histogram,
+	pub block_import_time: Histogram,
 	pub justification_import_time: Histogram,
 }
 
@@ -63,6 +64,15 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			block_import_time: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"block_import_time",
+						"Time taken to import verified blocks",
+					),
+				)?,
+				registry,
+			)?,
 			justification_import_time: register(
 				Histogram::with_opts(
 					HistogramOpts::new(
@@ -104,4 +114,8 @@ impl Metrics {
 	pub fn report_verification_and_import(&self, time: std::time::Duration) {
 		self.block_verification_and_import_time.observe(time.as_secs_f64());
 	}
+
+	pub fn report_import_time(&self, time: std::time::Duration) {
+		self.block_import_time.observe(time.as_secs_f64());
+	}
 }
@@ -0,0 +1,66 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Private implementation details of Sassafras digests.
+
+use super::{AuthorityIndex, AuthoritySignature, SlotNumber, SASSAFRAS_ENGINE_ID};
+use codec::{Codec, Decode, Encode};
+use sp_runtime::{generic::OpaqueDigestItemId, DigestItem, RuntimeDebug};
+
+/// A Sassafras pre-runtime digest, identifying the slot of the block and its author.
+#[derive(Clone, RuntimeDebug, Encode, Decode)]
+pub struct PreDigest {
+	/// Index of the authority assigned to the slot.
+	pub authority_index: AuthorityIndex,
+	/// Slot number
+	pub slot_number: SlotNumber,
+}
+
+/// A digest item which is usable with Sassafras consensus.
+pub trait CompatibleDigestItem: Sized {
+	/// Construct a digest item which contains a Sassafras pre-digest.
+	fn sassafras_pre_digest(seal: PreDigest) -> Self;
+
+	/// If this item is a Sassafras pre-digest, return it.
+	fn as_sassafras_pre_digest(&self) -> Option<PreDigest>;
+
+	/// Construct a digest item which contains a Sassafras seal.
+	fn sassafras_seal(signature: AuthoritySignature) -> Self;
+
+	/// If this item is a Sassafras signature, return the signature.
+	fn as_sassafras_seal(&self) -> Option<AuthoritySignature>;
+}
+
+impl<Hash> CompatibleDigestItem for DigestItem<Hash> where
+	Hash: Send + Sync + Eq + Clone + Codec + 'static
+{
+	fn sassafras_pre_digest(digest: PreDigest) -> Self {
+		DigestItem::PreRuntime(SASSAFRAS_ENGINE_ID, digest.encode())
+	}
+
+	fn as_sassafras_pre_digest(&self) -> Option<PreDigest> {
+		self.try_to(OpaqueDigestItemId::PreRuntime(&SASSAFRAS_ENGINE_ID))
+	}
+
+	fn sassafras_seal(signature: AuthoritySignature) -> Self {
+		DigestItem::Seal(SASSAFRAS_ENGINE_ID, signature.encode())
+	}
+
+	fn as_sassafras_seal(&self) -> Option<AuthoritySignature> {
+		self.try_to(OpaqueDigestItemId::Seal(&SASSAFRAS_ENGINE_ID))
+	}
+}
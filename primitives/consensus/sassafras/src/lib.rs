@@ -0,0 +1,186 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Primitives for Sassafras.
+//!
+//! Sassafras (Semi Anonymous Sortition of Staked Assignees For Fixed-time Rhythmic Assignment
+//! of Slots) assigns exactly one author to every slot of an epoch. During an epoch authorities
+//! submit VRF tickets for the next one, which the runtime sorts into a deterministic slot
+//! schedule before the epoch starts.
+#![deny(warnings)]
+#![forbid(unsafe_code, missing_docs, unused_variables, unused_imports)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod digests;
+pub mod inherents;
+
+pub use merlin::Transcript;
+pub use sp_consensus_vrf::schnorrkel::{
+	Randomness, VRFOutput, VRFProof, RANDOMNESS_LENGTH, VRF_OUTPUT_LENGTH, VRF_PROOF_LENGTH,
+};
+
+use codec::{Decode, Encode};
+#[cfg(feature = "std")]
+use sp_keystore::vrf::{VRFTranscriptData, VRFTranscriptValue};
+use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// Key type for Sassafras module.
+pub const KEY_TYPE: sp_core::crypto::KeyTypeId = sp_application_crypto::key_types::SASSAFRAS;
+
+mod app {
+	use sp_application_crypto::{app_crypto, key_types::SASSAFRAS, sr25519};
+	app_crypto!(sr25519, SASSAFRAS);
+}
+
+/// A Sassafras authority keypair. Necessarily equivalent to the schnorrkel public key used in
+/// the tickets VRF.
+#[cfg(feature = "std")]
+pub type AuthorityPair = app::Pair;
+
+/// A Sassafras authority signature.
+pub type AuthoritySignature = app::Signature;
+
+/// A Sassafras authority identifier. Necessarily equivalent to the schnorrkel public key used in
+/// the tickets VRF.
+pub type AuthorityId = app::Public;
+
+/// The `ConsensusEngineId` of Sassafras.
+pub const SASSAFRAS_ENGINE_ID: ConsensusEngineId = *b"SASS";
+
+/// The index of an authority.
+pub type AuthorityIndex = u32;
+
+/// A slot number.
+pub use sp_consensus_slots::SlotNumber;
+
+/// An equivocation proof for multiple block authorships on the same slot (i.e. double vote).
+pub type EquivocationProof<H> = sp_consensus_slots::EquivocationProof<H, AuthorityId>;
+
+/// A ticket claiming a slot of an upcoming epoch.
+///
+/// Tickets of an epoch are sorted by their VRF output and assigned to the slots of the epoch in
+/// that order.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct Ticket {
+	/// Index of the authority owning the ticket.
+	pub authority_index: AuthorityIndex,
+	/// The attempt the ticket was generated for, lower than the configured maximum.
+	pub attempt: u32,
+	/// VRF output over the ticket transcript.
+	pub vrf_output: VRFOutput,
+	/// VRF proof of the output.
+	pub vrf_proof: VRFProof,
+}
+
+impl Ticket {
+	/// The value tickets are sorted by when building the slot schedule.
+	pub fn score(&self) -> [u8; VRF_OUTPUT_LENGTH] {
+		self.vrf_output.0.to_bytes()
+	}
+
+	/// Check that the ticket was generated by `authority` for `epoch` using `randomness`.
+	pub fn verify(&self, authority: &AuthorityId, randomness: &Randomness, epoch: u64) -> bool {
+		let transcript = make_ticket_transcript(randomness, self.attempt, epoch);
+
+		sp_consensus_vrf::schnorrkel::PublicKey::from_bytes(authority.as_ref())
+			.and_then(|p| p.vrf_verify(transcript, &self.vrf_output, &self.vrf_proof))
+			.is_ok()
+	}
+}
+
+/// Make a ticket VRF transcript from given randomness, attempt and epoch.
+pub fn make_ticket_transcript(
+	randomness: &Randomness,
+	attempt: u32,
+	epoch: u64,
+) -> Transcript {
+	let mut transcript = Transcript::new(&SASSAFRAS_ENGINE_ID);
+	transcript.append_u64(b"attempt", attempt as u64);
+	transcript.append_u64(b"epoch", epoch);
+	transcript.append_message(b"chain randomness", &randomness[..]);
+	transcript
+}
+
+/// Make a ticket VRF transcript data container.
+#[cfg(feature = "std")]
+pub fn make_ticket_transcript_data(
+	randomness: &Randomness,
+	attempt: u32,
+	epoch: u64,
+) -> VRFTranscriptData {
+	VRFTranscriptData {
+		label: &SASSAFRAS_ENGINE_ID,
+		items: vec![
+			("attempt", VRFTranscriptValue::U64(attempt as u64)),
+			("epoch", VRFTranscriptValue::U64(epoch)),
+			("chain randomness", VRFTranscriptValue::Bytes(randomness.to_vec())),
+		]
+	}
+}
+
+/// Configuration data used by the Sassafras consensus engine.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub struct SassafrasConfiguration {
+	/// The slot duration in milliseconds for Sassafras. Currently, only
+	/// the value provided by this type at genesis will be used.
+	pub slot_duration: u64,
+
+	/// The duration of epochs in slots.
+	pub epoch_length: SlotNumber,
+
+	/// The index of the current epoch.
+	pub epoch_index: u64,
+
+	/// The authorities of the current epoch.
+	pub authorities: Vec<AuthorityId>,
+
+	/// The randomness of the current epoch, used for the tickets of the next epoch.
+	pub randomness: Randomness,
+
+	/// The maximum number of tickets an authority may submit for an epoch.
+	pub max_attempts: u32,
+}
+
+#[cfg(feature = "std")]
+impl sp_consensus::SlotData for SassafrasConfiguration {
+	fn slot_duration(&self) -> u64 {
+		self.slot_duration
+	}
+
+	const SLOT_KEY: &'static [u8] = b"sassafras_configuration";
+}
+
+sp_api::decl_runtime_apis! {
+	/// API necessary for block authorship with Sassafras.
+	pub trait SassafrasApi {
+		/// Return the configuration of the current epoch.
+		fn configuration() -> SassafrasConfiguration;
+
+		/// Return the index of the authority assigned to the given slot.
+		///
+		/// Slots of the current and the next epoch are assigned by the sorted tickets, any other
+		/// slot and slots left without a ticket fall back to round-robin assignment. Returns
+		/// `None` only if there are no authorities.
+		fn slot_author(slot_number: SlotNumber) -> Option<AuthorityIndex>;
+
+		/// Submits an unsigned extrinsic with tickets for the next epoch.
+		///
+		/// Returns `false` if the extrinsic could not be submitted to the transaction pool.
+		fn submit_tickets_unsigned_extrinsic(tickets: Vec<Ticket>) -> bool;
+	}
+}
@@ -32,6 +32,22 @@ pub mod testing;
 /// Local storage prefix used by the Offchain Worker API to
 pub const STORAGE_PREFIX : &'static [u8] = b"storage";
 
+/// Prefix of the offchain indexing keys under which the content of indexed transactions is
+/// stored.
+///
+/// Entries written by the runtime under this prefix are stored by the client in a dedicated
+/// column, keyed by the hash of their content, rather than in the offchain workers local storage.
+pub const TRANSACTION_INDEX_KEY_PREFIX: &'static [u8] = b":transaction_index:";
+
+/// Returns the offchain indexing key under which the data with the given content hash is
+/// indexed.
+pub fn transaction_index_key(content_hash: &[u8]) -> Vec<u8> {
+	let mut key = Vec::with_capacity(TRANSACTION_INDEX_KEY_PREFIX.len() + content_hash.len());
+	key.extend_from_slice(TRANSACTION_INDEX_KEY_PREFIX);
+	key.extend_from_slice(content_hash);
+	key
+}
+
 /// Offchain workers local storage.
 pub trait OffchainStorage: Clone + Send + Sync {
 	/// Persist a value in storage under given key and prefix.
@@ -43,6 +59,35 @@ pub trait OffchainStorage: Clone + Send + Sync {
 	/// Retrieve a value from storage under given key and prefix.
 	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Retrieve all the entries under given prefix whose key starts with `key_prefix`, in no
+	/// particular order.
+	///
+	/// The returned keys don't include `prefix`. Returns an error if the storage can't be iterated
+	/// over, e.g. because the underlying database doesn't support it.
+	fn iter_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError>;
+
+	/// Remove all the entries under given prefix whose key starts with `key_prefix`, all at once.
+	///
+	/// Returns the number of removed entries, or an error if the storage can't be iterated over.
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Result<u64, StorageError>;
+
+	/// Retrieve a value written by the runtime through offchain indexing, as seen by the block
+	/// with the given hash.
+	///
+	/// Returns an error if the storage doesn't know the view of that block, e.g. because it has
+	/// been pruned.
+	///
+	/// The default implementation doesn't track the indexed values per block, and returns the
+	/// latest value written under [`STORAGE_PREFIX`], whatever the block.
+	fn indexed_get(&self, at: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+		let _ = at;
+		Ok(self.get(STORAGE_PREFIX, key))
+	}
+
 	/// Replace the value in storage if given old_value matches the current one.
 	///
 	/// Returns `true` if the value has been set and false otherwise.
@@ -55,6 +100,37 @@ pub trait OffchainStorage: Clone + Send + Sync {
 	) -> bool;
 }
 
+/// An error returned by the operations of the offchain workers local storage which may fail.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug, Encode, Decode)]
+pub enum StorageError {
+	/// The storage doesn't support the operation.
+	Unsupported,
+	/// The underlying database failed to process the operation.
+	Database,
+	/// The block is unknown, or its view of the values written through offchain indexing has
+	/// been pruned.
+	UnknownBlock,
+	/// The block is too far from its last finalized ancestor for its view of the values written
+	/// through offchain indexing to be read.
+	TooFarFromFinalized,
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for StorageError {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			StorageError::Unsupported => write!(f, "Operation not supported by the storage"),
+			StorageError::Database => write!(f, "Database error"),
+			StorageError::UnknownBlock => write!(f, "Unknown or pruned block"),
+			StorageError::TooFarFromFinalized =>
+				write!(f, "Block too far from its last finalized ancestor"),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for StorageError {}
+
 /// A type of supported crypto.
 #[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, PassByEnum)]
 #[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
@@ -179,6 +255,30 @@ impl TryFrom<u32> for HttpRequestStatus {
 	}
 }
 
+/// Additional parameters of an HTTP request, passed SCALE-encoded as the `meta` of
+/// `http_request_start`.
+///
+/// An empty `meta` is equivalent to the default parameters.
+#[derive(Clone, Copy, PartialEq, Eq, Default, RuntimeDebug, Encode, Decode)]
+pub struct HttpRequestMeta {
+	/// Maximum duration between dispatching the request and receiving the response headers.
+	///
+	/// Contrary to the deadlines, which only limit how long the caller blocks, reaching the
+	/// timeout aborts the request, which then finishes with an `IoError`.
+	pub timeout: Option<Duration>,
+}
+
+impl HttpRequestMeta {
+	/// Decodes the parameters of a request from its `meta`.
+	pub fn decode_meta(meta: &[u8]) -> Result<Self, codec::Error> {
+		if meta.is_empty() {
+			Ok(Default::default())
+		} else {
+			Self::decode(&mut &meta[..])
+		}
+	}
+}
+
 /// A blob to hold information about the local node's network state
 /// without committing to its format.
 #[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug, PassByCodec)]
@@ -373,14 +473,28 @@ pub trait Externalities: Send {
 	/// offchain worker tasks running on the same machine. It _is_ persisted between runs.
 	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>>;
 
+	/// Gets a value written through offchain indexing, as seen by the block with the given hash.
+	///
+	/// Unlike [`Self::local_storage_get`], the changes made by the blocks which are not
+	/// ancestors of that block are not visible, even if they have been imported.
+	/// If the value does not exist `None` will be returned, and an error is returned if the view
+	/// of the block isn't known, e.g. because it has been pruned.
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError>;
+
 	/// Initiates a http request given HTTP verb and the URL.
 	///
-	/// Meta is a future-reserved field containing additional, parity-scale-codec encoded parameters.
+	/// Meta contains additional parameters of the request, as a SCALE-encoded [`HttpRequestMeta`].
+	/// An empty meta uses the default parameters.
 	/// Returns the id of newly started request.
 	///
 	/// Returns an error if:
 	/// - No new request identifier could be allocated.
 	/// - The method or URI contain invalid characters.
+	/// - The meta can't be decoded.
 	///
 	fn http_request_start(
 		&mut self,
@@ -543,6 +657,14 @@ impl<T: Externalities + ?Sized> Externalities for Box<T> {
 		(&mut **self).local_storage_get(kind, key)
 	}
 
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError> {
+		(&mut **self).local_storage_indexed_get(at, key)
+	}
+
 	fn http_request_start(&mut self, method: &str, uri: &str, meta: &[u8]) -> Result<HttpRequestId, ()> {
 		(&mut **self).http_request_start(method, uri, meta)
 	}
@@ -659,6 +781,15 @@ impl<T: Externalities> Externalities for LimitedExternalities<T> {
 		self.externalities.local_storage_get(kind, key)
 	}
 
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError> {
+		self.check(Capability::OffchainWorkerDbRead, "local_storage_indexed_get");
+		self.externalities.local_storage_indexed_get(at, key)
+	}
+
 	fn http_request_start(&mut self, method: &str, uri: &str, meta: &[u8]) -> Result<HttpRequestId, ()> {
 		self.check(Capability::Http, "http_request_start");
 		self.externalities.http_request_start(method, uri, meta)
@@ -18,7 +18,7 @@
 //! In-memory implementation of offchain workers database.
 
 use std::collections::hash_map::{HashMap, Entry};
-use crate::offchain::OffchainStorage;
+use crate::offchain::{OffchainStorage, StorageError};
 use std::iter::Iterator;
 
 /// In-memory storage for offchain workers.
@@ -61,6 +61,25 @@ impl OffchainStorage for InMemOffchainStorage {
 		self.storage.get(&key).cloned()
 	}
 
+	fn iter_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		Ok(self.storage.iter()
+			.filter(|(key, _)| key.starts_with(&full_prefix))
+			.map(|(key, value)| (key[prefix.len()..].to_vec(), value.clone()))
+			.collect())
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Result<u64, StorageError> {
+		let full_prefix: Vec<u8> = prefix.iter().chain(key_prefix).cloned().collect();
+		let count = self.storage.len();
+		self.storage.retain(|key, _| !key.starts_with(&full_prefix));
+		Ok((count - self.storage.len()) as u64)
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -37,6 +37,7 @@ use crate::offchain::{
 	OpaqueNetworkState,
 	TransactionPool,
 	OffchainStorage,
+	StorageError,
 };
 
 use parking_lot::RwLock;
@@ -113,6 +114,18 @@ impl OffchainStorage for TestPersistentOffchainDB {
 		self.persistent.read().get(prefix, key)
 	}
 
+	fn iter_prefix(
+		&self,
+		prefix: &[u8],
+		key_prefix: &[u8],
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+		self.persistent.read().iter_prefix(prefix, key_prefix)
+	}
+
+	fn clear_prefix(&mut self, prefix: &[u8], key_prefix: &[u8]) -> Result<u64, StorageError> {
+		self.persistent.write().clear_prefix(prefix, key_prefix)
+	}
+
 	fn compare_and_set(
 		&mut self,
 		prefix: &[u8],
@@ -282,6 +295,15 @@ impl offchain::Externalities for TestOffchainExt {
 		}
 	}
 
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError> {
+		let _ = at;
+		Ok(self.0.read().persistent_storage.get(key))
+	}
+
 	fn http_request_start(&mut self, method: &str, uri: &str, meta: &[u8]) -> Result<RequestId, ()> {
 		let mut state = self.0.write();
 		let id = RequestId(state.requests.len() as u16);
@@ -123,6 +123,19 @@ pub trait Database<H: Clone>: Send + Sync {
 		self.get(col, key).map(|v| f(&v));
 	}
 
+	/// Call `f` with every key and value of `col` whose key starts with `prefix`, in no particular
+	/// order.
+	///
+	/// Returns an error if the database doesn't support iterating, which is the default.
+	fn iter_prefix(
+		&self,
+		_col: ColumnId,
+		_prefix: &[u8],
+		_f: &mut dyn FnMut(&[u8], &[u8]),
+	) -> error::Result<()> {
+		Err(error::DatabaseError("Iterating is not supported by the database".into()))
+	}
+
 	/// Set the value of `key` in `col` to `value`, replacing anything that is there currently.
 	fn set(&self, col: ColumnId, key: &[u8], value: &[u8]) -> error::Result<()> {
 		let mut t = Transaction::new();
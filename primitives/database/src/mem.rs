@@ -48,6 +48,21 @@ impl<H> Database<H> for MemDb<H>
 		s.0.get(&col).and_then(|c| c.get(key).cloned())
 	}
 
+	fn iter_prefix(
+		&self,
+		col: ColumnId,
+		prefix: &[u8],
+		f: &mut dyn FnMut(&[u8], &[u8]),
+	) -> error::Result<()> {
+		let s = self.0.read();
+		for (key, value) in s.0.get(&col).into_iter().flatten() {
+			if key.starts_with(prefix) {
+				f(key, value);
+			}
+		}
+		Ok(())
+	}
+
 	fn lookup(&self, hash: &H) -> Option<Vec<u8>> {
 		let s = self.0.read();
 		s.1.get(hash).cloned()
@@ -150,7 +150,13 @@ pub trait Externalities: ExtensionStore {
 	fn kill_child_storage(&mut self, child_info: &ChildInfo, limit: Option<u32>) -> bool;
 
 	/// Clear storage entries which keys are start with the given prefix.
-	fn clear_prefix(&mut self, prefix: &[u8]);
+	///
+	/// Deletes all matching keys from the overlay and visits up to `limit` keys of the backend,
+	/// deleting those not already deleted in the overlay. No limit is applied if `limit` is
+	/// `None`. Returns `true` if all matching keys were removed and `false` if there are
+	/// remaining keys after the function returns, along with the number of keys that were
+	/// removed from the backend.
+	fn clear_prefix(&mut self, prefix: &[u8], limit: Option<u32>) -> (bool, u32);
 
 	/// Clear child storage entries which keys are start with the given prefix.
 	fn clear_child_prefix(
@@ -0,0 +1,162 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fuzzing for the verification of GRANDPA finality and authority set proofs.
+//!
+//! A finality proof and an authority set proof are generated by the client's proof providers
+//! and corrupted with the fuzzer's input. Their verification must never panic, and a corrupted
+//! proof must never prove anything else than what the valid proof proves.
+//!
+//! # Running
+//!
+//! Run with `cargo hfuzz run verify_proofs`. `honggfuzz` CLI options can be used by setting
+//! `HFUZZ_RUN_ARGS`, such as `-n 4` to use 4 threads.
+//!
+//! # Debugging a panic
+//!
+//! Once a panic is found, it can be debugged with
+//! `cargo hfuzz run-debug verify_proofs hfuzz_workspace/verify_proofs/*.fuzz`.
+
+use codec::Encode;
+use honggfuzz::fuzz;
+use sc_client_api::{in_mem::Backend as InMemoryBackend, Backend as _, NewBlockState};
+use sc_finality_grandpa::{AuthoritySet, AuthoritySetChanges, FinalityProofProvider};
+use sp_finality_grandpa::{
+	verification::{self, GrandpaJustification},
+	AuthorityList, ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID,
+};
+use sp_keyring::Ed25519Keyring;
+use sp_runtime::{
+	generic::{self, DigestItem},
+	traits::{BlakeTwo256, Header as HeaderT},
+	OpaqueExtrinsic,
+};
+use std::sync::Arc;
+
+type Header = generic::Header<u64, BlakeTwo256>;
+type Block = generic::Block<Header, OpaqueExtrinsic>;
+
+fn authorities(key: Ed25519Keyring) -> AuthorityList {
+	vec![(key.public().into(), 1)]
+}
+
+fn justification(header: &Header, set_id: u64, key: Ed25519Keyring) -> Vec<u8> {
+	let precommit = finality_grandpa::Precommit {
+		target_hash: header.hash(),
+		target_number: *header.number(),
+	};
+	let msg = finality_grandpa::Message::Precommit(precommit.clone());
+	let encoded = sp_finality_grandpa::localized_payload(1, set_id, &msg);
+	let commit = finality_grandpa::Commit {
+		target_hash: header.hash(),
+		target_number: *header.number(),
+		precommits: vec![finality_grandpa::SignedPrecommit {
+			precommit,
+			signature: key.sign(&encoded[..]).into(),
+			id: key.public().into(),
+		}],
+	};
+
+	GrandpaJustification::<Header> { round: 1, commit, votes_ancestries: Vec::new() }.encode()
+}
+
+/// Apply the fuzzer's corruption to the given proof.
+fn corrupt(proof: &[u8], edits: &[(usize, u8)], truncate: Option<usize>) -> Vec<u8> {
+	let mut proof = proof.to_vec();
+	for (index, value) in edits {
+		let len = proof.len();
+		proof[index % len] ^= value;
+	}
+	if let Some(len) = truncate {
+		proof.truncate(len);
+	}
+	proof
+}
+
+fn main() {
+	// block #4 hands over from alice (set 0) to bob (set 1), and block #7 is justified by bob.
+	let (alice, bob) = (authorities(Ed25519Keyring::Alice), authorities(Ed25519Keyring::Bob));
+	let backend = Arc::new(InMemoryBackend::<Block>::new());
+	let mut headers = Vec::<Header>::new();
+	for number in 0..8 {
+		let parent_hash = headers.last().map(|parent| parent.hash()).unwrap_or_default();
+		let mut header = Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			parent_hash,
+			Default::default(),
+		);
+		if number == 4 {
+			header.digest_mut().push(DigestItem::Consensus(
+				GRANDPA_ENGINE_ID,
+				ConsensusLog::ScheduledChange(
+					ScheduledChange { delay: 0u64, next_authorities: bob.clone() }
+				).encode(),
+			));
+		}
+
+		let header_justification = match number {
+			4 => Some(justification(&header, 0, Ed25519Keyring::Alice)),
+			7 => Some(justification(&header, 1, Ed25519Keyring::Bob)),
+			_ => None,
+		};
+		backend.blockchain()
+			.insert(header.hash(), header.clone(), header_justification, None, NewBlockState::Final)
+			.expect("inserting a block into the in-memory blockchain works");
+		headers.push(header);
+	}
+
+	let authority_set = AuthoritySet::new(
+		bob.clone(),
+		1,
+		Default::default(),
+		Vec::new(),
+		AuthoritySetChanges(vec![(0, 4), (1, 7)]),
+	).expect("bob's authority set is valid");
+	let provider = FinalityProofProvider::new(backend.clone(), Some(authority_set.into()));
+
+	let finality_proof = provider.prove_finality(5)
+		.expect("block #5 is finalized by the justification of block #7")
+		.expect("block #7 has a justification");
+	let authority_set_proof = sc_finality_grandpa::prove_warp_sync(
+		backend.blockchain(),
+		headers[0].hash(),
+		None,
+		None,
+	).expect("the authority set changes of the chain are justified");
+
+	loop {
+		fuzz!(|data: (Vec<(usize, u8)>, Option<usize>)| {
+			let (edits, truncate) = data;
+
+			let corrupted = corrupt(&finality_proof, &edits, truncate);
+			if let Ok(proof) = verification::verify_finality_proof::<Header>(&corrupted, 1, &bob) {
+				assert_eq!(proof.block, headers[7].hash());
+			}
+
+			let corrupted = corrupt(&authority_set_proof, &edits, truncate);
+			if let Ok((header, set_id, authorities)) =
+				verification::verify_authority_set_proof::<Header>(&corrupted, 0, alice.clone())
+			{
+				// dropping the last fragment still leaves a valid proof.
+				assert!(header == headers[4] || header == headers[7]);
+				assert_eq!((set_id, authorities), (1, bob.clone()));
+			}
+		});
+	}
+}
@@ -35,6 +35,10 @@ use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
 #[cfg(feature = "std")]
 use log::debug;
 
+pub mod verification;
+
+pub use verification::{AuthoritySetProof, AuthoritySetProofFragment, FinalityProof};
+
 /// Key type for GRANDPA module.
 pub const KEY_TYPE: sp_core::crypto::KeyTypeId = sp_application_crypto::key_types::GRANDPA;
 
@@ -86,6 +90,20 @@ pub struct ScheduledChange<N> {
 	pub delay: N,
 }
 
+/// Restrictions on the blocks GRANDPA voters vote on, set by on-chain governance.
+///
+/// Unlike authority set pauses these restrictions are not signaled through the
+/// consensus digest, they are only enforced by voters that use the corresponding
+/// client-side voting rule.
+#[cfg_attr(feature = "std", derive(Serialize))]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct FinalityDelay<N> {
+	/// The minimum number of blocks votes must lag behind the best block.
+	pub min_lag: N,
+	/// Whether voters should stop voting on any new blocks.
+	pub paused: bool,
+}
+
 /// An consensus log item for GRANDPA.
 #[cfg_attr(feature = "std", derive(Serialize))]
 #[derive(Decode, Encode, PartialEq, Eq, Clone, RuntimeDebug)]
@@ -496,7 +514,7 @@ sp_api::decl_runtime_apis! {
 	/// applied in the runtime after those N blocks have passed.
 	///
 	/// The consensus protocol will coordinate the handoff externally.
-	#[api_version(2)]
+	#[api_version(3)]
 	pub trait GrandpaApi {
 		/// Get the current GRANDPA authorities and weights. This should not change except
 		/// for when changes are scheduled and the corresponding delay has passed.
@@ -534,5 +552,10 @@ sp_api::decl_runtime_apis! {
 			set_id: SetId,
 			authority_id: AuthorityId,
 		) -> Option<OpaqueKeyOwnershipProof>;
+
+		/// Get the finality restrictions currently set by governance. Voters
+		/// should not vote on blocks that are less than `min_lag` blocks behind
+		/// the best block, nor on any new blocks at all while `paused` is set.
+		fn finality_delay() -> FinalityDelay<NumberFor<Block>>;
 	}
 }
@@ -0,0 +1,385 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of GRANDPA finality proofs.
+//!
+//! The proofs are checked against a trusted authority set only, without any access to
+//! a client or a database. This allows checking finality inside of a runtime, e.g. for
+//! bridges, or in a browser.
+//!
+//! The encodings are the ones used by the GRANDPA client: justifications are stored
+//! and sent over the network as `GrandpaJustification`, `FinalityProof` is produced by
+//! `prove_finality` and `AuthoritySetProof` by `prove_warp_sync`.
+
+use crate::{
+	AuthorityId, AuthorityList, AuthoritySignature, ConsensusLog, ScheduledChange, SetId,
+	GRANDPA_ENGINE_ID,
+};
+use codec::{Decode, DecodeAll, Encode};
+use grandpa::{voter_set::VoterSet, BlockNumberOps, Chain};
+use sp_runtime::{
+	generic::OpaqueDigestItemId,
+	traits::{Header as HeaderT, One, Saturating, Zero},
+	RuntimeDebug,
+};
+use sp_std::{
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+	prelude::*,
+};
+
+/// A GRANDPA commit for the given header type.
+pub type Commit<Header> = grandpa::Commit<
+	<Header as HeaderT>::Hash,
+	<Header as HeaderT>::Number,
+	AuthoritySignature,
+	AuthorityId,
+>;
+
+/// Errors returned when verifying finality proofs.
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum Error {
+	/// The proof could not be decoded.
+	Decode,
+	/// The trusted authority set is empty or has no weight.
+	InvalidAuthoritySet,
+	/// The commit isn't supported by a supermajority of the authority set.
+	InvalidCommit,
+	/// A precommit is not signed by the authority that cast it.
+	InvalidSignature,
+	/// A precommit target is not a descendant of the commit target, or some of the
+	/// ancestry headers are not used by any precommit.
+	InvalidAncestry,
+	/// The justification doesn't finalize the expected block.
+	UnexpectedTarget,
+	/// The headers included in the proof are not a chain.
+	InvalidHeaderChain,
+	/// A fragment of an authority set proof doesn't enact an authority set change.
+	InvalidFragment,
+	/// The proof doesn't contain anything to verify.
+	EmptyProof,
+}
+
+/// A GRANDPA justification for block finality. It includes a commit message and an
+/// ancestry proof including all headers routing all precommit target blocks to the
+/// commit target block.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct GrandpaJustification<Header: HeaderT> {
+	/// The round the commit was made in.
+	pub round: u64,
+	/// The commit finalizing the block.
+	pub commit: Commit<Header>,
+	/// Headers routing the precommit targets to the commit target.
+	pub votes_ancestries: Vec<Header>,
+}
+
+impl<Header: HeaderT> GrandpaJustification<Header> where
+	Header::Number: BlockNumberOps,
+{
+	/// The hash and number of the finalized block.
+	pub fn target(&self) -> (Header::Hash, Header::Number) {
+		(self.commit.target_hash, self.commit.target_number)
+	}
+
+	/// Verify the justification against the given authority set.
+	pub fn verify(&self, set_id: SetId, authorities: &AuthorityList) -> Result<(), Error> {
+		let voters = VoterSet::new(authorities.iter().cloned())
+			.ok_or(Error::InvalidAuthoritySet)?;
+
+		self.verify_with_voter_set(set_id, &voters)
+	}
+
+	/// Verify the justification against the given voter set.
+	pub fn verify_with_voter_set(
+		&self,
+		set_id: SetId,
+		voters: &VoterSet<AuthorityId>,
+	) -> Result<(), Error> {
+		let ancestry_chain = AncestryChain::<Header>::new(&self.votes_ancestries);
+
+		match grandpa::validate_commit(&self.commit, voters, &ancestry_chain) {
+			Ok(ref result) if result.ghost().is_some() => {},
+			_ => return Err(Error::InvalidCommit),
+		}
+
+		let mut buf = Vec::new();
+		let mut visited_hashes = BTreeSet::new();
+		for signed in self.commit.precommits.iter() {
+			if !crate::check_message_signature_with_buffer(
+				&grandpa::Message::Precommit(signed.precommit.clone()),
+				&signed.id,
+				&signed.signature,
+				self.round,
+				set_id,
+				&mut buf,
+			) {
+				return Err(Error::InvalidSignature);
+			}
+
+			if self.commit.target_hash == signed.precommit.target_hash {
+				continue;
+			}
+
+			let route = ancestry_chain
+				.ancestry(self.commit.target_hash, signed.precommit.target_hash)
+				.map_err(|_| Error::InvalidAncestry)?;
+
+			// ancestry starts from parent hash but the precommit target hash has been visited
+			visited_hashes.insert(signed.precommit.target_hash);
+			visited_hashes.extend(route);
+		}
+
+		let ancestry_hashes = self.votes_ancestries
+			.iter()
+			.map(|header| header.hash())
+			.collect::<BTreeSet<_>>();
+
+		if visited_hashes != ancestry_hashes {
+			return Err(Error::InvalidAncestry);
+		}
+
+		Ok(())
+	}
+
+	/// Decode a justification and verify it against the given authority set.
+	pub fn decode_and_verify(
+		encoded: &[u8],
+		set_id: SetId,
+		authorities: &AuthorityList,
+	) -> Result<Self, Error> {
+		let justification = Self::decode_all(encoded).map_err(|_| Error::Decode)?;
+		justification.verify(set_id, authorities)?;
+		Ok(justification)
+	}
+}
+
+/// Finality for block B is proved by providing:
+/// 1) the justification for the descendant block F;
+/// 2) headers sub-chain (B; F] if B != F;
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct FinalityProof<Header: HeaderT> {
+	/// The hash of block F for which justification is provided.
+	pub block: Header::Hash,
+	/// Justification of the block F.
+	pub justification: Vec<u8>,
+	/// The set of headers in the range (B; F] that we believe are unknown to the caller. Ordered.
+	pub unknown_headers: Vec<Header>,
+}
+
+/// Single fragment of authority set proof.
+///
+/// Finality for block B is proved by providing:
+/// 1) headers of this block;
+/// 2) the justification for the block containing a authority set change digest;
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct AuthoritySetProofFragment<Header: HeaderT> {
+	/// The header of the given block.
+	pub header: Header,
+	/// Justification of the block F.
+	pub justification: Vec<u8>,
+}
+
+/// Proof of authority set is the ordered set of authority set fragments, where:
+/// - last fragment match target block.
+pub type AuthoritySetProof<Header> = Vec<AuthoritySetProofFragment<Header>>;
+
+/// Verify an encoded `FinalityProof` against the given authority set.
+///
+/// Returns the proof once its justification was verified. The unknown headers are only
+/// checked to be a chain of blocks below the justified block.
+pub fn verify_finality_proof<Header: HeaderT>(
+	encoded: &[u8],
+	set_id: SetId,
+	authorities: &AuthorityList,
+) -> Result<FinalityProof<Header>, Error> where
+	Header::Number: BlockNumberOps,
+{
+	let proof = FinalityProof::<Header>::decode_all(encoded).map_err(|_| Error::Decode)?;
+
+	let justification = GrandpaJustification::<Header>::decode_and_verify(
+		&proof.justification,
+		set_id,
+		authorities,
+	)?;
+
+	let (target_hash, target_number) = justification.target();
+	if target_hash != proof.block {
+		return Err(Error::UnexpectedTarget);
+	}
+
+	for headers in proof.unknown_headers.windows(2) {
+		if headers[1].parent_hash() != &headers[0].hash() ||
+			*headers[1].number() != headers[0].number().saturating_add(One::one())
+		{
+			return Err(Error::InvalidHeaderChain);
+		}
+	}
+
+	if proof.unknown_headers.last().map_or(false, |header| *header.number() >= target_number) {
+		return Err(Error::InvalidHeaderChain);
+	}
+
+	Ok(proof)
+}
+
+/// Verify an encoded `AuthoritySetProof` starting at the given trusted authority set.
+///
+/// Every fragment is justified by the authority set enacted by the previous one. Returns
+/// the header of the last fragment, and the authority set that follows it.
+pub fn verify_authority_set_proof<Header: HeaderT>(
+	encoded: &[u8],
+	set_id: SetId,
+	authorities: AuthorityList,
+) -> Result<(Header, SetId, AuthorityList), Error> where
+	Header::Number: BlockNumberOps,
+{
+	let proof = AuthoritySetProof::<Header>::decode_all(encoded).map_err(|_| Error::Decode)?;
+
+	let last = proof.len().checked_sub(1).ok_or(Error::EmptyProof)?;
+	let mut current = (set_id, authorities, Header::Number::zero());
+
+	for (index, fragment) in proof.into_iter().enumerate() {
+		let (set_id, authorities, previous_checked_block) = current;
+
+		let justification = GrandpaJustification::<Header>::decode_and_verify(
+			&fragment.justification,
+			set_id,
+			&authorities,
+		)?;
+
+		if justification.target() != (fragment.header.hash(), *fragment.header.number()) {
+			return Err(Error::UnexpectedTarget);
+		}
+
+		let number = *fragment.header.number();
+		if number <= previous_checked_block {
+			return Err(Error::InvalidFragment);
+		}
+
+		let mut enacted_at = None;
+		if let Some(change) = find_scheduled_change(&fragment.header) {
+			enacted_at = Some((number.saturating_add(change.delay), change.next_authorities));
+		}
+		if let Some((median, change)) = find_forced_change(&fragment.header) {
+			enacted_at = Some((median.saturating_add(change.delay), change.next_authorities));
+		}
+
+		current = match enacted_at {
+			Some((enacted_at, next_authorities)) =>
+				(set_id.saturating_add(1), next_authorities, enacted_at),
+			// fragment without change only allowed for proof last block.
+			None if index == last => (set_id, authorities, number),
+			None => return Err(Error::InvalidFragment),
+		};
+
+		if index == last {
+			return Ok((fragment.header, current.0, current.1));
+		}
+	}
+
+	Err(Error::EmptyProof)
+}
+
+/// Verify the finality of a block justified by a later authority set than the trusted one.
+///
+/// The authority set proof is verified first, moving from the trusted authority set to
+/// the one which is then used to verify the finality proof.
+pub fn verify_finality_proof_with_authority_set_proof<Header: HeaderT>(
+	authority_set_proof: &[u8],
+	finality_proof: &[u8],
+	set_id: SetId,
+	authorities: AuthorityList,
+) -> Result<FinalityProof<Header>, Error> where
+	Header::Number: BlockNumberOps,
+{
+	let (_, set_id, authorities) = verify_authority_set_proof::<Header>(
+		authority_set_proof,
+		set_id,
+		authorities,
+	)?;
+
+	verify_finality_proof(finality_proof, set_id, &authorities)
+}
+
+fn find_scheduled_change<Header: HeaderT>(header: &Header) -> Option<ScheduledChange<Header::Number>> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+
+	let filter_log = |log: ConsensusLog<Header::Number>| log.try_into_change();
+
+	// find the first consensus digest with the right ID which converts to
+	// the right kind of consensus log.
+	header.digest().convert_first(|l| l.try_to(id).and_then(filter_log))
+}
+
+fn find_forced_change<Header: HeaderT>(
+	header: &Header,
+) -> Option<(Header::Number, ScheduledChange<Header::Number>)> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+
+	let filter_log = |log: ConsensusLog<Header::Number>| log.try_into_forced_change();
+
+	// find the first consensus digest with the right ID which converts to
+	// the right kind of consensus log.
+	header.digest().convert_first(|l| l.try_to(id).and_then(filter_log))
+}
+
+/// A `grandpa::Chain` implementation over the given set of headers, used to verify
+/// the ancestry route of the precommit targets to the commit target.
+struct AncestryChain<Header: HeaderT> {
+	ancestry: BTreeMap<Header::Hash, Header>,
+}
+
+impl<Header: HeaderT> AncestryChain<Header> {
+	fn new(ancestry: &[Header]) -> Self {
+		let ancestry = ancestry
+			.iter()
+			.cloned()
+			.map(|header| (header.hash(), header))
+			.collect();
+
+		AncestryChain { ancestry }
+	}
+}
+
+impl<Header: HeaderT> Chain<Header::Hash, Header::Number> for AncestryChain<Header> where
+	Header::Number: BlockNumberOps,
+{
+	fn ancestry(
+		&self,
+		base: Header::Hash,
+		block: Header::Hash,
+	) -> Result<Vec<Header::Hash>, grandpa::Error> {
+		let mut route = Vec::new();
+		let mut current_hash = block;
+		loop {
+			if current_hash == base { break; }
+			match self.ancestry.get(&current_hash) {
+				Some(current_header) => {
+					current_hash = *current_header.parent_hash();
+					route.push(current_hash);
+				},
+				_ => return Err(grandpa::Error::NotDescendent),
+			}
+		}
+		route.pop(); // remove the base
+
+		Ok(route)
+	}
+
+	fn best_chain_containing(&self, _block: Header::Hash) -> Option<(Header::Hash, Header::Number)> {
+		None
+	}
+}
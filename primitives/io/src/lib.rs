@@ -47,9 +47,10 @@ use sp_core::{
 use sp_keystore::{KeystoreExt, SyncCryptoStore};
 
 use sp_core::{
-	OpaquePeerId, crypto::KeyTypeId, ed25519, sr25519, ecdsa, H256, LogLevel,
+	OpaquePeerId, RuntimeDebug, crypto::KeyTypeId, ed25519, sr25519, ecdsa, H256, LogLevel,
 	offchain::{
 		Timestamp, HttpRequestId, HttpRequestStatus, HttpError, StorageKind, OpaqueNetworkState,
+		StorageError,
 	},
 };
 
@@ -57,7 +58,7 @@ use sp_core::{
 use sp_trie::{TrieConfiguration, trie_types::Layout};
 
 use sp_runtime_interface::{runtime_interface, Pointer};
-use sp_runtime_interface::pass_by::PassBy;
+use sp_runtime_interface::pass_by::{PassBy, PassByCodec};
 
 use codec::{Encode, Decode};
 
@@ -81,6 +82,20 @@ pub enum EcdsaVerifyError {
 	BadSignature,
 }
 
+/// The outcome of removing storage entries with a limit.
+#[derive(PassByCodec, Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum KillOutcome {
+	/// All matching keys were removed, with the number of keys removed from the backend.
+	AllRemoved(u32),
+	/// Some matching keys remain, with the number of keys removed from the backend.
+	SomeRemaining(u32),
+	/// Nothing was removed, because the prefix is part of or contains a child storage key.
+	///
+	/// Child tries can only be removed through the child storage functions, so retrying the
+	/// call will never make progress.
+	Refused,
+}
+
 /// Interface for accessing the storage from within the runtime.
 #[runtime_interface]
 pub trait Storage {
@@ -121,7 +136,46 @@ pub trait Storage {
 
 	/// Clear the storage of each key-value pair where the key starts with the given `prefix`.
 	fn clear_prefix(&mut self, prefix: &[u8]) {
-		Externalities::clear_prefix(*self, prefix)
+		let _ = Externalities::clear_prefix(*self, prefix, None);
+	}
+
+	/// Clear the storage of each key-value pair where the key starts with the given `prefix`.
+	///
+	/// Deletes all matching keys from the overlay and visits up to `limit` keys of the backend
+	/// if it is set to `Some`, deleting those that are not already deleted. No limit is applied
+	/// when `limit` is set to `None`. The returned number is the number of keys deleted from the
+	/// backend.
+	///
+	/// The limit can be used to partially delete a prefix storage in case it is too large
+	/// to delete in one go (block).
+	///
+	/// Returns [`KillOutcome::Refused`] without deleting anything if the `prefix` is part of or
+	/// contains a child storage key.
+	///
+	/// # Note
+	///
+	/// Please note that keys that are residing in the overlay for that prefix when
+	/// issuing this call are all deleted without counting towards the `limit`. Only keys
+	/// written during the current block are part of the overlay. Deleting with a `limit`
+	/// mostly makes sense with an empty overlay for that prefix.
+	///
+	/// Calling this function multiple times per block for the same `prefix` does
+	/// not make much sense because it is not cumulative when called inside the same block:
+	/// keys deleted by an earlier call are visited again and count towards the `limit`.
+	/// Use this function to distribute the deletion of a single prefix across multiple
+	/// blocks.
+	#[version(2)]
+	fn clear_prefix(&mut self, prefix: &[u8], limit: Option<u32>) -> KillOutcome {
+		if sp_core::storage::well_known_keys::starts_with_child_storage_key(prefix) {
+			return KillOutcome::Refused
+		}
+
+		let (all_removed, num_removed) = Externalities::clear_prefix(*self, prefix, limit);
+		if all_removed {
+			KillOutcome::AllRemoved(num_removed)
+		} else {
+			KillOutcome::SomeRemaining(num_removed)
+		}
 	}
 
 	/// Append the encoded `value` to the storage item at `key`.
@@ -894,9 +948,26 @@ pub trait Offchain {
 			.local_storage_get(kind, key)
 	}
 
+	/// Gets a value written through offchain indexing, as seen by the block with the given hash.
+	///
+	/// Only the changes made by that block and its ancestors are visible, so that the values
+	/// written by blocks of other forks are never returned.
+	/// If the value does not exist `None` will be returned. An error is returned if the view of
+	/// the block isn't known, e.g. because it has been pruned or is on a discarded fork.
+	fn local_storage_indexed_get(
+		&mut self,
+		at: &[u8],
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, StorageError> {
+		self.extension::<OffchainExt>()
+			.expect("local_storage_indexed_get can be called only in the offchain worker context")
+			.local_storage_indexed_get(at, key)
+	}
+
 	/// Initiates a http request given HTTP verb and the URL.
 	///
-	/// Meta is a future-reserved field containing additional, parity-scale-codec encoded parameters.
+	/// Meta contains additional parameters of the request, as a SCALE-encoded `HttpRequestMeta`.
+	/// An empty meta uses the default parameters.
 	/// Returns the id of newly started request.
 	fn http_request_start(
 		&mut self,
@@ -1426,7 +1497,7 @@ mod tests {
 		});
 
 		t.execute_with(|| {
-			storage::clear_prefix(b":abc");
+			assert_eq!(storage::clear_prefix(b":abc", None), KillOutcome::AllRemoved(2));
 
 			assert!(storage::get(b":a").is_some());
 			assert!(storage::get(b":abdd").is_some());
@@ -1435,6 +1506,42 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn clear_prefix_with_limit_works() {
+		let mut t = BasicExternalities::new(Storage {
+			top: map![
+				b":a".to_vec() => b"\x0b\0\0\0Hello world".to_vec(),
+				b":abcd".to_vec() => b"\x0b\0\0\0Hello world".to_vec(),
+				b":abc".to_vec() => b"\x0b\0\0\0Hello world".to_vec(),
+				b":abdd".to_vec() => b"\x0b\0\0\0Hello world".to_vec()
+			],
+			children_default: map![],
+		});
+
+		t.execute_with(|| {
+			assert_eq!(storage::clear_prefix(b":ab", Some(2)), KillOutcome::SomeRemaining(2));
+			assert!(storage::get(b":abdd").is_some());
+
+			assert_eq!(storage::clear_prefix(b":ab", Some(2)), KillOutcome::AllRemoved(1));
+			assert!(storage::get(b":abdd").is_none());
+			assert!(storage::get(b":a").is_some());
+		});
+	}
+
+	#[test]
+	fn clear_prefix_refuses_child_storage_roots() {
+		let mut t = BasicExternalities::new(Storage {
+			top: map![b":a".to_vec() => b"\x0b\0\0\0Hello world".to_vec()],
+			children_default: map![],
+		});
+
+		t.execute_with(|| {
+			assert_eq!(storage::clear_prefix(b"", Some(2)), KillOutcome::Refused);
+			assert_eq!(storage::clear_prefix(b":child_storage:", None), KillOutcome::Refused);
+			assert!(storage::get(b":a").is_some());
+		});
+	}
+
 	#[test]
 	fn batch_verify_start_finish_works() {
 		let mut ext = BasicExternalities::default();
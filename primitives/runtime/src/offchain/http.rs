@@ -53,8 +53,11 @@ use sp_std::prelude::Vec;
 #[cfg(not(feature = "std"))]
 use sp_std::prelude::vec;
 use sp_core::RuntimeDebug;
+use codec::Encode;
 use sp_core::offchain::{
 	Timestamp,
+	Duration,
+	HttpRequestMeta,
 	HttpRequestId as RequestId,
 	HttpRequestStatus as RequestStatus,
 	HttpError,
@@ -138,6 +141,8 @@ pub struct Request<'a, T = Vec<&'static [u8]>> {
 	pub body: T,
 	/// Deadline to finish sending the request
 	pub deadline: Option<Timestamp>,
+	/// Maximum duration to wait for the response once the request is dispatched
+	pub timeout: Option<Duration>,
 	/// Request list of headers.
 	headers: Vec<header::Header>,
 }
@@ -150,6 +155,7 @@ impl<T: Default> Default for Request<'static, T> {
 			headers: Vec::new(),
 			body: Default::default(),
 			deadline: None,
+			timeout: None,
 		}
 	}
 }
@@ -172,6 +178,7 @@ impl<'a, T> Request<'a, T> {
 			method: Method::Post,
 			headers: req.headers,
 			deadline: req.deadline,
+			timeout: req.timeout,
 		}
 	}
 }
@@ -211,6 +218,16 @@ impl<'a, T: Default> Request<'a, T> {
 		self.deadline = Some(deadline);
 		self
 	}
+
+	/// Set the timeout of the request.
+	///
+	/// Contrary to the deadline, which only limits how long `send` blocks, the node aborts the
+	/// request if no response has been received within the timeout, in which case waiting for
+	/// the response returns an `IoError`.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
 }
 
 impl<'a, I: AsRef<[u8]>, T: IntoIterator<Item=I>> Request<'a, T> {
@@ -219,13 +236,18 @@ impl<'a, I: AsRef<[u8]>, T: IntoIterator<Item=I>> Request<'a, T> {
 	/// Err is returned in case the deadline is reached
 	/// or the request timeouts.
 	pub fn send(self) -> Result<PendingRequest, HttpError> {
-		let meta = &[];
+		// the default parameters are encoded as an empty meta, which is understood by all the
+		// nodes, including the ones that don't support any parameter.
+		let meta = match self.timeout {
+			None => Vec::new(),
+			timeout => HttpRequestMeta { timeout }.encode(),
+		};
 
 		// start an http request.
 		let id = sp_io::offchain::http_request_start(
 			self.method.as_ref(),
 			self.url,
-			meta,
+			&meta,
 		).map_err(|_| HttpError::IoError)?;
 
 		// add custom headers
@@ -353,6 +375,18 @@ impl Response {
 		self.headers.as_ref().expect("Headers were just set; qed")
 	}
 
+	/// Retrieve the length of the body of this response, as announced by the server in the
+	/// `Content-Length` header.
+	///
+	/// Returns `None` if the header is missing or invalid, for example when the body is sent
+	/// in chunks.
+	pub fn content_length(&mut self) -> Option<u64> {
+		self.headers().raw.iter()
+			.find(|(name, _)| name.eq_ignore_ascii_case(b"content-length"))
+			.and_then(|(_, value)| str::from_utf8(value).ok())
+			.and_then(|value| value.trim().parse().ok())
+	}
+
 	/// Retrieve the body of this response.
 	pub fn body(&self) -> ResponseBody {
 		ResponseBody::new(self.id)
@@ -604,4 +638,34 @@ mod tests {
 			assert_eq!(body.error(), &None);
 		})
 	}
+
+	#[test]
+	fn should_pass_the_timeout_and_read_the_content_length() {
+		let (offchain, state) = testing::TestOffchainExt::new();
+		let mut t = TestExternalities::default();
+		t.register_extension(OffchainExt::new(offchain));
+
+		t.execute_with(|| {
+			let pending = Request::get("http://localhost:1234")
+				.timeout(Duration::from_millis(500))
+				.send()
+				.unwrap();
+			state.write().fulfill_pending_request(
+				0,
+				testing::PendingRequest {
+					method: "GET".into(),
+					uri: "http://localhost:1234".into(),
+					meta: HttpRequestMeta { timeout: Some(Duration::from_millis(500)) }.encode(),
+					sent: true,
+					..Default::default()
+				},
+				b"1234".to_vec(),
+				Some(("Content-Length".to_owned(), "4".to_owned())),
+			);
+
+			let mut response = pending.wait().unwrap();
+			assert_eq!(response.content_length(), Some(4));
+			assert_eq!(response.body().collect::<Vec<_>>(), b"1234".to_vec());
+		})
+	}
 }
@@ -101,6 +101,14 @@ pub trait Backend<H: Hasher>: sp_std::fmt::Debug {
 		f: F,
 	);
 
+	/// Retrieve all entries keys which start with the given prefix and
+	/// call `f` for each of those keys. Aborts as soon as `f` returns false.
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	);
+
 	/// Retrieve all entries keys which start with the given prefix and
 	/// call `f` for each of those keys.
 	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], mut f: F) {
@@ -272,6 +280,14 @@ impl<'a, T: Backend<H>, H: Hasher> Backend<H> for &'a T {
 		(*self).apply_to_child_keys_while(child_info, f)
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		(*self).apply_to_keys_with_prefix_while(prefix, f)
+	}
+
 	fn next_storage_key(&self, key: &[u8]) -> Result<Option<StorageKey>, Self::Error> {
 		(*self).next_storage_key(key)
 	}
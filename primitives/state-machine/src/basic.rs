@@ -216,24 +216,33 @@ impl Externalities for BasicExternalities {
 		true
 	}
 
-	fn clear_prefix(&mut self, prefix: &[u8]) {
+	fn clear_prefix(&mut self, prefix: &[u8], limit: Option<u32>) -> (bool, u32) {
 		if is_child_storage_key(prefix) {
 			warn!(
 				target: "trie",
 				"Refuse to clear prefix that is part of child storage key via main storage"
 			);
-			return;
+			return (false, 0);
 		}
 
-		let to_remove = self.inner.top.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+		let mut to_remove = self.inner.top.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
 			.map(|(k, _)| k)
 			.take_while(|k| k.starts_with(prefix))
 			.cloned()
 			.collect::<Vec<_>>();
 
+		let all_removed = match limit {
+			Some(limit) if to_remove.len() > limit as usize => {
+				to_remove.truncate(limit as usize);
+				false
+			},
+			_ => true,
+		};
+		let num_removed = to_remove.len() as u32;
 		for key in to_remove {
 			self.inner.top.remove(&key);
 		}
+		(all_removed, num_removed)
 	}
 
 	fn clear_child_prefix(
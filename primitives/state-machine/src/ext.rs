@@ -427,23 +427,44 @@ where
 		}
 	}
 
-	fn clear_prefix(&mut self, prefix: &[u8]) {
-		trace!(target: "state", "{:04x}: ClearPrefix {}",
+	fn clear_prefix(&mut self, prefix: &[u8], limit: Option<u32>) -> (bool, u32) {
+		trace!(target: "state", "{:04x}: ClearPrefix {} limit {:?}",
 			self.id,
 			HexDisplay::from(&prefix),
+			limit,
 		);
 		let _guard = guard();
 
 		if sp_core::storage::well_known_keys::starts_with_child_storage_key(prefix) {
 			warn!(target: "trie", "Refuse to directly clear prefix that is part or contains of child storage key");
-			return;
+			return (false, 0);
 		}
 
 		self.mark_dirty();
-		self.overlay.clear_prefix(prefix);
-		self.backend.for_keys_with_prefix(prefix, |key| {
-			self.overlay.set_storage(key.to_vec(), None);
+
+		// Every visited key of the backend counts towards the limit, so that it bounds the
+		// iteration. Keys which have already been deleted in the overlay, e.g. by a previous
+		// limited call within the same block, are not counted as deleted though. This must be
+		// checked before clearing the overlay below.
+		let mut num_visited: u32 = 0;
+		let mut num_deleted: u32 = 0;
+		let mut all_deleted = true;
+		let overlay = &mut self.overlay;
+		self.backend.apply_to_keys_with_prefix_while(prefix, |key| {
+			if limit.map_or(false, |limit| num_visited >= limit) {
+				all_deleted = false;
+				return false;
+			}
+			num_visited = num_visited.saturating_add(1);
+			if let Some(None) = overlay.storage(key) {
+				return true;
+			}
+			num_deleted = num_deleted.saturating_add(1);
+			overlay.set_storage(key.to_vec(), None);
+			true
 		});
+		self.overlay.clear_prefix(prefix);
+		(all_deleted, num_deleted)
 	}
 
 	fn clear_child_prefix(
@@ -1016,17 +1037,46 @@ mod tests {
 		not_under_prefix.extend(b"path");
 		ext.set_storage(not_under_prefix.clone(), vec![10]);
 
-		ext.clear_prefix(&[]);
-		ext.clear_prefix(&well_known_keys::CHILD_STORAGE_KEY_PREFIX[..4]);
+		ext.clear_prefix(&[], None);
+		ext.clear_prefix(&well_known_keys::CHILD_STORAGE_KEY_PREFIX[..4], None);
 		let mut under_prefix = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
 		under_prefix.extend(b"path");
-		ext.clear_prefix(&well_known_keys::CHILD_STORAGE_KEY_PREFIX[..4]);
+		ext.clear_prefix(&well_known_keys::CHILD_STORAGE_KEY_PREFIX[..4], None);
 		assert_eq!(ext.child_storage(child_info, &[30]), Some(vec![40]));
 		assert_eq!(ext.storage(not_under_prefix.as_slice()), Some(vec![10]));
-		ext.clear_prefix(&not_under_prefix[..5]);
+		ext.clear_prefix(&not_under_prefix[..5], None);
 		assert_eq!(ext.storage(not_under_prefix.as_slice()), None);
 	}
 
+	#[test]
+	fn limited_clear_prefix_counts_keys_deleted_in_the_overlay() {
+		let mut cache = StorageTransactionCache::default();
+		let mut overlay = OverlayedChanges::default();
+		let backend = Storage {
+			top: map![
+				vec![1, 1] => vec![1],
+				vec![1, 2] => vec![2],
+				vec![1, 3] => vec![3],
+				vec![2, 1] => vec![4]
+			],
+			children_default: map![],
+		}.into();
+
+		let mut ext = TestExt::new(&mut overlay, &mut cache, &backend, None, None);
+
+		assert_eq!(ext.clear_prefix(&[1], Some(2)), (false, 2));
+		// The keys deleted by the first call count towards the limit of the second one.
+		assert_eq!(ext.clear_prefix(&[1], Some(2)), (false, 0));
+		assert_eq!(ext.storage(&[1, 3]), Some(vec![3]));
+		assert_eq!(ext.clear_prefix(&[1], Some(3)), (true, 1));
+		assert_eq!(ext.clear_prefix(&[1], None), (true, 0));
+
+		assert_eq!(ext.storage(&[1, 1]), None);
+		assert_eq!(ext.storage(&[1, 2]), None);
+		assert_eq!(ext.storage(&[1, 3]), None);
+		assert_eq!(ext.storage(&[2, 1]), Some(vec![4]));
+	}
+
 	#[test]
 	fn storage_append_works() {
 		let mut data = Vec::new();
@@ -39,6 +39,8 @@ mod trie_backend_essence;
 mod stats;
 #[cfg(feature = "std")]
 mod read_only;
+#[cfg(feature = "std")]
+mod read_recording_backend;
 
 #[cfg(feature = "std")]
 pub use std_reexport::*;
@@ -144,6 +146,7 @@ mod std_reexport {
 	pub use crate::testing::TestExternalities;
 	pub use crate::basic::BasicExternalities;
 	pub use crate::read_only::{ReadOnlyExternalities, InspectState};
+	pub use crate::read_recording_backend::{ReadRecordingBackend, RecordedReads};
 	pub use crate::changes_trie::{
 		AnchorBlockId as ChangesTrieAnchorBlockId,
 		State as ChangesTrieState,
@@ -528,8 +531,11 @@ mod execution {
 					CallResult<R, Exec::Error>,
 				) -> CallResult<R, Exec::Error>
 		{
-			let changes_tries_enabled = self.changes_trie_state.is_some();
-			self.overlay.set_collect_extrinsics(changes_tries_enabled);
+			// Extrinsics are always collected when changes tries are enabled, callers may
+			// also request them explicitly by enabling collection on the overlay.
+			if self.changes_trie_state.is_some() {
+				self.overlay.set_collect_extrinsics(true);
+			}
 
 			let result = {
 				match manager {
@@ -784,6 +790,95 @@ mod execution {
 		Ok(proving_backend.extract_proof())
 	}
 
+	/// Generate storage read proof for a range of keys.
+	///
+	/// Reads up to `count` key/value pairs whose keys start with `prefix`, beginning
+	/// after `start_at` (or after `prefix` if it is not given), in lexicographic order.
+	/// Returns the pairs that were read together with the proof.
+	pub fn prove_range_read<B, H>(
+		mut backend: B,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		count: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, StorageProof), Box<dyn Error>>
+	where
+		B: Backend<H>,
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let trie_backend = backend.as_trie_backend()
+			.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+		prove_range_read_on_trie_backend(trie_backend, prefix, start_at, count)
+	}
+
+	/// Generate storage read proof for a range of keys on pre-created trie backend.
+	pub fn prove_range_read_on_trie_backend<S, H>(
+		trie_backend: &TrieBackend<S, H>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		count: usize,
+	) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, StorageProof), Box<dyn Error>>
+	where
+		S: trie_backend_essence::TrieBackendStorage<H>,
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+		let pairs = read_range_on_backend(&proving_backend, prefix, start_at, count)?;
+		Ok((pairs, proving_backend.extract_proof()))
+	}
+
+	/// Check storage range read proof, generated by `prove_range_read` call.
+	///
+	/// Returns the key/value pairs of the range, failing if the proof does not
+	/// cover it.
+	pub fn read_range_proof_check<H>(
+		root: H::Out,
+		proof: StorageProof,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<dyn Error>>
+	where
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+		read_range_on_backend(&proving_backend, prefix, start_at, count)
+	}
+
+	/// Read up to `count` key/value pairs of the given range from `backend`, like
+	/// `prove_range_read` but without recording a proof.
+	pub fn read_range_on_backend<B, H>(
+		backend: &B,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		count: usize,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<dyn Error>>
+	where
+		B: Backend<H>,
+		H: Hasher,
+	{
+		let prefix = prefix.unwrap_or(&[]);
+		let mut current_key = start_at.unwrap_or(prefix).to_vec();
+		let mut pairs = Vec::new();
+		while pairs.len() < count {
+			let next_key = match backend.next_storage_key(&current_key)
+				.map_err(|e| Box::new(e) as Box<dyn Error>)?
+			{
+				Some(key) if key.starts_with(prefix) => key,
+				_ => break,
+			};
+			if let Some(value) = backend.storage(&next_key)
+				.map_err(|e| Box::new(e) as Box<dyn Error>)?
+			{
+				pairs.push((next_key.clone(), value));
+			}
+			current_key = next_key;
+		}
+		Ok(pairs)
+	}
+
 	/// Check storage read proof, generated by `prove_read` call.
 	pub fn read_proof_check<H, I>(
 		root: H::Out,
@@ -1112,7 +1207,7 @@ mod tests {
 				changes_trie::disabled_state::<_, u64>(),
 				None,
 			);
-			ext.clear_prefix(b"ab");
+			assert_eq!(ext.clear_prefix(b"ab", None), (true, 2));
 		}
 		overlay.commit_transaction().unwrap();
 
@@ -1131,6 +1226,55 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn limited_clear_prefix_works() {
+		let initial: BTreeMap<_, _> = map![
+			b"aaa".to_vec() => b"0".to_vec(),
+			b"abb".to_vec() => b"1".to_vec(),
+			b"abc".to_vec() => b"2".to_vec(),
+			b"abd".to_vec() => b"3".to_vec(),
+			b"bbb".to_vec() => b"4".to_vec()
+		];
+		let backend = InMemoryBackend::<BlakeTwo256>::from(initial);
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_storage(b"aba".to_vec(), Some(b"1312".to_vec()));
+
+		{
+			let mut cache = StorageTransactionCache::default();
+			let mut ext = Ext::new(
+				&mut overlay,
+				&mut cache,
+				&backend,
+				changes_trie::disabled_state::<_, u64>(),
+				None,
+			);
+			assert_eq!(ext.clear_prefix(b"ab", Some(2)), (false, 2));
+		}
+
+		assert_eq!(
+			overlay.changes().map(|(k, v)| (k.clone(), v.value().cloned()))
+				.collect::<BTreeMap<_, _>>(),
+			map![
+				b"aba".to_vec() => None.into(),
+				b"abb".to_vec() => None.into(),
+				b"abc".to_vec() => None.into()
+			],
+		);
+
+		let mut cache = StorageTransactionCache::default();
+		let mut ext = Ext::new(
+			&mut overlay,
+			&mut cache,
+			&backend,
+			changes_trie::disabled_state::<_, u64>(),
+			None,
+		);
+		assert_eq!(ext.clear_prefix(b"ab", Some(0)), (false, 0));
+		assert_eq!(ext.clear_prefix(b"ab", Some(3)), (true, 1));
+		assert_eq!(ext.storage(b"abd"), None);
+		assert_eq!(ext.storage(b"bbb"), Some(b"4".to_vec()));
+	}
+
 	#[test]
 	fn limited_child_kill_works() {
 		let child_info = ChildInfo::new_default(b"sub1");
@@ -1456,6 +1600,54 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn prove_range_read_and_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(std::iter::empty()).0;
+		let (pairs, remote_proof) = prove_range_read(
+			remote_backend,
+			Some(b"value"),
+			None,
+			1,
+		).unwrap();
+		assert_eq!(pairs, vec![(b"value1".to_vec(), vec![42])]);
+		// check proof locally
+		let local_result = read_range_proof_check::<BlakeTwo256>(
+			remote_root,
+			remote_proof.clone(),
+			Some(b"value"),
+			None,
+			1,
+		).unwrap();
+		assert_eq!(local_result, pairs);
+		// the proof does not cover other ranges
+		assert!(read_range_proof_check::<BlakeTwo256>(
+			remote_root,
+			remote_proof,
+			Some(b":child_storage:"),
+			None,
+			1,
+		).is_err());
+
+		// continue from the last key of the previous page
+		let remote_backend = trie_backend::tests::test_trie();
+		let (pairs, remote_proof) = prove_range_read(
+			remote_backend,
+			Some(b"value"),
+			Some(b"value1"),
+			10,
+		).unwrap();
+		assert_eq!(pairs, vec![(b"value2".to_vec(), vec![24])]);
+		let local_result = read_range_proof_check::<BlakeTwo256>(
+			remote_root,
+			remote_proof,
+			Some(b"value"),
+			Some(b"value1"),
+			10,
+		).unwrap();
+		assert_eq!(local_result, pairs);
+	}
+
 	#[test]
 	fn child_storage_uuid() {
 
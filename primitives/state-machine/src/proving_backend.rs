@@ -212,6 +212,14 @@ impl<'a, S, H> Backend<H> for ProvingBackend<'a, S, H>
 		self.0.apply_to_child_keys_while(child_info, f)
 	}
 
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.0.apply_to_keys_with_prefix_while(prefix, f)
+	}
+
 	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
 		self.0.next_storage_key(key)
 	}
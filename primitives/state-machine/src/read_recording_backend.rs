@@ -0,0 +1,275 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State machine backend that records every value read from the wrapped backend.
+
+use std::collections::BTreeMap;
+use parking_lot::Mutex;
+use hash_db::Hasher;
+use sp_core::storage::ChildInfo;
+use crate::{Backend, StorageKey, StorageValue, UsageInfo, StateMachineStats};
+
+/// Values read from a [`ReadRecordingBackend`].
+///
+/// Entries are keyed by the (unprefixed) child storage key, `None` standing for
+/// the top-level trie, and by the storage key that was read.
+pub type RecordedReads = BTreeMap<(Option<StorageKey>, StorageKey), Option<StorageValue>>;
+
+/// Backend wrapper that records every storage value read from the inner backend.
+///
+/// Only reads that actually reach the backend are recorded, so values served from
+/// the overlay of the state machine are not part of the recording. Keys visited while
+/// iterating the backend are recorded as well, together with their current value.
+pub struct ReadRecordingBackend<'a, S, H> {
+	backend: &'a S,
+	reads: Mutex<RecordedReads>,
+	_marker: std::marker::PhantomData<H>,
+}
+
+impl<'a, S: Backend<H>, H: Hasher> ReadRecordingBackend<'a, S, H> {
+	/// Create a new read recording backend on top of `backend`.
+	pub fn new(backend: &'a S) -> Self {
+		ReadRecordingBackend {
+			backend,
+			reads: Default::default(),
+			_marker: Default::default(),
+		}
+	}
+
+	/// Consume the backend, returning all values read so far.
+	pub fn into_reads(self) -> RecordedReads {
+		self.reads.into_inner()
+	}
+
+	fn record(&self, child_info: Option<&ChildInfo>, key: &[u8], value: Option<StorageValue>) {
+		let storage_key = child_info.map(|child_info| child_info.storage_key().to_vec());
+		self.reads.lock().insert((storage_key, key.to_vec()), value);
+	}
+
+	/// Record the keys visited by a key only iteration, looking up their values.
+	fn record_keys(&self, child_info: Option<&ChildInfo>, keys: Vec<StorageKey>) {
+		for key in keys {
+			let value = match child_info {
+				Some(child_info) => self.backend.child_storage(child_info, &key),
+				None => self.backend.storage(&key),
+			};
+			if let Ok(value) = value {
+				self.record(child_info, &key, value);
+			}
+		}
+	}
+}
+
+impl<'a, S, H> std::fmt::Debug for ReadRecordingBackend<'a, S, H> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "ReadRecordingBackend")
+	}
+}
+
+impl<'a, S: Backend<H>, H: Hasher> Backend<H> for ReadRecordingBackend<'a, S, H> {
+	type Error = S::Error;
+	type Transaction = S::Transaction;
+	type TrieBackendStorage = S::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<StorageValue>, Self::Error> {
+		let value = self.backend.storage(key)?;
+		self.record(None, key, value.clone());
+		Ok(value)
+	}
+
+	fn child_storage(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageValue>, Self::Error> {
+		let value = self.backend.child_storage(child_info, key)?;
+		self.record(Some(child_info), key, value.clone());
+		Ok(value)
+	}
+
+	fn apply_to_child_keys_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		child_info: &ChildInfo,
+		mut f: F,
+	) {
+		let mut keys = Vec::new();
+		self.backend.apply_to_child_keys_while(child_info, |key| {
+			keys.push(key.to_vec());
+			f(key)
+		});
+		self.record_keys(Some(child_info), keys);
+	}
+
+	fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		mut f: F,
+	) {
+		let mut keys = Vec::new();
+		self.backend.apply_to_keys_with_prefix_while(prefix, |key| {
+			keys.push(key.to_vec());
+			f(key)
+		});
+		self.record_keys(None, keys);
+	}
+
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<StorageKey>, Self::Error> {
+		let next_key = self.backend.next_storage_key(key)?;
+		if let Some(next_key) = &next_key {
+			self.record_keys(None, vec![next_key.clone()]);
+		}
+		Ok(next_key)
+	}
+
+	fn next_child_storage_key(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageKey>, Self::Error> {
+		let next_key = self.backend.next_child_storage_key(child_info, key)?;
+		if let Some(next_key) = &next_key {
+			self.record_keys(Some(child_info), vec![next_key.clone()]);
+		}
+		Ok(next_key)
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], mut f: F) {
+		let mut keys = Vec::new();
+		self.backend.for_keys_with_prefix(prefix, |key| {
+			keys.push(key.to_vec());
+			f(key)
+		});
+		self.record_keys(None, keys);
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], mut f: F) {
+		let mut pairs = Vec::new();
+		self.backend.for_key_values_with_prefix(prefix, |key, value| {
+			pairs.push((key.to_vec(), value.to_vec()));
+			f(key, value)
+		});
+		for (key, value) in pairs {
+			self.record(None, &key, Some(value));
+		}
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
+		&self,
+		child_info: &ChildInfo,
+		prefix: &[u8],
+		mut f: F,
+	) {
+		let mut keys = Vec::new();
+		self.backend.for_child_keys_with_prefix(child_info, prefix, |key| {
+			keys.push(key.to_vec());
+			f(key)
+		});
+		self.record_keys(Some(child_info), keys);
+	}
+
+	fn storage_root<'b>(
+		&self,
+		delta: impl Iterator<Item=(&'b [u8], Option<&'b [u8]>)>,
+	) -> (H::Out, Self::Transaction) where H::Out: Ord {
+		self.backend.storage_root(delta)
+	}
+
+	fn child_storage_root<'b>(
+		&self,
+		child_info: &ChildInfo,
+		delta: impl Iterator<Item=(&'b [u8], Option<&'b [u8]>)>,
+	) -> (H::Out, bool, Self::Transaction) where H::Out: Ord {
+		self.backend.child_storage_root(child_info, delta)
+	}
+
+	fn pairs(&self) -> Vec<(StorageKey, StorageValue)> {
+		let pairs = self.backend.pairs();
+		for (key, value) in &pairs {
+			self.record(None, key, Some(value.clone()));
+		}
+		pairs
+	}
+
+	fn register_overlay_stats(&mut self, _stats: &StateMachineStats) {
+		// Overlay statistics are not part of the recording, and the wrapped backend is
+		// only borrowed immutably, so there is nothing to register them with.
+	}
+
+	fn usage_info(&self) -> UsageInfo {
+		self.backend.usage_info()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::InMemoryBackend;
+	use sp_runtime::traits::BlakeTwo256;
+
+	#[test]
+	fn records_top_and_child_reads() {
+		let child_info = ChildInfo::new_default(b"sub");
+		let backend = InMemoryBackend::<BlakeTwo256>::from(vec![
+			(None, vec![(b"key".to_vec(), Some(b"value".to_vec()))]),
+			(Some(child_info.clone()), vec![(b"child".to_vec(), Some(b"x".to_vec()))]),
+		]);
+		let recording = ReadRecordingBackend::new(&backend);
+
+		assert_eq!(recording.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(recording.storage(b"missing").unwrap(), None);
+		assert_eq!(recording.child_storage(&child_info, b"child").unwrap(), Some(b"x".to_vec()));
+
+		let reads = recording.into_reads();
+		assert_eq!(reads.len(), 3);
+		assert_eq!(reads.get(&(None, b"key".to_vec())), Some(&Some(b"value".to_vec())));
+		assert_eq!(reads.get(&(None, b"missing".to_vec())), Some(&None));
+		assert_eq!(
+			reads.get(&(Some(b"sub".to_vec()), b"child".to_vec())),
+			Some(&Some(b"x".to_vec())),
+		);
+	}
+
+	#[test]
+	fn records_keys_visited_by_iteration() {
+		let child_info = ChildInfo::new_default(b"sub");
+		let backend = InMemoryBackend::<BlakeTwo256>::from(vec![
+			(None, vec![
+				(b"a1".to_vec(), Some(b"x".to_vec())),
+				(b"a2".to_vec(), Some(b"y".to_vec())),
+				(b"b1".to_vec(), Some(b"z".to_vec())),
+			]),
+			(Some(child_info.clone()), vec![(b"c1".to_vec(), Some(b"w".to_vec()))]),
+		]);
+		let recording = ReadRecordingBackend::new(&backend);
+
+		let mut keys = Vec::new();
+		recording.for_keys_with_prefix(b"a", |key| keys.push(key.to_vec()));
+		assert_eq!(keys, vec![b"a1".to_vec(), b"a2".to_vec()]);
+		assert_eq!(recording.next_storage_key(b"a2").unwrap(), Some(b"b1".to_vec()));
+		recording.for_child_keys_with_prefix(&child_info, b"c", |_| {});
+
+		let reads = recording.into_reads();
+		assert_eq!(reads.len(), 4);
+		assert_eq!(reads.get(&(None, b"a1".to_vec())), Some(&Some(b"x".to_vec())));
+		assert_eq!(reads.get(&(None, b"a2".to_vec())), Some(&Some(b"y".to_vec())));
+		assert_eq!(reads.get(&(None, b"b1".to_vec())), Some(&Some(b"z".to_vec())));
+		assert_eq!(
+			reads.get(&(Some(b"sub".to_vec()), b"c1".to_vec())),
+			Some(&Some(b"w".to_vec())),
+		);
+	}
+}
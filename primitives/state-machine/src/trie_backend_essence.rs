@@ -233,6 +233,33 @@ impl<S: TrieBackendStorage<H>, H: Hasher> TrieBackendEssence<S, H> where H::Out:
 		self.keys_values_with_prefix_inner(&root, prefix, |k, _v| f(k), Some(child_info))
 	}
 
+	/// Execute given closure for all keys starting with prefix, until it returns false.
+	pub fn apply_to_keys_with_prefix_while<F: FnMut(&[u8]) -> bool>(
+		&self,
+		prefix: &[u8],
+		mut f: F,
+	) {
+		let mut iter = move |db| -> sp_std::result::Result<(), Box<TrieError<H::Out>>> {
+			let trie = TrieDB::<H>::new(db, &self.root)?;
+
+			for x in TrieDBIterator::new_prefixed(&trie, prefix)? {
+				let (key, _) = x?;
+
+				debug_assert!(key.starts_with(prefix));
+
+				if !f(&key) {
+					break;
+				}
+			}
+
+			Ok(())
+		};
+
+		if let Err(e) = iter(self) {
+			debug!(target: "trie", "Error while iterating by prefix: {}", e);
+		}
+	}
+
 	/// Execute given closure for all keys starting with prefix.
 	pub fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], mut f: F) {
 		self.keys_values_with_prefix_inner(&self.root, prefix, |k, _v| f(k), None)
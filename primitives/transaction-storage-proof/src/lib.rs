@@ -0,0 +1,296 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2021 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage proof primitives. Contains the types, inherent and runtime API used to prove that
+//! the data indexed by the transaction storage pallet is still stored by the block authors.
+//!
+//! Indexed data is split in chunks of [`CHUNK_SIZE`] bytes, which are put in a trie keyed by
+//! their SCALE-encoded compact index. The root of that trie is kept on chain. Every block must
+//! then contain a proof that a chunk, chosen at random among all the chunks of the data indexed
+//! exactly one storage period earlier, is part of that trie.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{prelude::*, result::Result};
+use codec::{Encode, Decode};
+use sp_inherents::{InherentIdentifier, InherentData, IsFatalError};
+use sp_runtime::{RuntimeDebug, traits::NumberFor};
+
+#[cfg(feature = "std")]
+use sp_inherents::ProvideInherentData;
+
+/// The identifier for the storage proof inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"tx_proof";
+/// Size of the chunks the indexed data is split into.
+pub const CHUNK_SIZE: usize = 256;
+/// Default number of blocks for which indexed data is stored: a week of 6 seconds blocks.
+pub const DEFAULT_STORAGE_PERIOD: u32 = 100800;
+/// Maximum size of a piece of indexed data. Indexed data is served over bitswap in a single
+/// message of at most 2 MiB, this leaves room for the framing of the message.
+pub const MAX_TRANSACTION_SIZE: u32 = 2 * 1024 * 1024 - 4 * 1024;
+
+/// Errors that can occur while checking the storage proof inherent.
+#[derive(Encode, RuntimeDebug, PartialEq)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+	/// The proof doesn't prove that the selected chunk is stored.
+	InvalidProof,
+	/// A proof was required but none was provided.
+	MissingProof,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+impl InherentError {
+	/// Try to create an instance ouf of the given identifier and data.
+	#[cfg(feature = "std")]
+	pub fn try_from(id: &InherentIdentifier, data: &[u8]) -> Option<Self> {
+		if id == &INHERENT_IDENTIFIER {
+			<InherentError as codec::Decode>::decode(&mut &data[..]).ok()
+		} else {
+			None
+		}
+	}
+}
+
+/// Proof that a chunk of indexed data is stored.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct TransactionStorageProof {
+	/// The chunk, selected by [`random_chunk`].
+	pub chunk: Vec<u8>,
+	/// Trie nodes proving that the chunk belongs to the chunk trie of its data.
+	pub proof: Vec<Vec<u8>>,
+}
+
+/// The type of the inherent.
+pub type InherentType = TransactionStorageProof;
+
+/// Auxiliary trait to extract the storage proof from the inherent data.
+pub trait TransactionStorageProofInherentData {
+	/// Get the storage proof, if any. A proof is only provided when some data must be proven.
+	fn storage_proof(&self) -> Result<Option<InherentType>, sp_inherents::Error>;
+}
+
+impl TransactionStorageProofInherentData for InherentData {
+	fn storage_proof(&self) -> Result<Option<InherentType>, sp_inherents::Error> {
+		self.get_data(&INHERENT_IDENTIFIER)
+	}
+}
+
+/// Number of chunks data of the given size is split into.
+pub fn num_chunks(size: u32) -> u32 {
+	((size as u64 + CHUNK_SIZE as u64 - 1) / CHUNK_SIZE as u64) as u32
+}
+
+/// Selects a chunk among `total_chunks` using the first 8 bytes of `random_hash`.
+///
+/// `total_chunks` must not be zero.
+pub fn random_chunk(random_hash: &[u8], total_chunks: u32) -> u32 {
+	let mut buf = [0u8; 8];
+	buf.copy_from_slice(&random_hash[0..8]);
+	let random_u64 = u64::from_be_bytes(buf);
+	(random_u64 % total_chunks as u64) as u32
+}
+
+/// Key of the chunk with the given index in the chunk trie.
+pub fn encode_index(index: u32) -> Vec<u8> {
+	codec::Compact(index).encode()
+}
+
+sp_api::decl_runtime_apis! {
+	/// Gives the node access to the data it has to prove the storage of.
+	pub trait TransactionStorageApi {
+		/// Number of blocks for which the indexed data is stored.
+		fn storage_period() -> NumberFor<Block>;
+
+		/// Content hashes and sizes of the data indexed in the given block, in order.
+		fn block_transactions(number: NumberFor<Block>) -> Vec<([u8; 32], u32)>;
+	}
+}
+
+/// Builds the proof that the chunk selected by `random_hash` is stored, given the data indexed
+/// in a block, in order.
+///
+/// Returns `None` if there is nothing to prove.
+#[cfg(feature = "std")]
+pub fn build_proof(
+	random_hash: &[u8],
+	transactions: &[Vec<u8>],
+) -> Result<Option<TransactionStorageProof>, sp_inherents::Error> {
+	use sp_trie::TrieMut;
+
+	type Layout = sp_trie::Layout<sp_core::Blake2Hasher>;
+
+	let total_chunks: u32 = transactions.iter().map(|data| num_chunks(data.len() as u32)).sum();
+	if total_chunks == 0 {
+		return Ok(None);
+	}
+
+	let mut selected = random_chunk(random_hash, total_chunks);
+	for data in transactions {
+		let chunks = num_chunks(data.len() as u32);
+		if selected >= chunks {
+			selected -= chunks;
+			continue;
+		}
+
+		let mut db = sp_trie::MemoryDB::<sp_core::Blake2Hasher>::default();
+		let mut root = Default::default();
+		let mut chunk = Vec::new();
+		{
+			let mut trie = sp_trie::TrieDBMut::<Layout>::new(&mut db, &mut root);
+			for (index, data_chunk) in data.chunks(CHUNK_SIZE).enumerate() {
+				trie.insert(&encode_index(index as u32), data_chunk)
+					.map_err(|e| format!("Failed to build the chunk trie: {:?}", e))?;
+				if index as u32 == selected {
+					chunk = data_chunk.to_vec();
+				}
+			}
+		}
+
+		let proof = sp_trie::generate_trie_proof::<Layout, _, _, _>(
+			&db,
+			root,
+			&[encode_index(selected)],
+		).map_err(|e| format!("Failed to generate the storage proof: {:?}", e))?;
+
+		return Ok(Some(TransactionStorageProof { chunk, proof }));
+	}
+
+	unreachable!("the selected chunk is lower than the total number of chunks; qed")
+}
+
+/// Gives access to the data indexed through the offchain indexing API.
+#[cfg(feature = "std")]
+pub trait IndexedTransactions<Block: sp_runtime::traits::Block> {
+	/// Get the data indexed under the given content hash. Returns `None` if it isn't stored.
+	fn indexed_transaction(
+		&self,
+		hash: &Block::Hash,
+	) -> sp_blockchain::Result<Option<Vec<u8>>>;
+}
+
+/// Provides the storage proof inherent of a block built on top of a given parent.
+///
+/// The proof depends on the parent, so a new provider has to be created for each block.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider<Block: sp_runtime::traits::Block, Client> {
+	client: std::sync::Arc<Client>,
+	parent: Block::Hash,
+}
+
+#[cfg(feature = "std")]
+impl<Block: sp_runtime::traits::Block, Client> InherentDataProvider<Block, Client> {
+	/// Creates a new provider for the child of `parent`, reading the indexed data from `client`.
+	pub fn new(client: std::sync::Arc<Client>, parent: Block::Hash) -> Self {
+		InherentDataProvider { client, parent }
+	}
+}
+
+#[cfg(feature = "std")]
+impl<Block, Client> ProvideInherentData for InherentDataProvider<Block, Client> where
+	Block: sp_runtime::traits::Block,
+	Client: sp_api::ProvideRuntimeApi<Block>
+		+ sp_blockchain::HeaderBackend<Block>
+		+ IndexedTransactions<Block>,
+	Client::Api: TransactionStorageApi<Block, Error = sp_blockchain::Error>,
+{
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		use sp_runtime::{generic::BlockId, traits::{One, Saturating}};
+
+		let parent = BlockId::Hash(self.parent);
+		let parent_number = self.client.number(self.parent)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown parent block {:?}", self.parent))?;
+		let api = self.client.runtime_api();
+
+		let period = api.storage_period(&parent).map_err(|e| e.to_string())?;
+		let number = parent_number + One::one();
+		if number <= period {
+			return Ok(());
+		}
+
+		let transactions = api.block_transactions(&parent, number.saturating_sub(period))
+			.map_err(|e| e.to_string())?
+			.into_iter()
+			.map(|(content_hash, _)| {
+				let hash = Block::Hash::decode(&mut &content_hash[..])
+					.map_err(|e| format!("Invalid content hash: {}", e))?;
+				self.client.indexed_transaction(&hash)
+					.map_err(|e| e.to_string())?
+					.ok_or_else(|| format!("Missing indexed transaction {:?}", hash).into())
+			})
+			.collect::<Result<Vec<_>, sp_inherents::Error>>()?;
+
+		match build_proof(self.parent.as_ref(), &transactions)? {
+			Some(proof) => inherent_data.put_data(INHERENT_IDENTIFIER, &proof),
+			None => Ok(()),
+		}
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_trie::TrieConfiguration;
+
+	#[test]
+	fn proof_of_selected_chunk_verifies() {
+		let transactions = vec![vec![1u8; CHUNK_SIZE * 2], vec![], vec![2u8; CHUNK_SIZE + 1]];
+		let chunk_roots: Vec<_> = transactions.iter()
+			.map(|data| sp_trie::Layout::<sp_core::Blake2Hasher>::ordered_trie_root(
+				data.chunks(CHUNK_SIZE).map(|chunk| chunk.to_vec()).collect::<Vec<_>>()
+			))
+			.collect();
+
+		// Chunk 2 is the first chunk of the third transaction.
+		let random_hash = [0, 0, 0, 0, 0, 0, 0, 2, 42];
+		let proof = build_proof(&random_hash, &transactions).unwrap().unwrap();
+		assert_eq!(proof.chunk, vec![2u8; CHUNK_SIZE]);
+		assert!(sp_trie::verify_trie_proof::<sp_trie::Layout<sp_core::Blake2Hasher>, _, _, _>(
+			&chunk_roots[2],
+			&proof.proof,
+			&[(encode_index(0), Some(&proof.chunk))],
+		).is_ok());
+		assert!(sp_trie::verify_trie_proof::<sp_trie::Layout<sp_core::Blake2Hasher>, _, _, _>(
+			&chunk_roots[0],
+			&proof.proof,
+			&[(encode_index(0), Some(&proof.chunk))],
+		).is_err());
+	}
+
+	#[test]
+	fn nothing_to_prove_without_data() {
+		assert_eq!(build_proof(&[0; 32], &[]).unwrap(), None);
+		assert_eq!(build_proof(&[0; 32], &[Vec::new()]).unwrap(), None);
+	}
+}
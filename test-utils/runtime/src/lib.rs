@@ -38,7 +38,7 @@ use sp_runtime::{
 	ApplyExtrinsicResult, Perbill,
 	transaction_validity::{
 		TransactionValidity, ValidTransaction, TransactionValidityError, InvalidTransaction,
-		TransactionSource,
+		TransactionSource, TransactionPriority,
 	},
 	traits::{
 		BlindCheckable, BlakeTwo256, Block as BlockT, Extrinsic as ExtrinsicT,
@@ -54,6 +54,7 @@ use sp_version::NativeVersion;
 use frame_support::{
 	impl_outer_origin, parameter_types,
 	traits::KeyOwnerProofSystem,
+	unsigned::ValidateUnsigned,
 	weights::RuntimeDbWeight,
 };
 use frame_system::limits::{BlockWeights, BlockLength};
@@ -151,6 +152,7 @@ pub enum Extrinsic {
 	ChangesTrieConfigUpdate(Option<ChangesTrieConfiguration>),
 	OffchainIndexSet(Vec<u8>, Vec<u8>),
 	OffchainIndexClear(Vec<u8>),
+	SassafrasTickets(Vec<sp_consensus_sassafras::Ticket>),
 }
 
 parity_util_mem::malloc_size_of_is_0!(Extrinsic); // non-opaque extrinsic does not need this
@@ -183,6 +185,7 @@ impl BlindCheckable for Extrinsic {
 				Ok(Extrinsic::OffchainIndexSet(key, value)),
 			Extrinsic::OffchainIndexClear(key) =>
 				Ok(Extrinsic::OffchainIndexClear(key)),
+			Extrinsic::SassafrasTickets(tickets) => Ok(Extrinsic::SassafrasTickets(tickets)),
 		}
 	}
 }
@@ -192,10 +195,9 @@ impl ExtrinsicT for Extrinsic {
 	type SignaturePayload = ();
 
 	fn is_signed(&self) -> Option<bool> {
-		if let Extrinsic::IncludeData(_) = *self {
-			Some(false)
-		} else {
-			Some(true)
+		match *self {
+			Extrinsic::IncludeData(_) | Extrinsic::SassafrasTickets(_) => Some(false),
+			_ => Some(true),
 		}
 	}
 
@@ -204,6 +206,15 @@ impl ExtrinsicT for Extrinsic {
 	}
 }
 
+impl From<pallet_sassafras::Call<Runtime>> for Extrinsic {
+	fn from(call: pallet_sassafras::Call<Runtime>) -> Self {
+		match call {
+			pallet_sassafras::Call::submit_tickets(tickets) => Extrinsic::SassafrasTickets(tickets),
+			_ => unreachable!("Only `submit_tickets` is submitted by the test runtime"),
+		}
+	}
+}
+
 impl sp_runtime::traits::Dispatchable for Extrinsic {
 	type Origin = Origin;
 	type Config = ();
@@ -507,6 +518,23 @@ impl pallet_babe::Config for Runtime {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub const SassafrasMaxAttempts: u32 = 2;
+	pub const SassafrasUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
+}
+
+impl pallet_sassafras::Config for Runtime {
+	type EpochDuration = EpochDuration;
+	type MaxAttempts = SassafrasMaxAttempts;
+	type UnsignedPriority = SassafrasUnsignedPriority;
+	type WeightInfo = ();
+}
+
+impl frame_system::offchain::SendTransactionTypes<pallet_sassafras::Call<Runtime>> for Runtime {
+	type Extrinsic = Extrinsic;
+	type OverarchingCall = Extrinsic;
+}
+
 /// Adds one to the given input and returns the final result.
 #[inline(never)]
 fn benchmark_add_one(i: u64) -> u64 {
@@ -585,9 +613,16 @@ cfg_if! {
 
 			impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
 				fn validate_transaction(
-					_source: TransactionSource,
+					source: TransactionSource,
 					utx: <Block as BlockT>::Extrinsic,
 				) -> TransactionValidity {
+					if let Extrinsic::SassafrasTickets(tickets) = utx {
+						return <pallet_sassafras::Module<Runtime> as ValidateUnsigned>::validate_unsigned(
+							source,
+							&pallet_sassafras::Call::submit_tickets(tickets),
+						);
+					}
+
 					if let Extrinsic::IncludeData(data) = utx {
 						return Ok(ValidTransaction {
 							priority: data.len() as u64,
@@ -724,6 +759,45 @@ cfg_if! {
 						AuraId::from(authority)
 					}).collect()
 				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: sp_consensus_aura::SlotNumber,
+					_authority_id: AuraId,
+				) -> Option<sp_consensus_aura::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: sp_consensus_aura::EquivocationProof<
+						<Block as BlockT>::Header,
+						AuraId,
+					>,
+					_key_owner_proof: sp_consensus_aura::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+			}
+
+			impl sp_consensus_sassafras::SassafrasApi<Block> for Runtime {
+				fn configuration() -> sp_consensus_sassafras::SassafrasConfiguration {
+					sp_consensus_sassafras::SassafrasConfiguration {
+						slot_duration: 1000,
+						authorities: system::sassafras_authorities(),
+						..<pallet_sassafras::Module<Runtime>>::configuration()
+					}
+				}
+
+				fn slot_author(
+					slot_number: sp_consensus_sassafras::SlotNumber,
+				) -> Option<sp_consensus_sassafras::AuthorityIndex> {
+					system::sassafras_slot_author(slot_number)
+				}
+
+				fn submit_tickets_unsigned_extrinsic(
+					tickets: Vec<sp_consensus_sassafras::Ticket>,
+				) -> bool {
+					<pallet_sassafras::Module<Runtime>>::submit_unsigned_tickets(tickets).is_some()
+				}
 			}
 
 			impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -808,6 +882,10 @@ cfg_if! {
 				) -> Option<sp_finality_grandpa::OpaqueKeyOwnershipProof> {
 					None
 				}
+
+				fn finality_delay() -> sp_finality_grandpa::FinalityDelay<NumberFor<Block>> {
+					Default::default()
+				}
 			}
 
 			impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -840,9 +918,16 @@ cfg_if! {
 
 			impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
 				fn validate_transaction(
-					_source: TransactionSource,
+					source: TransactionSource,
 					utx: <Block as BlockT>::Extrinsic,
 				) -> TransactionValidity {
+					if let Extrinsic::SassafrasTickets(tickets) = utx {
+						return <pallet_sassafras::Module<Runtime> as ValidateUnsigned>::validate_unsigned(
+							source,
+							&pallet_sassafras::Call::submit_tickets(tickets),
+						);
+					}
+
 					if let Extrinsic::IncludeData(data) = utx {
 						return Ok(ValidTransaction{
 							priority: data.len() as u64,
@@ -983,6 +1068,45 @@ cfg_if! {
 						AuraId::from(authority)
 					}).collect()
 				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: sp_consensus_aura::SlotNumber,
+					_authority_id: AuraId,
+				) -> Option<sp_consensus_aura::OpaqueKeyOwnershipProof> {
+					None
+				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: sp_consensus_aura::EquivocationProof<
+						<Block as BlockT>::Header,
+						AuraId,
+					>,
+					_key_owner_proof: sp_consensus_aura::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+			}
+
+			impl sp_consensus_sassafras::SassafrasApi<Block> for Runtime {
+				fn configuration() -> sp_consensus_sassafras::SassafrasConfiguration {
+					sp_consensus_sassafras::SassafrasConfiguration {
+						slot_duration: 1000,
+						authorities: system::sassafras_authorities(),
+						..<pallet_sassafras::Module<Runtime>>::configuration()
+					}
+				}
+
+				fn slot_author(
+					slot_number: sp_consensus_sassafras::SlotNumber,
+				) -> Option<sp_consensus_sassafras::AuthorityIndex> {
+					system::sassafras_slot_author(slot_number)
+				}
+
+				fn submit_tickets_unsigned_extrinsic(
+					tickets: Vec<sp_consensus_sassafras::Ticket>,
+				) -> bool {
+					<pallet_sassafras::Module<Runtime>>::submit_unsigned_tickets(tickets).is_some()
+				}
 			}
 
 			impl sp_consensus_babe::BabeApi<Block> for Runtime {
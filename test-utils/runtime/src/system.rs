@@ -24,7 +24,7 @@ use sp_io::{
 	hashing::blake2_256, trie,
 };
 use frame_support::storage;
-use frame_support::{decl_storage, decl_module};
+use frame_support::{decl_storage, decl_module, traits::UnfilteredDispatchable};
 use sp_runtime::{
 	traits::Header as _, generic, ApplyExtrinsicResult,
 	transaction_validity::{
@@ -34,9 +34,13 @@ use sp_runtime::{
 use codec::{KeyedVec, Encode, Decode};
 use frame_system::Config;
 use crate::{
-	AccountId, BlockNumber, Extrinsic, Transfer, H256 as Hash, Block, Header, Digest, AuthorityId
+	AccountId, BlockNumber, Extrinsic, Transfer, H256 as Hash, Block, Header, Digest, AuthorityId,
+	Runtime,
+};
+use sp_core::{sr25519, storage::well_known_keys, ChangesTrieConfiguration};
+use sp_consensus_sassafras::{
+	digests::PreDigest, AuthorityIndex, SlotNumber, Ticket, SASSAFRAS_ENGINE_ID,
 };
-use sp_core::{storage::well_known_keys, ChangesTrieConfiguration};
 
 const NONCE_OF: &[u8] = b"nonce:";
 const BALANCE_OF: &[u8] = b"balance:";
@@ -82,12 +86,68 @@ pub fn initialize_block(header: &Header) {
 	if let Some(generic::DigestItem::Other(v)) = header.digest().logs().iter().next() {
 		let _: Option<u32> = storage::unhashed::get(&v);
 	}
+
+	initialize_sassafras(header);
+}
+
+/// Track the slot of blocks authored with Sassafras, as `pallet_sassafras` does on
+/// initialization.
+///
+/// The test runtime has no genesis config for `pallet_sassafras`, so its authorities are set
+/// from the test authorities on the first block authored with Sassafras.
+fn initialize_sassafras(header: &Header) {
+	let pre_digest = header.digest().logs().iter()
+		.filter_map(|log| log.as_pre_runtime())
+		.find_map(|(id, mut data)| if id == SASSAFRAS_ENGINE_ID {
+			PreDigest::decode(&mut data).ok()
+		} else {
+			None
+		});
+
+	if let Some(pre_digest) = pre_digest {
+		if !<pallet_sassafras::Authorities as storage::StorageValue<_>>::exists() {
+			<pallet_sassafras::Authorities as storage::StorageValue<_>>::put(sassafras_authorities());
+		}
+
+		<pallet_sassafras::Module<Runtime>>::initialize_slot(pre_digest.slot_number);
+	}
 }
 
 pub fn authorities() -> Vec<AuthorityId> {
 	Authorities::get()
 }
 
+/// The authorities of `pallet_sassafras`, or the test authorities if no block was authored with
+/// Sassafras yet.
+pub fn sassafras_authorities() -> Vec<sp_consensus_sassafras::AuthorityId> {
+	let sassafras_authorities = <pallet_sassafras::Module<Runtime>>::authorities();
+	if !sassafras_authorities.is_empty() {
+		return sassafras_authorities;
+	}
+
+	authorities().into_iter().map(|a| {
+		let authority: sr25519::Public = a.into();
+		sp_consensus_sassafras::AuthorityId::from(authority)
+	}).collect()
+}
+
+/// The author of the given slot as assigned by `pallet_sassafras`.
+///
+/// Until the first block authored with Sassafras no epoch has started, so the pallet assigns
+/// slots round-robin over the test authorities.
+pub fn sassafras_slot_author(slot_number: SlotNumber) -> Option<AuthorityIndex> {
+	if <pallet_sassafras::Authorities as storage::StorageValue<_>>::exists() {
+		return <pallet_sassafras::Module<Runtime>>::slot_author(slot_number);
+	}
+
+	let authorities_len = authorities().len() as u64;
+	if authorities_len == 0 {
+		None
+	} else {
+		Some((slot_number % authorities_len) as AuthorityIndex)
+	}
+}
+
 pub fn get_block_number() -> Option<BlockNumber> {
 	Number::get()
 }
@@ -268,10 +328,18 @@ fn execute_transaction_backend(utx: &Extrinsic, extrinsic_index: u32) -> ApplyEx
 		Extrinsic::OffchainIndexClear(key) => {
 			sp_io::offchain_index::clear(&key);
 			Ok(Ok(()))
-		}
+		},
+		Extrinsic::SassafrasTickets(tickets) => execute_sassafras_tickets(tickets),
 	}
 }
 
+fn execute_sassafras_tickets(tickets: &[Ticket]) -> ApplyExtrinsicResult {
+	let call = pallet_sassafras::Call::<Runtime>::submit_tickets(tickets.to_vec());
+	let result = call.dispatch_bypass_filter(frame_system::RawOrigin::None.into());
+
+	Ok(result.map(|_| ()).map_err(|e| e.error))
+}
+
 fn execute_transfer_backend(tx: &Transfer) -> ApplyExtrinsicResult {
 	// check nonce
 	let nonce_key = tx.from.to_keyed_vec(NONCE_OF);
@@ -71,6 +71,7 @@ where
 		wasm_external_transport: Some(transport.clone()),
 		allow_private_ipv4: true,
 		enable_mdns: false,
+		secure_websocket: None,
 	};
 
 	let config = Configuration {